@@ -3,18 +3,20 @@ use std::sync::atomic::{AtomicUsize, Ordering};
 use std::{fs, io};
 
 use anyhow::Context;
-use clap::{arg, value_parser, ArgAction, ArgMatches, Command};
+use clap::{arg, value_parser, Arg, ArgAction, ArgMatches, Command};
 use crossterm::tty::IsTty;
 use superconsole::{Component, Line, Lines, Span};
 use yansi::Color::{Green, Red, Yellow};
-use yara_x_parser::{Parser, SourceCode};
+use yara_x::Compiler;
+use yara_x_parser::SourceCode;
 
+use crate::commands::external_var_parser;
 use crate::walk::Message;
 use crate::{help, walk};
 
 pub fn check() -> Command {
     super::command("check")
-        .about("Check if source files are syntactically correct")
+        .about("Check if source files are correct")
         .long_about(help::CHECK_LONG_HELP)
         .arg(
             arg!(<RULES_PATH>)
@@ -40,6 +42,16 @@ pub fn check() -> Command {
                 .required(false)
                 .value_parser(value_parser!(u8).range(1..)),
         )
+        .arg(
+            Arg::new("define")
+                .long("define")
+                .help("Define external variable")
+                .long_help(help::DEFINE_LONG_HELP)
+                .required(false)
+                .value_name("VAR=VALUE")
+                .value_parser(external_var_parser)
+                .action(ArgAction::Append),
+        )
 }
 
 pub fn exec_check(args: &ArgMatches) -> anyhow::Result<()> {
@@ -48,6 +60,18 @@ pub fn exec_check(args: &ArgMatches) -> anyhow::Result<()> {
     let filters = args.get_many::<String>("filter");
     let num_threads = args.get_one::<u8>("threads");
 
+    let external_vars: Vec<(String, serde_json::Value)> = args
+        .get_many::<(String, serde_json::Value)>("define")
+        .map(|var| var.cloned().collect())
+        .unwrap_or_default();
+
+    // Make sure that the external variables are valid before checking any
+    // file, so that an invalid variable is reported only once.
+    let mut compiler = Compiler::new();
+    for (ident, value) in external_vars.iter() {
+        compiler.define_global(ident.as_str(), value.clone())?;
+    }
+
     let mut w = walk::ParDirWalker::new();
 
     if let Some(max_depth) = max_depth {
@@ -83,12 +107,21 @@ pub fn exec_check(args: &ArgMatches) -> anyhow::Result<()> {
 
             let mut lines = Vec::new();
 
-            match Parser::new()
-                .colorize_errors(io::stdout().is_tty())
-                .build_ast(src)
-            {
-                Ok(ast) => {
-                    if ast.warnings.is_empty() {
+            let mut compiler =
+                Compiler::new().colorize_errors(io::stdout().is_tty());
+
+            // Define the external variables, so that rules using them are
+            // not reported as errors.
+            for (ident, value) in external_vars.iter() {
+                compiler.define_global(ident.as_str(), value.clone())?;
+            }
+
+            // Compile the file recovering from errors, so that all the
+            // errors in the file are reported, not only the first one.
+            match compiler.add_source_with_recovery(src) {
+                Ok(compiler) => {
+                    let warnings = compiler.warnings();
+                    if warnings.is_empty() {
                         state.files_passed.fetch_add(1, Ordering::Relaxed);
                         lines.push(format!(
                             "[ {} ] {}",
@@ -98,25 +131,27 @@ pub fn exec_check(args: &ArgMatches) -> anyhow::Result<()> {
                     } else {
                         state
                             .warnings
-                            .fetch_add(ast.warnings.len(), Ordering::Relaxed);
+                            .fetch_add(warnings.len(), Ordering::Relaxed);
                         lines.push(format!(
                             "[ {} ] {}",
                             Yellow.paint("WARN").bold(),
                             file_path.display()
                         ));
-                        for warning in ast.warnings {
+                        for warning in warnings {
                             lines.push(warning.to_string());
                         }
                     }
                 }
-                Err(err) => {
-                    state.errors.fetch_add(1, Ordering::Relaxed);
+                Err(errors) => {
+                    state.errors.fetch_add(errors.len(), Ordering::Relaxed);
                     lines.push(format!(
-                        "[ {} ] {}\n{}",
+                        "[ {} ] {}",
                         Red.paint("FAIL").bold(),
                         file_path.display(),
-                        err,
                     ));
+                    for error in errors {
+                        lines.push(error.to_string());
+                    }
                 }
             };
 
@@ -16,6 +16,7 @@ use yara_x_proto_yaml::Serializer;
 
 #[derive(Debug, Clone, ValueEnum, Display)]
 enum SupportedModules {
+    Dotnet,
     Lnk,
     Macho,
     Elf,
@@ -147,6 +148,9 @@ pub fn exec_dump(args: &ArgMatches) -> anyhow::Result<()> {
                 SupportedModules::Pe => {
                     yara_x::mods::invoke_mod_dyn::<yara_x::mods::PE>(&buffer)
                 }
+                SupportedModules::Dotnet => yara_x::mods::invoke_mod_dyn::<
+                    yara_x::mods::Dotnet,
+                >(&buffer),
             } {
                 obtain_module_info(output_format, module, &*output)?;
             }
@@ -197,6 +201,17 @@ pub fn exec_dump(args: &ArgMatches) -> anyhow::Result<()> {
                 )?;
             }
         }
+        if let Some(dotnet_output) =
+            yara_x::mods::invoke_mod::<yara_x::mods::Dotnet>(&buffer)
+        {
+            if dotnet_output.is_dotnet() {
+                obtain_module_info(
+                    output_format,
+                    &SupportedModules::Dotnet,
+                    &*dotnet_output,
+                )?;
+            }
+        }
     }
 
     Ok(())
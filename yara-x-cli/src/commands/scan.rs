@@ -1,4 +1,7 @@
 use std::cmp::min;
+#[cfg(feature = "rules-profiling")]
+use std::collections::hash_map::{Entry, HashMap};
+use std::fs;
 use std::fs::File;
 use std::io::Read;
 use std::path::{Path, PathBuf};
@@ -6,14 +9,17 @@ use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Mutex;
 use std::time::{Duration, Instant};
 
-use anyhow::{bail, Context, Error};
+use anyhow::{anyhow, bail, Context, Error};
 use clap::{arg, value_parser, Arg, ArgAction, ArgMatches, Command};
 use crossbeam::channel::Sender;
 use indent::indent_all_by;
+use protobuf::MessageDyn;
 use superconsole::style::Stylize;
 use superconsole::{Component, Line, Lines, Span};
 use yansi::Color::{Cyan, Red, Yellow};
 use yansi::Paint;
+#[cfg(feature = "rules-profiling")]
+use yara_x::ProfilingData;
 use yara_x::{Rule, Rules, ScanError, Scanner};
 
 use crate::commands::{compile_rules, external_var_parser};
@@ -21,9 +27,10 @@ use crate::walk::Message;
 use crate::{help, walk};
 
 #[rustfmt::skip]
+#[cfg_attr(not(feature = "rules-profiling"), allow(clippy::let_and_return))]
 pub fn scan() -> Command {
-    super::command("scan")
-        .about("Scan a file or directory")
+    let command = super::command("scan")
+        .about("Scan a file, directory or process")
         .arg(
             arg!(<RULES_PATH>)
                 .help("Path to YARA source file")
@@ -31,10 +38,17 @@ pub fn scan() -> Command {
                 .action(ArgAction::Append)
         )
         .arg(
-            arg!(<PATH>)
+            arg!([PATH])
                 .help("Path to the file or directory that will be scanned")
+                .required_unless_present("pid")
                 .value_parser(value_parser!(PathBuf))
         )
+        .arg(
+            arg!(--"pid" <PID>)
+                .help("Scan the memory of the process with the given PID")
+                .conflicts_with("PATH")
+                .value_parser(value_parser!(u32))
+        )
         .arg(
             arg!(-e - -"print-namespace")
                 .help("Print rule namespace")
@@ -94,11 +108,38 @@ pub fn scan() -> Command {
                 .value_parser(external_var_parser)
                 .action(ArgAction::Append)
         )
+        .arg(
+            Arg::new("module-data")
+                .short('x')
+                .long("module-data")
+                .help("Pass FILE's content as extra data to MODULE")
+                .long_help(help::MODULE_DATA_LONG_HELP)
+                .required(false)
+                .value_name("MODULE=FILE")
+                .conflicts_with("pid")
+                .value_parser(module_data_parser)
+                .action(ArgAction::Append)
+        );
+
+    // The `--profile` argument exists only when profiling is supported.
+    #[cfg(feature = "rules-profiling")]
+    let command = command
+        .arg(
+            arg!(--"profile" [N])
+                .help("Print the N slowest rules after scanning (default: 10)")
+                .long_help(help::PROFILE_LONG_HELP)
+                .num_args(0..=1)
+                .default_missing_value("10")
+                .value_parser(value_parser!(usize))
+        );
+
+    command
 }
 
 pub fn exec_scan(args: &ArgMatches) -> anyhow::Result<()> {
     let mut rules_path = args.get_many::<PathBuf>("RULES_PATH").unwrap();
-    let path = args.get_one::<PathBuf>("PATH").unwrap();
+    let path = args.get_one::<PathBuf>("PATH");
+    let pid = args.get_one::<u32>("pid");
     let compiled_rules = args.get_flag("compiled-rules");
     let num_threads = args.get_one::<u8>("threads");
     let path_as_namespace = args.get_flag("path-as-namespace");
@@ -106,11 +147,15 @@ pub fn exec_scan(args: &ArgMatches) -> anyhow::Result<()> {
     let negate = args.get_flag("negate");
     let dump_module_output = args.get_flag("dump-module-output");
     let timeout = args.get_one::<u64>("timeout");
+    #[cfg(feature = "rules-profiling")]
+    let profile = args.get_one::<usize>("profile");
 
     let mut external_vars: Option<Vec<(String, serde_json::Value)>> = args
         .get_many::<(String, serde_json::Value)>("define")
         .map(|var| var.cloned().collect());
 
+    let module_outputs = load_module_data(args)?;
+
     let rules = if compiled_rules {
         if rules_path.len() > 1 {
             bail!(
@@ -150,6 +195,29 @@ pub fn exec_scan(args: &ArgMatches) -> anyhow::Result<()> {
         compile_rules(rules_path, path_as_namespace, external_vars.take())?
     };
 
+    let timeout = if let Some(timeout) = timeout {
+        Duration::from_secs(*timeout)
+    } else {
+        Duration::from_secs(u64::MAX)
+    };
+
+    if let Some(pid) = pid {
+        #[cfg(target_os = "linux")]
+        return scan_proc(
+            args,
+            &rules,
+            *pid,
+            external_vars,
+            timeout,
+        );
+        #[cfg(not(target_os = "linux"))]
+        bail!(
+            "'{}' is supported only in Linux (pid: {})",
+            Paint::new("--pid").bold(),
+            pid
+        );
+    }
+
     let rules_ref = &rules;
 
     let mut w = walk::ParDirWalker::new();
@@ -162,20 +230,19 @@ pub fn exec_scan(args: &ArgMatches) -> anyhow::Result<()> {
         w.metadata_filter(|metadata| metadata.len() <= *max_file_size);
     }
 
-    let timeout = if let Some(timeout) = timeout {
-        Duration::from_secs(*timeout)
-    } else {
-        Duration::from_secs(u64::MAX)
-    };
-
     let start_time = Instant::now();
     let state = ScanState::new(start_time);
 
+    #[cfg(feature = "rules-profiling")]
+    let profiling_report = Mutex::new(ProfilingReport::default());
+
     w.walk(
-        path,
+        path.unwrap(),
         state,
         || {
             let mut scanner = Scanner::new(rules_ref);
+            // Messages logged with the `console` module go to stderr.
+            scanner.console_log(|message| eprintln!("{}", message));
             if let Some(ref vars) = external_vars {
                 for (ident, value) in vars {
                     // It's ok to use `unwrap()`, this can not fail because
@@ -202,6 +269,10 @@ pub fn exec_scan(args: &ArgMatches) -> anyhow::Result<()> {
                 .unwrap()
                 .push((file_path.clone(), now));
 
+            for (module, output) in &module_outputs {
+                scanner.set_module_output(module, output.clone_box())?;
+            }
+
             let scan_results = scanner
                 .scan_file(&file_path)
                 .with_context(|| format!("scanning {:?}", &file_path));
@@ -258,6 +329,17 @@ pub fn exec_scan(args: &ArgMatches) -> anyhow::Result<()> {
 
             state.num_scanned_files.fetch_add(1, Ordering::Relaxed);
 
+            // Profiling data is accumulated by the scanner, move it to the
+            // report after each file, as each thread has its own scanner.
+            #[cfg(feature = "rules-profiling")]
+            if profile.is_some() {
+                profiling_report
+                    .lock()
+                    .unwrap()
+                    .add(scanner.slowest_rules(usize::MAX));
+                scanner.clear_profiling_data();
+            }
+
             Ok(())
         },
         |err, output| {
@@ -280,6 +362,117 @@ pub fn exec_scan(args: &ArgMatches) -> anyhow::Result<()> {
     )
     .unwrap();
 
+    #[cfg(feature = "rules-profiling")]
+    if let Some(n) = profile {
+        profiling_report.into_inner().unwrap().print(*n);
+    }
+
+    Ok(())
+}
+
+fn module_data_parser(option: &str) -> Result<(String, PathBuf), Error> {
+    let (module, path) = option.split_once('=').ok_or(anyhow!(
+        "the equal sign is missing, use the syntax MODULE=FILE (example: cuckoo={})",
+        option
+    ))?;
+
+    Ok((module.to_string(), PathBuf::from(path)))
+}
+
+/// Loads the files passed with `--module-data` and converts them into the
+/// outputs of the corresponding modules.
+fn load_module_data(
+    args: &ArgMatches,
+) -> anyhow::Result<Vec<(String, Box<dyn MessageDyn>)>> {
+    let mut module_outputs = Vec::new();
+
+    for (module, path) in
+        args.get_many::<(String, PathBuf)>("module-data").into_iter().flatten()
+    {
+        // Without any module that accepts data, `data` is never used.
+        #[cfg_attr(not(feature = "cuckoo-module"), allow(unused_variables))]
+        let data = fs::read(path)
+            .with_context(|| format!("can not read {:?}", path))?;
+
+        let output: Box<dyn MessageDyn> = match module.as_str() {
+            #[cfg(feature = "cuckoo-module")]
+            "cuckoo" => Box::new(
+                yara_x::mods::Cuckoo::from_json_report(data.as_slice())
+                    .with_context(|| format!("invalid report {:?}", path))?,
+            ),
+            _ => bail!(
+                "module `{}` doesn't accept data with '{}'",
+                module,
+                Paint::new("--module-data").bold()
+            ),
+        };
+
+        module_outputs.push((module.clone(), output));
+    }
+
+    Ok(module_outputs)
+}
+
+#[cfg(target_os = "linux")]
+fn scan_proc(
+    args: &ArgMatches,
+    rules: &Rules,
+    pid: u32,
+    external_vars: Option<Vec<(String, serde_json::Value)>>,
+    timeout: Duration,
+) -> anyhow::Result<()> {
+    let negate = args.get_flag("negate");
+    let mut scanner = Scanner::new(rules);
+
+    // Messages logged with the `console` module go to stderr.
+    scanner.console_log(|message| eprintln!("{}", message));
+
+    if let Some(ref vars) = external_vars {
+        for (ident, value) in vars {
+            scanner.set_global(ident.as_str(), value)?;
+        }
+    }
+
+    scanner.timeout(timeout);
+
+    let scan_results = scanner
+        .scan_proc(pid)
+        .with_context(|| format!("scanning process {}", pid))?;
+
+    let (output, messages) = crossbeam::channel::unbounded();
+    let process = PathBuf::from(pid.to_string());
+
+    if negate {
+        print_matching_rules(
+            args,
+            &process,
+            &mut scan_results.non_matching_rules(),
+            &output,
+        );
+    } else {
+        print_matching_rules(
+            args,
+            &process,
+            &mut scan_results.matching_rules(),
+            &output,
+        );
+    }
+
+    drop(output);
+
+    for message in messages {
+        if let Message::Info(line) = message {
+            println!("{}", line);
+        }
+    }
+
+    #[cfg(feature = "rules-profiling")]
+    if let Some(n) = args.get_one::<usize>("profile") {
+        let mut report = ProfilingReport::default();
+        report.add(scanner.slowest_rules(usize::MAX));
+        report.print(*n);
+    }
+
     Ok(())
 }
 
@@ -339,6 +532,66 @@ fn print_matching_rules(
     }
 }
 
+/// Profiling data accumulated across all the scanned files.
+#[cfg(feature = "rules-profiling")]
+#[derive(Default)]
+struct ProfilingReport<'r> {
+    rules: HashMap<(&'r str, &'r str), ProfilingData<'r>>,
+}
+
+#[cfg(feature = "rules-profiling")]
+impl<'r> ProfilingReport<'r> {
+    /// Adds the profiling data obtained from a scanner to the report.
+    fn add(&mut self, data: Vec<ProfilingData<'r>>) {
+        for rule in data {
+            match self.rules.entry((rule.namespace, rule.rule)) {
+                Entry::Occupied(mut entry) => {
+                    let acc = entry.get_mut();
+                    acc.condition_exec_time += rule.condition_exec_time;
+                    acc.pattern_matching_time += rule.pattern_matching_time;
+                    for (acc, pattern) in
+                        acc.patterns.iter_mut().zip(rule.patterns)
+                    {
+                        acc.atom_matches += pattern.atom_matches;
+                        acc.verification_time += pattern.verification_time;
+                    }
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(rule);
+                }
+            }
+        }
+    }
+
+    /// Prints the `n` slowest rules to stdout.
+    fn print(self, n: usize) {
+        let mut rules = self.rules.into_values().collect::<Vec<_>>();
+
+        rules.sort_by(|a, b| b.total_time().cmp(&a.total_time()));
+
+        println!("\n{}", Paint::new("Slowest rules:").bold());
+
+        for rule in rules.iter().take(n) {
+            println!(
+                "\n{}:{}",
+                Cyan.paint(rule.namespace),
+                Paint::new(rule.rule).bold()
+            );
+            println!("  condition: {:?}", rule.condition_exec_time);
+            println!("  patterns: {:?}", rule.pattern_matching_time);
+            println!("  atom matches: {}", rule.atom_matches());
+            for pattern in rule.patterns.iter() {
+                println!(
+                    "    {}: {} atom matches, {:?}",
+                    pattern.identifier,
+                    pattern.atom_matches,
+                    pattern.verification_time,
+                );
+            }
+        }
+    }
+}
+
 struct ScanState {
     start_time: Instant,
     num_scanned_files: AtomicUsize,
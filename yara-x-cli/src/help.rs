@@ -1,4 +1,10 @@
-pub const CHECK_LONG_HELP: &str = r#"Check if YARA source files are syntactically correct
+pub const CHECK_LONG_HELP: &str = r#"Check if YARA source files are correct
+
+Source files are compiled and all the errors found in each file are reported,
+not only the first one.
+
+Rules that use external variables are reported as errors unless the variables
+are defined with `--define`.
 
 If <PATH> is a directory, all files with extensions `yar` and `yara` will be
 checked. The `--filter` option allows changing this behavior."#;
@@ -44,7 +50,29 @@ Examples:
 --define some_bool=true
 --define some_str=\"foobar\""#;
 
+pub const MODULE_DATA_LONG_HELP: &str = r#"Pass the content of FILE as extra data to MODULE
+
+Some modules don't get their data from the scanned file, but from some other
+source. Currently, the only module that accepts extra data is `cuckoo`, which
+expects a JSON report produced by the Cuckoo sandbox. The same data is used
+for every scanned file.
+
+Examples:
+
+--module-data cuckoo=report.json"#;
+
 pub const COMPILED_RULES_HELP: &str = r#"Indicates that <RULES_PATH> is a file containing compiled rules
 
 YARA rules can be compiled with the `yr compile` command. The file produced by
 this command can be passed later to `yr scan` by using this flag."#;
+
+#[cfg(feature = "rules-profiling")]
+pub const PROFILE_LONG_HELP: &str = r#"Print the N slowest rules after scanning (default: 10)
+
+For each rule it shows the time spent evaluating its condition, the time spent
+verifying its patterns, and the number of atom matches for each pattern. The
+values are the totals for all the scanned files. Patterns with a large number
+of atom matches are usually slow.
+
+This option is available only when yr is built with the `rules-profiling`
+feature, which has a noticeable impact on scanning performance."#;
@@ -271,6 +271,20 @@ impl Formatter {
                 processor::actions::newline,
             )
             //
+            // Same as above, but for include statements.
+            //
+            .add_rule(
+                |ctx| {
+                    let next_token = ctx.token(1);
+                    let prev_token = ctx.token(-1);
+
+                    next_token.eq(&Begin(GrammarRule::include_stmt))
+                        && prev_token.neq(&Begin(GrammarRule::source_file))
+                        && prev_token.is_not(*NEWLINE)
+                },
+                processor::actions::newline,
+            )
+            //
             // Insert newline in front of rule declarations, making sure that
             // rule declarations starts at a new line. The newline is not
             // inserted if the rule is at the start of the file.
@@ -753,6 +767,7 @@ impl Formatter {
             // - No space after "(" and "["
             // - No space before ")" and "]"
             // - No space before ":"
+            // - No space before "," in `with` declarations
             // - No space before or after ".." (e.g: (0..10))
             // - No space before or after "." (e.g: foo.bar)
             // - No space in-between identifiers and "(" or "[" (e.g: array[0], 
@@ -772,6 +787,10 @@ impl Formatter {
                     let drop_space =
                         // Don't insert space if next token is ":"
                         next_token.eq(&COLON)
+                        // Don't insert space before "," in `with`
+                        // declarations (e.g: with a = 1, b = 2: ...)
+                        || ctx.in_rule(GrammarRule::with_expr, false)
+                            && next_token.eq(&COMMA)
                         // Don't insert spaces around ".."
                         || prev_token.eq(&DOT_DOT)
                         || next_token.eq(&DOT_DOT)
@@ -136,6 +136,7 @@ pub(crate) mod categories {
 
 lazy_static! {
     pub(crate) static ref COLON: Token<'static> = Token::Punctuation(":");
+    pub(crate) static ref COMMA: Token<'static> = Token::Punctuation(",");
     pub(crate) static ref DOT: Token<'static> = Token::Punctuation(".");
     pub(crate) static ref DOT_DOT: Token<'static> = Token::Punctuation("..");
     pub(crate) static ref EQUAL: Token<'static> = Token::Punctuation("=");
@@ -365,6 +366,7 @@ impl<'a> Token<'a> {
             | GrammarRule::k_IEQUALS
             | GrammarRule::k_IMPORT
             | GrammarRule::k_IN
+            | GrammarRule::k_INCLUDE
             | GrammarRule::k_ISTARTSWITH
             | GrammarRule::k_MATCHES
             | GrammarRule::k_META
@@ -380,6 +382,7 @@ impl<'a> Token<'a> {
             | GrammarRule::k_THEM
             | GrammarRule::k_TRUE
             | GrammarRule::k_WIDE
+            | GrammarRule::k_WITH
             | GrammarRule::k_XOR => Token::Keyword(src),
             // Punctuation.
             GrammarRule::ASTERISK
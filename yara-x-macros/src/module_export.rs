@@ -9,6 +9,7 @@ use syn::{AttributeArgs, Expr, FnArg, ItemFn, Pat};
 /// Arguments received by the `#[module_export]` macro.
 pub struct ModuleExportsArgs {
     name: Option<String>,
+    method_of: Option<String>,
 }
 
 /// Implementation for the `#[module_export]` attribute macro.
@@ -43,6 +44,35 @@ pub struct ModuleExportsArgs {
 ///   a + b
 /// }
 /// ```
+///
+/// When the function is a method of some structure (i.e: it was declared
+/// with `#[module_export(method_of = "my_module.MyStruct")]`), the function
+/// receives the structure as its second argument, right after the
+/// `&ScanContext`. In that case the thunk function doesn't have that
+/// argument, instead, it receives two extra `i32` arguments at the end that
+/// are used for locating the structure with [`lookup_object`].
+///
+/// ```text
+/// #[module_export(method_of = "my_module.MyStruct")]
+/// fn foo(ctx: &ScanContext, obj: Rc<Struct>, a: i64) -> i64 {
+///   ...
+/// }
+/// ```
+///
+/// The code generated will be:
+///
+/// ```text
+/// #[wasm_export(name = "foo", public = true, method_of = "my_module.MyStruct")]
+/// fn __thunk__foo(
+///   mut caller: Caller<'_, ScanContext>,
+///   a: i64,
+///   num_lookup_indexes: i32,
+///   struct_var: i32,
+/// ) -> i64 {
+///   let obj = lookup_object(&mut caller, num_lookup_indexes, struct_var);
+///   foo(caller.data_mut(), obj, a)
+/// }
+/// ```
 pub(crate) fn impl_module_export_macro(
     attr_args: AttributeArgs,
     mut func: ItemFn,
@@ -64,10 +94,19 @@ pub(crate) fn impl_module_export_macro(
         mut caller: Caller<'_, ScanContext>
     })?);
 
-    fn_args.extend(func.sig.inputs.into_iter().skip(1));
+    // Methods receive the object as the second argument, this argument is
+    // not passed to the thunk function, which obtains the object by other
+    // means.
+    let skip = if attr_args.method_of.is_some() { 2 } else { 1 };
+
+    fn_args.extend(func.sig.inputs.into_iter().skip(skip));
 
     let mut arg_pats: Punctuated<Expr, Comma> = Punctuated::new();
 
+    if attr_args.method_of.is_some() {
+        arg_pats.push(Expr::Verbatim(quote! {obj}));
+    }
+
     for arg in fn_args.iter().skip(1).cloned() {
         if let FnArg::Typed(pat_type) = arg {
             if let Pat::Ident(ident) = *pat_type.pat {
@@ -92,18 +131,44 @@ pub(crate) fn impl_module_export_macro(
     func.sig.ident = format_ident!("__thunk__{}", fn_name);
     func.sig.inputs = fn_args;
 
-    func.block = syn::parse2(quote! {{
-        #fn_name(caller.data_mut(), #arg_pats)
-    }})
-    .unwrap();
+    if let Some(method_of) = attr_args.method_of {
+        // The thunk function for a method receives two additional
+        // arguments that are used for locating the object.
+        func.sig.inputs.push(syn::parse2(quote! {
+            num_lookup_indexes: i32
+        })?);
 
-    // Add the thunk function to the output.
-    token_stream.extend(quote! {
-        #[wasm_export(name = #fn_name_str, public = true)]
-        #[inline(always)]
-        #[allow(non_snake_case)]
-        #func
-    });
+        func.sig.inputs.push(syn::parse2(quote! {
+            struct_var: i32
+        })?);
+
+        func.block = syn::parse2(quote! {{
+            let obj = lookup_object(&mut caller, num_lookup_indexes, struct_var);
+            #fn_name(caller.data_mut(), #arg_pats)
+        }})
+        .unwrap();
+
+        // Add the thunk function to the output.
+        token_stream.extend(quote! {
+            #[wasm_export(name = #fn_name_str, public = true, method_of = #method_of)]
+            #[inline(always)]
+            #[allow(non_snake_case)]
+            #func
+        });
+    } else {
+        func.block = syn::parse2(quote! {{
+            #fn_name(caller.data_mut(), #arg_pats)
+        }})
+        .unwrap();
+
+        // Add the thunk function to the output.
+        token_stream.extend(quote! {
+            #[wasm_export(name = #fn_name_str, public = true)]
+            #[inline(always)]
+            #[allow(non_snake_case)]
+            #func
+        });
+    }
 
     Ok(token_stream)
 }
@@ -15,11 +15,20 @@ use syn::{
 /// Parses signature of a Rust function and returns its mangled named.
 struct FuncSignatureParser<'ast> {
     arg_types: Option<VecDeque<&'ast Type>>,
+    /// Number of trailing arguments that are not included in the mangled
+    /// name. Methods receive two additional arguments that are used for
+    /// locating the object, and they are not part of the signature seen
+    /// by YARA rules.
+    hidden_args: usize,
 }
 
 impl<'ast> FuncSignatureParser<'ast> {
     fn new() -> Self {
-        Self { arg_types: None }
+        Self { arg_types: None, hidden_args: 0 }
+    }
+
+    fn with_hidden_args(hidden_args: usize) -> Self {
+        Self { arg_types: None, hidden_args }
     }
 
     #[inline(always)]
@@ -50,14 +59,22 @@ impl<'ast> FuncSignatureParser<'ast> {
     fn mangled_type(ty: &Type) -> syn::Result<Cow<'static, str>> {
         match ty {
             Type::Path(type_path) => {
-                if Self::type_ident(type_path) == "Option" {
+                let type_ident = Self::type_ident(type_path);
+                if type_ident == "Option" || type_ident == "Result" {
                     if let PathArguments::AngleBracketed(angle_bracketed) =
                         &type_path.path.segments.last().unwrap().arguments
                     {
                         if let GenericArgument::Type(ty) =
                             angle_bracketed.args.first().unwrap()
                         {
-                            Ok(Self::mangled_type(ty)?.add("u"))
+                            // The error in a `Result` is not passed to WASM,
+                            // it interrupts the execution of the WASM code,
+                            // so the type is mangled as the `Ok` type.
+                            if type_ident == "Option" {
+                                Ok(Self::mangled_type(ty)?.add("u"))
+                            } else {
+                                Self::mangled_type(ty)
+                            }
                         } else {
                             unreachable!()
                         }
@@ -117,6 +134,17 @@ impl<'ast> FuncSignatureParser<'ast> {
             ));
         }
 
+        if arg_types.len() < self.hidden_args {
+            return Err(syn::Error::new_spanned(
+                &func.sig,
+                format!(
+                    "function `{}` must have at least {} arguments besides `Caller<'_, ScanContext>`",
+                    func.sig.ident, self.hidden_args),
+            ));
+        }
+
+        arg_types.truncate(arg_types.len() - self.hidden_args);
+
         let mut mangled_name = String::from("@");
 
         for arg_type in arg_types {
@@ -142,6 +170,7 @@ pub struct WasmExportArgs {
     name: Option<String>,
     #[darling(default)]
     public: bool,
+    method_of: Option<String>,
 }
 
 /// Implementation for the `#[wasm_export]` attribute macro.
@@ -169,6 +198,8 @@ pub struct WasmExportArgs {
 /// pub(crate) static export__add: WasmExport = WasmExport {
 ///     name: "add",
 ///     mangled_name: "add@ii@i",
+///     public: false,
+///     method_of: None,
 ///     rust_module_path: "yara_x::modules::my_module",
 ///     func: &WasmExportedFn2 { target_fn: &add },
 /// };
@@ -177,6 +208,11 @@ pub struct WasmExportArgs {
 /// Notice that the generated code uses `WasmExportedFn2` because the function
 /// receives two parameters (not counting `caller: Caller<'_, ScanContext>`)
 ///
+/// When `method_of` is specified the function is a method of the given
+/// structure type (e.g: `method_of = "my_module.MyStruct"`). The last two
+/// arguments of a method are the number of lookup indexes and the variable
+/// that are used for locating the object, these arguments are not included
+/// in the mangled name.
 pub(crate) fn impl_wasm_export_macro(
     attr_args: AttributeArgs,
     func: ItemFn,
@@ -205,11 +241,20 @@ pub(crate) fn impl_wasm_export_macro(
     let exported_fn_ident = format_ident!("WasmExportedFn{}", num_args);
     let public = attr_args.public;
 
-    let mut func_sig_parser = FuncSignatureParser::new();
+    let mut func_sig_parser = if attr_args.method_of.is_some() {
+        FuncSignatureParser::with_hidden_args(2)
+    } else {
+        FuncSignatureParser::new()
+    };
 
     let mangled_fn_name =
         format!("{}{}", fn_name_str, func_sig_parser.parse(&func)?);
 
+    let method_of = match attr_args.method_of {
+        Some(method_of) => quote! { Some(#method_of) },
+        None => quote! { None },
+    };
+
     let fn_descriptor = quote! {
         #[allow(non_upper_case_globals)]
         #[distributed_slice(WASM_EXPORTS)]
@@ -217,6 +262,7 @@ pub(crate) fn impl_wasm_export_macro(
             name: #fn_name_str,
             mangled_name: #mangled_fn_name,
             public: #public,
+            method_of: #method_of,
             rust_module_path: module_path!(),
             func: &#exported_fn_ident { target_fn: &#fn_name },
         };
@@ -296,5 +342,25 @@ mod tests {
         };
 
         assert_eq!(parser.parse(&func).unwrap(), "@@is");
+
+        let func = parse_quote! {
+          fn foo(caller: Caller<'_, ScanContext>, a: i32) -> Result<(), ScanAborted> { Ok(()) }
+        };
+
+        assert_eq!(parser.parse(&func).unwrap(), "@i@");
+
+        let func = parse_quote! {
+          fn foo(caller: Caller<'_, ScanContext>) -> Result<bool, ScanAborted> { Ok(true) }
+        };
+
+        assert_eq!(parser.parse(&func).unwrap(), "@@b");
+
+        let mut parser = FuncSignatureParser::with_hidden_args(2);
+
+        let func = parse_quote! {
+          fn foo(caller: Caller<'_, ScanContext>, a: i64, b: i32, c: i32) -> Option<bool> { None }
+        };
+
+        assert_eq!(parser.parse(&func).unwrap(), "@i@bu");
     }
 }
@@ -303,6 +303,12 @@ pub(crate) fn expr_ascii_tree(expr: &Expr) -> Tree {
                     "<items: boolean_expr_set>".to_string(),
                     set.iter().map(expr_ascii_tree).collect(),
                 ),
+                OfItems::RuleSet(set) => Node(
+                    "<items: rule_set>".to_string(),
+                    vec![Leaf(
+                        set.iter().map(|s| s.identifier.to_string()).collect(),
+                    )],
+                ),
             };
 
             let mut children = vec![
@@ -427,6 +433,33 @@ pub(crate) fn expr_ascii_tree(expr: &Expr) -> Tree {
 
             Node(node_title, children)
         }
+        Expr::With(w) => {
+            let mut children = Vec::new();
+            let mut labels = Vec::new();
+
+            for (i, declaration) in w.declarations.iter().enumerate() {
+                let label = format!("<identifier{i}> = <expression{i}>");
+                children.push(Node(
+                    format!("<identifier{i}>"),
+                    vec![Leaf(vec![declaration.identifier.name.to_string()])],
+                ));
+                children.push(Node(
+                    format!("<expression{i}>"),
+                    vec![expr_ascii_tree(&declaration.expression)],
+                ));
+                labels.push(label);
+            }
+
+            children.push(Node(
+                "<condition>".to_string(),
+                vec![expr_ascii_tree(&w.condition)],
+            ));
+
+            Node(
+                format!("with {} : ( <condition> )", labels.join(", ")),
+                children,
+            )
+        }
     }
 }
 
@@ -24,13 +24,13 @@ mod span;
 
 use std::borrow::Cow;
 use std::collections::btree_map::Values;
-use std::collections::{BTreeMap, HashSet};
+use std::collections::BTreeMap;
 use std::fmt;
 use std::fmt::{Debug, Display, Formatter};
 use std::slice::Iter;
 
 use bitmask::bitmask;
-use bstr::BStr;
+use bstr::{BStr, BString, ByteSlice};
 use yara_x_macros::*;
 
 use crate::warnings::Warning;
@@ -44,6 +44,9 @@ pub struct AST<'src> {
     pub source: SourceCode<'src>,
     /// The list of imports.
     pub imports: Vec<Import>,
+    /// The list of include statements, in the order they appear in the
+    /// source code.
+    pub includes: Vec<Include>,
     /// The list of rules in the AST.
     pub rules: Vec<Rule<'src>>,
     /// Warnings generated while building this AST.
@@ -94,12 +97,23 @@ pub struct Import {
     pub module_name: String,
 }
 
+/// An include statement.
+///
+/// Include statements can appear anywhere between rule declarations. The
+/// position of the statement relative to the rules can be determined by
+/// comparing its span with the span of the rule identifiers.
+#[derive(Debug, HasSpan)]
+pub struct Include {
+    pub span: Span,
+    pub file_name: String,
+}
+
 /// A YARA rule.
 #[derive(Debug)]
 pub struct Rule<'src> {
     pub flags: RuleFlags,
     pub identifier: Ident<'src>,
-    pub tags: Option<HashSet<&'src str>>,
+    pub tags: Option<Vec<Ident<'src>>>,
     pub meta: Option<Vec<Meta<'src>>>,
     pub patterns: Option<Vec<Pattern<'src>>>,
     pub condition: Expr<'src>,
@@ -118,7 +132,13 @@ pub enum MetaValue<'src> {
     Bool(bool),
     Integer(i64),
     Float(f64),
+    /// A string that doesn't contain escape sequences. Doesn't include the
+    /// quotes.
     String(&'src str),
+    /// A string that contained escape sequences, which were already
+    /// unescaped. As escape sequences like `\xFF` can produce arbitrary
+    /// bytes, the result is not guaranteed to be valid UTF-8.
+    Bytes(BString),
 }
 
 impl<'src> Display for MetaValue<'src> {
@@ -127,7 +147,8 @@ impl<'src> Display for MetaValue<'src> {
             Self::Bool(v) => write!(f, "{}", v),
             Self::Integer(v) => write!(f, "{}", v),
             Self::Float(v) => write!(f, "{:.1}", v),
-            Self::String(v) => write!(f, "{}", v),
+            Self::String(v) => write!(f, "\"{}\"", v),
+            Self::Bytes(v) => write!(f, "\"{}\"", v.escape_bytes()),
         }
     }
 }
@@ -568,6 +589,9 @@ pub enum Expr<'src> {
 
     /// A `for <quantifier> <vars> in ...` expression. (e.g. `for all i in (1..100) : ( ... )`)
     ForIn(Box<ForIn<'src>>),
+
+    /// A `with <identifier> = <expr> : ...` expression. (e.g. `with a = 1 + 1 : ( ... )`)
+    With(Box<With<'src>>),
 }
 
 /// A pattern match expression (e.g. `$a`, `$b at 0`, `$c in (0..10)`).
@@ -851,11 +875,28 @@ pub struct ForIn<'src> {
     pub condition: Expr<'src>,
 }
 
+/// A `with` expression (e.g `with foo = 1 + 1 : (..)`)
+#[derive(Debug, HasSpan)]
+pub struct With<'src> {
+    pub span: Span,
+    pub declarations: Vec<WithDeclaration<'src>>,
+    pub condition: Expr<'src>,
+}
+
+/// Each of the declarations in a `with` expression (e.g. `foo = 1 + 1`).
+#[derive(Debug, HasSpan)]
+pub struct WithDeclaration<'src> {
+    pub span: Span,
+    pub identifier: Ident<'src>,
+    pub expression: Expr<'src>,
+}
+
 /// Items in a `of` expression.
 #[derive(Debug)]
 pub enum OfItems<'src> {
     PatternSet(PatternSet<'src>),
     BoolExprTuple(Vec<Expr<'src>>),
+    RuleSet(Vec<RuleSetItem<'src>>),
 }
 
 /// A quantifier used in `for` and `of` expressions.
@@ -915,3 +956,32 @@ impl PatternSetItem<'_> {
         }
     }
 }
+
+/// Each individual item in a set of rules.
+///
+/// In the rule set `(foo, bar*)`, `foo` and `bar*` are represented by a
+/// [`RuleSetItem`].
+#[derive(Debug, HasSpan)]
+pub struct RuleSetItem<'src> {
+    pub span: Span,
+    pub identifier: &'src str,
+}
+
+impl RuleSetItem<'_> {
+    /// Returns true if `ident` matches this [`RuleSetItem`].
+    ///
+    /// For example, identifiers `foo` and `foo_bar` both match the
+    /// [`RuleSetItem`] for `foo*`.
+    pub fn matches(&self, ident: &str) -> bool {
+        if let Some(prefix) = self.identifier.strip_suffix('*') {
+            ident.starts_with(prefix)
+        } else {
+            ident == self.identifier
+        }
+    }
+
+    /// Returns true if this item contains a wildcard.
+    pub fn is_wildcard(&self) -> bool {
+        self.identifier.ends_with('*')
+    }
+}
@@ -22,13 +22,17 @@ let root = cst.next().unwrap();
 assert_eq!(root.as_rule(), GrammarRule::source_file);
 
 // With the `into_inner` method we obtain a new CST with the children of
-// the top-level node. At this level there are three possible grammar
-// rules, `import_stmt` and `rule_decl` and `EOI` (end-of-input).
+// the top-level node. At this level there are four possible grammar
+// rules, `import_stmt`, `include_stmt`, `rule_decl` and `EOI`
+// (end-of-input).
 for child in root.into_inner() {
     match child.as_rule() {
         GrammarRule::import_stmt => {
             // import statement
         },
+        GrammarRule::include_stmt => {
+            // include statement
+        },
         GrammarRule::rule_decl => {
             // rule declaration
         },
@@ -284,8 +284,9 @@ fn check_pattern_modifiers(
 pub(crate) fn ast_from_cst<'src>(
     ctx: &mut Context<'src, '_>,
     cst: CST<'src>,
-) -> Result<(Vec<Import>, Vec<Rule<'src>>), Error> {
+) -> Result<(Vec<Import>, Vec<Include>, Vec<Rule<'src>>), Error> {
     let mut imports: Vec<Import> = Vec::new();
+    let mut includes: Vec<Include> = Vec::new();
     let mut rules: Vec<Rule> = Vec::new();
 
     for node in cst {
@@ -319,7 +320,19 @@ pub(crate) fn ast_from_cst<'src>(
                     module_name: module_name.to_string(),
                 });
             }
-            // .. or rule declarations.
+            // ... or include statements ...
+            GrammarRule::include_stmt => {
+                let span = ctx.span(&node);
+                let mut children = node.into_inner();
+                expect!(children.next().unwrap(), GrammarRule::k_INCLUDE);
+
+                let file_name =
+                    utf8_string_lit_from_cst(ctx, children.next().unwrap())?;
+
+                includes
+                    .push(Include { span, file_name: file_name.to_string() });
+            }
+            // ... or rule declarations.
             GrammarRule::rule_decl => {
                 rules.push(rule_from_cst(ctx, node)?);
             }
@@ -330,7 +343,7 @@ pub(crate) fn ast_from_cst<'src>(
             rule => unreachable!("unexpected grammar rule: `{:?}`", rule),
         }
     }
-    Ok((imports, rules))
+    Ok((imports, includes, rules))
 }
 
 /// Given a CST node corresponding to the grammar rule` rule_decl`, returns a
@@ -386,22 +399,25 @@ fn rule_from_cst<'src>(
     // └─ ident "baz"
     //
     let tags = if let GrammarRule::rule_tags = node.as_rule() {
-        let mut tags = HashSet::new();
+        let mut tags = Vec::new();
+        let mut seen_tags = HashSet::new();
 
         // Iterate over all `ident`s that are children of `rule_tags`,
-        // ignoring other grammar rules like `COLON`.
+        // ignoring other grammar rules like `COLON`. Tags are kept in
+        // the same order they appear in the source code.
         let idents = node
             .into_inner()
             .filter(|item| item.as_rule() == GrammarRule::ident);
 
         for ident in idents {
-            if !tags.insert(ident.as_str()) {
+            if !seen_tags.insert(ident.as_str()) {
                 return Err(Error::from(ErrorInfo::duplicate_tag(
                     ctx.report_builder,
                     ident.as_str().to_string(),
                     ctx.span(&ident),
                 )));
             }
+            tags.push(ident_from_cst(ctx, ident));
         }
 
         node = children.next().unwrap();
@@ -896,7 +912,19 @@ fn meta_from_cst<'src>(
             GrammarRule::float_lit => {
                 MetaValue::Float(float_lit_from_cst(ctx, value_node)?)
             }
-            GrammarRule::string_lit => MetaValue::String(value_node.as_str()),
+            GrammarRule::string_lit => {
+                match string_lit_from_cst(ctx, value_node, true)? {
+                    // A string borrowed directly from the source code doesn't
+                    // contain escape sequences, and it's guaranteed to be
+                    // valid UTF-8.
+                    Cow::Borrowed(s) => {
+                        MetaValue::String(unsafe { s.to_str_unchecked() })
+                    }
+                    // An owned string contained some escape sequence, it may
+                    // not be valid UTF-8.
+                    Cow::Owned(s) => MetaValue::Bytes(s),
+                }
+            }
             rule => unreachable!("{:?}", rule),
         };
 
@@ -1121,6 +1149,9 @@ fn boolean_term_from_cst<'src>(
         GrammarRule::for_expr => {
             for_expr_from_cst(ctx, children.next().unwrap())?
         }
+        GrammarRule::with_expr => {
+            with_expr_from_cst(ctx, children.next().unwrap())?
+        }
         _ => unreachable!(),
     };
 
@@ -1470,6 +1501,9 @@ fn of_expr_from_cst<'src>(
         GrammarRule::boolean_expr_tuple => {
             OfItems::BoolExprTuple(boolean_expr_tuple_from_cst(ctx, node)?)
         }
+        GrammarRule::rule_ident_tuple => {
+            OfItems::RuleSet(rule_ident_tuple(ctx, node)?)
+        }
         rule => unreachable!("{:?}", rule),
     };
 
@@ -1574,6 +1608,57 @@ fn for_expr_from_cst<'src>(
     Ok(expr)
 }
 
+/// From a CST node corresponding to the grammar rule `with_expr`, returns
+/// an [`Expr`] describing the `with` statement.
+fn with_expr_from_cst<'src>(
+    ctx: &mut Context<'src, '_>,
+    with_expr: CSTNode<'src>,
+) -> Result<Expr<'src>, Error> {
+    expect!(with_expr, GrammarRule::with_expr);
+
+    let span = ctx.span(&with_expr);
+    let mut children = with_expr.into_inner();
+
+    // The statement starts with the `with` keyword...
+    expect!(children.next().unwrap(), GrammarRule::k_WITH);
+
+    let mut declarations = Vec::new();
+
+    // ...followed by one or more declarations separated by commas, as in..
+    //
+    //   with a = <expr>, b = <expr> : ...
+    //
+    for node in children.by_ref() {
+        match node.as_rule() {
+            GrammarRule::with_declaration => {
+                let span = ctx.span(&node);
+                let mut children = node.into_inner();
+                let identifier = ident_from_cst(ctx, children.next().unwrap());
+                expect!(children.next().unwrap(), GrammarRule::EQUAL);
+                let expression = expr_from_cst(ctx, children.next().unwrap())?;
+                declarations.push(WithDeclaration {
+                    span,
+                    identifier,
+                    expression,
+                });
+            }
+            GrammarRule::COMMA => {}
+            GrammarRule::COLON => {
+                break;
+            }
+            rule => unreachable!("{:?}", rule),
+        }
+    }
+
+    expect!(children.next().unwrap(), GrammarRule::LPAREN);
+
+    let condition = boolean_expr_from_cst(ctx, children.next().unwrap())?;
+
+    expect!(children.next().unwrap(), GrammarRule::RPAREN);
+
+    Ok(Expr::With(Box::new(With { span, declarations, condition })))
+}
+
 fn anchor_from_cst<'src>(
     ctx: &mut Context<'src, '_>,
     mut iter: impl Iterator<Item = CSTNode<'src>>,
@@ -1689,6 +1774,44 @@ fn pattern_ident_tuple<'src>(
     Ok(result)
 }
 
+/// From a CST node corresponding to the grammar rule `rule_ident_tuple`,
+/// returns a vector of [`RuleSetItem`].
+fn rule_ident_tuple<'src>(
+    ctx: &mut Context<'src, '_>,
+    rule_ident_tuple: CSTNode<'src>,
+) -> Result<Vec<RuleSetItem<'src>>, Error> {
+    expect!(rule_ident_tuple, GrammarRule::rule_ident_tuple);
+
+    let mut children = rule_ident_tuple.into_inner();
+
+    // The tuple should start with an opening parenthesis.
+    expect!(children.next().unwrap(), GrammarRule::LPAREN);
+
+    let mut result = Vec::new();
+
+    // For all CST nodes after the opening parenthesis...
+    for node in children.by_ref() {
+        match node.as_rule() {
+            // ... if the node is rule_ident_wildcarded
+            GrammarRule::rule_ident_wildcarded => {
+                result.push(RuleSetItem {
+                    span: ctx.span(&node),
+                    identifier: node.as_str(),
+                });
+            }
+            // ... if the node is a comma or a closing parenthesis
+            // ignore it and continue.
+            GrammarRule::COMMA | GrammarRule::RPAREN => {}
+            rule => unreachable!("{:?}", rule),
+        };
+    }
+
+    // Make sure that there are no more nodes.
+    assert!(children.next().is_none());
+
+    Ok(result)
+}
+
 /// From a CST node corresponding to the grammar rule `boolean_expr_tuple`,
 /// returns a vector of [`Expr`].
 fn boolean_expr_tuple_from_cst<'src>(
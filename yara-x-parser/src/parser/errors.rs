@@ -313,6 +313,7 @@ impl ErrorInfo {
             GrammarRule::k_GLOBAL => "`global`",
             GrammarRule::k_IMPORT => "`import`",
             GrammarRule::k_IN => "`in`",
+            GrammarRule::k_INCLUDE => "`include`",
             GrammarRule::k_META => "`meta`",
             GrammarRule::k_NOCASE => "`nocase`",
             GrammarRule::k_NOT => "`not`",
@@ -323,6 +324,7 @@ impl ErrorInfo {
             GrammarRule::k_THEM => "`them`",
             GrammarRule::k_TRUE => "`true`",
             GrammarRule::k_WIDE => "`wide`",
+            GrammarRule::k_WITH => "`with`",
             GrammarRule::k_XOR => "`xor`",
 
             GrammarRule::boolean_expr | GrammarRule::boolean_term => {
@@ -69,6 +69,11 @@ impl<'src> SourceCode<'src> {
         }
     }
 
+    /// Returns the origin of the source code, if any.
+    pub fn origin(&self) -> Option<&str> {
+        self.origin.as_deref()
+    }
+
     /// Make sure that the source code is valid UTF-8. If that's the case
     /// sets the `valid` field, if not, returns an error.
     fn validate_utf8(&mut self) -> Result<(), bstr::Utf8Error> {
@@ -192,9 +197,16 @@ impl<'a> Parser<'a> {
 
         let mut ctx = Context::new(report_builder);
 
-        let (imports, rules) = ast_from_cst(&mut ctx, root.into_inner())?;
+        let (imports, includes, rules) =
+            ast_from_cst(&mut ctx, root.into_inner())?;
 
-        Ok(AST { source: src, imports, rules, warnings: ctx.warnings })
+        Ok(AST {
+            source: src,
+            imports,
+            includes,
+            rules,
+            warnings: ctx.warnings,
+        })
     }
 
     /// Build the Concrete Syntax Tree (CST) for a YARA source.
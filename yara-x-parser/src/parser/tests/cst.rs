@@ -82,6 +82,34 @@ rule test : foo bar baz {
     │  └─ boolean_term
     │     └─ k_TRUE "true"
     └─ RBRACE "}"
+"#,
+        ),
+        ////////////////////////////////////////////////////////////
+        (
+            line!(),
+            GrammarRule::source_file,
+            r#"
+import "pe"
+include "common.yar"
+rule test { condition: true }"#,
+            r#"
+ source_file
+ ├─ import_stmt
+ │  ├─ k_IMPORT "import"
+ │  └─ string_lit ""pe""
+ ├─ include_stmt
+ │  ├─ k_INCLUDE "include"
+ │  └─ string_lit ""common.yar""
+ └─ rule_decl
+    ├─ k_RULE "rule"
+    ├─ ident "test"
+    ├─ LBRACE "{"
+    ├─ k_CONDITION "condition"
+    ├─ COLON ":"
+    ├─ boolean_expr
+    │  └─ boolean_term
+    │     └─ k_TRUE "true"
+    └─ RBRACE "}"
 "#,
         ),
         /////////////////////////////////////////////////////////////
@@ -53,10 +53,30 @@ impl Compiler {
     /// Adds a YARA source code to be compiled.
     ///
     /// This function can be used multiple times before calling [`Compiler::build`].
-    fn add_source(&mut self, src: &str) -> PyResult<()> {
-        self.inner
-            .add_source(src)
-            .map_err(|err| PySyntaxError::new_err(err.to_string()))?;
+    ///
+    /// If `recover` is true, the raised `SyntaxError` describes all the errors
+    /// in the source code, not only the first one, and rules that don't have
+    /// errors are compiled anyway, they will be part of the [`Rules`] returned
+    /// by [`Compiler::build`].
+    #[pyo3(signature = (src, recover = false))]
+    fn add_source(&mut self, src: &str, recover: bool) -> PyResult<()> {
+        if !recover {
+            self.inner
+                .add_source(src)
+                .map_err(|err| PySyntaxError::new_err(err.to_string()))?;
+            return Ok(());
+        }
+
+        self.inner.add_source_with_recovery(src).map_err(|errors| {
+            PySyntaxError::new_err(
+                errors
+                    .iter()
+                    .map(|err| err.to_string())
+                    .collect::<Vec<String>>()
+                    .join("\n"),
+            )
+        })?;
+
         Ok(())
     }
 
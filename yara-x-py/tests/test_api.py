@@ -7,6 +7,27 @@ def test_syntax_error():
     compiler.add_source('bad rule')
 
 
+def test_multiple_errors():
+  src = ('rule foo {condition: true} '
+         'rule bar {condition: unknown_1} '
+         'rule baz {condition: unknown_2}')
+
+  compiler = yara_x.Compiler()
+  with pytest.raises(SyntaxError) as e:
+    compiler.add_source(src)
+  assert 'unknown identifier `unknown_1`' in str(e.value)
+  assert 'unknown identifier `unknown_2`' not in str(e.value)
+
+  compiler = yara_x.Compiler()
+  with pytest.raises(SyntaxError) as e:
+    compiler.add_source(src, recover=True)
+  assert 'unknown identifier `unknown_1`' in str(e.value)
+  assert 'unknown identifier `unknown_2`' in str(e.value)
+  scanner = yara_x.Scanner(compiler.build())
+  matches = scanner.scan(b'')
+  assert len(matches) == 1
+
+
 def test_bad_variable_type():
   compiler = yara_x.Compiler()
   with pytest.raises(TypeError):
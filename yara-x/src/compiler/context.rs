@@ -33,6 +33,10 @@ pub(in crate::compiler) struct CompileContext<'a, 'src, 'sym> {
     pub current_rule_patterns:
         &'a mut Vec<(PatternId, ir::PatternInRule<'src>)>,
 
+    /// Rules used in the condition of the current rule. The same rule can
+    /// appear multiple times.
+    pub current_rule_deps: &'a mut Vec<RuleId>,
+
     /// Warnings generated during the compilation.
     pub warnings: &'a mut Vec<Warning>,
 
@@ -20,7 +20,7 @@ use yara_x_parser::ast::{RuleFlag, RuleFlags};
 
 use crate::compiler::context::VarStack;
 use crate::compiler::ir::{
-    Expr, ForIn, ForOf, Iterable, MatchAnchor, Of, OfItems, Quantifier,
+    Expr, ForIn, ForOf, Iterable, MatchAnchor, Of, OfItems, Quantifier, With,
 };
 use crate::compiler::{LiteralId, RegexpId, RuleId, Var, VarStackFrame};
 use crate::string_pool::{BStringPool, StringPool};
@@ -31,8 +31,7 @@ use crate::wasm;
 use crate::wasm::builder::WasmModuleBuilder;
 use crate::wasm::string::RuntimeString;
 use crate::wasm::{
-    WasmSymbols, LOOKUP_INDEXES_END, LOOKUP_INDEXES_START,
-    MATCHING_RULES_BITMAP_BASE, VARS_STACK_START,
+    WasmSymbols, LOOKUP_INDEXES_END, LOOKUP_INDEXES_START, VARS_STACK_START,
 };
 
 /// This macro emits the code for the left and right operands of some
@@ -237,57 +236,92 @@ pub(super) fn emit_rule_condition(
         builder.new_rule()
     };
 
-    // When the "logging" feature is enabled, print a log before the starting
-    // evaluating the rule's condition. In case of error during the evaluation
-    // this helps in determining the rule causing the issue.
-    #[cfg(feature = "logging")]
-    {
-        instr.i32_const(rule_id.0);
-        instr.call(
-            ctx.function_id(wasm::export__log_rule_eval_start.mangled_name),
-        );
-    }
-
-    // Emit WASM code for the rule's condition.
-    catch_undef(ctx, &mut instr, |ctx, instr| {
-        emit_bool_expr(ctx, instr, condition);
-    });
-
-    // Check if the result from the condition is zero (false).
-    instr.unop(UnaryOp::I32Eqz);
+    // Rules disabled by the rule filter are not evaluated at all, their
+    // conditions are skipped.
+    emit_check_for_rule_enabled(ctx, &mut instr, rule_id);
     instr.if_else(
         None,
         |then_| {
-            // The condition is false. For normal rules we don't do anything,
-            // but for global rules we must call `global_rule_no_match` and
-            // return 1.
-            //
-            // By returning 1 the function that contains the logic for this
-            // rule exits immediately, preventing any other rule (both global
-            // and non-global) in the same namespace is executed, and therefore
-            // they will remain false.
-            //
-            // This guarantees that any global rule that returns false, forces
-            // the non-global rules in the same namespace to be false. There
-            // may be some global rules that matched before, though. The
-            // purpose of `global_rule_no_match` is reverting those previous
-            // matches.
-            if rule_flags.contains(RuleFlag::Global) {
-                // Call `global_rule_no_match`.
+            // When the "logging" feature is enabled, print a log before the
+            // starting evaluating the rule's condition. In case of error
+            // during the evaluation this helps in determining the rule causing
+            // the issue.
+            #[cfg(feature = "logging")]
+            {
                 then_.i32_const(rule_id.0);
                 then_.call(ctx.function_id(
-                    wasm::export__global_rule_no_match.mangled_name,
+                    wasm::export__log_rule_eval_start.mangled_name,
                 ));
-                // Return 1.
-                then_.i32_const(1);
-                then_.return_();
             }
+
+            // When the "rules-profiling" feature is enabled, notify when
+            // the evaluation of the rule's condition starts and ends, for
+            // measuring the time spent in it.
+            #[cfg(feature = "rules-profiling")]
+            {
+                then_.i32_const(rule_id.0);
+                then_.call(
+                    ctx.function_id(
+                        wasm::export__rule_eval_start.mangled_name,
+                    ),
+                );
+            }
+
+            // Emit WASM code for the rule's condition.
+            catch_undef(ctx, then_, |ctx, instr| {
+                emit_bool_expr(ctx, instr, condition);
+            });
+
+            // The result of the condition remains in the stack, the call to
+            // `rule_eval_end` doesn't alter it.
+            #[cfg(feature = "rules-profiling")]
+            {
+                then_.i32_const(rule_id.0);
+                then_.call(
+                    ctx.function_id(wasm::export__rule_eval_end.mangled_name),
+                );
+            }
+
+            // Check if the result from the condition is zero (false).
+            then_.unop(UnaryOp::I32Eqz);
+            then_.if_else(
+                None,
+                |then_| {
+                    // The condition is false. For normal rules we don't do
+                    // anything, but for global rules we must call
+                    // `global_rule_no_match` and return 1.
+                    //
+                    // By returning 1 the function that contains the logic for
+                    // this rule exits immediately, preventing any other rule
+                    // (both global and non-global) in the same namespace is
+                    // executed, and therefore they will remain false.
+                    //
+                    // This guarantees that any global rule that returns false,
+                    // forces the non-global rules in the same namespace to be
+                    // false. There may be some global rules that matched
+                    // before, though. The purpose of `global_rule_no_match` is
+                    // reverting those previous matches.
+                    if rule_flags.contains(RuleFlag::Global) {
+                        // Call `global_rule_no_match`.
+                        then_.i32_const(rule_id.0);
+                        then_.call(ctx.function_id(
+                            wasm::export__global_rule_no_match.mangled_name,
+                        ));
+                        // Return 1.
+                        then_.i32_const(1);
+                        then_.return_();
+                    }
+                },
+                |else_| {
+                    // The condition is true, call `rule_match`.
+                    else_.i32_const(rule_id.0);
+                    else_.call(
+                        ctx.function_id(wasm::export__rule_match.mangled_name),
+                    );
+                },
+            );
         },
-        |else_| {
-            // The condition is true, call `rule_match`.
-            else_.i32_const(rule_id.0);
-            else_.call(ctx.function_id(wasm::export__rule_match.mangled_name));
-        },
+        |_| {},
     );
 }
 
@@ -327,6 +361,10 @@ fn emit_expr(
             instr.global_get(ctx.wasm_symbols.filesize);
         }
 
+        Expr::Undefined => {
+            throw_undef(ctx, instr);
+        }
+
         Expr::Entrypoint { .. } => {
             // TODO
             // todo!()
@@ -381,6 +419,18 @@ fn emit_expr(
                             // don't need to emit a call for retrieving a value.
                         }
                         TypeValue::Func(func) => {
+                            let signature = &func.signatures()
+                                [ctx.current_signature.unwrap()];
+                            // Methods receive as their last arguments the
+                            // lookup indexes and variable that identify the
+                            // structure they were called on. The index of
+                            // the method itself must be removed from the
+                            // lookup stack, as it is not part of the path
+                            // to that structure.
+                            if signature.mangled_name.method_of().is_some() {
+                                ctx.lookup_stack.pop_back();
+                                emit_lookup_common(ctx, instr);
+                            }
                             emit_func_call(ctx, instr, func);
                             ctx.lookup_stack.clear();
                         }
@@ -398,6 +448,11 @@ fn emit_expr(
                         }
                     }
                 }
+                SymbolKind::IgnoredModule => {
+                    // Expressions that depend on ignored modules are
+                    // replaced with `Expr::Undefined` while building the IR.
+                    unreachable!();
+                }
             }
         }
 
@@ -820,6 +875,10 @@ fn emit_expr(
             }
         },
 
+        Expr::With(with) => {
+            emit_with(ctx, instr, with);
+        }
+
         Expr::FuncCall(fn_call) => {
             // Emit the arguments first.
             for expr in fn_call.args.iter_mut() {
@@ -1076,24 +1135,24 @@ fn emit_check_for_rule_match(
     instr: &mut InstrSeqBuilder,
     rule_id: RuleId,
 ) {
-    // Starting at MATCHING_RULES_BITMAP_BASE there's a
-    // bitmap where the N-th bit corresponds to the rule
+    // Starting at the offset indicated by `matching_rules_bitmap_base`
+    // there's a bitmap where the N-th bit corresponds to the rule
     // with RuleId = N. If the bit is 1 the rule matched.
     //
     // Notice that the bits in a byte are numbered starting
     // from the least significant bit (LSB). So, the bit
     // corresponding to RuleId = 0, is the LSB of the byte
-    // at MATCHING_RULES_BITMAP_BASE.
+    // at `matching_rules_bitmap_base`.
     //
     // The first thing is loading the byte where the bit
     // resides..
-    instr.i32_const(rule_id.0 / 8);
+    instr.global_get(ctx.wasm_symbols.matching_rules_bitmap_base);
     instr.load(
         ctx.wasm_symbols.main_memory,
         LoadKind::I32_8 { kind: ZeroExtend },
         MemArg {
             align: size_of::<i8>() as u32,
-            offset: MATCHING_RULES_BITMAP_BASE as u32,
+            offset: (rule_id.0 / 8) as u32,
         },
     );
 
@@ -1108,6 +1167,38 @@ fn emit_check_for_rule_match(
     instr.binop(BinaryOp::I32ShrU);
 }
 
+/// Emits the code that checks if a rule is enabled by the rule filter.
+///
+/// The emitted code leaves an I32 at the top of the stack, which is zero
+/// if the rule is disabled, and non-zero if it is enabled.
+fn emit_check_for_rule_enabled(
+    ctx: &mut EmitContext,
+    instr: &mut InstrSeqBuilder,
+    rule_id: RuleId,
+) {
+    // Starting at the offset indicated by `enabled_rules_bitmap_base`
+    // there's a bitmap where the N-th bit corresponds to the rule with
+    // RuleId = N. If the bit is 1 the rule is enabled. The bitmap is
+    // filled by the scanner, see `ScanContext::set_filtered_rules`.
+    //
+    // The first thing is loading the byte where the bit resides.
+    instr.global_get(ctx.wasm_symbols.enabled_rules_bitmap_base);
+    instr.load(
+        ctx.wasm_symbols.main_memory,
+        LoadKind::I32_8 { kind: ZeroExtend },
+        MemArg {
+            align: size_of::<i8>() as u32,
+            offset: (rule_id.0 / 8) as u32,
+        },
+    );
+
+    // Compute byte & (1 << (rule_id % 8)), which clears all bits except
+    // the one we are interested in. The result is used as a boolean, so
+    // it's not necessary to shift the bit to the LSB.
+    instr.i32_const(1 << (rule_id.0 % 8));
+    instr.binop(BinaryOp::I32And);
+}
+
 /// Emits the code that checks if a pattern (a.k.a string) has matched.
 ///
 /// This function assumes that the PatternId is at the top of the stack as a
@@ -1514,9 +1605,10 @@ fn emit_for_of_pattern_set(
             // Get the i-th pattern ID, and store it in `next_pattern_id`.
             set_var(ctx, instr, next_pattern_id, |ctx, instr| {
                 load_var(ctx, instr, i);
-                emit_switch(ctx, I64, instr, |_, instr| {
+                emit_switch(ctx, I64, instr, |ctx, instr| {
                     if let Some(pattern_id) = pattern_ids.next() {
-                        instr.i64_const((*pattern_id).into());
+                        instr.i32_const((*pattern_id).into());
+                        instr.unop(UnaryOp::I64ExtendUI32);
                         return true;
                     }
                     false
@@ -1830,6 +1922,35 @@ fn emit_for_in_expr_tuple(
     );
 }
 
+/// Emits the code for a `with` statement.
+///
+/// Each of the expressions in the statement is evaluated once, and its
+/// result is stored in the corresponding variable before evaluating the
+/// condition.
+fn emit_with(
+    ctx: &mut EmitContext,
+    instr: &mut InstrSeqBuilder,
+    with: &mut With,
+) {
+    for (var, expr) in with.declarations.iter_mut() {
+        match var.ty {
+            Type::Struct | Type::Array | Type::Map => {
+                // Emit the expression and store the resulting object in the
+                // host-side variable.
+                emit_expr(ctx, instr, expr);
+                emit_lookup_value(ctx, instr, *var);
+            }
+            _ => {
+                set_var(ctx, instr, *var, |ctx, instr| {
+                    emit_expr(ctx, instr, expr);
+                });
+            }
+        }
+    }
+
+    emit_bool_expr(ctx, instr, &mut with.condition);
+}
+
 /// Emits a `for` loop.
 ///
 /// This function allows creating different types of `for` loops by receiving
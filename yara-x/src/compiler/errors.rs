@@ -18,10 +18,26 @@ pub enum SerializationError {
     #[error("invalid YARA-X compiled rules file")]
     InvalidEncoding(#[from] bincode::Error),
 
+    #[error("unsupported version of YARA-X compiled rules file: {0}")]
+    UnsupportedVersion(u32),
+
     #[error(transparent)]
     IoError(#[from] io::Error),
 }
 
+/// Errors returned while linking compiled rules with [`crate::Rules::link`].
+#[derive(Error, Debug)]
+pub enum LinkError {
+    #[error("duplicate rule `{rule}` in namespace `{namespace}`")]
+    DuplicateRule { namespace: String, rule: String },
+
+    #[error("namespace `{0}` contains global rules and can't be shared")]
+    ConflictingNamespace(String),
+
+    #[error("global variable or module `{0}` has different types")]
+    ConflictingGlobal(String),
+}
+
 /// Error returned by [`crate::Compiler::emit_wasm_file`].
 #[derive(Error, Debug)]
 #[error(transparent)]
@@ -142,6 +158,15 @@ pub enum CompileErrorInfo {
     #[label("module `{identifier}` not found", span)]
     UnknownModule { detailed_report: String, identifier: String, span: Span },
 
+    #[error("module `{identifier}` can't be used")]
+    #[label("{error_message}", span)]
+    BannedModule {
+        detailed_report: String,
+        identifier: String,
+        error_message: String,
+        span: Span,
+    },
+
     #[error("invalid range")]
     #[label("higher bound must be greater or equal than lower bound", span)]
     InvalidRange { detailed_report: String, span: Span },
@@ -218,4 +243,22 @@ pub enum CompileErrorInfo {
         span: Span,
         note: Option<String>,
     },
+
+    #[error("no matching rules")]
+    #[label("there's no rule in this set", span)]
+    #[note(note)]
+    EmptyRuleSet { detailed_report: String, span: Span, note: Option<String> },
+
+    #[error("can't include `{file_name}`")]
+    #[label("{error}", span)]
+    IncludeError {
+        detailed_report: String,
+        file_name: String,
+        error: String,
+        span: Span,
+    },
+
+    #[error("circular include of `{file_name}`")]
+    #[label("`{file_name}` is already being included", span)]
+    CircularInclude { detailed_report: String, file_name: String, span: Span },
 }
@@ -14,10 +14,11 @@ use crate::compiler::ir::hex2hir::hex_pattern_hir_from_ast;
 use crate::compiler::ir::{
     Expr, ForIn, ForOf, FuncCall, Iterable, LiteralPattern, Lookup,
     MatchAnchor, Of, OfItems, Pattern, PatternFlagSet, PatternFlags,
-    PatternInRule, Quantifier, Range, RegexpPattern,
+    PatternInRule, Quantifier, Range, RegexpPattern, With,
 };
 use crate::compiler::{
-    CompileContext, CompileError, CompileErrorInfo, PatternId,
+    CompileContext, CompileError, CompileErrorInfo, PatternId, RuleId, Var,
+    VarStackFrame,
 };
 use crate::modules::BUILTIN_MODULES;
 use crate::re;
@@ -25,6 +26,29 @@ use crate::re::parser::Error;
 use crate::symbols::{Symbol, SymbolKind, SymbolLookup, SymbolTable};
 use crate::types::{Map, Regexp, Type, TypeValue, Value};
 
+/// Error returned while converting an expression from AST to IR.
+pub(in crate::compiler) enum IrError {
+    /// An actual error that must be reported to the user.
+    CompileError(CompileError),
+    /// The expression depends on a module that was ignored with
+    /// [`crate::Compiler::ignore_module`]. This is not reported to the user,
+    /// instead, the closest boolean expression that contains the failing
+    /// expression is replaced with [`Expr::Undefined`].
+    IgnoredModule,
+}
+
+impl From<CompileError> for IrError {
+    fn from(err: CompileError) -> Self {
+        Self::CompileError(err)
+    }
+}
+
+impl From<CompileErrorInfo> for IrError {
+    fn from(info: CompileErrorInfo) -> Self {
+        Self::CompileError(CompileError::from(info))
+    }
+}
+
 pub(in crate::compiler) fn patterns_from_ast<'src>(
     report_builder: &ReportBuilder,
     patterns: Option<&Vec<ast::Pattern<'src>>>,
@@ -203,7 +227,7 @@ pub(in crate::compiler) fn regexp_pattern_from_ast<'src>(
 pub(in crate::compiler) fn expr_from_ast(
     ctx: &mut CompileContext,
     expr: &ast::Expr,
-) -> Result<Expr, CompileError> {
+) -> Result<Expr, IrError> {
     match expr {
         ast::Expr::Entrypoint { .. } => Ok(Expr::Entrypoint),
         ast::Expr::Filesize { .. } => Ok(Expr::Filesize),
@@ -287,6 +311,7 @@ pub(in crate::compiler) fn expr_from_ast(
         ast::Expr::Of(of) => of_expr_from_ast(ctx, of),
         ast::Expr::ForOf(for_of) => for_of_expr_from_ast(ctx, for_of),
         ast::Expr::ForIn(for_in) => for_in_expr_from_ast(ctx, for_in),
+        ast::Expr::With(with) => with_expr_from_ast(ctx, with),
         ast::Expr::FuncCall(fn_call) => func_call_from_ast(ctx, fn_call),
 
         ast::Expr::FieldAccess(expr) => {
@@ -336,7 +361,7 @@ pub(in crate::compiler) fn expr_from_ast(
             };
 
             if symbol.is_none() {
-                return Err(CompileError::from(
+                return Err(IrError::from(
                     CompileErrorInfo::unknown_identifier(
                         ctx.report_builder,
                         ident.name.to_string(),
@@ -360,32 +385,15 @@ pub(in crate::compiler) fn expr_from_ast(
 
             let symbol = symbol.unwrap();
 
-            // Return error if a global rule depends on a non-global rule. This
-            // is an error because global rules are evaluated before non-global
-            // rules, even if the global rule appears after the non-global one
-            // in the source code. This means that by the time the global rule
-            // is being evaluated we can't know if the non-global rule matched
-            // or not.
-            // A global rule can depend on another global rule. And non-global
-            // rules can depend both on global rules and non-global ones.
+            // Expressions that depend on an ignored module can't be
+            // compiled. This is handled by the closest boolean expression,
+            // which evaluates as undefined instead.
+            if let SymbolKind::IgnoredModule = symbol.kind() {
+                return Err(IrError::IgnoredModule);
+            }
+
             if let SymbolKind::Rule(rule_id) = symbol.kind() {
-                let current_rule = ctx.get_current_rule();
-                let used_rule = ctx.get_rule(*rule_id);
-                if current_rule.is_global && !used_rule.is_global {
-                    return Err(CompileError::from(
-                        CompileErrorInfo::wrong_rule_dependency(
-                            ctx.report_builder,
-                            ctx.ident_pool
-                                .get(current_rule.ident_id)
-                                .unwrap()
-                                .to_string(),
-                            ident.name.to_string(),
-                            current_rule.ident_span,
-                            used_rule.ident_span,
-                            ident.span,
-                        ),
-                    ));
-                }
+                add_rule_dependency(ctx, *rule_id, ident.span)?;
             }
 
             let type_value = symbol.type_value();
@@ -578,7 +586,7 @@ pub(in crate::compiler) fn expr_from_ast(
                     // The type of the key/index expression should correspond
                     // with the type of the map's keys.
                     if key_ty != ty {
-                        return Err(CompileError::from(
+                        return Err(IrError::from(
                             CompileErrorInfo::wrong_type(
                                 ctx.report_builder,
                                 format!("`{}`", key_ty),
@@ -595,7 +603,7 @@ pub(in crate::compiler) fn expr_from_ast(
                     })))
                 }
                 type_value => {
-                    Err(CompileError::from(CompileErrorInfo::wrong_type(
+                    Err(IrError::from(CompileErrorInfo::wrong_type(
                         ctx.report_builder,
                         format!("`{}` or `{}`", Type::Array, Type::Map),
                         type_value.ty().to_string(),
@@ -611,15 +619,32 @@ pub(in crate::compiler) fn bool_expr_from_ast(
     ctx: &mut CompileContext,
     ast: &ast::Expr,
 ) -> Result<Expr, CompileError> {
-    let expr = expr_from_ast(ctx, ast)?;
+    let expr = bool_operand_from_ast(ctx, ast)?;
     warn_if_not_bool(ctx, expr.ty(), ast.span());
     Ok(expr)
 }
 
+/// Similar to [`expr_from_ast`], but used for expressions that are going to
+/// be evaluated as booleans, like the operands of `and` and `or`.
+///
+/// If the expression depends on a module that was ignored with
+/// [`crate::Compiler::ignore_module`], the result is an expression that
+/// always evaluates as undefined, instead of an error.
+fn bool_operand_from_ast(
+    ctx: &mut CompileContext,
+    ast: &ast::Expr,
+) -> Result<Expr, CompileError> {
+    match expr_from_ast(ctx, ast) {
+        Ok(expr) => Ok(expr),
+        Err(IrError::IgnoredModule) => Ok(Expr::Undefined),
+        Err(IrError::CompileError(err)) => Err(err),
+    }
+}
+
 fn of_expr_from_ast(
     ctx: &mut CompileContext,
     of: &ast::Of,
-) -> Result<Expr, CompileError> {
+) -> Result<Expr, IrError> {
     let quantifier = quantifier_from_ast(ctx, &of.quantifier)?;
     // Create new stack frame with 5 slots:
     //   1 slot for the loop variable, a bool in this case.
@@ -632,11 +657,11 @@ fn of_expr_from_ast(
             let tuple = tuple
                 .iter()
                 .map(|e| {
-                    let expr = expr_from_ast(ctx, e)?;
+                    let expr = bool_operand_from_ast(ctx, e)?;
                     check_type(ctx, expr.ty(), e.span(), &[Type::Bool])?;
                     Ok(expr)
                 })
-                .collect::<Result<Vec<Expr>, CompileError>>()?;
+                .collect::<Result<Vec<Expr>, IrError>>()?;
 
             let num_items = tuple.len();
             (OfItems::BoolExprTuple(tuple), num_items)
@@ -647,6 +672,12 @@ fn of_expr_from_ast(
             let num_patterns = pattern_ids.len();
             (OfItems::PatternSet(pattern_ids), num_patterns)
         }
+        // `x of (foo*, bar)`
+        ast::OfItems::RuleSet(rule_set) => {
+            let tuple = rule_set_from_ast(ctx, rule_set)?;
+            let num_items = tuple.len();
+            (OfItems::BoolExprTuple(tuple), num_items)
+        }
     };
 
     // If the quantifier expression is greater than the number of items,
@@ -724,7 +755,7 @@ fn of_expr_from_ast(
 fn for_of_expr_from_ast(
     ctx: &mut CompileContext,
     for_of: &ast::ForOf,
-) -> Result<Expr, CompileError> {
+) -> Result<Expr, IrError> {
     let quantifier = quantifier_from_ast(ctx, &for_of.quantifier)?;
     let pattern_set = pattern_set_from_ast(ctx, &for_of.pattern_set)?;
     // Create new stack frame with 5 slots:
@@ -744,11 +775,13 @@ fn for_of_expr_from_ast(
 
     ctx.symbol_table.push(Rc::new(loop_vars));
 
-    let condition = bool_expr_from_ast(ctx, &for_of.condition)?;
+    let condition = bool_expr_from_ast(ctx, &for_of.condition);
 
     ctx.symbol_table.pop();
     ctx.vars.unwind(&stack_frame);
 
+    let condition = condition?;
+
     Ok(Expr::ForOf(Box::new(ForOf {
         quantifier,
         pattern_set,
@@ -761,7 +794,7 @@ fn for_of_expr_from_ast(
 fn for_in_expr_from_ast(
     ctx: &mut CompileContext,
     for_in: &ast::ForIn,
-) -> Result<Expr, CompileError> {
+) -> Result<Expr, IrError> {
     let quantifier = quantifier_from_ast(ctx, &for_in.quantifier)?;
     let iterable = iterable_from_ast(ctx, &for_in.iterable)?;
 
@@ -804,15 +837,13 @@ fn for_in_expr_from_ast(
     if loop_vars.len() != expected_vars.len() {
         let span = loop_vars.first().unwrap().span();
         let span = span.combine(&loop_vars.last().unwrap().span());
-        return Err(CompileError::from(
-            CompileErrorInfo::assignment_mismatch(
-                ctx.report_builder,
-                loop_vars.len() as u8,
-                expected_vars.len() as u8,
-                for_in.iterable.span(),
-                span,
-            ),
-        ));
+        return Err(IrError::from(CompileErrorInfo::assignment_mismatch(
+            ctx.report_builder,
+            loop_vars.len() as u8,
+            expected_vars.len() as u8,
+            for_in.iterable.span(),
+            span,
+        )));
     }
 
     // Create stack frame with capacity for the loop variables, plus 4
@@ -861,13 +892,15 @@ fn for_in_expr_from_ast(
     // Put the loop variables into scope.
     ctx.symbol_table.push(Rc::new(symbols));
 
-    let condition = bool_expr_from_ast(ctx, &for_in.condition)?;
+    let condition = bool_expr_from_ast(ctx, &for_in.condition);
 
     // Leaving the condition's scope. Remove loop variables.
     ctx.symbol_table.pop();
 
     ctx.vars.unwind(&stack_frame);
 
+    let condition = condition?;
+
     Ok(Expr::ForIn(Box::new(ForIn {
         quantifier,
         variables,
@@ -877,10 +910,90 @@ fn for_in_expr_from_ast(
     })))
 }
 
+fn with_expr_from_ast(
+    ctx: &mut CompileContext,
+    with: &ast::With,
+) -> Result<Expr, IrError> {
+    // Create stack frame with capacity for the variables declared in the
+    // `with` statement.
+    let mut stack_frame = ctx.vars.new_frame(with.declarations.len() as i32);
+    let mut declarations = Vec::with_capacity(with.declarations.len());
+
+    let condition =
+        with_scope_from_ast(ctx, with, &mut stack_frame, &mut declarations);
+
+    // Leaving the condition's scope. Remove the declared variables, this
+    // must be done even if some declaration failed to compile.
+    for _ in &declarations {
+        ctx.symbol_table.pop();
+    }
+
+    ctx.vars.unwind(&stack_frame);
+
+    Ok(Expr::With(Box::new(With { declarations, condition: condition? })))
+}
+
+/// Puts the variables declared in a `with` statement into scope, and
+/// returns the condition compiled within that scope.
+///
+/// A symbol table is pushed for each item added to `declarations`, the
+/// caller is responsible for removing them.
+fn with_scope_from_ast(
+    ctx: &mut CompileContext,
+    with: &ast::With,
+    stack_frame: &mut VarStackFrame,
+    declarations: &mut Vec<(Var, Expr)>,
+) -> Result<Expr, IrError> {
+    for declaration in &with.declarations {
+        let span = declaration.expression.span();
+        let expr = expr_from_ast(ctx, &declaration.expression)?;
+
+        check_type(
+            ctx,
+            expr.ty(),
+            span,
+            &[
+                Type::Integer,
+                Type::Float,
+                Type::Bool,
+                Type::String,
+                Type::Struct,
+                Type::Array,
+                Type::Map,
+            ],
+        )?;
+
+        // Values of primitive types are stored in WASM-side variables,
+        // while structs, arrays and maps are stored in host-side variables.
+        let var = stack_frame.new_var(expr.ty());
+        let symbol_kind = match var.ty {
+            Type::Struct | Type::Array | Type::Map => SymbolKind::HostVar(var),
+            _ => SymbolKind::WasmVar(var),
+        };
+
+        // The variable's value is not known until the expression is
+        // evaluated at scan time, that's why `clone_without_value` is used.
+        let mut symbols = SymbolTable::new();
+
+        symbols.insert(
+            declaration.identifier.name,
+            Symbol::new(expr.type_value().clone_without_value(), symbol_kind),
+        );
+
+        // Put the variable into scope. Each variable is visible to the
+        // declarations that follow it, and to the condition.
+        ctx.symbol_table.push(Rc::new(symbols));
+
+        declarations.push((var, expr));
+    }
+
+    Ok(bool_expr_from_ast(ctx, &with.condition)?)
+}
+
 fn iterable_from_ast(
     ctx: &mut CompileContext,
     iter: &ast::Iterable,
-) -> Result<Iterable, CompileError> {
+) -> Result<Iterable, IrError> {
     match iter {
         ast::Iterable::Range(range) => {
             Ok(Iterable::Range(range_from_ast(ctx, range)?))
@@ -912,7 +1025,7 @@ fn iterable_from_ast(
                 // type mismatch.
                 if let Some((prev_ty, prev_span)) = prev {
                     if prev_ty != ty {
-                        return Err(CompileError::from(
+                        return Err(IrError::from(
                             CompileErrorInfo::mismatching_types(
                                 ctx.report_builder,
                                 prev_ty.to_string(),
@@ -934,7 +1047,7 @@ fn iterable_from_ast(
 fn anchor_from_ast(
     ctx: &mut CompileContext,
     anchor: &Option<ast::MatchAnchor>,
-) -> Result<MatchAnchor, CompileError> {
+) -> Result<MatchAnchor, IrError> {
     match anchor {
         Some(ast::MatchAnchor::At(at_)) => Ok(MatchAnchor::At(Box::new(
             non_negative_integer_from_ast(ctx, &at_.expr)?,
@@ -949,7 +1062,7 @@ fn anchor_from_ast(
 fn range_from_ast(
     ctx: &mut CompileContext,
     range: &ast::Range,
-) -> Result<Range, CompileError> {
+) -> Result<Range, IrError> {
     let lower_bound =
         Box::new(non_negative_integer_from_ast(ctx, &range.lower_bound)?);
 
@@ -966,7 +1079,7 @@ fn range_from_ast(
     ) = (lower_bound.type_value(), upper_bound.type_value())
     {
         if lower_bound > upper_bound {
-            return Err(CompileError::from(CompileErrorInfo::invalid_range(
+            return Err(IrError::from(CompileErrorInfo::invalid_range(
                 ctx.report_builder,
                 range.span,
             )));
@@ -979,7 +1092,7 @@ fn range_from_ast(
 fn non_negative_integer_from_ast(
     ctx: &mut CompileContext,
     expr: &ast::Expr,
-) -> Result<Expr, CompileError> {
+) -> Result<Expr, IrError> {
     let span = expr.span();
     let expr = expr_from_ast(ctx, expr)?;
     let type_value = expr.type_value();
@@ -988,7 +1101,7 @@ fn non_negative_integer_from_ast(
 
     if let TypeValue::Integer(Value::Const(value)) = type_value {
         if value < 0 {
-            return Err(CompileError::from(
+            return Err(IrError::from(
                 CompileErrorInfo::unexpected_negative_number(
                     ctx.report_builder,
                     span,
@@ -1004,7 +1117,7 @@ fn integer_in_range_from_ast(
     ctx: &mut CompileContext,
     expr: &ast::Expr,
     range: RangeInclusive<i64>,
-) -> Result<Expr, CompileError> {
+) -> Result<Expr, IrError> {
     let span = expr.span();
     let expr = expr_from_ast(ctx, expr)?;
     let type_value = expr.type_value();
@@ -1015,14 +1128,12 @@ fn integer_in_range_from_ast(
     // the given range.
     if let TypeValue::Integer(Value::Const(value)) = type_value {
         if !range.contains(&value) {
-            return Err(CompileError::from(
-                CompileErrorInfo::number_out_of_range(
-                    ctx.report_builder,
-                    *range.start(),
-                    *range.end(),
-                    span,
-                ),
-            ));
+            return Err(IrError::from(CompileErrorInfo::number_out_of_range(
+                ctx.report_builder,
+                *range.start(),
+                *range.end(),
+                span,
+            )));
         }
     }
 
@@ -1032,7 +1143,7 @@ fn integer_in_range_from_ast(
 fn quantifier_from_ast(
     ctx: &mut CompileContext,
     quantifier: &ast::Quantifier,
-) -> Result<Quantifier, CompileError> {
+) -> Result<Quantifier, IrError> {
     match quantifier {
         ast::Quantifier::None { .. } => Ok(Quantifier::None),
         ast::Quantifier::All { .. } => Ok(Quantifier::All),
@@ -1051,6 +1162,107 @@ fn quantifier_from_ast(
     }
 }
 
+/// Expands a set of rules like `(foo*, bar)` into a vector of expressions,
+/// one per rule in the set.
+///
+/// Wildcards are expanded to the rules declared so far in the current
+/// namespace, the rule being compiled is never part of the expansion.
+fn rule_set_from_ast(
+    ctx: &mut CompileContext,
+    rule_set: &[ast::RuleSetItem],
+) -> Result<Vec<Expr>, IrError> {
+    let mut result = Vec::new();
+
+    for item in rule_set {
+        // Items without wildcards are simply identifiers, they go through
+        // the same process than any other identifier in the condition, so
+        // that they can refer to rules in other namespaces, or even to
+        // other boolean symbols.
+        if !item.is_wildcard() {
+            let expr = expr_from_ast(
+                ctx,
+                &ast::Expr::Ident(Box::new(ast::Ident {
+                    span: item.span,
+                    name: item.identifier,
+                })),
+            )?;
+            check_type(ctx, expr.ty(), item.span, &[Type::Bool])?;
+            result.push(expr);
+            continue;
+        }
+
+        let current_rule = ctx.get_current_rule();
+        let namespace_id = current_rule.namespace_id;
+
+        // The rule being compiled is the last one in `ctx.rules`, it
+        // must be excluded from the expansion.
+        let matching_rules: Vec<RuleId> = ctx.rules[..ctx.rules.len() - 1]
+            .iter()
+            .enumerate()
+            .filter(|(_, rule)| {
+                rule.namespace_id == namespace_id
+                    && item.matches(ctx.ident_pool.get(rule.ident_id).unwrap())
+            })
+            .map(|(rule_id, _)| RuleId(rule_id as i32))
+            .collect();
+
+        if matching_rules.is_empty() {
+            return Err(IrError::from(CompileErrorInfo::empty_rule_set(
+                ctx.report_builder,
+                item.span,
+                Some(format!(
+                    "`{}` doesn't match any rule declared before this one",
+                    item.identifier,
+                )),
+            )));
+        }
+
+        for rule_id in matching_rules {
+            add_rule_dependency(ctx, rule_id, item.span)?;
+            result.push(Expr::Ident {
+                symbol: Symbol::new(
+                    TypeValue::Bool(Value::Unknown),
+                    SymbolKind::Rule(rule_id),
+                ),
+            });
+        }
+    }
+
+    Ok(result)
+}
+
+/// Checks that the current rule can depend on the rule identified by
+/// `rule_id`, and records the dependency.
+///
+/// Global rules are evaluated before non-global rules, even if the global
+/// rule appears after the non-global one in the source code. This means
+/// that by the time the global rule is being evaluated we can't know if the
+/// non-global rule matched or not. So, a global rule can depend on another
+/// global rule, and non-global rules can depend both on global rules and
+/// non-global ones, but global rules can't depend on non-global ones.
+fn add_rule_dependency(
+    ctx: &mut CompileContext,
+    rule_id: RuleId,
+    usage_span: Span,
+) -> Result<(), CompileError> {
+    let current_rule = ctx.get_current_rule();
+    let used_rule = ctx.get_rule(rule_id);
+    if current_rule.is_global && !used_rule.is_global {
+        return Err(CompileError::from(
+            CompileErrorInfo::wrong_rule_dependency(
+                ctx.report_builder,
+                ctx.ident_pool.get(current_rule.ident_id).unwrap().to_string(),
+                ctx.ident_pool.get(used_rule.ident_id).unwrap().to_string(),
+                current_rule.ident_span,
+                used_rule.ident_span,
+                usage_span,
+            ),
+        ));
+    }
+    ctx.current_rule_deps.push(rule_id);
+    Ok(())
+}
+
 fn pattern_set_from_ast(
     ctx: &mut CompileContext,
     pattern_set: &ast::PatternSet,
@@ -1124,7 +1336,7 @@ fn pattern_set_from_ast(
 fn func_call_from_ast(
     ctx: &mut CompileContext,
     func_call: &ast::FuncCall,
-) -> Result<Expr, CompileError> {
+) -> Result<Expr, IrError> {
     let callable = expr_from_ast(ctx, &func_call.callable)?;
     let type_value = callable.type_value();
 
@@ -1139,7 +1351,7 @@ fn func_call_from_ast(
         .args
         .iter()
         .map(|arg| expr_from_ast(ctx, arg))
-        .collect::<Result<Vec<Expr>, CompileError>>()?;
+        .collect::<Result<Vec<Expr>, IrError>>()?;
 
     let arg_types: Vec<Type> = args.iter().map(|arg| arg.ty()).collect();
 
@@ -1164,7 +1376,7 @@ fn func_call_from_ast(
     // No matching signature was found, that means that the arguments
     // provided were incorrect.
     if matching_signature.is_none() {
-        return Err(CompileError::from(CompileErrorInfo::wrong_arguments(
+        return Err(IrError::from(CompileErrorInfo::wrong_arguments(
             ctx.report_builder,
             func_call.args_span,
             Some(format!(
@@ -1199,7 +1411,7 @@ fn func_call_from_ast(
 fn matches_expr_from_ast(
     ctx: &mut CompileContext,
     expr: &ast::BinaryExpr,
-) -> Result<Expr, CompileError> {
+) -> Result<Expr, IrError> {
     let lhs_span = expr.lhs.span();
     let rhs_span = expr.rhs.span();
 
@@ -1358,12 +1570,12 @@ pub(in crate::compiler) fn warn_if_not_bool(
 }
 
 macro_rules! gen_unary_op {
-    ($name:ident, $variant:ident, $( $accepted_types:path )|+, $check_fn:expr) => {
+    ($name:ident, $variant:ident, $operand_fn:ident, $( $accepted_types:path )|+, $check_fn:expr) => {
         fn $name(
             ctx: &mut CompileContext,
             expr: &ast::UnaryExpr,
-        ) -> Result<Expr, CompileError> {
-            let operand = Box::new(expr_from_ast(ctx, &expr.operand)?);
+        ) -> Result<Expr, IrError> {
+            let operand = Box::new($operand_fn(ctx, &expr.operand)?);
 
             // The `not` operator accepts integers, floats and strings because
             // those types can be casted to bool.
@@ -1398,7 +1610,7 @@ macro_rules! gen_binary_op {
         fn $name(
             ctx: &mut CompileContext,
             expr: &ast::BinaryExpr,
-        ) -> Result<Expr, CompileError> {
+        ) -> Result<Expr, IrError> {
             let lhs_span = expr.lhs.span();
             let rhs_span = expr.rhs.span();
 
@@ -1439,7 +1651,7 @@ macro_rules! gen_string_op {
         fn $name(
             ctx: &mut CompileContext,
             expr: &ast::BinaryExpr,
-        ) -> Result<Expr, CompileError> {
+        ) -> Result<Expr, IrError> {
             let lhs_span = expr.lhs.span();
             let rhs_span = expr.rhs.span();
 
@@ -1468,18 +1680,18 @@ macro_rules! gen_string_op {
 }
 
 macro_rules! gen_n_ary_operation {
-    ($name:ident, $variant:ident, $( $accepted_types:path )|+, $( $compatible_types:path )|+, $check_fn:expr) => {
+    ($name:ident, $variant:ident, $operand_fn:ident, $( $accepted_types:path )|+, $( $compatible_types:path )|+, $check_fn:expr) => {
         fn $name(
             ctx: &mut CompileContext,
             expr: &ast::NAryExpr,
-        ) -> Result<Expr, CompileError> {
+        ) -> Result<Expr, IrError> {
             let accepted_types = &[$( $accepted_types ),+];
             let compatible_types = &[$( $compatible_types ),+];
 
             let operands_hir: Vec<Expr> = expr
                 .operands()
-                .map(|expr| expr_from_ast(ctx, expr))
-                .collect::<Result<Vec<Expr>, CompileError>>()?;
+                .map(|expr| $operand_fn(ctx, expr).map_err(IrError::from))
+                .collect::<Result<Vec<Expr>, IrError>>()?;
 
             let check_fn:
                 Option<fn(&mut CompileContext, &Expr, Span) -> Result<(), CompileError>>
@@ -1513,7 +1725,7 @@ macro_rules! gen_n_ary_operation {
                 };
 
                 if !types_are_compatible {
-                    return Err(CompileError::from(
+                    return Err(IrError::from(
                         CompileErrorInfo::mismatching_types(
                             ctx.report_builder,
                             lhs_ty.to_string(),
@@ -1539,6 +1751,7 @@ macro_rules! gen_n_ary_operation {
 gen_unary_op!(
     defined_expr_from_ast,
     Defined,
+    bool_operand_from_ast,
     Type::Bool | Type::Integer | Type::Float | Type::String,
     None
 );
@@ -1546,6 +1759,7 @@ gen_unary_op!(
 gen_unary_op!(
     not_expr_from_ast,
     Not,
+    bool_operand_from_ast,
     // Boolean operations accept integer, float and string operands.
     // If operands are not boolean they are casted to boolean.
     Type::Bool | Type::Integer | Type::Float | Type::String,
@@ -1559,6 +1773,7 @@ gen_unary_op!(
 gen_n_ary_operation!(
     and_expr_from_ast,
     And,
+    bool_operand_from_ast,
     // Boolean operations accept integer, float and string operands.
     // If operands are not boolean they are casted to boolean.
     Type::Bool | Type::Integer | Type::Float | Type::String,
@@ -1574,6 +1789,7 @@ gen_n_ary_operation!(
 gen_n_ary_operation!(
     or_expr_from_ast,
     Or,
+    bool_operand_from_ast,
     // Boolean operations accept integer, float and string operands.
     // If operands are not boolean they are casted to boolean.
     Type::Bool | Type::Integer | Type::Float | Type::String,
@@ -1586,11 +1802,18 @@ gen_n_ary_operation!(
     })
 );
 
-gen_unary_op!(minus_expr_from_ast, Minus, Type::Integer | Type::Float, None);
+gen_unary_op!(
+    minus_expr_from_ast,
+    Minus,
+    expr_from_ast,
+    Type::Integer | Type::Float,
+    None
+);
 
 gen_n_ary_operation!(
     add_expr_from_ast,
     Add,
+    expr_from_ast,
     Type::Integer | Type::Float,
     Type::Integer | Type::Float,
     None
@@ -1599,6 +1822,7 @@ gen_n_ary_operation!(
 gen_n_ary_operation!(
     sub_expr_from_ast,
     Sub,
+    expr_from_ast,
     Type::Integer | Type::Float,
     Type::Integer | Type::Float,
     None
@@ -1607,6 +1831,7 @@ gen_n_ary_operation!(
 gen_n_ary_operation!(
     mul_expr_from_ast,
     Mul,
+    expr_from_ast,
     Type::Integer | Type::Float,
     Type::Integer | Type::Float,
     None
@@ -1615,6 +1840,7 @@ gen_n_ary_operation!(
 gen_n_ary_operation!(
     div_expr_from_ast,
     Div,
+    expr_from_ast,
     Type::Integer | Type::Float,
     Type::Integer | Type::Float,
     None
@@ -1623,6 +1849,7 @@ gen_n_ary_operation!(
 gen_n_ary_operation!(
     mod_expr_from_ast,
     Mod,
+    expr_from_ast,
     Type::Integer,
     Type::Integer,
     None
@@ -1668,7 +1895,13 @@ gen_binary_op!(
     })
 );
 
-gen_unary_op!(bitwise_not_expr_from_ast, BitwiseNot, Type::Integer, None);
+gen_unary_op!(
+    bitwise_not_expr_from_ast,
+    BitwiseNot,
+    expr_from_ast,
+    Type::Integer,
+    None
+);
 
 gen_binary_op!(
     bitwise_and_expr_from_ast,
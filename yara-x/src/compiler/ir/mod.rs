@@ -217,6 +217,10 @@ pub(in crate::compiler) enum Expr {
     Filesize,
     Entrypoint,
 
+    /// Boolean expression that always evaluates as undefined. This replaces
+    /// boolean expressions that depend on an ignored module.
+    Undefined,
+
     /// Boolean `not` expression
     Not {
         operand: Box<Expr>,
@@ -456,6 +460,9 @@ pub(in crate::compiler) enum Expr {
     /// A `for <quantifier> <vars> in ...` expression. (e.g. `for all i in (1..100) : ( ... )`)
     ForIn(Box<ForIn>),
 
+    /// A `with <identifier> = <expr> : ...` expression. (e.g. `with a = 1 + 1 : ( ... )`)
+    With(Box<With>),
+
     /// Array or dictionary lookup expression (e.g. `array[1]`, `dict["key"]`)
     Lookup(Box<Lookup>),
 }
@@ -509,6 +516,14 @@ pub(in crate::compiler) struct ForIn {
     pub stack_frame: VarStackFrame,
 }
 
+/// A `with` expression (e.g `with a = 1 + 1 : (..)`)
+pub(in crate::compiler) struct With {
+    /// Variables declared in the `with` statement, together with the
+    /// expressions that produce their values.
+    pub declarations: Vec<(Var, Expr)>,
+    pub condition: Expr,
+}
+
 /// A quantifier used in `for` and `of` expressions.
 pub(in crate::compiler) enum Quantifier {
     None,
@@ -594,7 +609,9 @@ impl Expr {
             | Expr::PatternMatchVar { .. }
             | Expr::Of(_)
             | Expr::ForOf(_)
-            | Expr::ForIn(_) => Type::Bool,
+            | Expr::ForIn(_)
+            | Expr::With(_)
+            | Expr::Undefined => Type::Bool,
 
             Expr::Minus { operand, .. } => match operand.ty() {
                 Type::Integer => Type::Integer,
@@ -662,7 +679,9 @@ impl Expr {
             | Expr::PatternMatchVar { .. }
             | Expr::Of(_)
             | Expr::ForOf(_)
-            | Expr::ForIn(_) => TypeValue::Bool(Value::Unknown),
+            | Expr::ForIn(_)
+            | Expr::With(_)
+            | Expr::Undefined => TypeValue::Bool(Value::Unknown),
 
             Expr::Minus { operand, .. } => match operand.ty() {
                 Type::Integer => TypeValue::Integer(Value::Unknown),
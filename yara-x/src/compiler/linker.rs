@@ -0,0 +1,458 @@
+/*! Linking of compiled rules.
+
+This module implements [`Rules::link`], which combines multiple sets of
+already compiled rules into a single one without compiling them again from
+source code.
+
+The resulting [`Rules`] contain the WASM modules from all the linked rules,
+which are executed one after the other during the scan. Everything else
+(rules, patterns, atoms, literals, etc.) is merged. As the IDs assigned by
+the compiler to rules and patterns change while merging, each WASM module
+keeps using the IDs assigned by its compiler, and the host translates them
+while scanning (see [`WasmMod`]). The same happens with literals, regular
+expressions and global variables, using the tables that accompany each
+module. This way the modules don't need to be compiled again.
+*/
+
+use bincode::Options;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::compiler::{
+    IdentId, LinkError, LiteralId, MetaValue, NamespaceId, PatternId,
+    RegexpId, RuleId, RuleInfo, Rules, SubPattern, SubPatternAtom,
+    SubPatternId, WasmMod,
+};
+use crate::string_pool::{BStringPool, StringPool};
+use crate::types::Struct;
+
+impl Rules {
+    /// Links multiple sets of compiled rules together, producing a new set
+    /// that contains all of them.
+    ///
+    /// Scanning with the linked rules is equivalent to scanning with each
+    /// of the original sets, but the data is scanned only once. This allows
+    /// combining rules that were compiled independently (for instance,
+    /// rules distributed in compiled form) without compiling them again.
+    ///
+    /// Rules from different sets can live in the same namespace, but rule
+    /// names must be unique within each namespace, or
+    /// [`LinkError::DuplicateRule`] is returned. Also, a namespace containing
+    /// global rules can't be shared by multiple sets, as global rules would
+    /// affect only the rules from their own set, which is not what happens
+    /// when all the rules are compiled together. In that case the result is
+    /// [`LinkError::ConflictingNamespace`]. Global variables and modules can
+    /// be shared as long as they have the same type in all the sets, if
+    /// not, [`LinkError::ConflictingGlobal`] is returned. When multiple sets
+    /// define the same global variable, its initial value is taken from the
+    /// first one.
+    ///
+    /// The resulting rules don't contain the warnings produced while
+    /// compiling the original ones.
+    ///
+    /// ```
+    /// # use yara_x::{compile, Rules, Scanner};
+    /// let foo = compile(r#"rule foo { strings: $a = "foo" condition: $a }"#)?;
+    /// let bar = compile(r#"rule bar { strings: $a = "bar" condition: $a }"#)?;
+    ///
+    /// let rules = Rules::link([&foo, &bar])?;
+    /// let mut scanner = Scanner::new(&rules);
+    /// let results = scanner.scan(b"foobar")?;
+    ///
+    /// assert_eq!(results.matching_rules().len(), 2);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn link<'a, I>(rules: I) -> Result<Rules, LinkError>
+    where
+        I: IntoIterator<Item = &'a Rules>,
+    {
+        let mut linker = Linker::new();
+
+        for rules in rules {
+            linker.add(rules)?;
+        }
+
+        Ok(linker.build())
+    }
+}
+
+/// Accumulates the rules being linked.
+struct Linker {
+    ident_pool: StringPool<IdentId>,
+    regexp_pool: StringPool<RegexpId>,
+    lit_pool: BStringPool<LiteralId>,
+    wasm_mods: Vec<WasmMod>,
+    imported_modules: Vec<IdentId>,
+    rules: Vec<RuleInfo>,
+    num_patterns: usize,
+    sub_patterns: Vec<(PatternId, SubPattern)>,
+    anchored_sub_patterns: Vec<SubPatternId>,
+    atoms: Vec<SubPatternAtom>,
+    re_code: Vec<u8>,
+    globals: Struct,
+    /// Namespace IDs in the linked rules, indexed by namespace name.
+    namespaces: FxHashMap<IdentId, NamespaceId>,
+    /// Namespaces that contain some global rule.
+    namespaces_with_globals: FxHashSet<NamespaceId>,
+    /// Pairs (namespace, rule name) for every rule added so far.
+    rule_names: FxHashSet<(IdentId, IdentId)>,
+}
+
+impl Linker {
+    fn new() -> Self {
+        Self {
+            ident_pool: StringPool::new(),
+            regexp_pool: StringPool::new(),
+            lit_pool: BStringPool::new(),
+            wasm_mods: Vec::new(),
+            imported_modules: Vec::new(),
+            rules: Vec::new(),
+            num_patterns: 0,
+            sub_patterns: Vec::new(),
+            anchored_sub_patterns: Vec::new(),
+            atoms: Vec::new(),
+            re_code: Vec::new(),
+            globals: Struct::new(),
+            namespaces: FxHashMap::default(),
+            namespaces_with_globals: FxHashSet::default(),
+            rule_names: FxHashSet::default(),
+        }
+    }
+
+    /// Adds a set of rules to the linked ones.
+    fn add(&mut self, rules: &Rules) -> Result<(), LinkError> {
+        // Tables that translate the IDs in `rules` into the IDs in the
+        // linked rules. The N-th item in each vector is the new ID for the
+        // item with ID = N. As strings are interned in the same order they
+        // were interned in the original pools, the tables for the first set
+        // of rules are the identity.
+        let idents: Vec<IdentId> = rules
+            .ident_pool
+            .iter()
+            .map(|(_, s)| self.ident_pool.get_or_intern(s))
+            .collect();
+
+        let literals: Vec<LiteralId> = rules
+            .lit_pool
+            .iter()
+            .map(|(_, s)| self.lit_pool.get_or_intern(s))
+            .collect();
+
+        let regexps: Vec<RegexpId> = rules
+            .regexp_pool
+            .iter()
+            .map(|(_, s)| self.regexp_pool.get_or_intern(s))
+            .collect();
+
+        let ident = |id: IdentId| idents[u32::from(id) as usize];
+        let literal = |id: LiteralId| literals[u32::from(id) as usize];
+
+        // Make sure that namespaces shared with the rules already linked
+        // don't contain global rules, neither in one side nor the other.
+        // While doing so, assign the ID of each namespace in the linked
+        // rules.
+        let mut namespaces = FxHashMap::default();
+
+        for rule in rules.rules() {
+            if namespaces.contains_key(&rule.namespace_id) {
+                continue;
+            }
+
+            let namespace_ident_id = ident(rule.namespace_ident_id);
+
+            let namespace_id = if let Some(namespace_id) =
+                self.namespaces.get(&namespace_ident_id)
+            {
+                let has_globals = rules.rules().iter().any(|r| {
+                    r.is_global && r.namespace_id == rule.namespace_id
+                });
+
+                if has_globals
+                    || self.namespaces_with_globals.contains(namespace_id)
+                {
+                    return Err(LinkError::ConflictingNamespace(
+                        self.ident_pool
+                            .get(namespace_ident_id)
+                            .unwrap()
+                            .to_string(),
+                    ));
+                }
+
+                *namespace_id
+            } else {
+                let namespace_id = NamespaceId(self.namespaces.len() as i32);
+                self.namespaces.insert(namespace_ident_id, namespace_id);
+                namespace_id
+            };
+
+            namespaces.insert(rule.namespace_id, namespace_id);
+        }
+
+        // Merge the global variables and modules. Fields that already exist
+        // must have the same type. The N-th item in `root_fields` is the
+        // index in the linked root structure for the field with index N in
+        // the root structure of `rules`.
+        let globals = rules.globals();
+        let mut root_fields = Vec::new();
+
+        for field in (0..).map_while(|i| globals.field_by_index(i)) {
+            if let Some(existing) = self.globals.field_by_name(&field.name) {
+                if !existing.type_value.eq_type(&field.type_value) {
+                    return Err(LinkError::ConflictingGlobal(
+                        field.name.clone(),
+                    ));
+                }
+            } else {
+                self.globals.add_field(&field.name, field.type_value.clone());
+            }
+            root_fields.push(self.globals.index_of(&field.name) as i32);
+        }
+
+        for module in rules.imported_modules.iter() {
+            let module = ident(*module);
+            if !self.imported_modules.contains(&module) {
+                self.imported_modules.push(module);
+            }
+        }
+
+        let rule_id_base = self.rules.len() as i32;
+        let pattern_id_base = self.num_patterns as i32;
+        let sub_pattern_id_base = self.sub_patterns.len() as u32;
+        let re_code_base = self.re_code.len();
+
+        let pattern = |id: PatternId| PatternId(id.0 + pattern_id_base);
+        let sub_pattern_id =
+            |id: SubPatternId| SubPatternId(id.0 + sub_pattern_id_base);
+
+        for rule in rules.rules() {
+            let namespace_ident_id = ident(rule.namespace_ident_id);
+            let ident_id = ident(rule.ident_id);
+
+            if !self.rule_names.insert((namespace_ident_id, ident_id)) {
+                return Err(LinkError::DuplicateRule {
+                    namespace: self
+                        .ident_pool
+                        .get(namespace_ident_id)
+                        .unwrap()
+                        .to_string(),
+                    rule: self.ident_pool.get(ident_id).unwrap().to_string(),
+                });
+            }
+
+            let namespace_id = namespaces[&rule.namespace_id];
+
+            if rule.is_global {
+                self.namespaces_with_globals.insert(namespace_id);
+            }
+
+            self.rules.push(RuleInfo {
+                namespace_id,
+                namespace_ident_id,
+                ident_id,
+                ident_span: rule.ident_span,
+                patterns: rule
+                    .patterns
+                    .iter()
+                    .map(|(ident_id, pattern_id)| {
+                        (ident(*ident_id), pattern(*pattern_id))
+                    })
+                    .collect(),
+                tags: rule.tags.iter().map(|tag| ident(*tag)).collect(),
+                metadata: rule
+                    .metadata
+                    .iter()
+                    .map(|(ident_id, value)| {
+                        let value = match value {
+                            MetaValue::Bool(v) => MetaValue::Bool(*v),
+                            MetaValue::Integer(v) => MetaValue::Integer(*v),
+                            MetaValue::Float(v) => MetaValue::Float(*v),
+                            MetaValue::String(id) => {
+                                MetaValue::String(literal(*id))
+                            }
+                            MetaValue::Bytes(id) => {
+                                MetaValue::Bytes(literal(*id))
+                            }
+                        };
+                        (ident(*ident_id), value)
+                    })
+                    .collect(),
+                depends_on: rule
+                    .depends_on
+                    .iter()
+                    .map(|rule_id| RuleId(rule_id.0 + rule_id_base))
+                    .collect(),
+                is_global: rule.is_global,
+                is_private: rule.is_private,
+            });
+        }
+
+        for (pattern_id, sub_pattern) in rules.sub_patterns.iter() {
+            self.sub_patterns.push((
+                pattern(*pattern_id),
+                link_sub_pattern(sub_pattern, literal, sub_pattern_id),
+            ));
+        }
+
+        self.anchored_sub_patterns.extend(
+            rules.anchored_sub_patterns.iter().map(|id| sub_pattern_id(*id)),
+        );
+
+        self.atoms.extend(
+            rules
+                .atoms
+                .iter()
+                .map(|atom| atom.shifted(sub_pattern_id_base, re_code_base)),
+        );
+
+        self.re_code.extend_from_slice(rules.re_code.as_slice());
+        self.num_patterns += rules.num_patterns;
+
+        // Finally, add the WASM modules. The rules may be the result of a
+        // previous link, in which case there are multiple modules, each of
+        // them with its own translation tables. In that case the tables are
+        // composed with the ones for this link. Rule and pattern IDs are
+        // shifted by the same amount as the rules and patterns themselves.
+        for wasm_mod in rules.wasm_mods() {
+            self.wasm_mods.push(WasmMod {
+                module: wasm_mod.module.clone(),
+                rule_id_base: wasm_mod.rule_id_base + rule_id_base,
+                pattern_id_base: wasm_mod.pattern_id_base + pattern_id_base,
+                literals: compose(&wasm_mod.literals, &literals, |id| {
+                    u32::from(id) as usize
+                }),
+                regexps: compose(&wasm_mod.regexps, &regexps, |id| {
+                    usize::from(id)
+                }),
+                root_fields: compose(
+                    &wasm_mod.root_fields,
+                    &root_fields,
+                    |i| i as usize,
+                ),
+            })
+        }
+
+        Ok(())
+    }
+
+    fn build(self) -> Rules {
+        let serialized_globals = bincode::DefaultOptions::new()
+            .serialize(&self.globals)
+            .expect("failed to serialize global variables");
+
+        let mut rules = Rules {
+            serialized_globals,
+            wasm_mods: self.wasm_mods,
+            ac: None,
+            num_patterns: self.num_patterns,
+            ident_pool: self.ident_pool,
+            regexp_pool: self.regexp_pool,
+            lit_pool: self.lit_pool,
+            imported_modules: self.imported_modules,
+            rules: self.rules,
+            sub_patterns: self.sub_patterns,
+            anchored_sub_patterns: self.anchored_sub_patterns,
+            atoms: self.atoms,
+            re_code: self.re_code,
+            warnings: Vec::new(),
+        };
+
+        rules.build_ac_automaton();
+
+        rules
+    }
+}
+
+/// Returns a copy of `sub_pattern` where literals and chained sub-patterns
+/// are translated to their IDs in the linked rules.
+fn link_sub_pattern<L, S>(
+    sub_pattern: &SubPattern,
+    literal: L,
+    sub_pattern_id: S,
+) -> SubPattern
+where
+    L: Fn(LiteralId) -> LiteralId,
+    S: Fn(SubPatternId) -> SubPatternId,
+{
+    match sub_pattern {
+        SubPattern::Literal { pattern, anchored_at, flags } => {
+            SubPattern::Literal {
+                pattern: literal(*pattern),
+                anchored_at: *anchored_at,
+                flags: *flags,
+            }
+        }
+        SubPattern::LiteralChainHead { pattern, flags } => {
+            SubPattern::LiteralChainHead {
+                pattern: literal(*pattern),
+                flags: *flags,
+            }
+        }
+        SubPattern::LiteralChainTail { pattern, chained_to, gap, flags } => {
+            SubPattern::LiteralChainTail {
+                pattern: literal(*pattern),
+                chained_to: sub_pattern_id(*chained_to),
+                gap: gap.clone(),
+                flags: *flags,
+            }
+        }
+        SubPattern::Regexp { flags } => SubPattern::Regexp { flags: *flags },
+        SubPattern::RegexpChainHead { flags } => {
+            SubPattern::RegexpChainHead { flags: *flags }
+        }
+        SubPattern::RegexpChainTail { chained_to, gap, flags } => {
+            SubPattern::RegexpChainTail {
+                chained_to: sub_pattern_id(*chained_to),
+                gap: gap.clone(),
+                flags: *flags,
+            }
+        }
+        SubPattern::Xor { pattern, flags } => {
+            SubPattern::Xor { pattern: literal(*pattern), flags: *flags }
+        }
+        SubPattern::Base64 { pattern, padding } => SubPattern::Base64 {
+            pattern: literal(*pattern),
+            padding: *padding,
+        },
+        SubPattern::Base64Wide { pattern, padding } => {
+            SubPattern::Base64Wide {
+                pattern: literal(*pattern),
+                padding: *padding,
+            }
+        }
+        SubPattern::CustomBase64 { pattern, alphabet, padding } => {
+            SubPattern::CustomBase64 {
+                pattern: literal(*pattern),
+                alphabet: literal(*alphabet),
+                padding: *padding,
+            }
+        }
+        SubPattern::CustomBase64Wide { pattern, alphabet, padding } => {
+            SubPattern::CustomBase64Wide {
+                pattern: literal(*pattern),
+                alphabet: literal(*alphabet),
+                padding: *padding,
+            }
+        }
+    }
+}
+
+/// Composes two translation tables, returning a table that is equivalent to
+/// translating with `first`, and then with `second`. An empty `first` table
+/// is the identity, and the result is empty if the composed table is the
+/// identity too.
+fn compose<T, F>(first: &[T], second: &[T], index: F) -> Vec<T>
+where
+    T: Copy + PartialEq,
+    F: Fn(T) -> usize,
+{
+    let composed: Vec<T> = if first.is_empty() {
+        second.to_vec()
+    } else {
+        first.iter().map(|id| second[index(*id)]).collect()
+    };
+
+    if composed.iter().enumerate().all(|(i, id)| index(*id) == i) {
+        Vec::new()
+    } else {
+        composed
+    }
+}
@@ -8,11 +8,12 @@ use std::cell::RefCell;
 use std::collections::hash_map::Entry;
 use std::collections::{HashSet, VecDeque};
 use std::ops::RangeInclusive;
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
 use std::rc::Rc;
 #[cfg(feature = "logging")]
 use std::time::Instant;
 use std::{fmt, iter, u32};
+use std::{fs, io};
 
 use bincode::Options;
 use bitmask::bitmask;
@@ -25,7 +26,7 @@ use serde::{Deserialize, Serialize};
 use walrus::FunctionId;
 
 use yara_x_parser::ast;
-use yara_x_parser::ast::{HasSpan, Ident, Import, RuleFlag, Span};
+use yara_x_parser::ast::{HasSpan, Ident, Import, Include, RuleFlag, Span};
 use yara_x_parser::report::ReportBuilder;
 use yara_x_parser::warnings::Warning;
 use yara_x_parser::{Parser, SourceCode};
@@ -61,6 +62,7 @@ mod context;
 mod emit;
 mod errors;
 mod ir;
+mod linker;
 mod rules;
 
 pub mod base64;
@@ -102,6 +104,17 @@ struct Namespace {
     symbols: Rc<RefCell<SymbolTable>>,
 }
 
+/// Function used for resolving `include` statements.
+///
+/// Receives the file name that appears in the `include` statement, and the
+/// origin of the source code containing the statement, if any. Returns the
+/// origin of the included source code, and the code itself. The origin
+/// returned is used in error messages, is passed as the including origin
+/// when resolving nested includes, and identifies the included source while
+/// detecting circular includes.
+type IncludeResolver<'a> =
+    Box<dyn Fn(&str, Option<&str>) -> io::Result<(String, Vec<u8>)> + 'a>;
+
 /// Compiles YARA source code producing a set of compiled [`Rules`].
 ///
 /// The two most important methods in this type are [`Compiler::add_source`]
@@ -231,6 +244,30 @@ pub struct Compiler<'a> {
     /// is the structure that describes the module.
     root_struct: Struct,
 
+    /// Function used for resolving `include` statements, set with
+    /// [`Compiler::include_resolver`]. If `None`, included files are read
+    /// from the filesystem by [`default_include_resolver`].
+    include_resolver: Option<IncludeResolver<'a>>,
+
+    /// Stack with the origins of the source files being compiled. The
+    /// top-most item corresponds to the source code being compiled at the
+    /// moment, the remaining ones are the sources that included it, directly
+    /// or indirectly. Used for detecting circular includes.
+    include_stack: Vec<String>,
+
+    /// Modules that can't be imported by the rules. Keys are module names,
+    /// and values are the error messages shown when some rule imports the
+    /// module. See [`Compiler::ban_module`].
+    banned_modules: FxHashMap<String, String>,
+
+    /// Modules ignored by the compiler. See [`Compiler::ignore_module`].
+    ignored_modules: HashSet<String>,
+
+    /// Errors collected while compiling a source code with
+    /// [`Compiler::add_source_with_recovery`]. This is `None` while the
+    /// compiler is not recovering from errors.
+    recovered_errors: Option<Vec<Error>>,
+
     /// Warnings generated while compiling the rules.
     warnings: Vec<Warning>,
 }
@@ -244,7 +281,11 @@ impl<'a> Compiler<'a> {
         // Add symbols for built-in functions like uint8, uint16, etc.
         let global_symbols = symbol_table.push_new();
 
-        for export in WASM_EXPORTS.iter().filter(|e| e.public) {
+        // Methods are not added to the global symbols, as they are not
+        // callable by themselves, they must be called on some structure.
+        for export in
+            WASM_EXPORTS.iter().filter(|e| e.public && e.method_of.is_none())
+        {
             let func = Rc::new(Func::with_signature(FuncSignature::from(
                 export.mangled_name.to_string(),
             )));
@@ -300,6 +341,11 @@ impl<'a> Compiler<'a> {
             re_code: Vec::new(),
             imported_modules: Vec::new(),
             root_struct: Struct::new(),
+            include_resolver: None,
+            include_stack: Vec::new(),
+            banned_modules: FxHashMap::default(),
+            ignored_modules: HashSet::new(),
+            recovered_errors: None,
             report_builder: ReportBuilder::new(),
             lit_pool: BStringPool::new(),
             regexp_pool: StringPool::new(),
@@ -318,30 +364,198 @@ impl<'a> Compiler<'a> {
         // else, like a &str.
         let src = src.into();
 
-        // Parse the source code and build the Abstract Syntax Tree.
-        let mut ast = Parser::new()
-            .set_report_builder(&self.report_builder)
-            .build_ast(src)?;
+        // If the source code has some origin, put it in the include stack,
+        // so that the source code can't include itself. When included files
+        // are read from the filesystem, the origin is normalized in the same
+        // way that `default_include_resolver` normalizes the paths of
+        // included files, otherwise `a.yar` including `./a.yar` wouldn't be
+        // detected as circular. Origins used with a custom resolver are not
+        // necessarily paths, and they are left untouched.
+        let origin = src.origin().map(|origin| {
+            if self.include_resolver.is_none() {
+                normalize_path(Path::new(origin))
+                    .to_string_lossy()
+                    .into_owned()
+            } else {
+                origin.to_string()
+            }
+        });
 
-        // Process import statements. Checks that all imported modules
-        // actually exist, and raise warnings in case of duplicated
-        // imports within the same source file. For each module add a
-        // symbol to the current namespace.
-        self.c_imports(&ast.imports)?;
+        if let Some(origin) = &origin {
+            self.include_stack.push(origin.clone());
+        }
 
-        // Iterate over the list of declared rules and verify that their
-        // conditions are semantically valid. For each rule add a symbol
-        // to the current namespace.
-        for rule in &ast.rules {
-            self.c_rule(rule)?;
+        let result = self.c_source(src);
+
+        if origin.is_some() {
+            self.include_stack.pop();
         }
 
-        // Transfer the warnings generated by the parser to the compiler
-        self.warnings.append(&mut ast.warnings);
+        result?;
 
         Ok(self)
     }
 
+    /// Adds a YARA source code to be compiled, recovering from errors.
+    ///
+    /// This is similar to [`Compiler::add_source`], but instead of stopping
+    /// at the first error, rules that fail to compile are skipped and the
+    /// compilation continues with the remaining ones. If any error is found,
+    /// the function returns all of them. Either way, the rules that were
+    /// compiled successfully remain in the compiler and are part of the
+    /// [`Rules`] produced by [`Compiler::build`].
+    ///
+    /// Recovery happens at rule granularity. Errors that affect the source
+    /// code as a whole, like syntax errors or unknown modules in `import`
+    /// statements, prevent any rule in that source code from being compiled.
+    /// When such an error is found in an included file, only the rules in
+    /// that file are skipped.
+    ///
+    /// ```
+    /// # use yara_x::{Compiler, Scanner};
+    /// let mut compiler = Compiler::new();
+    ///
+    /// let errors = compiler
+    ///     .add_source_with_recovery(
+    ///         r#"
+    ///         rule foo { condition: true }
+    ///         rule bar { condition: qux }
+    ///         rule baz { condition: "qux" + 1 }
+    ///         "#,
+    ///     )
+    ///     .unwrap_err();
+    ///
+    /// assert_eq!(errors.len(), 2);
+    ///
+    /// let rules = compiler.build();
+    /// let mut scanner = Scanner::new(&rules);
+    /// let results = scanner.scan(&[]).unwrap();
+    /// assert_eq!(results.matching_rules().len(), 1);
+    /// ```
+    pub fn add_source_with_recovery<'src, S>(
+        &mut self,
+        src: S,
+    ) -> Result<&mut Self, Vec<Error>>
+    where
+        S: Into<SourceCode<'src>>,
+    {
+        self.recovered_errors = Some(Vec::new());
+
+        let result = self.add_source(src).map(|_| ());
+        let mut errors = self.recovered_errors.take().unwrap();
+
+        if let Err(err) = result {
+            errors.push(err);
+        }
+
+        if errors.is_empty() {
+            Ok(self)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Sets the function used for resolving `include` statements.
+    ///
+    /// The function receives the file name that appears in the `include`
+    /// statement, and the origin of the source code where the statement was
+    /// found (see [`SourceCode::with_origin`]), if any. It must return the
+    /// origin of the included source code, and the source code itself. The
+    /// origin returned by this function is the one that appears in error
+    /// messages produced while compiling the included source.
+    ///
+    /// By default included files are read from the filesystem, and relative
+    /// paths are interpreted as relative to the directory containing the
+    /// including file. This function allows obtaining the included source
+    /// code from somewhere else, like a database or an in-memory map. In
+    /// that case origins are not required to be paths, and they are used
+    /// exactly as provided, without normalizing them.
+    ///
+    /// ```
+    /// # use std::io;
+    /// # use yara_x::Compiler;
+    /// let mut compiler = Compiler::new();
+    ///
+    /// compiler.include_resolver(|file_name, _| match file_name {
+    ///     "common.yar" => Ok((
+    ///         file_name.to_string(),
+    ///         b"rule common { condition: true }".to_vec(),
+    ///     )),
+    ///     _ => Err(io::Error::from(io::ErrorKind::NotFound)),
+    /// });
+    ///
+    /// assert!(compiler
+    ///     .add_source(r#"include "common.yar" rule test { condition: common }"#)
+    ///     .is_ok());
+    /// ```
+    pub fn include_resolver<F>(&mut self, resolver: F) -> &mut Self
+    where
+        F: Fn(&str, Option<&str>) -> io::Result<(String, Vec<u8>)> + 'a,
+    {
+        self.include_resolver = Some(Box::new(resolver));
+        self
+    }
+
+    /// Tells the compiler that a YARA module can't be used.
+    ///
+    /// Rules that import a banned module fail to compile with a
+    /// [`CompileErrorInfo::BannedModule`] error that points to the `import`
+    /// statement, and includes the given error message.
+    ///
+    /// ```
+    /// # use yara_x::Compiler;
+    /// let mut compiler = Compiler::new();
+    ///
+    /// compiler.ban_module("time", "`time` is not deterministic");
+    ///
+    /// assert!(compiler
+    ///     .add_source(r#"import "time" rule test { condition: true }"#)
+    ///     .is_err());
+    /// ```
+    pub fn ban_module<M: Into<String>, E: Into<String>>(
+        &mut self,
+        module: M,
+        error_message: E,
+    ) -> &mut Self {
+        self.banned_modules.insert(module.into(), error_message.into());
+        self
+    }
+
+    /// Tells the compiler that a YARA module must be ignored.
+    ///
+    /// Rules can import an ignored module, even if such module doesn't
+    /// exist, but any expression that depends on the module evaluates as
+    /// undefined. For instance, if module `foo` is ignored, the condition
+    /// `foo.bar == 1 or true` is always true, while `foo.bar == 1` is always
+    /// false.
+    ///
+    /// ```
+    /// # use yara_x::{Compiler, Scanner};
+    /// let mut compiler = Compiler::new();
+    ///
+    /// compiler.ignore_module("unsupported_module");
+    ///
+    /// compiler
+    ///     .add_source(
+    ///         r#"
+    ///         import "unsupported_module"
+    ///         rule test {
+    ///           condition:
+    ///             unsupported_module.some_field == 1 or true
+    ///         }"#,
+    ///     )
+    ///     .unwrap();
+    ///
+    /// let rules = compiler.build();
+    /// let mut scanner = Scanner::new(&rules);
+    /// let results = scanner.scan(&[]).unwrap();
+    /// assert_eq!(results.matching_rules().len(), 1);
+    /// ```
+    pub fn ignore_module<M: Into<String>>(&mut self, module: M) -> &mut Self {
+        self.ignored_modules.insert(module.into());
+        self
+    }
+
     /// Defines a global variable and sets its initial value.    
     ///   
     /// Global variables must be defined before using [`Compiler::add_source`]
@@ -482,7 +696,7 @@ impl<'a> Compiler<'a> {
 
         let mut rules = Rules {
             serialized_globals,
-            wasm_mod: compiled_wasm_mod,
+            wasm_mods: vec![WasmMod::new(compiled_wasm_mod)],
             ac: None,
             num_patterns: self.next_pattern_id.0 as usize,
             ident_pool: self.ident_pool,
@@ -502,6 +716,11 @@ impl<'a> Compiler<'a> {
         rules
     }
 
+    /// Warnings produced so far while compiling the rules.
+    pub fn warnings(&self) -> &[Warning] {
+        self.warnings.as_slice()
+    }
+
     /// Specifies whether the compiler should produce colorful error messages.
     ///
     /// Colorized error messages contain ANSI escape sequences that make them
@@ -639,6 +858,23 @@ impl<'a> Compiler<'a> {
     /// imported modules. This field is created only if it don't exist yet.
     fn import_module(&mut self, import: &Import) -> Result<(), CompileError> {
         let module_name = import.module_name.as_str();
+
+        // Banned modules can't be imported.
+        if let Some(error_message) = self.banned_modules.get(module_name) {
+            return Err(CompileError::from(CompileErrorInfo::banned_module(
+                &self.report_builder,
+                module_name.to_string(),
+                error_message.clone(),
+                import.span(),
+            )));
+        }
+
+        // Ignored modules are not actually imported. They don't need to
+        // exist.
+        if self.ignored_modules.contains(module_name) {
+            return Ok(());
+        }
+
         let module = BUILTIN_MODULES.get(module_name);
 
         // Does a module with the given name actually exist? ...
@@ -679,12 +915,35 @@ impl<'a> Compiler<'a> {
             let mut functions: FxHashMap<&'static str, Func> =
                 FxHashMap::default();
 
+            // Similar to `functions`, but contains the methods exported by
+            // the YARA module. Keys are (type name, method name) tuples.
+            let mut methods: FxHashMap<(&'static str, &'static str), Func> =
+                FxHashMap::default();
+
             // Iterate over public functions in WASM_EXPORTS looking
             // for those that were exported by the current YARA module.
             // Add them to `functions` map, or update the `Func` object
             // an additional signature if the function is overloaded.
             for export in WASM_EXPORTS.iter().filter(|e| e.public) {
-                if export.rust_module_path.contains(mod_name) {
+                if !export.rust_module_path.contains(mod_name) {
+                    continue;
+                }
+                if let Some(method_of) = export.method_of {
+                    let signature = FuncSignature::from(format!(
+                        "{}::{}",
+                        method_of, export.mangled_name
+                    ));
+                    if let Some(method) =
+                        methods.get_mut(&(method_of, export.name))
+                    {
+                        method.add_signature(signature)
+                    } else {
+                        methods.insert(
+                            (method_of, export.name),
+                            Func::with_signature(signature),
+                        );
+                    }
+                } else {
                     let signature = FuncSignature::from(format!(
                         "{}.{}",
                         module_name, export.mangled_name
@@ -713,6 +972,19 @@ impl<'a> Compiler<'a> {
                     panic!("duplicate function `{}`", name)
                 }
             }
+
+            // Insert the methods in every structure of the corresponding
+            // type.
+            for ((type_name, name), method) in methods.drain() {
+                if module_struct.add_method(type_name, name, Rc::new(method))
+                    == 0
+                {
+                    panic!(
+                        "method `{}` declared for type `{}`, but no such type was found",
+                        name, type_name
+                    )
+                }
+            }
         }
 
         // Insert the module in the struct that contains all imported
@@ -785,16 +1057,40 @@ impl<'a> Compiler<'a> {
 
         let rule_id = RuleId(self.rules.len() as i32);
 
+        let tags = rule
+            .tags
+            .iter()
+            .flatten()
+            .map(|tag| self.ident_pool.get_or_intern(tag.name))
+            .collect();
+
+        let metadata = rule
+            .meta
+            .iter()
+            .flatten()
+            .map(|meta| {
+                (
+                    self.ident_pool.get_or_intern(meta.identifier.name),
+                    self.c_meta_value(&meta.value),
+                )
+            })
+            .collect();
+
         self.rules.push(RuleInfo {
             namespace_id: self.current_namespace.id,
             namespace_ident_id: self.current_namespace.ident_id,
             ident_id: self.ident_pool.get_or_intern(rule.identifier.name),
             ident_span: rule.identifier.span,
             patterns: ident_and_pattern_ids,
+            tags,
+            metadata,
+            depends_on: Vec::new(),
             is_global: rule.flags.contains(RuleFlag::Global),
             is_private: rule.flags.contains(RuleFlag::Private),
         });
 
+        let mut depends_on = Vec::new();
+
         // Convert the rule condition's AST to the intermediate representation
         // (IR).
         let condition = bool_expr_from_ast(
@@ -805,6 +1101,7 @@ impl<'a> Compiler<'a> {
                 report_builder: &self.report_builder,
                 rules: &self.rules,
                 current_rule_patterns: &mut patterns_with_ids,
+                current_rule_deps: &mut depends_on,
                 warnings: &mut self.warnings,
                 vars: VarStack::new(),
             },
@@ -821,6 +1118,13 @@ impl<'a> Compiler<'a> {
             }
         };
 
+        // A rule can use the same rule multiple times in its condition, but
+        // each dependency is recorded only once.
+        depends_on.sort();
+        depends_on.dedup();
+
+        self.rules.last_mut().unwrap().depends_on = depends_on;
+
         // Create a new symbol of bool type for the rule.
         let new_symbol = Symbol::new(
             TypeValue::Bool(Value::Unknown),
@@ -904,6 +1208,28 @@ impl<'a> Compiler<'a> {
         Ok(())
     }
 
+    fn c_meta_value(&mut self, value: &ast::MetaValue) -> MetaValue {
+        match value {
+            ast::MetaValue::Bool(b) => MetaValue::Bool(*b),
+            ast::MetaValue::Integer(i) => MetaValue::Integer(*i),
+            ast::MetaValue::Float(f) => MetaValue::Float(*f),
+            ast::MetaValue::String(s) => {
+                MetaValue::String(self.lit_pool.get_or_intern(s))
+            }
+            // Strings that contained escape sequences are stored as strings
+            // if they are valid UTF-8 after being unescaped, and as bytes if
+            // otherwise.
+            ast::MetaValue::Bytes(b) => {
+                let lit_id = self.lit_pool.get_or_intern(b);
+                if b.to_str().is_ok() {
+                    MetaValue::String(lit_id)
+                } else {
+                    MetaValue::Bytes(lit_id)
+                }
+            }
+        }
+    }
+
     fn c_literal_pattern(
         &mut self,
         pattern: LiteralPattern,
@@ -1482,6 +1808,110 @@ impl<'a> Compiler<'a> {
         )
     }
 
+    fn c_source(&mut self, src: SourceCode) -> Result<(), Error> {
+        // Parse the source code and build the Abstract Syntax Tree.
+        let mut ast = Parser::new()
+            .set_report_builder(&self.report_builder)
+            .build_ast(src)?;
+
+        // Process import statements. Checks that all imported modules
+        // actually exist, and raise warnings in case of duplicated
+        // imports within the same source file. For each module add a
+        // symbol to the current namespace.
+        self.c_imports(&ast.imports)?;
+
+        let origin = ast.source.origin();
+        let mut includes = ast.includes.iter().peekable();
+
+        // Iterate over the list of declared rules and verify that their
+        // conditions are semantically valid. For each rule add a symbol
+        // to the current namespace. Include statements are processed in
+        // the same order they appear in the source, so rules declared in
+        // included files are visible only to the rules that come after the
+        // `include` statement.
+        for rule in &ast.rules {
+            let rule_start = rule.identifier.span().start();
+            while let Some(include) =
+                includes.next_if(|include| include.span().start() < rule_start)
+            {
+                if let Err(err) = self.c_include(include, origin) {
+                    self.recover(err)?;
+                }
+            }
+            if let Err(err) = self.c_rule(rule) {
+                self.recover(Error::from(err))?;
+            }
+        }
+
+        for include in includes {
+            if let Err(err) = self.c_include(include, origin) {
+                self.recover(err)?;
+            }
+        }
+
+        // Transfer the warnings generated by the parser to the compiler
+        self.warnings.append(&mut ast.warnings);
+
+        Ok(())
+    }
+
+    /// Handles an error found while compiling a rule or an `include`
+    /// statement.
+    ///
+    /// If the compiler is recovering from errors (see
+    /// [`Compiler::add_source_with_recovery`]) the error is collected and the
+    /// compilation continues. If not, the error is returned back.
+    fn recover(&mut self, err: Error) -> Result<(), Error> {
+        match self.recovered_errors.as_mut() {
+            Some(errors) => {
+                errors.push(err);
+                Ok(())
+            }
+            None => Err(err),
+        }
+    }
+
+    fn c_include(
+        &mut self,
+        include: &Include,
+        includer: Option<&str>,
+    ) -> Result<(), Error> {
+        let file_name = include.file_name.as_str();
+
+        let (origin, src) = match &self.include_resolver {
+            Some(resolver) => resolver(file_name, includer),
+            None => default_include_resolver(file_name, includer),
+        }
+        .map_err(|err| {
+            CompileError::from(CompileErrorInfo::include_error(
+                &self.report_builder,
+                include.file_name.clone(),
+                err.to_string(),
+                include.span(),
+            ))
+        })?;
+
+        if self.include_stack.contains(&origin) {
+            return Err(Error::from(CompileError::from(
+                CompileErrorInfo::circular_include(
+                    &self.report_builder,
+                    include.file_name.clone(),
+                    include.span(),
+                ),
+            )));
+        }
+
+        let src = SourceCode::from(src.as_slice()).with_origin(&origin);
+
+        self.include_stack.push(origin);
+
+        let result = self.c_source(src);
+
+        self.include_stack.pop();
+
+        result
+    }
+
     fn c_imports(&mut self, imports: &[Import]) -> Result<(), CompileError> {
         for import in imports {
             // Import the module. This updates `self.root_struct` if
@@ -1494,29 +1924,88 @@ impl<'a> Compiler<'a> {
 
             // Create a symbol for the module and insert it in the symbol
             // table for this namespace, if it doesn't exist.
-            if !symbol_table.contains(module_name) {
+            if symbol_table.contains(module_name) {
+                continue;
+            }
+
+            // Ignored modules have a symbol of their own, which tells the
+            // compiler that expressions using the module evaluate as
+            // undefined.
+            if self.ignored_modules.contains(module_name) {
                 symbol_table.insert(
                     module_name,
-                    Symbol::new(
-                        // At this point the module must be found in
-                        // `self.root_struct`.
-                        self.root_struct
-                            .field_by_name(module_name)
-                            .unwrap()
-                            .type_value
-                            .clone(),
-                        SymbolKind::FieldIndex(
-                            self.root_struct.index_of(module_name),
-                        ),
-                    ),
+                    Symbol::new(TypeValue::Unknown, SymbolKind::IgnoredModule),
                 );
+                continue;
             }
+
+            symbol_table.insert(
+                module_name,
+                Symbol::new(
+                    // At this point the module must be found in
+                    // `self.root_struct`.
+                    self.root_struct
+                        .field_by_name(module_name)
+                        .unwrap()
+                        .type_value
+                        .clone(),
+                    SymbolKind::FieldIndex(
+                        self.root_struct.index_of(module_name),
+                    ),
+                ),
+            );
         }
 
         Ok(())
     }
 }
 
+/// Resolves `include` statements by reading files from the filesystem.
+///
+/// Relative paths are interpreted as relative to the directory that contains
+/// the including file, or to the current directory if the including source
+/// code doesn't have an origin. The resulting path is normalized lexically,
+/// so that the same file is always identified by the same path, no matter
+/// how it was included.
+fn default_include_resolver(
+    file_name: &str,
+    includer: Option<&str>,
+) -> io::Result<(String, Vec<u8>)> {
+    let path = match includer.and_then(|includer| Path::new(includer).parent())
+    {
+        Some(dir) => dir.join(file_name),
+        None => PathBuf::from(file_name),
+    };
+
+    let normalized = normalize_path(&path);
+    let src = fs::read(&normalized)?;
+
+    Ok((normalized.to_string_lossy().into_owned(), src))
+}
+
+/// Normalizes a path lexically, by removing `.` components and resolving
+/// `..` components, without accessing the filesystem.
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    normalized
+}
+
 impl fmt::Debug for Compiler<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "Compiler")
@@ -1587,7 +2076,18 @@ impl From<LiteralId> for u64 {
 pub(crate) struct NamespaceId(i32);
 
 /// ID associated to each rule.
-#[derive(Copy, Clone, Debug)]
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Hash,
+    Serialize,
+    Deserialize,
+)]
 pub(crate) struct RuleId(i32);
 
 impl From<i32> for RuleId {
@@ -1612,7 +2112,8 @@ impl From<RuleId> for usize {
 }
 
 /// ID associated to each regexp used in a rule condition.
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
 pub(crate) struct RegexpId(i32);
 
 impl From<i32> for RegexpId {
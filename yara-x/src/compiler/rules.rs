@@ -17,10 +17,16 @@ use crate::compiler::{
     IdentId, Imports, LiteralId, NamespaceId, PatternId, RegexpId, RuleId,
     SubPattern, SubPatternId,
 };
-use crate::re::{BckCodeLoc, FwdCodeLoc, RegexpAtom};
+use crate::re::{BckCodeLoc, CodeLoc, FwdCodeLoc, RegexpAtom};
 use crate::string_pool::{BStringPool, StringPool};
 use crate::{types, SerializationError};
 
+/// Version of the format produced by [`Rules::serialize`]. It must be
+/// incremented every time the layout of serialized [`Rules`] changes, so
+/// that [`Rules::deserialize`] rejects rules serialized with a different
+/// layout instead of misinterpreting them.
+const SERIALIZATION_VERSION: u32 = 2;
+
 /// A set of YARA rules in compiled form.
 ///
 /// This is the result from [`crate::Compiler::build`].
@@ -42,12 +48,12 @@ pub struct Rules {
     /// string as `&BStr`.
     pub(in crate::compiler) lit_pool: BStringPool<LiteralId>,
 
-    /// WASM module already compiled into native code for the current platform.
-    #[serde(
-        serialize_with = "serialize_wasm_mod",
-        deserialize_with = "deserialize_wasm_mod"
-    )]
-    pub(in crate::compiler) wasm_mod: wasmtime::Module,
+    /// WASM modules already compiled into native code for the current
+    /// platform. Rules produced by [`crate::Compiler::build`] have a single
+    /// module, but rules produced by [`Rules::link`] have one module for each
+    /// of the linked rules. The modules are executed in order, and each of
+    /// them evaluates a subset of the rules.
+    pub(in crate::compiler) wasm_mods: Vec<WasmMod>,
 
     /// Vector with the names of all the imported modules. The vector contains
     /// the [`IdentId`] corresponding to the module's identifier.
@@ -142,10 +148,18 @@ impl Rules {
         #[cfg(feature = "logging")]
         let start = Instant::now();
 
-        // Skip the magic and deserialize the remaining data.
-        let mut rules = bincode::DefaultOptions::new()
-            .with_varint_encoding()
-            .deserialize::<Self>(&bytes[magic.len()..])?;
+        let options = bincode::DefaultOptions::new().with_varint_encoding();
+
+        // Skip the magic and read the version, which is followed by the
+        // serialized rules.
+        let mut data = &bytes[magic.len()..];
+        let version: u32 = options.deserialize_from(&mut data)?;
+
+        if version != SERIALIZATION_VERSION {
+            return Err(SerializationError::UnsupportedVersion(version));
+        }
+
+        let mut rules = options.deserialize::<Self>(data)?;
 
         #[cfg(feature = "logging")]
         info!("Deserialization time: {:?}", Instant::elapsed(&start));
@@ -175,13 +189,14 @@ impl Rules {
     {
         let mut writer = BufWriter::new(writer);
 
-        // Write file header.
+        let options = bincode::DefaultOptions::new().with_varint_encoding();
+
+        // Write file header, which includes the format version.
         writer.write_all(b"YARA-X")?;
+        options.serialize_into(&mut writer, &SERIALIZATION_VERSION)?;
 
         // Serialize rules.
-        Ok(bincode::DefaultOptions::new()
-            .with_varint_encoding()
-            .serialize_into(writer, self)?)
+        Ok(options.serialize_into(writer, self)?)
     }
 
     /// Returns a [`RuleInfo`] given its [`RuleId`].
@@ -338,8 +353,119 @@ impl Rules {
     }
 
     #[inline]
-    pub(crate) fn wasm_mod(&self) -> &wasmtime::Module {
-        &self.wasm_mod
+    pub(crate) fn wasm_mods(&self) -> &[WasmMod] {
+        self.wasm_mods.as_slice()
+    }
+}
+
+/// A compiled WASM module that evaluates the conditions for some of the
+/// rules in a [`Rules`].
+///
+/// The identifiers for rules, patterns, literals and regular expressions
+/// embedded in the module's code are the ones assigned by the compiler
+/// that produced it. When the module belongs to rules that were linked
+/// with other rules, those identifiers don't necessarily match the ones in
+/// the linked [`Rules`], and the host translates them using the
+/// information in this structure. This way the module can be linked
+/// without compiling it again.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct WasmMod {
+    /// The module, already compiled into native code.
+    #[serde(
+        serialize_with = "serialize_wasm_mod",
+        deserialize_with = "deserialize_wasm_mod"
+    )]
+    pub(in crate::compiler) module: wasmtime::Module,
+    /// [`RuleId`] in the linked [`Rules`] of the rule that the module
+    /// identifies with `RuleId = 0`. The rules in the module have
+    /// consecutive IDs starting at this one.
+    pub(in crate::compiler) rule_id_base: i32,
+    /// Like `rule_id_base`, but for patterns.
+    pub(in crate::compiler) pattern_id_base: i32,
+    /// The N-th item in this vector is the [`LiteralId`] corresponding to
+    /// the literal that the module identifies with `LiteralId = N`. When
+    /// the vector is empty the module's literal IDs are used as is.
+    pub(in crate::compiler) literals: Vec<LiteralId>,
+    /// Like `literals`, but for regular expressions.
+    pub(in crate::compiler) regexps: Vec<RegexpId>,
+    /// Like `literals`, but for the index of the fields in the root
+    /// structure (i.e: global variables and modules).
+    pub(in crate::compiler) root_fields: Vec<i32>,
+}
+
+impl WasmMod {
+    /// Creates a [`WasmMod`] from a module compiled by the compiler, where
+    /// identifiers don't need to be translated.
+    pub(in crate::compiler) fn new(module: wasmtime::Module) -> Self {
+        Self {
+            module,
+            rule_id_base: 0,
+            pattern_id_base: 0,
+            literals: Vec::new(),
+            regexps: Vec::new(),
+            root_fields: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn module(&self) -> &wasmtime::Module {
+        &self.module
+    }
+
+    /// Returns the [`RuleId`] of the first rule in the module.
+    #[inline]
+    pub(crate) fn first_rule_id(&self) -> RuleId {
+        RuleId(self.rule_id_base)
+    }
+
+    /// Returns the [`PatternId`] of the first pattern in the module.
+    #[inline]
+    pub(crate) fn first_pattern_id(&self) -> PatternId {
+        PatternId(self.pattern_id_base)
+    }
+
+    /// Translates a [`RuleId`] used by the module into the one used by
+    /// the [`Rules`] containing the module.
+    #[inline]
+    pub(crate) fn rule_id(&self, rule_id: RuleId) -> RuleId {
+        RuleId(rule_id.0 + self.rule_id_base)
+    }
+
+    /// Translates a [`PatternId`] used by the module into the one used by
+    /// the [`Rules`] containing the module.
+    #[inline]
+    pub(crate) fn pattern_id(&self, pattern_id: PatternId) -> PatternId {
+        PatternId(pattern_id.0 + self.pattern_id_base)
+    }
+
+    /// Translates a [`LiteralId`] used by the module into the one used by
+    /// the [`Rules`] containing the module.
+    #[inline]
+    pub(crate) fn literal_id(&self, literal_id: LiteralId) -> LiteralId {
+        match self.literals.get(u32::from(literal_id) as usize) {
+            Some(id) => *id,
+            None => literal_id,
+        }
+    }
+
+    /// Translates a [`RegexpId`] used by the module into the one used by
+    /// the [`Rules`] containing the module.
+    #[inline]
+    pub(crate) fn regexp_id(&self, regexp_id: RegexpId) -> RegexpId {
+        match self.regexps.get(usize::from(regexp_id)) {
+            Some(id) => *id,
+            None => regexp_id,
+        }
+    }
+
+    /// Translates the index of a field in the root structure used by the
+    /// module into the one used by the [`Rules`] containing the module.
+    #[inline]
+    pub(crate) fn root_field_index(&self, index: i32) -> i32 {
+        match self.root_fields.get(index as usize) {
+            Some(index) => *index,
+            None => index,
+        }
     }
 }
 
@@ -387,12 +513,35 @@ pub(crate) struct RuleInfo {
     pub(crate) ident_span: Span,
     /// Vector with all the patterns defined by this rule.
     pub(crate) patterns: Vec<(IdentId, PatternId)>,
+    /// Vector with the [`IdentId`] of each tag associated to the rule, in
+    /// the same order they appear in the source code.
+    pub(crate) tags: Vec<IdentId>,
+    /// Vector with the metadata entries defined by the rule. Each entry is
+    /// composed of the [`IdentId`] of the metadata identifier and its value.
+    pub(crate) metadata: Vec<(IdentId, MetaValue)>,
+    /// Rules used in the condition of this rule, sorted by [`RuleId`] and
+    /// without duplicates.
+    pub(crate) depends_on: Vec<RuleId>,
     /// True if the rule is global.
     pub(crate) is_global: bool,
     /// True if the rule is private.
     pub(crate) is_private: bool,
 }
 
+/// The value of a metadata entry in a compiled rule.
+///
+/// String and bytes values are stored in the literals pool, the variants
+/// for those types contain the [`LiteralId`] that identifies the value in
+/// the pool.
+#[derive(Serialize, Deserialize)]
+pub(crate) enum MetaValue {
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    String(LiteralId),
+    Bytes(LiteralId),
+}
+
 /// Represents an atom extracted from a pattern and added to the Aho-Corasick
 /// automata.
 ///
@@ -430,6 +579,30 @@ impl SubPatternAtom {
         }
     }
 
+    /// Returns a copy of this atom where the [`SubPatternId`] is incremented
+    /// by `sub_pattern_offset`, and the locations of the forward and backward
+    /// code are incremented by `re_code_offset`. This is used while linking
+    /// rules, when the sub-patterns and the code of some rules are appended
+    /// to the ones from some other rules.
+    pub(in crate::compiler) fn shifted(
+        &self,
+        sub_pattern_offset: u32,
+        re_code_offset: usize,
+    ) -> Self {
+        Self {
+            sub_pattern_id: SubPatternId(
+                self.sub_pattern_id.0 + sub_pattern_offset,
+            ),
+            atom: self.atom.clone(),
+            fwd_code: self
+                .fwd_code
+                .map(|loc| FwdCodeLoc::from(loc.location() + re_code_offset)),
+            bck_code: self
+                .bck_code
+                .map(|loc| BckCodeLoc::from(loc.location() + re_code_offset)),
+        }
+    }
+
     #[inline]
     pub(crate) fn sub_pattern_id(&self) -> SubPatternId {
         self.sub_pattern_id
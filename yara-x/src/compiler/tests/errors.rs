@@ -890,6 +890,57 @@ rule test {
    │ 
    │ Note: this rule doesn't define any patterns
 ───╯
+"#,
+        ),
+        ////////////////////////////////////////////////////////////
+        (
+            line!(),
+            r#"
+rule test {
+  condition:
+    all of (foo*)
+}
+"#,
+            r#"error: no matching rules
+   ╭─[line:4:13]
+   │
+ 4 │     all of (foo*)
+   │             ──┬─  
+   │               ╰─── there's no rule in this set
+   │ 
+   │ Note: `foo*` doesn't match any rule declared before this one
+───╯
+"#,
+        ),
+        ////////////////////////////////////////////////////////////
+        (
+            line!(),
+            r#"
+rule test_1 {
+  condition:
+    true
+}
+
+global rule test_2 {
+  condition:
+    all of (test_*)
+}
+"#,
+            r#"error: global rule `test_2` depends on non-global rule `test_1`
+   ╭─[line:9:13]
+   │
+ 2 │ rule test_1 {
+   │      ───┬──  
+   │         ╰──── non-global rule `test_1` declared here
+   │ 
+ 7 │ global rule test_2 {
+   │             ───┬──  
+   │                ╰──── global rule `test_2` declared here
+   │ 
+ 9 │     all of (test_*)
+   │             ───┬──  
+   │                ╰──── `test_1` is used in the condition of `test_2`
+───╯
 "#,
         ),
         ////////////////////////////////////////////////////////////
@@ -1,9 +1,14 @@
 use pretty_assertions::assert_eq;
 use serde_json::json;
+use std::collections::HashMap;
+use std::io;
 use std::mem::size_of;
 
+use yara_x_parser::SourceCode;
+
 use crate::compiler::{
-    SerializationError, SubPattern, Var, VarStack, VariableError,
+    CompileErrorInfo, Error, LinkError, SerializationError, SubPattern, Var,
+    VarStack, VariableError,
 };
 use crate::types::Type;
 use crate::{compile, Compiler, Rules, Scanner};
@@ -23,6 +28,11 @@ fn serialization() {
         SerializationError::InvalidEncoding(_)
     ));
 
+    assert!(matches!(
+        Rules::deserialize(b"YARA-X\x00").err().unwrap(),
+        SerializationError::UnsupportedVersion(0)
+    ));
+
     let rules = compile(r#"rule test { strings: $a = "foo" condition: $a }"#)
         .unwrap()
         .serialize()
@@ -43,6 +53,145 @@ fn serialization() {
     assert_eq!(size_of::<SubPattern>(), 24);
 }
 
+#[test]
+fn link() {
+    let mut compiler = Compiler::new();
+
+    compiler
+        .define_global("bar", "bar")
+        .unwrap()
+        .add_source(
+            r#"rule foo_1 {
+                 strings: $a = "foo"
+                 condition: $a and bar matches /ba./
+               }"#,
+        )
+        .unwrap()
+        .add_source(
+            r#"rule foo_2 { condition: foo_1 and "foo" contains "o" }"#,
+        )
+        .unwrap();
+
+    let foo = compiler.build();
+
+    // These rules define the global variable `bar` too, and also `baz`,
+    // so the index of `bar` in the root structure is different in the
+    // linked rules. The initial value of `bar` is taken from `foo`.
+    let mut compiler = Compiler::new();
+
+    compiler
+        .define_global("baz", 1)
+        .unwrap()
+        .define_global("bar", "qux")
+        .unwrap()
+        .new_namespace("bar")
+        .add_source(
+            r#"rule bar_1 {
+                 strings: $a = "bar" $b = "baz"
+                 condition: $a and $b and baz == 1 and "qux" matches /q.x/
+               }"#,
+        )
+        .unwrap()
+        .add_source(r#"rule bar_2 { condition: bar_1 and bar == "bar" }"#)
+        .unwrap();
+
+    let bar = compiler.build();
+
+    let matching_rules = |rules: &Rules, data: &[u8]| {
+        let mut scanner = Scanner::new(rules);
+        let scan_results = scanner.scan(data).expect("scan should not fail");
+        let mut matching_rules: Vec<String> = scan_results
+            .matching_rules()
+            .map(|r| format!("{}:{}", r.namespace(), r.name()))
+            .collect();
+        // The order of matching rules is arbitrary.
+        matching_rules.sort();
+        matching_rules
+    };
+
+    let linked = Rules::link([&foo, &bar]).unwrap();
+
+    assert_eq!(
+        matching_rules(&linked, b"foobarbaz"),
+        vec!["bar:bar_1", "bar:bar_2", "default:foo_1", "default:foo_2"]
+    );
+
+    assert_eq!(
+        matching_rules(&linked, b"barbaz"),
+        vec!["bar:bar_1", "bar:bar_2"]
+    );
+
+    // Linked rules can be serialized.
+    let linked = Rules::deserialize(linked.serialize().unwrap()).unwrap();
+
+    assert_eq!(
+        matching_rules(&linked, b"foobaz"),
+        vec!["default:foo_1", "default:foo_2"]
+    );
+
+    // Linked rules can be linked again.
+    let qux =
+        compile(r#"rule qux { strings: $a = "qux" condition: $a }"#).unwrap();
+
+    let linked = Rules::link([&linked, &qux]).unwrap();
+
+    assert_eq!(
+        matching_rules(&linked, b"qux foo"),
+        vec!["default:foo_1", "default:foo_2", "default:qux"]
+    );
+
+    // Deserialized rules can be linked after some other rules, which
+    // changes the IDs of their rules and patterns. The initial value of
+    // `bar` is taken from `bar` this time, so `bar_2` doesn't match.
+    let bar = Rules::deserialize(bar.serialize().unwrap()).unwrap();
+    let linked = Rules::link([&qux, &bar]).unwrap();
+
+    assert_eq!(matching_rules(&linked, b"barbaz"), vec!["bar:bar_1"]);
+
+    let mut scanner = Scanner::new(&linked);
+    let scan_results = scanner.scan(b"barbaz").unwrap();
+
+    assert_eq!(
+        scan_results
+            .non_matching_rules()
+            .map(|r| r.name())
+            .collect::<Vec<_>>(),
+        vec!["qux", "bar_2"]
+    );
+
+    assert!(matches!(
+        Rules::link([&foo, &foo]).err().unwrap(),
+        LinkError::DuplicateRule { namespace, rule }
+            if namespace == "default" && rule == "foo_1"
+    ));
+
+    // A namespace with global rules can't be shared.
+    let global = compile(r#"global rule g { condition: true }"#).unwrap();
+
+    assert!(matches!(
+        Rules::link([&foo, &global]).err().unwrap(),
+        LinkError::ConflictingNamespace(namespace) if namespace == "default"
+    ));
+
+    // But it can be linked with rules in other namespaces.
+    assert!(Rules::link([&bar, &global]).is_ok());
+
+    // Global variables must have the same type.
+    let mut compiler = Compiler::new();
+
+    compiler
+        .define_global("bar", 1)
+        .unwrap()
+        .new_namespace("other")
+        .add_source("rule test { condition: bar == 1 }")
+        .unwrap();
+
+    assert!(matches!(
+        Rules::link([&foo, &compiler.build()]).err().unwrap(),
+        LinkError::ConflictingGlobal(global) if global == "bar"
+    ));
+}
+
 #[test]
 fn namespaces() {
     // `foo` and `bar` are both in the default namespace, this compiles
@@ -65,6 +214,19 @@ fn namespaces() {
         .new_namespace("bar")
         .add_source("rule bar {condition: foo}")
         .is_err());
+
+    let mut compiler = Compiler::new();
+
+    // Wildcards in rule sets are expanded only to the rules in the current
+    // namespace.
+    assert!(compiler
+        .add_source("rule foo_1 {condition: true}")
+        .unwrap()
+        .add_source("rule foo_2 {condition: any of (foo_*)}")
+        .unwrap()
+        .new_namespace("bar")
+        .add_source("rule bar {condition: any of (foo_*)}")
+        .is_err());
 }
 
 #[test]
@@ -523,6 +685,138 @@ fn import_modules() {
         .is_ok());
 }
 
+#[cfg(feature = "test_proto2-module")]
+#[test]
+fn methods() {
+    // Methods are available in every structure of the type they belong to.
+    assert!(Compiler::new()
+        .add_source(
+            r#"
+            import "test_proto2"
+            rule test {
+              condition:
+                test_proto2.nested.nested_method(1) == 2 and
+                test_proto2.array_struct[0].nested_method(1) == 2 and
+                test_proto2.map_int64_struct[100].nested_method(1) == 2
+            }"#
+        )
+        .is_ok());
+
+    // Methods can't be called as functions of the module, nor on structures
+    // of a different type.
+    assert!(Compiler::new()
+        .add_source(
+            r#"
+            import "test_proto2"
+            rule test { condition: test_proto2.nested_method(1) == 2 }"#
+        )
+        .is_err());
+
+    // Arguments are checked like in any other function.
+    assert!(Compiler::new()
+        .add_source(
+            r#"
+            import "test_proto2"
+            rule test { condition: test_proto2.nested.nested_method("1") }"#
+        )
+        .is_err());
+
+    // Methods are not global functions.
+    assert!(Compiler::new()
+        .add_source(r#"rule test { condition: nested_method(1) == 2 }"#)
+        .is_err());
+
+    // Rules that use methods keep working after being serialized.
+    let rules = compile(
+        r#"
+        import "test_proto2"
+        rule test { condition: test_proto2.nested.nested_method(1) == 2 }"#,
+    )
+    .unwrap();
+
+    let rules = Rules::deserialize(rules.serialize().unwrap()).unwrap();
+    let mut scanner = Scanner::new(&rules);
+
+    assert_eq!(scanner.scan(&[]).unwrap().matching_rules().len(), 1);
+}
+
+#[test]
+fn banned_modules() {
+    let mut compiler = Compiler::new();
+
+    compiler.ban_module("time", "`time` is not deterministic");
+
+    assert_eq!(
+        compiler
+            .add_source(r#"import "time" rule test { condition: true }"#)
+            .unwrap_err()
+            .to_string(),
+        r#"error: module `time` can't be used
+   ╭─[line:1:1]
+   │
+ 1 │ import "time" rule test { condition: true }
+   │ ──────┬──────  
+   │       ╰──────── `time` is not deterministic
+───╯
+"#
+    );
+
+    // Rules that don't import the banned module are not affected.
+    assert!(compiler.add_source("rule test { condition: true }").is_ok());
+}
+
+#[test]
+fn ignored_modules() {
+    let mut compiler = Compiler::new();
+
+    compiler.ignore_module("foo").ignore_module("test_proto2");
+
+    // Ignored modules don't need to exist, and expressions that use them
+    // evaluate as undefined, even when they are type-incorrect.
+    compiler
+        .add_source(
+            r#"
+            import "foo"
+            import "test_proto2"
+            rule test_1 { condition: foo.bar == 1 }
+            rule test_2 { condition: foo.bar == 1 or true }
+            rule test_3 { condition: not defined foo.bar }
+            rule test_4 { condition: defined foo.bar("baz")[0].qux }
+            rule test_5 { condition: test_proto2.int64_one == 1 }
+            rule test_6 { condition: any of (foo, test_proto2, true) }
+            rule test_7 {
+              condition:
+                for any x in foo.bar : ( x == 1 ) or
+                with x = test_proto2.int64_one : ( x == 1 ) or
+                foo.bar + 1 == 2 and true
+            }
+            rule test_8 { condition: not foo.bar }
+            rule test_9 { condition: none of (foo.bar == 1, false) }
+            "#,
+        )
+        .unwrap();
+
+    let rules = compiler.build();
+    let mut scanner = Scanner::new(&rules);
+    let scan_results = scanner.scan(b"").expect("scan should not fail");
+
+    let mut matching_rules: Vec<&str> =
+        scan_results.matching_rules().map(|r| r.name()).collect();
+
+    // The order of matching rules is arbitrary.
+    matching_rules.sort();
+
+    // Items in `of` tuples that depend on an ignored module make the whole
+    // `of` expression undefined, like any other undefined item.
+    assert_eq!(matching_rules, vec!["test_2", "test_3"]);
+
+    // The `import` statement is still required.
+    assert!(Compiler::new()
+        .ignore_module("foo")
+        .add_source("rule test { condition: foo.bar }")
+        .is_err());
+}
+
 #[test]
 fn continue_after_error() {
     let mut compiler = Compiler::new();
@@ -560,3 +854,216 @@ fn continue_after_error() {
 
     assert!(compiler.add_source(r#"rule test { condition: true }"#).is_ok());
 }
+
+#[test]
+fn error_recovery() {
+    let mut compiler = Compiler::new();
+
+    let errors = compiler
+        .add_source_with_recovery(
+            r#"
+            rule foo { condition: true }
+            rule bar { condition: unknown }
+            rule baz { condition: foo }
+            rule qux { condition: bar }
+            "#,
+        )
+        .unwrap_err();
+
+    // `bar` fails because `unknown` doesn't exist, `qux` fails because it
+    // depends on `bar`, which was skipped.
+    assert_eq!(errors.len(), 2);
+
+    assert!(errors.iter().all(|err| matches!(
+        err,
+        Error::CompileError(err)
+            if matches!(err.info(), CompileErrorInfo::UnknownIdentifier { .. })
+    )));
+
+    // The rules that compiled successfully can be used by subsequent
+    // sources.
+    assert!(compiler.add_source("rule quux { condition: baz }").is_ok());
+
+    let rules = compiler.build();
+    let mut scanner = Scanner::new(&rules);
+
+    assert_eq!(
+        scanner
+            .scan(b"")
+            .expect("scan should not fail")
+            .matching_rules()
+            .len(),
+        3
+    );
+
+    // Syntax errors affect the source code as a whole.
+    let errors = Compiler::new()
+        .add_source_with_recovery(
+            "rule foo { condition: true } rule bar { condition: }",
+        )
+        .unwrap_err();
+
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(errors[0], Error::ParseError(_)));
+
+    // Errors in included files are recovered too.
+    let mut compiler = Compiler::new();
+
+    compiler.include_resolver(|file_name, _| match file_name {
+        "bad.yar" => Ok((file_name.to_string(), b"rule bad {".to_vec())),
+        _ => Err(io::Error::from(io::ErrorKind::NotFound)),
+    });
+
+    let errors = compiler
+        .add_source_with_recovery(
+            r#"
+            include "bad.yar"
+            rule foo { condition: true }
+            include "missing.yar"
+            "#,
+        )
+        .unwrap_err();
+
+    assert_eq!(errors.len(), 2);
+    assert!(matches!(errors[0], Error::ParseError(_)));
+    assert!(matches!(
+        &errors[1],
+        Error::CompileError(err)
+            if matches!(err.info(), CompileErrorInfo::IncludeError { .. })
+    ));
+
+    // Without recovery, the compilation stops at the first error.
+    let err = Compiler::new()
+        .add_source("rule a { condition: b } rule b { condition: c }")
+        .err()
+        .unwrap();
+
+    assert!(err.to_string().starts_with("error: unknown identifier `b`"));
+}
+
+#[test]
+fn includes() {
+    let files = HashMap::from([
+        ("common.yar", "rule common { condition: true }"),
+        (
+            "family.yar",
+            r#"include "common.yar" rule family { condition: common }"#,
+        ),
+        ("loop1.yar", r#"include "loop2.yar""#),
+        ("loop2.yar", r#"include "loop1.yar""#),
+        ("bad.yar", "rule bad {\n  condition: \"foo\" == 2\n}"),
+    ]);
+
+    let resolver = |file_name: &str, _: Option<&str>| {
+        files
+            .get(file_name)
+            .map(|src| (file_name.to_string(), src.as_bytes().to_vec()))
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+    };
+
+    let mut compiler = Compiler::new();
+
+    compiler
+        .include_resolver(resolver)
+        .add_source(
+            r#"
+            include "family.yar"
+            rule test { condition: common and family }"#,
+        )
+        .unwrap();
+
+    let rules = compiler.build();
+    let mut scanner = Scanner::new(&rules);
+
+    assert_eq!(
+        scanner
+            .scan(b"")
+            .expect("scan should not fail")
+            .matching_rules()
+            .len(),
+        3
+    );
+
+    // Rules in an included file are not visible to the rules declared
+    // before the `include` statement.
+    assert!(Compiler::new()
+        .include_resolver(resolver)
+        .add_source(r#"rule test { condition: common } include "common.yar""#)
+        .is_err());
+
+    let mut compiler = Compiler::new();
+    compiler.include_resolver(resolver);
+
+    assert!(matches!(
+        compiler.add_source(r#"include "missing.yar""#).err().unwrap(),
+        Error::CompileError(err)
+            if matches!(err.info(), CompileErrorInfo::IncludeError { .. })
+    ));
+
+    assert!(matches!(
+        compiler.add_source(r#"include "loop1.yar""#).err().unwrap(),
+        Error::CompileError(err)
+            if matches!(err.info(), CompileErrorInfo::CircularInclude { .. })
+    ));
+
+    // Errors in included files must refer to the included file.
+    assert!(compiler
+        .add_source(r#"include "bad.yar""#)
+        .err()
+        .unwrap()
+        .to_string()
+        .contains("[bad.yar:2:14]"));
+
+    // A file that includes itself is detected as circular, even if the
+    // origin of the top-level source is not normalized. The file in disk
+    // doesn't include itself, so the error can only come from the
+    // top-level source.
+    let dir = std::env::temp_dir()
+        .join(format!("yara-x-includes-{}", std::process::id()));
+
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("a.yar"), "rule a { condition: true }").unwrap();
+
+    let origin = dir.join(".").join("a.yar");
+    let origin = origin.to_str().unwrap();
+
+    let err = Compiler::new()
+        .add_source(
+            SourceCode::from(r#"include "./a.yar""#).with_origin(origin),
+        )
+        .err()
+        .unwrap();
+
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(matches!(
+        err,
+        Error::CompileError(err)
+            if matches!(err.info(), CompileErrorInfo::CircularInclude { .. })
+    ));
+}
+
+#[test]
+fn includes_with_custom_origins() {
+    let origin = "https://example.com/rules/../main.yar";
+
+    let mut compiler = Compiler::new();
+
+    // Origins used with a custom resolver are not necessarily paths, and
+    // they are not normalized. Here the resolver returns the origin of the
+    // top-level source, so the include is circular.
+    compiler.include_resolver(|_, _| {
+        Ok((origin.to_string(), b"rule a { condition: true }".to_vec()))
+    });
+
+    assert!(matches!(
+        compiler
+            .add_source(
+                SourceCode::from(r#"include "main.yar""#).with_origin(origin),
+            )
+            .err()
+            .unwrap(),
+        Error::CompileError(err)
+            if matches!(err.info(), CompileErrorInfo::CircularInclude { .. })
+    ));
+}
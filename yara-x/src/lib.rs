@@ -46,20 +46,36 @@ pub use compiler::CompileError;
 pub use compiler::CompileErrorInfo;
 pub use compiler::Compiler;
 pub use compiler::Error;
+pub use compiler::LinkError;
 pub use compiler::Rules;
 pub use compiler::SerializationError;
 
+pub use scanner::BlockScanner;
+pub use scanner::CallbackAction;
+pub use scanner::CancelHandle;
+pub use scanner::LimitKind;
 pub use scanner::Match;
 pub use scanner::Matches;
 pub use scanner::MatchingRules;
+pub use scanner::MetaValue;
+pub use scanner::Metadata;
 pub use scanner::ModuleOutputs;
 pub use scanner::NonMatchingRules;
 pub use scanner::Pattern;
+#[cfg(feature = "rules-profiling")]
+pub use scanner::PatternProfilingData;
 pub use scanner::Patterns;
+pub use scanner::PooledScanner;
+#[cfg(feature = "rules-profiling")]
+pub use scanner::ProfilingData;
 pub use scanner::Rule;
+pub use scanner::RuleFilter;
 pub use scanner::ScanError;
+pub use scanner::ScanEvent;
 pub use scanner::ScanResults;
 pub use scanner::Scanner;
+pub use scanner::ScannerPool;
+pub use scanner::Tags;
 
 pub use modules::mods;
 
@@ -0,0 +1,97 @@
+/*! YARA module for printing values from rule conditions.
+
+The functions in this module always return `true`, which means that they
+can be used at any point in a rule condition without altering its result.
+The messages are sent to the callback set with
+[`crate::Scanner::console_log`], if no callback is set the messages are
+silently discarded.
+ */
+
+use crate::modules::prelude::*;
+use crate::modules::protos::console::*;
+
+#[cfg(test)]
+mod tests;
+
+#[module_main]
+fn main(_data: &[u8]) -> Console {
+    // Nothing to do, but we have to return our protobuf
+    Console::new()
+}
+
+#[module_export(name = "log")]
+fn log_str(ctx: &mut ScanContext, string: RuntimeString) -> bool {
+    let message = escape(string.as_bstr(ctx));
+    ctx.console_log(message);
+    true
+}
+
+#[module_export(name = "log")]
+fn log_msg_str(
+    ctx: &mut ScanContext,
+    message: RuntimeString,
+    string: RuntimeString,
+) -> bool {
+    let message =
+        format!("{}{}", message.as_bstr(ctx), escape(string.as_bstr(ctx)));
+    ctx.console_log(message);
+    true
+}
+
+#[module_export(name = "log")]
+fn log_int(ctx: &mut ScanContext, i: i64) -> bool {
+    ctx.console_log(format!("{}", i));
+    true
+}
+
+#[module_export(name = "log")]
+fn log_msg_int(ctx: &mut ScanContext, message: RuntimeString, i: i64) -> bool {
+    let message = format!("{}{}", message.as_bstr(ctx), i);
+    ctx.console_log(message);
+    true
+}
+
+#[module_export(name = "log")]
+fn log_float(ctx: &mut ScanContext, f: f64) -> bool {
+    ctx.console_log(format!("{}", f));
+    true
+}
+
+#[module_export(name = "log")]
+fn log_msg_float(
+    ctx: &mut ScanContext,
+    message: RuntimeString,
+    f: f64,
+) -> bool {
+    let message = format!("{}{}", message.as_bstr(ctx), f);
+    ctx.console_log(message);
+    true
+}
+
+#[module_export(name = "hex")]
+fn hex_int(ctx: &mut ScanContext, i: i64) -> bool {
+    ctx.console_log(format!("0x{:x}", i));
+    true
+}
+
+#[module_export(name = "hex")]
+fn hex_msg_int(ctx: &mut ScanContext, message: RuntimeString, i: i64) -> bool {
+    let message = format!("{}0x{:x}", message.as_bstr(ctx), i);
+    ctx.console_log(message);
+    true
+}
+
+/// Returns a string where printable ASCII characters are kept as is, and
+/// any other byte is represented as `\xNN`. The strings logged may come
+/// from the scanned data, which can contain arbitrary bytes.
+fn escape(s: &[u8]) -> String {
+    let mut result = String::with_capacity(s.len());
+    for b in s {
+        if b.is_ascii_graphic() || *b == b' ' {
+            result.push(*b as char);
+        } else {
+            result.push_str(&format!("\\x{:02x}", b));
+        }
+    }
+    result
+}
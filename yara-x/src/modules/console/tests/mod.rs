@@ -0,0 +1,72 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::tests;
+use tests::*;
+
+#[test]
+#[cfg(feature = "console-module")]
+fn console_log() {
+    let rules = crate::compile(
+        r#"
+        import "console"
+        rule test {
+          condition:
+            console.log("foo") and
+            console.log("bar: ", "baz") and
+            console.log(1) and
+            console.log("int: ", -1) and
+            console.log(1.5) and
+            console.log("float: ", 0.25) and
+            console.hex(255) and
+            console.hex("hex: ", 16) and
+            console.log("data: ", uint8(0)) and
+            console.log("str: ", "\x00\x01ab")
+        }
+        "#,
+    )
+    .unwrap();
+
+    let messages = Rc::new(RefCell::new(Vec::new()));
+    let mut scanner = crate::scanner::Scanner::new(&rules);
+
+    scanner.console_log({
+        let messages = messages.clone();
+        move |message| messages.borrow_mut().push(message)
+    });
+
+    assert_eq!(scanner.scan(b"\x07").unwrap().matching_rules().len(), 1);
+    assert_eq!(
+        messages.borrow().as_slice(),
+        [
+            "foo",
+            "bar: baz",
+            "1",
+            "int: -1",
+            "1.5",
+            "float: 0.25",
+            "0xff",
+            "hex: 0x10",
+            "data: 7",
+            "str: \\x00\\x01ab",
+        ]
+    );
+}
+
+#[test]
+#[cfg(feature = "console-module")]
+fn console_without_callback() {
+    rule_true!(
+        r#"
+        import "console"
+        rule test {
+          condition:
+            console.log("foo") and
+            console.log("int: ", -1) and
+            console.hex("hex: ", 16) and
+            console.log("data: ", uint8(0))
+        }
+        "#,
+        b"\x07"
+    );
+}
@@ -0,0 +1,198 @@
+/*! YARA module for writing rules based on the behaviour of a file.
+
+This module is compatible with the `cuckoo` module in YARA. The behaviour
+is obtained from a JSON report produced by a sandbox like [Cuckoo][1]. The
+module doesn't parse the scanned data, instead, the report is converted with
+[`Cuckoo::from_json_report`] and passed to the scanner with
+[`crate::Scanner::set_module_output`]. When no report is provided, all the
+functions in this module return `false`.
+
+[1]: https://cuckoosandbox.org
+ */
+
+use protobuf::MessageField;
+use serde::Deserialize;
+
+use crate::compiler::RegexpId;
+use crate::modules::prelude::*;
+use crate::modules::protos::cuckoo::*;
+
+#[cfg(test)]
+mod tests;
+
+#[module_main]
+fn main(_data: &[u8]) -> Cuckoo {
+    // The output of this module is always provided by the user, when this
+    // function is called there's no report for the scanned file.
+    Cuckoo::new()
+}
+
+/// Returns true if the file resolved some domain name that matches the
+/// given regular expression.
+#[module_export(name = "network.dns_lookup")]
+fn network_dns_lookup(ctx: &ScanContext, regexp_id: RegexpId) -> Option<bool> {
+    let cuckoo = ctx.module_output::<Cuckoo>()?;
+    Some(cuckoo.network.dns.iter().any(|dns| {
+        dns.request.as_ref().is_some_and(|request| {
+            ctx.regexp_matches(regexp_id, request.as_bytes())
+        })
+    }))
+}
+
+/// Returns true if the file sent some HTTP request to a URI that matches the
+/// given regular expression.
+#[module_export(name = "network.http_request")]
+fn network_http_request(
+    ctx: &ScanContext,
+    regexp_id: RegexpId,
+) -> Option<bool> {
+    let cuckoo = ctx.module_output::<Cuckoo>()?;
+    Some(cuckoo.network.http.iter().any(|http| {
+        http.uri
+            .as_ref()
+            .is_some_and(|uri| ctx.regexp_matches(regexp_id, uri.as_bytes()))
+    }))
+}
+
+/// Returns true if the file created or opened some mutex with a name that
+/// matches the given regular expression.
+#[module_export(name = "sync.mutex")]
+fn sync_mutex(ctx: &ScanContext, regexp_id: RegexpId) -> Option<bool> {
+    let cuckoo = ctx.module_output::<Cuckoo>()?;
+    Some(any_matches(ctx, regexp_id, &cuckoo.sync.mutexes))
+}
+
+/// Returns true if the file accessed some file with a path that matches the
+/// given regular expression.
+#[module_export(name = "filesystem.file_access")]
+fn filesystem_file_access(
+    ctx: &ScanContext,
+    regexp_id: RegexpId,
+) -> Option<bool> {
+    let cuckoo = ctx.module_output::<Cuckoo>()?;
+    Some(any_matches(ctx, regexp_id, &cuckoo.filesystem.files))
+}
+
+/// Returns true if the file accessed some registry key that matches the
+/// given regular expression.
+#[module_export(name = "registry.key_access")]
+fn registry_key_access(
+    ctx: &ScanContext,
+    regexp_id: RegexpId,
+) -> Option<bool> {
+    let cuckoo = ctx.module_output::<Cuckoo>()?;
+    Some(any_matches(ctx, regexp_id, &cuckoo.registry.keys))
+}
+
+fn any_matches(
+    ctx: &ScanContext,
+    regexp_id: RegexpId,
+    items: &[String],
+) -> bool {
+    items.iter().any(|item| ctx.regexp_matches(regexp_id, item.as_bytes()))
+}
+
+impl Cuckoo {
+    /// Creates a [`Cuckoo`] structure from a JSON report produced by the
+    /// Cuckoo sandbox.
+    ///
+    /// Only the parts of the report used by this module are taken into
+    /// account, any other field is ignored. The resulting structure can be
+    /// passed to [`crate::Scanner::set_module_output`].
+    pub fn from_json_report(report: &[u8]) -> Result<Self, serde_json::Error> {
+        let report: Report = serde_json::from_slice(report)?;
+        Ok(report.into())
+    }
+}
+
+/// Subset of the JSON report produced by the Cuckoo sandbox.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Report {
+    network: ReportNetwork,
+    behavior: ReportBehavior,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ReportNetwork {
+    dns: Vec<ReportDns>,
+    http: Vec<ReportHttp>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ReportDns {
+    #[serde(alias = "hostname")]
+    request: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ReportHttp {
+    method: Option<String>,
+    uri: Option<String>,
+    #[serde(rename = "user-agent")]
+    user_agent: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ReportBehavior {
+    summary: ReportSummary,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ReportSummary {
+    mutexes: Vec<String>,
+    files: Vec<String>,
+    keys: Vec<String>,
+}
+
+impl From<Report> for Cuckoo {
+    fn from(report: Report) -> Self {
+        let mut network = Network::new();
+
+        network.dns = report
+            .network
+            .dns
+            .into_iter()
+            .map(|dns| DnsLookup {
+                request: dns.request,
+                ..Default::default()
+            })
+            .collect();
+
+        network.http = report
+            .network
+            .http
+            .into_iter()
+            .map(|http| HttpRequest {
+                method: http.method,
+                uri: http.uri,
+                user_agent: http.user_agent,
+                ..Default::default()
+            })
+            .collect();
+
+        let summary = report.behavior.summary;
+
+        let mut sync = Synchronization::new();
+        sync.mutexes = summary.mutexes;
+
+        let mut filesystem = Filesystem::new();
+        filesystem.files = summary.files;
+
+        let mut registry = Registry::new();
+        registry.keys = summary.keys;
+
+        let mut cuckoo = Cuckoo::new();
+
+        cuckoo.network = MessageField::some(network);
+        cuckoo.sync = MessageField::some(sync);
+        cuckoo.filesystem = MessageField::some(filesystem);
+        cuckoo.registry = MessageField::some(registry);
+        cuckoo
+    }
+}
@@ -0,0 +1,110 @@
+use crate::modules::protos::cuckoo::Cuckoo;
+use crate::scanner::Scanner;
+
+const REPORT: &str = "src/modules/cuckoo/tests/testdata/report.json";
+
+/// Returns true if the rule matches when the report in [`REPORT`] is used
+/// as the output of the `cuckoo` module.
+fn matches_with_report(rule: &str) -> bool {
+    let rules = crate::compile(rule).unwrap();
+    let report = std::fs::read(REPORT).unwrap();
+    let cuckoo = Cuckoo::from_json_report(report.as_slice()).unwrap();
+
+    let mut scanner = Scanner::new(&rules);
+
+    scanner.set_module_output("cuckoo", Box::new(cuckoo)).unwrap();
+    scanner.scan(b"").unwrap().matching_rules().len() == 1
+}
+
+#[test]
+fn network() {
+    assert!(matches_with_report(
+        r#"
+        import "cuckoo"
+        rule test {
+          condition:
+            cuckoo.network.dns_lookup(/update\.example\.com/) and
+            cuckoo.network.dns_lookup(/^time\./) and
+            not cuckoo.network.dns_lookup(/evil/)
+        }
+        "#
+    ));
+
+    assert!(matches_with_report(
+        r#"
+        import "cuckoo"
+        rule test {
+          condition:
+            cuckoo.network.http_request(/\/gate\.php$/) and
+            not cuckoo.network.http_request(/https:/)
+        }
+        "#
+    ));
+}
+
+#[test]
+fn behavior() {
+    assert!(matches_with_report(
+        r#"
+        import "cuckoo"
+        rule test {
+          condition:
+            cuckoo.sync.mutex(/SampleMutex_\d+/) and
+            not cuckoo.sync.mutex(/samplemutex/) and
+            cuckoo.sync.mutex(/samplemutex/i)
+        }
+        "#
+    ));
+
+    assert!(matches_with_report(
+        r#"
+        import "cuckoo"
+        rule test {
+          condition:
+            cuckoo.filesystem.file_access(/\\svchost\.exe$/) and
+            not cuckoo.filesystem.file_access(/\.dll$/)
+        }
+        "#
+    ));
+
+    assert!(matches_with_report(
+        r#"
+        import "cuckoo"
+        rule test {
+          condition:
+            cuckoo.registry.key_access(/CurrentVersion\\Run$/) and
+            not cuckoo.registry.key_access(/RunOnce/)
+        }
+        "#
+    ));
+}
+
+#[test]
+fn without_report() {
+    let rules = crate::compile(
+        r#"
+        import "cuckoo"
+        rule test {
+          condition:
+            cuckoo.network.dns_lookup(/.*/) or
+            cuckoo.network.http_request(/.*/) or
+            cuckoo.sync.mutex(/.*/) or
+            cuckoo.filesystem.file_access(/.*/) or
+            cuckoo.registry.key_access(/.*/)
+        }
+        "#,
+    )
+    .unwrap();
+
+    let mut scanner = Scanner::new(&rules);
+
+    assert_eq!(scanner.scan(b"").unwrap().matching_rules().len(), 0);
+}
+
+#[test]
+fn invalid_report() {
+    assert!(Cuckoo::from_json_report(b"{").is_err());
+    assert!(Cuckoo::from_json_report(b"{\"network\": 1}").is_err());
+    // Reports without any of the fields used by the module are accepted.
+    assert!(Cuckoo::from_json_report(b"{\"info\": {}}").is_ok());
+}
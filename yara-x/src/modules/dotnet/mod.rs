@@ -0,0 +1,32 @@
+/*! YARA module that parses .NET files.
+
+.NET assemblies are PE files containing a CLR header that points to the
+assembly's metadata. This module parses the metadata, including the streams
+(#Strings, #US, #GUID, #Blob) and the metadata tables, and exposes
+information like the assembly name and version, the referenced assemblies,
+resources, user strings, classes and methods, etc.
+
+This module is based on the [`ECMA-335 specification`][1].
+
+[1]: https://www.ecma-international.org/publications-and-standards/standards/ecma-335/
+ */
+
+use crate::modules::prelude::*;
+use crate::modules::protos::dotnet::*;
+
+#[cfg(test)]
+mod tests;
+
+pub mod parser;
+
+#[module_main]
+fn main(input: &[u8]) -> Dotnet {
+    match parser::Dotnet::parse(input) {
+        Ok(dotnet) => dotnet.into(),
+        Err(_) => {
+            let mut dotnet = Dotnet::new();
+            dotnet.is_dotnet = Some(false);
+            dotnet
+        }
+    }
+}
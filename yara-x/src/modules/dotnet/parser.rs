@@ -0,0 +1,1515 @@
+use std::cmp::min;
+use std::ops::{Range, RangeInclusive};
+use std::str::from_utf8;
+
+use byteorder::{ByteOrder, LE};
+use nom::bytes::complete::{take, take_till};
+use nom::combinator::{cond, fail, map, verify};
+use nom::error::ErrorKind;
+use nom::multi::{count, length_data};
+use nom::number::complete::{le_u16, le_u32, le_u64, u8};
+use nom::sequence::{preceded, tuple};
+use nom::{Err, IResult};
+use protobuf::MessageField;
+use rustc_hash::FxHashMap;
+
+use crate::modules::pe::parser::PE;
+use crate::modules::protos::dotnet;
+use Column::*;
+
+type Error<'a> = nom::error::Error<&'a [u8]>;
+
+/// Represents a .NET assembly.
+///
+/// New instances of this type are created by parsing the content of a .NET
+/// file with the [`Dotnet::parse`] function. .NET assemblies are PE files
+/// with a CLR header that points to the metadata. The metadata consists of
+/// a series of streams, some of them are heaps that contain strings, GUIDs
+/// and blobs, and one of them (`#~`) contains the metadata tables, which
+/// describe the types, methods, resources, etc. defined by the assembly.
+pub struct Dotnet<'a> {
+    /// Slice that contains the whole file.
+    data: &'a [u8],
+
+    /// Runtime version (major, minor) indicated in the CLR header.
+    runtime_version: (u16, u16),
+
+    /// File offset where the resources indicated in the CLR header start.
+    resources_offset: Option<u32>,
+
+    /// Version string stored in the metadata root.
+    version: Option<&'a str>,
+
+    /// Metadata streams.
+    streams: Vec<Stream<'a>>,
+
+    /// Content of the `#Strings` heap.
+    strings_heap: &'a [u8],
+
+    /// Content of the `#US` heap.
+    user_strings_heap: &'a [u8],
+
+    /// Content of the `#GUID` heap.
+    guid_heap: &'a [u8],
+
+    /// Content of the `#Blob` heap.
+    blob_heap: &'a [u8],
+
+    /// Metadata tables, indexed by table number. Tables not present in
+    /// the file have zero rows.
+    tables: Vec<Table<'a>>,
+
+    /// Maps nested types (TypeDef row IDs) to their enclosing types.
+    enclosing_types: FxHashMap<u32, u32>,
+
+    /// Maps types (TypeDef row IDs) to the interfaces they implement. The
+    /// interfaces are TypeDefOrRef coded indexes.
+    interfaces: FxHashMap<u32, Vec<u32>>,
+
+    /// Names of the generic parameters for each type or method. Keys are
+    /// (table, row ID) pairs, where table is either [`TYPE_DEF`] or
+    /// [`METHOD_DEF`].
+    generic_params: FxHashMap<(usize, u32), Vec<&'a str>>,
+}
+
+impl<'a> Dotnet<'a> {
+    /// Given the content of .NET file, parses it and returns a [`Dotnet`]
+    /// object representing the file.
+    pub fn parse(input: &'a [u8]) -> Result<Self, Err<Error<'a>>> {
+        let pe = PE::parse(input)?;
+
+        let (_, _, cli_header) = pe
+            .get_dir_entry_data(PE::IMAGE_DIRECTORY_ENTRY_COM_DESCRIPTOR)
+            .ok_or_else(|| Self::error(input))?;
+
+        let (
+            _,
+            (
+                _cb,
+                major_runtime_version,
+                minor_runtime_version,
+                metadata_rva,
+                _metadata_size,
+                _flags,
+                _entry_point,
+                resources_rva,
+                _resources_size,
+            ),
+        ) = tuple((
+            le_u32, // cb
+            le_u16, // major_runtime_version
+            le_u16, // minor_runtime_version
+            le_u32, // metadata_rva
+            le_u32, // metadata_size
+            le_u32, // flags
+            le_u32, // entry_point_token
+            le_u32, // resources_rva
+            le_u32, // resources_size
+        ))(cli_header)?;
+
+        let metadata_offset = pe
+            .rva_to_offset(metadata_rva)
+            .ok_or_else(|| Self::error(input))?;
+
+        // The metadata size indicated in the CLR header is not used for
+        // limiting the metadata, streams can go beyond that size, as long
+        // as they are within the file.
+        let metadata = input
+            .get(metadata_offset as usize..)
+            .ok_or_else(|| Self::error(input))?;
+
+        let (_, (version, stream_headers)) =
+            Self::parse_metadata_root(metadata)?;
+
+        let streams = stream_headers
+            .into_iter()
+            .map(|(offset, size, name)| {
+                let data = metadata
+                    .get(offset as usize..)
+                    .map(|data| &data[..min(data.len(), size as usize)])
+                    .unwrap_or_default();
+                Stream {
+                    name,
+                    offset: metadata_offset.saturating_add(offset),
+                    size,
+                    data,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // If some stream appears more than once, the first one is used.
+        let stream_data = |names: &[&str]| {
+            streams
+                .iter()
+                .find(|stream| names.contains(&stream.name))
+                .map(|stream| stream.data)
+                .unwrap_or_default()
+        };
+
+        // The `#-` stream is the uncompressed version of `#~`. Both of them
+        // have the same layout, except that `#-` may contain tables that
+        // are not parsed here, like FieldPtr or MethodPtr.
+        let tables = Self::parse_tables(stream_data(&["#~", "#-"]))
+            .map(|(_, tables)| tables)
+            .unwrap_or_else(|_| vec![Table::default(); NUM_TABLES]);
+
+        let mut dotnet = Dotnet {
+            data: input,
+            runtime_version: (major_runtime_version, minor_runtime_version),
+            resources_offset: if resources_rva != 0 {
+                pe.rva_to_offset(resources_rva)
+            } else {
+                None
+            },
+            version: from_utf8(version).ok(),
+            strings_heap: stream_data(&["#Strings"]),
+            user_strings_heap: stream_data(&["#US"]),
+            guid_heap: stream_data(&["#GUID"]),
+            blob_heap: stream_data(&["#Blob"]),
+            streams,
+            tables,
+            enclosing_types: FxHashMap::default(),
+            interfaces: FxHashMap::default(),
+            generic_params: FxHashMap::default(),
+        };
+
+        dotnet.enclosing_types = dotnet.parse_nested_classes();
+        dotnet.interfaces = dotnet.parse_interface_impls();
+        dotnet.generic_params = dotnet.parse_generic_params();
+
+        Ok(dotnet)
+    }
+}
+
+impl<'a> Dotnet<'a> {
+    /// Signature of the metadata root ("BSJB").
+    const METADATA_SIGNATURE: u32 = 0x424A5342;
+
+    /// Maximum depth while resolving nested types and type signatures.
+    const MAX_TYPE_DEPTH: usize = 16;
+
+    /// Maximum rank for arrays in type signatures.
+    const MAX_ARRAY_RANK: u32 = 32;
+
+    /// Calling convention flag indicating that a method is generic.
+    const SIG_GENERIC: u8 = 0x10;
+
+    /// Bit in the `heap_sizes` field indicating that extra data follows
+    /// the number of rows in the tables header.
+    const EXTRA_DATA: u8 = 0x40;
+
+    // Flags in TypeDef.Flags.
+    const TYPE_VISIBILITY_MASK: u32 = 0x07;
+    const TYPE_INTERFACE: u32 = 0x20;
+    const TYPE_ABSTRACT: u32 = 0x80;
+    const TYPE_SEALED: u32 = 0x100;
+
+    // Flags in MethodDef.Flags.
+    const METHOD_ACCESS_MASK: u32 = 0x07;
+    const METHOD_STATIC: u32 = 0x10;
+    const METHOD_FINAL: u32 = 0x20;
+    const METHOD_VIRTUAL: u32 = 0x40;
+    const METHOD_ABSTRACT: u32 = 0x400;
+
+    fn error(input: &'a [u8]) -> Err<Error<'a>> {
+        Err::Error(Error::new(input, ErrorKind::Fail))
+    }
+
+    /// Parses the metadata root, returning the version string and the
+    /// stream headers as (offset, size, name) tuples.
+    #[allow(clippy::type_complexity)]
+    fn parse_metadata_root(
+        input: &'a [u8],
+    ) -> IResult<&'a [u8], (&'a [u8], Vec<(u32, u32, &'a str)>)> {
+        let (input, _signature) = verify(le_u32, |signature| {
+            *signature == Self::METADATA_SIGNATURE
+        })(input)?;
+
+        let (input, (_major, _minor, _reserved)) =
+            tuple((le_u16, le_u16, le_u32))(input)?;
+
+        // The version string is prefixed by its length, which includes the
+        // null terminator and is rounded up to a multiple of 4.
+        let (input, version) = length_data(le_u32)(input)?;
+        let (_, version) = take_till(|c| c == 0)(version)?;
+
+        let (input, (_flags, num_streams)) = tuple((le_u16, le_u16))(input)?;
+
+        let (input, stream_headers) =
+            count(Self::parse_stream_header, num_streams as usize)(input)?;
+
+        Ok((input, (version, stream_headers)))
+    }
+
+    /// Parses a stream header, returning the stream's offset, size and name.
+    ///
+    /// The stream name is a null-terminated ASCII string padded to the next
+    /// 4-byte boundary.
+    fn parse_stream_header(
+        input: &'a [u8],
+    ) -> IResult<&'a [u8], (u32, u32, &'a str)> {
+        let (input, (offset, size)) = tuple((le_u32, le_u32))(input)?;
+        let (_, name) = take_till(|c| c == 0)(input)?;
+        let (input, _) = take(Self::round_up(name.len() + 1))(input)?;
+        let name = from_utf8(name).map_err(|_| Self::error(input))?;
+        Ok((input, (offset, size, name)))
+    }
+
+    /// Parses the `#~` stream, which contains the metadata tables.
+    fn parse_tables(input: &'a [u8]) -> IResult<&'a [u8], Vec<Table<'a>>> {
+        let (
+            mut input,
+            (
+                _reserved,
+                _major,
+                _minor,
+                heap_sizes,
+                _reserved2,
+                valid,
+                _sorted,
+            ),
+        ) = tuple((le_u32, u8, u8, u8, u8, le_u64, le_u64))(input)?;
+
+        // The header is followed by the number of rows in each table, but
+        // only for tables that are present according to the `valid` bitmap.
+        let mut rows = [0_u32; 64];
+
+        for (i, num_rows) in rows.iter_mut().enumerate() {
+            if valid & (1 << i) != 0 {
+                (input, *num_rows) = le_u32(input)?;
+            }
+        }
+
+        // When this bit is set there are 4 extra bytes after the number of
+        // rows. This is not documented in ECMA-335, but the CLR handles it.
+        let (mut input, _) =
+            cond(heap_sizes & Self::EXTRA_DATA != 0, le_u32)(input)?;
+
+        let sizes = IndexSizes { heap_sizes, rows };
+        let mut tables = vec![Table::default(); NUM_TABLES];
+
+        for (id, schema) in TABLE_SCHEMAS.iter().enumerate() {
+            if rows[id] == 0 {
+                continue;
+            }
+
+            let mut columns = Vec::with_capacity(schema.len());
+            let mut row_size = 0;
+
+            for column in schema.iter() {
+                let width = sizes.width(column);
+                columns.push((row_size, width));
+                row_size += width;
+            }
+
+            // Tables are stored one after the other. If some table is
+            // truncated, the tables that come after it can't be parsed.
+            let table_size = row_size.saturating_mul(rows[id] as usize);
+
+            let (remainder, data) =
+                match take::<_, _, Error>(table_size)(input) {
+                    Ok(result) => result,
+                    Err(_) => break,
+                };
+
+            tables[id] = Table { data, num_rows: rows[id], row_size, columns };
+
+            input = remainder;
+        }
+
+        Ok((input, tables))
+    }
+
+    fn parse_nested_classes(&self) -> FxHashMap<u32, u32> {
+        let nested_classes = &self.tables[NESTED_CLASS];
+        nested_classes
+            .rids()
+            .filter_map(|rid| {
+                Some((
+                    nested_classes.get(rid, 0)?,
+                    nested_classes.get(rid, 1)?,
+                ))
+            })
+            .collect()
+    }
+
+    fn parse_interface_impls(&self) -> FxHashMap<u32, Vec<u32>> {
+        let interface_impls = &self.tables[INTERFACE_IMPL];
+        let mut result: FxHashMap<u32, Vec<u32>> = FxHashMap::default();
+
+        for rid in interface_impls.rids() {
+            if let (Some(class), Some(interface)) =
+                (interface_impls.get(rid, 0), interface_impls.get(rid, 1))
+            {
+                result.entry(class).or_default().push(interface);
+            }
+        }
+
+        result
+    }
+
+    fn parse_generic_params(&self) -> FxHashMap<(usize, u32), Vec<&'a str>> {
+        let generic_params = &self.tables[GENERIC_PARAM];
+        let mut params: FxHashMap<(usize, u32), Vec<(u32, &'a str)>> =
+            FxHashMap::default();
+
+        for rid in generic_params.rids() {
+            let number = generic_params.get(rid, 0);
+            let owner = generic_params.get(rid, 2);
+            let name =
+                generic_params.get(rid, 3).and_then(|name| self.string(name));
+
+            if let (Some(number), Some(owner), Some(name)) =
+                (number, owner, name)
+            {
+                let owner = match TYPE_OR_METHOD_DEF.decode(owner) {
+                    (0, rid) => (TYPE_DEF, rid),
+                    (_, rid) => (METHOD_DEF, rid),
+                };
+                params.entry(owner).or_default().push((number, name));
+            }
+        }
+
+        params
+            .into_iter()
+            .map(|(owner, mut params)| {
+                params.sort_by_key(|(number, _)| *number);
+                (owner, params.into_iter().map(|(_, name)| name).collect())
+            })
+            .collect()
+    }
+
+    fn round_up(n: usize) -> usize {
+        // TODO: use usize:div_ceil when we bump the MSRV to 1.73.0.
+        num::Integer::div_ceil(&n, &4) * 4
+    }
+}
+
+impl<'a> Dotnet<'a> {
+    /// Returns the string at the given index in the `#Strings` heap.
+    fn string(&self, index: u32) -> Option<&'a str> {
+        let input = self.strings_heap.get(index as usize..)?;
+        let (_, s) = take_till::<_, _, Error>(|c| c == 0)(input).ok()?;
+        from_utf8(s).ok()
+    }
+
+    /// Returns the blob at the given index in the `#Blob` heap.
+    fn blob(&self, index: u32) -> Option<&'a [u8]> {
+        let input = self.blob_heap.get(index as usize..)?;
+        length_data(compressed_u32)(input).map(|(_, blob)| blob).ok()
+    }
+
+    /// Returns the GUID at the given index in the `#GUID` heap. Contrary
+    /// to other heaps, GUID indexes start at 1.
+    fn guid(&self, index: u32) -> Option<String> {
+        let start = (index as usize).checked_sub(1)? * 16;
+        self.guid_heap.get(start..start + 16).map(format_guid)
+    }
+
+    /// Returns the strings in the `#US` heap.
+    fn user_strings(&self) -> Vec<&'a [u8]> {
+        let mut user_strings = Vec::new();
+
+        // The first entry in the heap is always the empty string.
+        let mut input = self.user_strings_heap.get(1..).unwrap_or_default();
+
+        while let Ok((remainder, s)) = length_data(compressed_u32)(input) {
+            // Each string is followed by an extra byte that indicates if
+            // the string contains non-ASCII characters.
+            if let Some((_, s)) = s.split_last() {
+                if !s.is_empty() {
+                    user_strings.push(s);
+                }
+            }
+            input = remainder;
+        }
+
+        user_strings
+    }
+
+    /// Returns the range of rows in the `target` table that are owned by the
+    /// row `rid` in `table`.
+    ///
+    /// This is used with columns like TypeDef.MethodList, where the value
+    /// is the first row in a run of rows that ends where the run owned by
+    /// the next row starts.
+    fn list_range(
+        &self,
+        table: usize,
+        column: usize,
+        rid: u32,
+        target: usize,
+    ) -> Range<u32> {
+        let table = &self.tables[table];
+        let end_of_target = self.tables[target].num_rows.saturating_add(1);
+        let start = table.get(rid, column).unwrap_or(end_of_target);
+        let end =
+            table.get(rid.saturating_add(1), column).unwrap_or(end_of_target);
+        start..min(end, end_of_target)
+    }
+
+    /// Returns the namespace for the type identified by `rid` in the TypeDef
+    /// table. For nested types the namespace is the full name of the
+    /// enclosing type.
+    fn type_def_namespace(&self, rid: u32, depth: usize) -> Option<String> {
+        if depth > Self::MAX_TYPE_DEPTH {
+            return None;
+        }
+        match self.enclosing_types.get(&rid) {
+            Some(enclosing) => self.type_def_fullname(*enclosing, depth + 1),
+            None => Some(
+                self.string(self.tables[TYPE_DEF].get(rid, 2)?)?.to_string(),
+            ),
+        }
+    }
+
+    /// Returns the full name (namespace + name) for the type identified by
+    /// `rid` in the TypeDef table.
+    fn type_def_fullname(&self, rid: u32, depth: usize) -> Option<String> {
+        let name = self.string(self.tables[TYPE_DEF].get(rid, 1)?)?;
+        let namespace = self.type_def_namespace(rid, depth)?;
+        Some(join_name(&namespace, name))
+    }
+
+    /// Returns the full name (namespace + name) for the type identified by
+    /// `rid` in the TypeRef table.
+    fn type_ref_fullname(&self, rid: u32, depth: usize) -> Option<String> {
+        if depth > Self::MAX_TYPE_DEPTH {
+            return None;
+        }
+
+        let type_refs = &self.tables[TYPE_REF];
+        let name = self.string(type_refs.get(rid, 1)?)?;
+
+        let namespace = match RESOLUTION_SCOPE.decode(type_refs.get(rid, 0)?) {
+            // When the resolution scope is another TypeRef, this is a nested
+            // type, and the namespace is the full name of the enclosing type.
+            (3, enclosing) => self.type_ref_fullname(enclosing, depth + 1)?,
+            _ => self.string(type_refs.get(rid, 2)?)?.to_string(),
+        };
+
+        Some(join_name(&namespace, name))
+    }
+
+    /// Returns the name of the type identified by a TypeDefOrRef coded
+    /// index.
+    fn type_def_or_ref_name(
+        &self,
+        coded_index: u32,
+        ctx: &GenericContext,
+        depth: usize,
+    ) -> Option<String> {
+        match TYPE_DEF_OR_REF.decode(coded_index) {
+            (0, rid) => self.type_def_fullname(rid, depth),
+            (1, rid) => self.type_ref_fullname(rid, depth),
+            (2, rid) => {
+                let signature =
+                    self.blob(self.tables[TYPE_SPEC].get(rid, 0)?)?;
+                self.parse_type(signature, ctx, depth + 1)
+                    .map(|(_, name)| name)
+                    .ok()
+            }
+            _ => None,
+        }
+    }
+
+    /// Parses a type in a signature blob, returning its name as it would
+    /// appear in C#.
+    ///
+    /// See ECMA-335 section II.23.2.12.
+    fn parse_type(
+        &self,
+        input: &'a [u8],
+        ctx: &GenericContext,
+        depth: usize,
+    ) -> IResult<&'a [u8], String> {
+        if depth > Self::MAX_TYPE_DEPTH {
+            return fail(input);
+        }
+
+        let (input, element_type) = u8(input)?;
+
+        let primitive = match element_type {
+            0x01 => Some("void"),
+            0x02 => Some("bool"),
+            0x03 => Some("char"),
+            0x04 => Some("sbyte"),
+            0x05 => Some("byte"),
+            0x06 => Some("short"),
+            0x07 => Some("ushort"),
+            0x08 => Some("int"),
+            0x09 => Some("uint"),
+            0x0A => Some("long"),
+            0x0B => Some("ulong"),
+            0x0C => Some("float"),
+            0x0D => Some("double"),
+            0x0E => Some("string"),
+            0x16 => Some("TypedReference"),
+            0x18 => Some("IntPtr"),
+            0x19 => Some("UIntPtr"),
+            0x1C => Some("object"),
+            _ => None,
+        };
+
+        if let Some(primitive) = primitive {
+            return Ok((input, primitive.to_string()));
+        }
+
+        match element_type {
+            // ELEMENT_TYPE_PTR
+            0x0F => map(
+                |input| self.parse_type(input, ctx, depth + 1),
+                |ty| format!("Ptr<{}>", ty),
+            )(input),
+            // ELEMENT_TYPE_BYREF
+            0x10 => map(
+                |input| self.parse_type(input, ctx, depth + 1),
+                |ty| format!("ref {}", ty),
+            )(input),
+            // ELEMENT_TYPE_VALUETYPE, ELEMENT_TYPE_CLASS
+            0x11 | 0x12 => {
+                let (input, coded_index) = compressed_u32(input)?;
+                match self.type_def_or_ref_name(coded_index, ctx, depth + 1) {
+                    Some(name) => Ok((input, name)),
+                    None => fail(input),
+                }
+            }
+            // ELEMENT_TYPE_VAR
+            0x13 => map(compressed_u32, |n| match ctx.class.get(n as usize) {
+                Some(name) => name.to_string(),
+                None => format!("!{}", n),
+            })(input),
+            // ELEMENT_TYPE_ARRAY
+            0x14 => {
+                let (input, ty) = self.parse_type(input, ctx, depth + 1)?;
+                let (input, rank) = verify(compressed_u32, |rank| {
+                    *rank <= Self::MAX_ARRAY_RANK
+                })(input)?;
+                let (input, num_sizes) = compressed_u32(input)?;
+                let (input, _sizes) =
+                    count(compressed_u32, num_sizes as usize)(input)?;
+                let (input, num_lo_bounds) = compressed_u32(input)?;
+                let (input, _lo_bounds) =
+                    count(compressed_u32, num_lo_bounds as usize)(input)?;
+                Ok((
+                    input,
+                    format!(
+                        "{}[{}]",
+                        ty,
+                        ",".repeat(rank.saturating_sub(1) as usize)
+                    ),
+                ))
+            }
+            // ELEMENT_TYPE_GENERICINST
+            0x15 => {
+                let (input, _) =
+                    verify(u8, |ty| *ty == 0x11 || *ty == 0x12)(input)?;
+                let (input, coded_index) = compressed_u32(input)?;
+                let name = match self.type_def_or_ref_name(
+                    coded_index,
+                    ctx,
+                    depth + 1,
+                ) {
+                    Some(name) => name,
+                    None => return fail(input),
+                };
+                let (input, num_args) = compressed_u32(input)?;
+                let (input, args) = count(
+                    |input| self.parse_type(input, ctx, depth + 1),
+                    num_args as usize,
+                )(input)?;
+                Ok((
+                    input,
+                    format!(
+                        "{}<{}>",
+                        strip_generic_arity(&name),
+                        args.join(",")
+                    ),
+                ))
+            }
+            // ELEMENT_TYPE_FNPTR
+            0x1B => map(
+                |input| self.parse_method_sig(input, ctx, depth + 1),
+                |(return_type, params)| {
+                    format!("FnPtr<{}({})>", return_type, params.join(", "))
+                },
+            )(input),
+            // ELEMENT_TYPE_SZARRAY
+            0x1D => map(
+                |input| self.parse_type(input, ctx, depth + 1),
+                |ty| format!("{}[]", ty),
+            )(input),
+            // ELEMENT_TYPE_MVAR
+            0x1E => {
+                map(compressed_u32, |n| match ctx.method.get(n as usize) {
+                    Some(name) => name.to_string(),
+                    None => format!("!!{}", n),
+                })(input)
+            }
+            // ELEMENT_TYPE_CMOD_REQD, ELEMENT_TYPE_CMOD_OPT. Custom modifiers
+            // are followed by a TypeDefOrRef coded index and the modified
+            // type. The modifier itself is ignored.
+            0x1F | 0x20 => preceded(compressed_u32, |input| {
+                self.parse_type(input, ctx, depth + 1)
+            })(input),
+            // ELEMENT_TYPE_SENTINEL, ELEMENT_TYPE_PINNED
+            0x41 | 0x45 => self.parse_type(input, ctx, depth + 1),
+            _ => fail(input),
+        }
+    }
+
+    /// Parses a method signature, returning the return type and the types
+    /// of the parameters.
+    ///
+    /// See ECMA-335 section II.23.2.1.
+    fn parse_method_sig(
+        &self,
+        input: &'a [u8],
+        ctx: &GenericContext,
+        depth: usize,
+    ) -> IResult<&'a [u8], (String, Vec<String>)> {
+        let (input, calling_convention) = u8(input)?;
+        let (input, _num_generic_params) = cond(
+            calling_convention & Self::SIG_GENERIC != 0,
+            compressed_u32,
+        )(input)?;
+        let (input, num_params) = compressed_u32(input)?;
+        let (input, return_type) = self.parse_type(input, ctx, depth)?;
+        let (input, params) = count(
+            |input| self.parse_type(input, ctx, depth),
+            num_params as usize,
+        )(input)?;
+        Ok((input, (return_type, params)))
+    }
+
+    /// Returns the GUID indicated by the GuidAttribute applied to the
+    /// assembly, if any. This is the GUID of the type library.
+    fn typelib(&self) -> Option<String> {
+        let custom_attributes = &self.tables[CUSTOM_ATTRIBUTE];
+        custom_attributes.rids().find_map(|rid| {
+            // Only attributes applied to the assembly (tag 14) are relevant.
+            if HAS_CUSTOM_ATTRIBUTE.decode(custom_attributes.get(rid, 0)?).0
+                != 14
+            {
+                return None;
+            }
+            // The attribute's constructor must be a MemberRef (tag 3).
+            let ctor = match CUSTOM_ATTRIBUTE_TYPE
+                .decode(custom_attributes.get(rid, 1)?)
+            {
+                (3, ctor) => ctor,
+                _ => return None,
+            };
+            // The parent of the MemberRef must be a TypeRef (tag 1).
+            let class = match MEMBER_REF_PARENT
+                .decode(self.tables[MEMBER_REF].get(ctor, 0)?)
+            {
+                (1, class) => class,
+                _ => return None,
+            };
+            if self.string(self.tables[TYPE_REF].get(class, 1)?)?
+                != "GuidAttribute"
+            {
+                return None;
+            }
+            // The value starts with the prolog 0x0001, followed by the
+            // GUID as a SerString, which is a length-prefixed UTF-8 string.
+            let value = self.blob(custom_attributes.get(rid, 2)?)?;
+            let (_, guid) = preceded(
+                verify(le_u16, |prolog| *prolog == 0x0001),
+                length_data(compressed_u32),
+            )(value)
+            .ok()?;
+            from_utf8(guid).ok().map(String::from)
+        })
+    }
+
+    fn assembly(&self) -> Option<dotnet::Assembly> {
+        let assembly = &self.tables[ASSEMBLY];
+        Some(dotnet::Assembly {
+            name: self.string(assembly.get(1, 7)?).map(String::from),
+            culture: assembly
+                .get(1, 8)
+                .and_then(|culture| self.string(culture))
+                .filter(|culture| !culture.is_empty())
+                .map(String::from),
+            version: MessageField::from_option(version(assembly, 1, 1)),
+            ..Default::default()
+        })
+    }
+
+    fn assembly_refs(&self) -> Vec<dotnet::AssemblyRef> {
+        let assembly_refs = &self.tables[ASSEMBLY_REF];
+        assembly_refs
+            .rids()
+            .map(|rid| dotnet::AssemblyRef {
+                name: assembly_refs
+                    .get(rid, 6)
+                    .and_then(|name| self.string(name))
+                    .map(String::from),
+                public_key_or_token: assembly_refs
+                    .get(rid, 5)
+                    .and_then(|key| self.blob(key))
+                    .filter(|key| !key.is_empty())
+                    .map(|key| key.to_vec()),
+                version: MessageField::from_option(version(
+                    assembly_refs,
+                    rid,
+                    0,
+                )),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn resources(&self) -> Vec<dotnet::Resource> {
+        let resources_offset = match self.resources_offset {
+            Some(offset) => offset,
+            None => return vec![],
+        };
+
+        let resources = &self.tables[MANIFEST_RESOURCE];
+
+        resources
+            .rids()
+            .filter_map(|rid| {
+                // Resources with a non-null implementation are not located
+                // in this file.
+                if IMPLEMENTATION.decode(resources.get(rid, 3)?).1 != 0 {
+                    return None;
+                }
+                // The resource data is prefixed by its length.
+                let offset =
+                    resources_offset.checked_add(resources.get(rid, 0)?)?;
+                let (data, length) =
+                    le_u32::<_, Error>(self.data.get(offset as usize..)?)
+                        .ok()?;
+                if length as usize > data.len() {
+                    return None;
+                }
+                Some(dotnet::Resource {
+                    name: self
+                        .string(resources.get(rid, 2)?)
+                        .map(String::from),
+                    offset: Some(offset + 4),
+                    length: Some(length),
+                    ..Default::default()
+                })
+            })
+            .collect()
+    }
+
+    fn classes(&self) -> Vec<dotnet::Class> {
+        // In a well-formed file each method belongs to a single class. This
+        // budget prevents malformed files with overlapping method lists from
+        // producing a huge number of methods.
+        let mut methods_budget = self.tables[METHOD_DEF].num_rows as usize;
+
+        self.tables[TYPE_DEF]
+            .rids()
+            .filter_map(|rid| self.class(rid, &mut methods_budget))
+            .collect()
+    }
+
+    fn class(
+        &self,
+        rid: u32,
+        methods_budget: &mut usize,
+    ) -> Option<dotnet::Class> {
+        let type_defs = &self.tables[TYPE_DEF];
+        let flags = type_defs.get(rid, 0)?;
+        let name = self.string(type_defs.get(rid, 1)?)?;
+
+        // The first type in the table is a pseudo-class that contains global
+        // functions and variables, it's not a real class.
+        if name == "<Module>" {
+            return None;
+        }
+
+        let namespace = self.type_def_namespace(rid, 0)?;
+
+        let generic_params = self
+            .generic_params
+            .get(&(TYPE_DEF, rid))
+            .map(|params| params.as_slice())
+            .unwrap_or_default();
+
+        let ctx = GenericContext { class: generic_params, method: &[] };
+
+        let mut base_types = Vec::new();
+
+        if let Some(extends) = type_defs.get(rid, 3) {
+            if TYPE_DEF_OR_REF.decode(extends).1 != 0 {
+                base_types.extend(self.type_def_or_ref_name(extends, &ctx, 0));
+            }
+        }
+
+        if let Some(interfaces) = self.interfaces.get(&rid) {
+            for interface in interfaces {
+                base_types
+                    .extend(self.type_def_or_ref_name(*interface, &ctx, 0));
+            }
+        }
+
+        let method_rids = self.list_range(TYPE_DEF, 5, rid, METHOD_DEF);
+        let num_methods = min(method_rids.len(), *methods_budget);
+
+        *methods_budget -= num_methods;
+
+        let methods = method_rids
+            .take(num_methods)
+            .filter_map(|method_rid| self.method(method_rid, generic_params))
+            .collect::<Vec<_>>();
+
+        let generic_params = generic_params
+            .iter()
+            .map(|param| param.to_string())
+            .collect::<Vec<_>>();
+
+        Some(dotnet::Class {
+            fullname: Some(join_name(&namespace, name)),
+            name: Some(name.to_string()),
+            namespace: Some(namespace),
+            visibility: Some(
+                match flags & Self::TYPE_VISIBILITY_MASK {
+                    0x1 | 0x2 => "public",
+                    0x3 => "private",
+                    0x4 => "protected",
+                    0x6 => "private protected",
+                    0x7 => "protected internal",
+                    _ => "internal",
+                }
+                .to_string(),
+            ),
+            type_: Some(
+                if flags & Self::TYPE_INTERFACE != 0 {
+                    "interface"
+                } else {
+                    "class"
+                }
+                .to_string(),
+            ),
+            abstract_: Some(flags & Self::TYPE_ABSTRACT != 0),
+            sealed: Some(flags & Self::TYPE_SEALED != 0),
+            number_of_generic_parameters: Some(generic_params.len() as u64),
+            number_of_base_types: Some(base_types.len() as u64),
+            number_of_methods: Some(methods.len() as u64),
+            generic_parameters: generic_params,
+            base_types,
+            methods,
+            ..Default::default()
+        })
+    }
+
+    fn method(
+        &self,
+        rid: u32,
+        class_generic_params: &[&str],
+    ) -> Option<dotnet::Method> {
+        let method_defs = &self.tables[METHOD_DEF];
+        let flags = method_defs.get(rid, 2)?;
+        let name = self.string(method_defs.get(rid, 3)?)?;
+
+        let generic_params = self
+            .generic_params
+            .get(&(METHOD_DEF, rid))
+            .map(|params| params.as_slice())
+            .unwrap_or_default();
+
+        let ctx = GenericContext {
+            class: class_generic_params,
+            method: generic_params,
+        };
+
+        let (return_type, param_types) = method_defs
+            .get(rid, 4)
+            .and_then(|signature| self.blob(signature))
+            .and_then(|signature| {
+                self.parse_method_sig(signature, &ctx, 0).ok()
+            })
+            .map(|(_, (return_type, params))| (Some(return_type), params))
+            .unwrap_or_default();
+
+        let param_names = self.param_names(rid, param_types.len());
+
+        let params = param_types
+            .into_iter()
+            .zip(param_names)
+            .map(|(ty, name)| dotnet::Param {
+                name: name.map(String::from),
+                type_: Some(ty),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
+        Some(dotnet::Method {
+            name: Some(name.to_string()),
+            visibility: Some(
+                match flags & Self::METHOD_ACCESS_MASK {
+                    0x2 => "private protected",
+                    0x3 => "internal",
+                    0x4 => "protected",
+                    0x5 => "protected internal",
+                    0x6 => "public",
+                    _ => "private",
+                }
+                .to_string(),
+            ),
+            abstract_: Some(flags & Self::METHOD_ABSTRACT != 0),
+            final_: Some(flags & Self::METHOD_FINAL != 0),
+            virtual_: Some(flags & Self::METHOD_VIRTUAL != 0),
+            static_: Some(flags & Self::METHOD_STATIC != 0),
+            return_type,
+            number_of_generic_parameters: Some(generic_params.len() as u64),
+            number_of_parameters: Some(params.len() as u64),
+            generic_parameters: generic_params
+                .iter()
+                .map(|param| param.to_string())
+                .collect(),
+            parameters: params,
+            ..Default::default()
+        })
+    }
+
+    /// Returns the names of the first `num_params` parameters of the method
+    /// identified by `rid` in the MethodDef table.
+    fn param_names(
+        &self,
+        rid: u32,
+        num_params: usize,
+    ) -> Vec<Option<&'a str>> {
+        let params = &self.tables[PARAM];
+        let mut names = vec![None; num_params];
+
+        // The return value may have its own row in the Param table, so
+        // there can be up to `num_params + 1` rows for this method.
+        for param_rid in
+            self.list_range(METHOD_DEF, 5, rid, PARAM).take(num_params + 1)
+        {
+            // Sequence number 0 corresponds to the return value, parameters
+            // start at 1.
+            let name = params
+                .get(param_rid, 1)
+                .and_then(|sequence| (sequence as usize).checked_sub(1))
+                .and_then(|i| names.get_mut(i));
+
+            if let Some(name) = name {
+                *name = params
+                    .get(param_rid, 2)
+                    .and_then(|name| self.string(name));
+            }
+        }
+
+        names
+    }
+}
+
+#[rustfmt::skip]
+impl From<Dotnet<'_>> for dotnet::Dotnet {
+    fn from(dotnet: Dotnet) -> Self {
+        let mut result = dotnet::Dotnet::new();
+        let module = &dotnet.tables[MODULE];
+
+        result.set_is_dotnet(true);
+        result.version = dotnet.version.map(String::from);
+        result.mvid = module.get(1, 2).and_then(|guid| dotnet.guid(guid));
+        result.module_name = module
+            .get(1, 1)
+            .and_then(|name| dotnet.string(name))
+            .map(String::from);
+        result.typelib = dotnet.typelib();
+
+        result.runtime_version = MessageField::some(dotnet::Version {
+            major: Some(dotnet.runtime_version.0.into()),
+            minor: Some(dotnet.runtime_version.1.into()),
+            ..Default::default()
+        });
+
+        result.assembly = MessageField::from_option(dotnet.assembly());
+        result.assembly_refs = dotnet.assembly_refs();
+        result.resources = dotnet.resources();
+        result.classes = dotnet.classes();
+
+        result.streams = dotnet
+            .streams
+            .iter()
+            .map(dotnet::Stream::from)
+            .collect();
+
+        result.guids = dotnet
+            .guid_heap
+            .chunks_exact(16)
+            .map(format_guid)
+            .collect();
+
+        result.user_strings = dotnet
+            .user_strings()
+            .into_iter()
+            .map(|s| s.to_vec())
+            .collect();
+
+        let constants = &dotnet.tables[CONSTANT];
+
+        result.constants = constants
+            .rids()
+            .filter_map(|rid| dotnet.blob(constants.get(rid, 3)?))
+            .map(|value| value.to_vec())
+            .collect();
+
+        let module_refs = &dotnet.tables[MODULE_REF];
+
+        result.modulerefs = module_refs
+            .rids()
+            .filter_map(|rid| dotnet.string(module_refs.get(rid, 0)?))
+            .map(String::from)
+            .collect();
+
+        result.set_number_of_streams(result.streams.len() as u64);
+        result.set_number_of_guids(result.guids.len() as u64);
+        result.set_number_of_resources(result.resources.len() as u64);
+        result.set_number_of_user_strings(result.user_strings.len() as u64);
+        result.set_number_of_constants(result.constants.len() as u64);
+        result.set_number_of_assembly_refs(result.assembly_refs.len() as u64);
+        result.set_number_of_modulerefs(result.modulerefs.len() as u64);
+        result.set_number_of_classes(result.classes.len() as u64);
+
+        result
+    }
+}
+
+/// A metadata stream.
+pub struct Stream<'a> {
+    name: &'a str,
+    /// Offset of the stream within the file.
+    offset: u32,
+    /// Size of the stream as indicated in the stream header.
+    size: u32,
+    /// Stream content. This can be shorter than `size` if the stream
+    /// is truncated.
+    data: &'a [u8],
+}
+
+impl From<&Stream<'_>> for dotnet::Stream {
+    fn from(value: &Stream) -> Self {
+        let mut stream = dotnet::Stream::new();
+        stream.name = Some(value.name.to_string());
+        stream.offset = Some(value.offset);
+        stream.size = Some(value.size);
+        stream
+    }
+}
+
+/// A metadata table.
+///
+/// The rows are not parsed in advance, the value of each column is read
+/// from the table's data when needed.
+#[derive(Default, Clone)]
+struct Table<'a> {
+    data: &'a [u8],
+    num_rows: u32,
+    row_size: usize,
+    /// Offset and width of each column within a row.
+    columns: Vec<(usize, usize)>,
+}
+
+impl<'a> Table<'a> {
+    /// Returns the value of a column in the row identified by `rid`. Row IDs
+    /// start at 1.
+    fn get(&self, rid: u32, column: usize) -> Option<u32> {
+        if rid == 0 || rid > self.num_rows {
+            return None;
+        }
+        let (offset, width) = *self.columns.get(column)?;
+        let start = (rid as usize - 1) * self.row_size + offset;
+        let value = self.data.get(start..start + width)?;
+        match width {
+            1 => Some(value[0] as u32),
+            2 => Some(LE::read_u16(value) as u32),
+            _ => Some(LE::read_u32(value)),
+        }
+    }
+
+    /// Returns the IDs of all rows in the table.
+    fn rids(&self) -> RangeInclusive<u32> {
+        1..=self.num_rows
+    }
+}
+
+/// Types of the columns in metadata tables.
+#[derive(Clone, Copy)]
+enum Column {
+    U8,
+    U16,
+    U32,
+    /// Index into the `#Strings` heap.
+    StringIdx,
+    /// Index into the `#GUID` heap.
+    GuidIdx,
+    /// Index into the `#Blob` heap.
+    BlobIdx,
+    /// Index into the given table.
+    TableIdx(usize),
+    /// Coded index that can point to rows in multiple tables.
+    CodedIdx(CodedIndex),
+}
+
+/// Describes a coded index.
+///
+/// A coded index is an index that can point to rows in different tables.
+/// The least significant bits (tag) identify the table, and the remaining
+/// bits are the row ID. See ECMA-335 section II.24.2.6.
+#[derive(Clone, Copy)]
+struct CodedIndex {
+    tag_bits: u32,
+    /// Tables that can be referenced by the coded index. These determine
+    /// whether the coded index is stored in 2 or 4 bytes.
+    tables: &'static [usize],
+}
+
+impl CodedIndex {
+    /// Splits a coded index into its (tag, row ID) components.
+    fn decode(&self, value: u32) -> (u32, u32) {
+        (value & ((1 << self.tag_bits) - 1), value >> self.tag_bits)
+    }
+}
+
+/// Information required for computing the size of table columns.
+struct IndexSizes {
+    heap_sizes: u8,
+    rows: [u32; 64],
+}
+
+impl IndexSizes {
+    /// Returns the width in bytes of a given column.
+    fn width(&self, column: &Column) -> usize {
+        let heap_index = |flag: u8| {
+            if self.heap_sizes & flag != 0 {
+                4
+            } else {
+                2
+            }
+        };
+        match column {
+            Column::U8 => 1,
+            Column::U16 => 2,
+            Column::U32 => 4,
+            Column::StringIdx => heap_index(0x01),
+            Column::GuidIdx => heap_index(0x02),
+            Column::BlobIdx => heap_index(0x04),
+            Column::TableIdx(table) => {
+                if self.rows[*table] < (1 << 16) {
+                    2
+                } else {
+                    4
+                }
+            }
+            Column::CodedIdx(coded_index) => {
+                let max_rows = coded_index
+                    .tables
+                    .iter()
+                    .map(|table| self.rows[*table])
+                    .max()
+                    .unwrap_or(0);
+                if max_rows < (1 << (16 - coded_index.tag_bits)) {
+                    2
+                } else {
+                    4
+                }
+            }
+        }
+    }
+}
+
+/// Context used for resolving generic parameters in type signatures.
+struct GenericContext<'b> {
+    /// Generic parameters of the class, referenced by ELEMENT_TYPE_VAR.
+    class: &'b [&'b str],
+    /// Generic parameters of the method, referenced by ELEMENT_TYPE_MVAR.
+    method: &'b [&'b str],
+}
+
+/// Parses a compressed unsigned integer, as described in ECMA-335 section
+/// II.23.2.
+fn compressed_u32(input: &[u8]) -> IResult<&[u8], u32> {
+    let (input, first) = u8(input)?;
+    match first {
+        b if b & 0x80 == 0 => Ok((input, b as u32)),
+        b if b & 0xC0 == 0x80 => {
+            map(u8, |second| ((b as u32 & 0x3F) << 8) | second as u32)(input)
+        }
+        b if b & 0xE0 == 0xC0 => map(take(3_usize), |rest: &[u8]| {
+            ((b as u32 & 0x1F) << 24)
+                | (rest[0] as u32) << 16
+                | (rest[1] as u32) << 8
+                | rest[2] as u32
+        })(input),
+        _ => fail(input),
+    }
+}
+
+/// Formats a 16-bytes GUID in the usual form:
+/// `xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx`. The first three components are
+/// stored in little-endian.
+fn format_guid(guid: &[u8]) -> String {
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:02x}{:02x}-{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}",
+        LE::read_u32(&guid[0..4]),
+        LE::read_u16(&guid[4..6]),
+        LE::read_u16(&guid[6..8]),
+        guid[8],
+        guid[9],
+        guid[10],
+        guid[11],
+        guid[12],
+        guid[13],
+        guid[14],
+        guid[15],
+    )
+}
+
+/// Joins a namespace and a type name.
+fn join_name(namespace: &str, name: &str) -> String {
+    if namespace.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", namespace, name)
+    }
+}
+
+/// Removes the arity suffix from the name of a generic type. For instance,
+/// ``System.Collections.Generic.List`1`` becomes
+/// `System.Collections.Generic.List`.
+fn strip_generic_arity(name: &str) -> &str {
+    match name.rsplit_once('`') {
+        Some((name, arity)) if arity.bytes().all(|c| c.is_ascii_digit()) => {
+            name
+        }
+        _ => name,
+    }
+}
+
+/// Returns the version stored in four consecutive 16-bit columns, starting
+/// at `first_column`, in the row `rid` of the given table.
+fn version(
+    table: &Table,
+    rid: u32,
+    first_column: usize,
+) -> Option<dotnet::Version> {
+    Some(dotnet::Version {
+        major: Some(table.get(rid, first_column)?),
+        minor: Some(table.get(rid, first_column + 1)?),
+        build_number: Some(table.get(rid, first_column + 2)?),
+        revision_number: Some(table.get(rid, first_column + 3)?),
+        ..Default::default()
+    })
+}
+
+// Metadata tables. See ECMA-335 section II.22. Only tables used by this
+// module, or referenced by columns in other tables, are listed here.
+const MODULE: usize = 0x00;
+const TYPE_REF: usize = 0x01;
+const TYPE_DEF: usize = 0x02;
+const FIELD: usize = 0x04;
+const METHOD_DEF: usize = 0x06;
+const PARAM: usize = 0x08;
+const INTERFACE_IMPL: usize = 0x09;
+const MEMBER_REF: usize = 0x0A;
+const CONSTANT: usize = 0x0B;
+const CUSTOM_ATTRIBUTE: usize = 0x0C;
+const FIELD_MARSHAL: usize = 0x0D;
+const DECL_SECURITY: usize = 0x0E;
+const STAND_ALONE_SIG: usize = 0x11;
+const EVENT: usize = 0x14;
+const PROPERTY: usize = 0x17;
+const MODULE_REF: usize = 0x1A;
+const TYPE_SPEC: usize = 0x1B;
+const ASSEMBLY: usize = 0x20;
+const ASSEMBLY_REF: usize = 0x23;
+const FILE: usize = 0x26;
+const EXPORTED_TYPE: usize = 0x27;
+const MANIFEST_RESOURCE: usize = 0x28;
+const NESTED_CLASS: usize = 0x29;
+const GENERIC_PARAM: usize = 0x2A;
+const METHOD_SPEC: usize = 0x2B;
+const GENERIC_PARAM_CONSTRAINT: usize = 0x2C;
+
+const NUM_TABLES: usize = 0x2D;
+
+// Coded indexes. See ECMA-335 section II.24.2.6.
+const TYPE_DEF_OR_REF: CodedIndex =
+    CodedIndex { tag_bits: 2, tables: &[TYPE_DEF, TYPE_REF, TYPE_SPEC] };
+
+const HAS_CONSTANT: CodedIndex =
+    CodedIndex { tag_bits: 2, tables: &[FIELD, PARAM, PROPERTY] };
+
+const HAS_CUSTOM_ATTRIBUTE: CodedIndex = CodedIndex {
+    tag_bits: 5,
+    tables: &[
+        METHOD_DEF,
+        FIELD,
+        TYPE_REF,
+        TYPE_DEF,
+        PARAM,
+        INTERFACE_IMPL,
+        MEMBER_REF,
+        MODULE,
+        DECL_SECURITY,
+        PROPERTY,
+        EVENT,
+        STAND_ALONE_SIG,
+        MODULE_REF,
+        TYPE_SPEC,
+        ASSEMBLY,
+        ASSEMBLY_REF,
+        FILE,
+        EXPORTED_TYPE,
+        MANIFEST_RESOURCE,
+        GENERIC_PARAM,
+        GENERIC_PARAM_CONSTRAINT,
+        METHOD_SPEC,
+    ],
+};
+
+const HAS_FIELD_MARSHAL: CodedIndex =
+    CodedIndex { tag_bits: 1, tables: &[FIELD, PARAM] };
+
+const HAS_DECL_SECURITY: CodedIndex =
+    CodedIndex { tag_bits: 2, tables: &[TYPE_DEF, METHOD_DEF, ASSEMBLY] };
+
+const MEMBER_REF_PARENT: CodedIndex = CodedIndex {
+    tag_bits: 3,
+    tables: &[TYPE_DEF, TYPE_REF, MODULE_REF, METHOD_DEF, TYPE_SPEC],
+};
+
+const HAS_SEMANTICS: CodedIndex =
+    CodedIndex { tag_bits: 1, tables: &[EVENT, PROPERTY] };
+
+const METHOD_DEF_OR_REF: CodedIndex =
+    CodedIndex { tag_bits: 1, tables: &[METHOD_DEF, MEMBER_REF] };
+
+const MEMBER_FORWARDED: CodedIndex =
+    CodedIndex { tag_bits: 1, tables: &[FIELD, METHOD_DEF] };
+
+const IMPLEMENTATION: CodedIndex =
+    CodedIndex { tag_bits: 2, tables: &[FILE, ASSEMBLY_REF, EXPORTED_TYPE] };
+
+// Tags 0, 1 and 4 are not used, tag 2 is MethodDef and tag 3 is MemberRef.
+const CUSTOM_ATTRIBUTE_TYPE: CodedIndex =
+    CodedIndex { tag_bits: 3, tables: &[METHOD_DEF, MEMBER_REF] };
+
+const RESOLUTION_SCOPE: CodedIndex = CodedIndex {
+    tag_bits: 2,
+    tables: &[MODULE, MODULE_REF, ASSEMBLY_REF, TYPE_REF],
+};
+
+const TYPE_OR_METHOD_DEF: CodedIndex =
+    CodedIndex { tag_bits: 1, tables: &[TYPE_DEF, METHOD_DEF] };
+
+/// Columns in each metadata table, indexed by table number.
+const TABLE_SCHEMAS: [&[Column]; NUM_TABLES] = [
+    // Module
+    &[U16, StringIdx, GuidIdx, GuidIdx, GuidIdx],
+    // TypeRef
+    &[CodedIdx(RESOLUTION_SCOPE), StringIdx, StringIdx],
+    // TypeDef
+    &[
+        U32,
+        StringIdx,
+        StringIdx,
+        CodedIdx(TYPE_DEF_OR_REF),
+        TableIdx(FIELD),
+        TableIdx(METHOD_DEF),
+    ],
+    // FieldPtr
+    &[TableIdx(FIELD)],
+    // Field
+    &[U16, StringIdx, BlobIdx],
+    // MethodPtr
+    &[TableIdx(METHOD_DEF)],
+    // MethodDef
+    &[U32, U16, U16, StringIdx, BlobIdx, TableIdx(PARAM)],
+    // ParamPtr
+    &[TableIdx(PARAM)],
+    // Param
+    &[U16, U16, StringIdx],
+    // InterfaceImpl
+    &[TableIdx(TYPE_DEF), CodedIdx(TYPE_DEF_OR_REF)],
+    // MemberRef
+    &[CodedIdx(MEMBER_REF_PARENT), StringIdx, BlobIdx],
+    // Constant
+    &[U8, U8, CodedIdx(HAS_CONSTANT), BlobIdx],
+    // CustomAttribute
+    &[
+        CodedIdx(HAS_CUSTOM_ATTRIBUTE),
+        CodedIdx(CUSTOM_ATTRIBUTE_TYPE),
+        BlobIdx,
+    ],
+    // FieldMarshal
+    &[CodedIdx(HAS_FIELD_MARSHAL), BlobIdx],
+    // DeclSecurity
+    &[U16, CodedIdx(HAS_DECL_SECURITY), BlobIdx],
+    // ClassLayout
+    &[U16, U32, TableIdx(TYPE_DEF)],
+    // FieldLayout
+    &[U32, TableIdx(FIELD)],
+    // StandAloneSig
+    &[BlobIdx],
+    // EventMap
+    &[TableIdx(TYPE_DEF), TableIdx(EVENT)],
+    // EventPtr
+    &[TableIdx(EVENT)],
+    // Event
+    &[U16, StringIdx, CodedIdx(TYPE_DEF_OR_REF)],
+    // PropertyMap
+    &[TableIdx(TYPE_DEF), TableIdx(PROPERTY)],
+    // PropertyPtr
+    &[TableIdx(PROPERTY)],
+    // Property
+    &[U16, StringIdx, BlobIdx],
+    // MethodSemantics
+    &[U16, TableIdx(METHOD_DEF), CodedIdx(HAS_SEMANTICS)],
+    // MethodImpl
+    &[
+        TableIdx(TYPE_DEF),
+        CodedIdx(METHOD_DEF_OR_REF),
+        CodedIdx(METHOD_DEF_OR_REF),
+    ],
+    // ModuleRef
+    &[StringIdx],
+    // TypeSpec
+    &[BlobIdx],
+    // ImplMap
+    &[U16, CodedIdx(MEMBER_FORWARDED), StringIdx, TableIdx(MODULE_REF)],
+    // FieldRVA
+    &[U32, TableIdx(FIELD)],
+    // EncLog
+    &[U32, U32],
+    // EncMap
+    &[U32],
+    // Assembly
+    &[U32, U16, U16, U16, U16, U32, BlobIdx, StringIdx, StringIdx],
+    // AssemblyProcessor
+    &[U32],
+    // AssemblyOS
+    &[U32, U32, U32],
+    // AssemblyRef
+    &[U16, U16, U16, U16, U32, BlobIdx, StringIdx, StringIdx, BlobIdx],
+    // AssemblyRefProcessor
+    &[U32, TableIdx(ASSEMBLY_REF)],
+    // AssemblyRefOS
+    &[U32, U32, U32, TableIdx(ASSEMBLY_REF)],
+    // File
+    &[U32, StringIdx, BlobIdx],
+    // ExportedType
+    &[U32, U32, StringIdx, StringIdx, CodedIdx(IMPLEMENTATION)],
+    // ManifestResource
+    &[U32, U32, StringIdx, CodedIdx(IMPLEMENTATION)],
+    // NestedClass
+    &[TableIdx(TYPE_DEF), TableIdx(TYPE_DEF)],
+    // GenericParam
+    &[U16, U16, CodedIdx(TYPE_OR_METHOD_DEF), StringIdx],
+    // MethodSpec
+    &[CodedIdx(METHOD_DEF_OR_REF), BlobIdx],
+    // GenericParamConstraint
+    &[TableIdx(GENERIC_PARAM), CodedIdx(TYPE_DEF_OR_REF)],
+];
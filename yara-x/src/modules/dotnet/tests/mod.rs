@@ -0,0 +1,196 @@
+use crate::modules::tests::create_binary_from_zipped_ihex;
+use crate::tests::rule_false;
+use crate::tests::rule_true;
+use crate::tests::test_rule;
+
+#[test]
+fn assembly() {
+    let dotnet = create_binary_from_zipped_ihex(
+        "src/modules/pe/tests/testdata/09e7d832320e51bcc80b9aecde2a4135267a9b0156642a9596a62e85c9998cc9.in.zip",
+    );
+
+    rule_true!(
+        r#"
+        import "dotnet"
+        rule test {
+          condition:
+            dotnet.is_dotnet and
+            dotnet.version == "v4.0.30319" and
+            dotnet.module_name == "System.Linq.Queryable.dll" and
+            dotnet.assembly.name == "System.Linq.Queryable" and
+            dotnet.assembly.version.major == 4 and
+            dotnet.assembly.version.minor == 0 and
+            dotnet.assembly.version.build_number == 0 and
+            dotnet.assembly.version.revision_number == 0 and
+            not defined dotnet.assembly.culture and
+            not defined dotnet.typelib and
+            dotnet.number_of_classes == 0 and
+            dotnet.number_of_user_strings == 0 and
+            dotnet.number_of_resources == 0
+        }
+        "#,
+        &dotnet
+    );
+
+    rule_true!(
+        r##"
+        import "dotnet"
+        rule test {
+          condition:
+            dotnet.number_of_streams == 5 and
+            dotnet.streams[0].name == "#~" and
+            dotnet.streams[0].offset == 0x2bc and
+            dotnet.streams[0].size == 484 and
+            dotnet.streams[4].name == "#Blob" and
+            dotnet.number_of_guids == 1 and
+            dotnet.guids[0] == "d4565b1c-a7f1-4ffb-8287-9061a0da4ae7" and
+            dotnet.mvid == dotnet.guids[0]
+        }
+        "##,
+        &dotnet
+    );
+
+    rule_true!(
+        r#"
+        import "dotnet"
+        rule test {
+          condition:
+            dotnet.number_of_assembly_refs == 2 and
+            dotnet.assembly_refs[0].name == "mscorlib" and
+            dotnet.assembly_refs[0].version.major == 4 and
+            dotnet.assembly_refs[0].public_key_or_token ==
+              "\xb7\x7a\x5c\x56\x19\x34\xe0\x89" and
+            dotnet.assembly_refs[1].name == "System.Core"
+        }
+        "#,
+        &dotnet
+    );
+}
+
+#[test]
+fn classes() {
+    let dotnet = create_binary_from_zipped_ihex(
+        "src/modules/pe/tests/testdata/9bcf79a99ffbb1bd649503ce1406dea4181f6477cfb61f2130f37d014ce21888.in.zip",
+    );
+
+    rule_true!(
+        r#"
+        import "dotnet"
+        rule test {
+          condition:
+            dotnet.module_name == "UnityEngine.Purchasing.AppleStub.dll" and
+            dotnet.assembly.name == "UnityEngine.Purchasing.AppleStub" and
+            dotnet.number_of_assembly_refs == 3 and
+            dotnet.assembly_refs[0].name == "netstandard" and
+            dotnet.assembly_refs[0].version.major == 2 and
+            not defined dotnet.assembly_refs[1].public_key_or_token and
+            dotnet.mvid == "1ee296ed-a8cf-47f5-88de-880cb902eafa"
+        }
+        "#,
+        &dotnet
+    );
+
+    rule_true!(
+        r#"
+        import "dotnet"
+        rule test {
+          condition:
+            dotnet.number_of_classes == 1 and
+            dotnet.classes[0].fullname ==
+              "UnityEngine.Purchasing.iOSStoreBindings" and
+            dotnet.classes[0].name == "iOSStoreBindings" and
+            dotnet.classes[0].namespace == "UnityEngine.Purchasing" and
+            dotnet.classes[0].visibility == "internal" and
+            dotnet.classes[0].type == "class" and
+            not dotnet.classes[0].abstract and
+            not dotnet.classes[0].sealed and
+            dotnet.classes[0].number_of_generic_parameters == 0 and
+            dotnet.classes[0].number_of_base_types == 3 and
+            dotnet.classes[0].base_types[0] == "System.Object" and
+            dotnet.classes[0].base_types[1] ==
+              "UnityEngine.Purchasing.INativeAppleStore" and
+            dotnet.classes[0].base_types[2] ==
+              "UnityEngine.Purchasing.INativeStore"
+        }
+        "#,
+        &dotnet
+    );
+
+    rule_true!(
+        r#"
+        import "dotnet"
+        rule test {
+          condition:
+            dotnet.classes[0].number_of_methods == 21 and
+            dotnet.classes[0].methods[0].name == "SetUnityPurchasingCallback" and
+            dotnet.classes[0].methods[0].visibility == "public" and
+            dotnet.classes[0].methods[0].virtual and
+            dotnet.classes[0].methods[0].final and
+            not dotnet.classes[0].methods[0].static and
+            not dotnet.classes[0].methods[0].abstract and
+            dotnet.classes[0].methods[0].return_type == "void" and
+            dotnet.classes[0].methods[0].number_of_parameters == 1 and
+            dotnet.classes[0].methods[0].parameters[0].name == "AsyncCallback" and
+            dotnet.classes[0].methods[0].parameters[0].type ==
+              "UnityEngine.Purchasing.UnityPurchasingCallback" and
+            dotnet.classes[0].methods[6].name == "Purchase" and
+            dotnet.classes[0].methods[6].number_of_parameters == 2 and
+            dotnet.classes[0].methods[6].parameters[0].name == "productJSON" and
+            dotnet.classes[0].methods[6].parameters[0].type == "string" and
+            dotnet.classes[0].methods[6].parameters[1].name == "developerPayload" and
+            dotnet.classes[0].methods[9].name == "get_canMakePayments" and
+            dotnet.classes[0].methods[9].return_type == "bool" and
+            dotnet.classes[0].methods[9].number_of_parameters == 0 and
+            dotnet.classes[0].methods[20].name == ".ctor" and
+            not dotnet.classes[0].methods[20].virtual
+        }
+        "#,
+        &dotnet
+    );
+
+    rule_true!(
+        r#"
+        import "dotnet"
+        rule test {
+          condition:
+            for any method in dotnet.classes[0].methods : (
+              method.name == "set_simulateAskToBuy" and
+              method.parameters[0].name == "value" and
+              method.parameters[0].type == "bool"
+            )
+        }
+        "#,
+        &dotnet
+    );
+}
+
+#[test]
+fn not_dotnet() {
+    let pe = create_binary_from_zipped_ihex(
+        "src/modules/pe/tests/testdata/079a472d22290a94ebb212aa8015cdc8dd28a968c6b4d3b88acdd58ce2d3b885.in.zip",
+    );
+
+    rule_false!(
+        r#"
+        import "dotnet"
+        rule test {
+          condition:
+            dotnet.is_dotnet
+        }
+        "#,
+        &pe
+    );
+
+    rule_true!(
+        r#"
+        import "dotnet"
+        rule test {
+          condition:
+            not dotnet.is_dotnet and
+            not defined dotnet.module_name and
+            not defined dotnet.number_of_classes
+        }
+        "#,
+        b"MZ"
+    );
+}
@@ -0,0 +1,293 @@
+/*! YARA module that identifies the type of the scanned file.
+
+This module plays the role of the `magic` module in YARA, but it doesn't
+depend on libmagic. File types are identified by a built-in database of
+signatures, and by the same parsers used by the `pe`, `elf`, `macho` and
+`lnk` modules, so the results are the same in every platform.
+ */
+
+use memchr::memmem;
+
+use crate::modules::elf::parser::ElfParser;
+use crate::modules::lnk::parser::LnkParser;
+use crate::modules::macho::{is_fat_macho_file_block, is_macho_file_block};
+use crate::modules::pe::parser::PE;
+use crate::modules::prelude::*;
+use crate::modules::protos::elf;
+use crate::modules::protos::filetype::*;
+
+#[cfg(test)]
+mod tests;
+
+/// Signatures that identify a file type by the bytes that appear at a given
+/// offset. Each entry contains the offset, the bytes, the file type, the
+/// MIME type and the description. Signatures are checked in order, and the
+/// first one that matches wins.
+#[rustfmt::skip]
+const SIGNATURES: &[(usize, &[u8], Type, &str, &str)] = &[
+    (0, b"PK\x03\x04", Type::ZIP, "application/zip", "Zip archive data"),
+    (0, b"PK\x05\x06", Type::ZIP, "application/zip", "Zip archive data (empty)"),
+    (0, b"Rar!\x1a\x07", Type::RAR, "application/x-rar", "RAR archive data"),
+    (0, b"7z\xbc\xaf\x27\x1c", Type::SEVENZIP, "application/x-7z-compressed", "7-zip archive data"),
+    (0, b"\x1f\x8b", Type::GZIP, "application/gzip", "gzip compressed data"),
+    (0, b"BZh", Type::BZIP2, "application/x-bzip2", "bzip2 compressed data"),
+    (0, b"\xfd7zXZ\x00", Type::XZ, "application/x-xz", "XZ compressed data"),
+    (0, b"MSCF\x00\x00\x00\x00", Type::CAB, "application/vnd.ms-cab-compressed", "Microsoft Cabinet archive data"),
+    (0, b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1", Type::OLE, "application/x-ole-storage", "Composite Document File V2 Document"),
+    (0, b"{\\rtf", Type::RTF, "text/rtf", "Rich Text Format data"),
+    (0, b"dex\n", Type::DEX, "application/vnd.android.dex", "Dalvik dex file"),
+    (0, b"\xff\xd8\xff", Type::IMAGE, "image/jpeg", "JPEG image data"),
+    (0, b"\x89PNG\r\n\x1a\n", Type::IMAGE, "image/png", "PNG image data"),
+    (0, b"GIF87a", Type::IMAGE, "image/gif", "GIF image data"),
+    (0, b"GIF89a", Type::IMAGE, "image/gif", "GIF image data"),
+];
+
+/// Interpreters that can appear in the shebang line of a script, together
+/// with the MIME type and description of the script. The interpreter name
+/// must start with the given prefix (e.g: "python3" matches "python").
+#[rustfmt::skip]
+const INTERPRETERS: &[(&str, &str, &str)] = &[
+    ("sh", "text/x-shellscript", "POSIX shell script"),
+    ("bash", "text/x-shellscript", "Bourne-Again shell script"),
+    ("dash", "text/x-shellscript", "POSIX shell script"),
+    ("zsh", "text/x-shellscript", "Zsh script"),
+    ("ksh", "text/x-shellscript", "Korn shell script"),
+    ("csh", "text/x-shellscript", "C shell script"),
+    ("tcsh", "text/x-shellscript", "Tenex C shell script"),
+    ("python", "text/x-python", "Python script"),
+    ("perl", "text/x-perl", "Perl script"),
+    ("ruby", "text/x-ruby", "Ruby script"),
+    ("node", "application/javascript", "Node.js script"),
+    ("php", "text/x-php", "PHP script"),
+    ("pwsh", "text/x-powershell", "PowerShell script"),
+];
+
+/// PDF files can have some garbage before the "%PDF-" header, this is the
+/// maximum number of bytes where the header is searched for.
+const PDF_HEADER_SEARCH_LIMIT: usize = 1024;
+
+/// Number of bytes at the start of the file that are inspected when
+/// deciding if the file is a text file.
+const TEXT_SAMPLE_SIZE: usize = 8192;
+
+#[module_main]
+fn main(data: &[u8]) -> Filetype {
+    if data.is_empty() {
+        return filetype(Type::UNKNOWN, "application/x-empty", "empty");
+    }
+
+    // Executable formats go first, as they are identified by the same
+    // parsers used by their corresponding modules.
+    if let Some(result) = identify_pe(data)
+        .or_else(|| identify_elf(data))
+        .or_else(|| identify_java_class(data))
+        .or_else(|| identify_macho(data))
+        .or_else(|| identify_lnk(data))
+    {
+        return result;
+    }
+
+    for (offset, magic, file_type, mime, description) in SIGNATURES {
+        if data.get(*offset..).is_some_and(|data| data.starts_with(magic)) {
+            return filetype(*file_type, mime, description);
+        }
+    }
+
+    if let Some(result) = identify_pdf(data) {
+        return result;
+    }
+
+    identify_text(data)
+}
+
+/// Creates a [`Filetype`] with the given file type, MIME type and
+/// description.
+fn filetype(file_type: Type, mime: &str, description: &str) -> Filetype {
+    let mut result = Filetype::new();
+    result.set_type(file_type);
+    result.set_mime(mime.to_string());
+    result.set_description(description.to_string());
+    result
+}
+
+fn identify_pe(data: &[u8]) -> Option<Filetype> {
+    let pe = PE::parse(data).ok()?;
+
+    let mut description = String::from(if pe.is_64bits() {
+        "PE32+ executable"
+    } else {
+        "PE32 executable"
+    });
+
+    if pe.is_dll() {
+        description.push_str(" (DLL)");
+    }
+
+    // .NET files have a non-empty COM descriptor directory entry.
+    if pe
+        .get_dir_entry_data(PE::IMAGE_DIRECTORY_ENTRY_COM_DESCRIPTOR)
+        .is_some_and(|(addr, size, _)| addr != 0 && size != 0)
+    {
+        description.push_str(" (.NET)");
+    }
+
+    Some(filetype(Type::PE, "application/x-dosexec", description.as_str()))
+}
+
+fn identify_elf(data: &[u8]) -> Option<Filetype> {
+    let elf = ElfParser::new().parse(data).ok()?;
+
+    let elf_type = elf.type_.and_then(|t| t.enum_value().ok());
+
+    let (mime, description) = match elf_type {
+        Some(elf::Type::ET_EXEC) => {
+            ("application/x-executable", "ELF executable")
+        }
+        Some(elf::Type::ET_DYN) => {
+            ("application/x-sharedlib", "ELF shared object")
+        }
+        Some(elf::Type::ET_REL) => ("application/x-object", "ELF relocatable"),
+        Some(elf::Type::ET_CORE) => {
+            ("application/x-coredump", "ELF core file")
+        }
+        _ => ("application/x-elf", "ELF file"),
+    };
+
+    Some(filetype(Type::ELF, mime, description))
+}
+
+fn identify_java_class(data: &[u8]) -> Option<Filetype> {
+    // Java class files share the 0xCAFEBABE magic with FAT Mach-O files.
+    // In class files the magic is followed by the minor and major versions,
+    // where major version is 45 or higher. In FAT Mach-O files the magic is
+    // followed by the number of architectures, which is a small number.
+    let major_version = data.get(6..8)?;
+    if data.starts_with(b"\xca\xfe\xba\xbe")
+        && u16::from_be_bytes([major_version[0], major_version[1]]) >= 45
+    {
+        Some(filetype(
+            Type::JAVA_CLASS,
+            "application/x-java-applet",
+            "compiled Java class data",
+        ))
+    } else {
+        None
+    }
+}
+
+fn identify_macho(data: &[u8]) -> Option<Filetype> {
+    if is_macho_file_block(data) {
+        Some(filetype(Type::MACHO, "application/x-mach-binary", "Mach-O file"))
+    } else if is_fat_macho_file_block(data) {
+        Some(filetype(
+            Type::MACHO,
+            "application/x-mach-binary",
+            "Mach-O universal binary",
+        ))
+    } else {
+        None
+    }
+}
+
+fn identify_lnk(data: &[u8]) -> Option<Filetype> {
+    LnkParser::new().parse(data).ok()?;
+    Some(filetype(
+        Type::LNK,
+        "application/x-ms-shortcut",
+        "MS Windows shortcut",
+    ))
+}
+
+fn identify_pdf(data: &[u8]) -> Option<Filetype> {
+    let header = &data[..data.len().min(PDF_HEADER_SEARCH_LIMIT)];
+    memmem::find(header, b"%PDF-")?;
+    Some(filetype(Type::PDF, "application/pdf", "PDF document"))
+}
+
+/// Identifies text files, including scripts, HTML and XML documents. If the
+/// data doesn't look like text, the file type is [`Type::UNKNOWN`].
+fn identify_text(data: &[u8]) -> Filetype {
+    let sample = &data[..data.len().min(TEXT_SAMPLE_SIZE)];
+    let sample = sample.strip_prefix(b"\xef\xbb\xbf").unwrap_or(sample);
+
+    // The sample must be valid UTF-8, except for a character that may have
+    // been cut in half at the end of the sample.
+    let is_utf8 = match std::str::from_utf8(sample) {
+        Ok(_) => true,
+        Err(err) => err.error_len().is_none(),
+    };
+
+    // Control characters other than the usual whitespace characters,
+    // backspace and escape are not expected in text files.
+    let is_text = is_utf8
+        && !sample.iter().any(|&c| {
+            c.is_ascii_control()
+                && !matches!(
+                    c,
+                    b'\t' | b'\n' | b'\r' | b'\x08' | b'\x0c' | b'\x1b'
+                )
+        });
+
+    if !is_text {
+        return filetype(Type::UNKNOWN, "application/octet-stream", "data");
+    }
+
+    if let Some(result) = identify_script(sample) {
+        return result;
+    }
+
+    let trimmed = sample.trim_start();
+
+    if starts_with_ignore_case(trimmed, b"<!doctype html")
+        || starts_with_ignore_case(trimmed, b"<html")
+    {
+        return filetype(Type::HTML, "text/html", "HTML document");
+    }
+
+    if trimmed.starts_with(b"<?xml") {
+        return filetype(Type::XML, "text/xml", "XML document");
+    }
+
+    if sample.is_ascii() {
+        filetype(Type::TEXT, "text/plain", "ASCII text")
+    } else {
+        filetype(Type::TEXT, "text/plain", "Unicode text, UTF-8 text")
+    }
+}
+
+/// Identifies scripts by their shebang line (e.g: "#!/bin/sh").
+fn identify_script(data: &[u8]) -> Option<Filetype> {
+    if data.starts_with(b"<?php") {
+        return Some(filetype(Type::SCRIPT, "text/x-php", "PHP script"));
+    }
+
+    let shebang = data.strip_prefix(b"#!")?;
+    let line = shebang.lines().next().unwrap_or_default();
+
+    // The interpreter is the last component of the first path in the line,
+    // except with "/usr/bin/env", where the interpreter is the first
+    // argument that is not an option.
+    let mut args = line.fields();
+    let mut interpreter = basename(args.next()?);
+
+    if interpreter == b"env" {
+        interpreter = basename(args.find(|arg| !arg.starts_with(b"-"))?);
+    }
+
+    let (mime, description) = INTERPRETERS
+        .iter()
+        .find(|(name, _, _)| interpreter.starts_with(name.as_bytes()))
+        .map(|(_, mime, description)| (*mime, *description))
+        .unwrap_or(("text/x-script", "script text executable"));
+
+    Some(filetype(Type::SCRIPT, mime, description))
+}
+
+fn basename(path: &[u8]) -> &[u8] {
+    path.rsplit(|c| *c == b'/').next().unwrap_or(path)
+}
+
+fn starts_with_ignore_case(data: &[u8], prefix: &[u8]) -> bool {
+    data.get(..prefix.len())
+        .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+}
@@ -0,0 +1,281 @@
+use crate::modules::tests::create_binary_from_zipped_ihex;
+use crate::tests::rule_false;
+use crate::tests::rule_true;
+use crate::tests::test_rule;
+
+#[test]
+fn executables() {
+    let pe = create_binary_from_zipped_ihex(
+        "src/modules/pe/tests/testdata/04ac6dd0c1cc33a49962ee0f3222597104f54a75683a5adee235401778279818.in.zip",
+    );
+
+    rule_true!(
+        r#"
+        import "filetype"
+        rule test {
+          condition:
+            filetype.type == filetype.PE and
+            filetype.mime == "application/x-dosexec" and
+            filetype.description == "PE32 executable"
+        }
+        "#,
+        &pe
+    );
+
+    let dotnet = create_binary_from_zipped_ihex(
+        "src/modules/pe/tests/testdata/09e7d832320e51bcc80b9aecde2a4135267a9b0156642a9596a62e85c9998cc9.in.zip",
+    );
+
+    rule_true!(
+        r#"
+        import "filetype"
+        rule test {
+          condition:
+            filetype.type == filetype.PE and
+            filetype.description == "PE32 executable (DLL) (.NET)"
+        }
+        "#,
+        &dotnet
+    );
+
+    let elf = create_binary_from_zipped_ihex(
+        "src/modules/elf/tests/testdata/ac64f17c42eec4ec357e5fe9e58b51e4e1d3a026356da4043095f9b96ebce51c.in.zip",
+    );
+
+    rule_true!(
+        r#"
+        import "filetype"
+        rule test {
+          condition:
+            filetype.type == filetype.ELF and
+            filetype.mime == "application/x-executable"
+        }
+        "#,
+        &elf
+    );
+
+    let macho = create_binary_from_zipped_ihex(
+        "src/modules/macho/tests/testdata/macho_x86_file.in.zip",
+    );
+
+    rule_true!(
+        r#"
+        import "filetype"
+        rule test {
+          condition:
+            filetype.type == filetype.MACHO and
+            filetype.mime == "application/x-mach-binary"
+        }
+        "#,
+        &macho
+    );
+
+    let fat_macho = create_binary_from_zipped_ihex(
+        "src/modules/macho/tests/testdata/tiny_universal.in.zip",
+    );
+
+    rule_true!(
+        r#"
+        import "filetype"
+        rule test {
+          condition:
+            filetype.type == filetype.MACHO and
+            filetype.description == "Mach-O universal binary"
+        }
+        "#,
+        &fat_macho
+    );
+
+    let lnk = create_binary_from_zipped_ihex(
+        "src/modules/lnk/tests/testdata/lnk-extradata-1.in.zip",
+    );
+
+    rule_true!(
+        r#"
+        import "filetype"
+        rule test {
+          condition:
+            filetype.type == filetype.LNK and
+            filetype.mime == "application/x-ms-shortcut"
+        }
+        "#,
+        &lnk
+    );
+}
+
+#[test]
+fn signatures() {
+    rule_true!(
+        r#"
+        import "filetype"
+        rule test {
+          condition:
+            filetype.type == filetype.PDF and
+            filetype.mime == "application/pdf"
+        }
+        "#,
+        b"%PDF-1.7\n%\xe2\xe3\xcf\xd3\n1 0 obj"
+    );
+
+    // The PDF header doesn't need to be at the start of the file.
+    rule_true!(
+        r#"
+        import "filetype"
+        rule test {
+          condition:
+            filetype.mime == "application/pdf"
+        }
+        "#,
+        b"\x00\x00\x00\x00%PDF-1.4"
+    );
+
+    rule_true!(
+        r#"
+        import "filetype"
+        rule test {
+          condition:
+            filetype.type == filetype.ZIP and
+            filetype.mime == "application/zip"
+        }
+        "#,
+        b"PK\x03\x04\x14\x00\x00\x00"
+    );
+
+    rule_true!(
+        r#"
+        import "filetype"
+        rule test {
+          condition:
+            filetype.type == filetype.OLE
+        }
+        "#,
+        b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1\x00\x00\x00\x00"
+    );
+
+    rule_true!(
+        r#"
+        import "filetype"
+        rule test {
+          condition:
+            filetype.type == filetype.IMAGE and
+            filetype.mime == "image/png"
+        }
+        "#,
+        b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR"
+    );
+
+    // 0xCAFEBABE followed by a major version of 52 is a Java class, not a
+    // FAT Mach-O file.
+    rule_true!(
+        r#"
+        import "filetype"
+        rule test {
+          condition:
+            filetype.type == filetype.JAVA_CLASS
+        }
+        "#,
+        b"\xca\xfe\xba\xbe\x00\x00\x00\x34"
+    );
+}
+
+#[test]
+fn text() {
+    rule_true!(
+        r#"
+        import "filetype"
+        rule test {
+          condition:
+            filetype.type == filetype.SCRIPT and
+            filetype.mime == "text/x-shellscript"
+        }
+        "#,
+        b"#!/bin/sh\necho hello\n"
+    );
+
+    rule_true!(
+        r#"
+        import "filetype"
+        rule test {
+          condition:
+            filetype.type == filetype.SCRIPT and
+            filetype.mime == "text/x-python"
+        }
+        "#,
+        b"#!/usr/bin/env -S python3 -u\nprint('hello')\n"
+    );
+
+    rule_true!(
+        r#"
+        import "filetype"
+        rule test {
+          condition:
+            filetype.type == filetype.HTML and
+            filetype.mime == "text/html"
+        }
+        "#,
+        b"\n  <!DOCTYPE html>\n<html></html>"
+    );
+
+    rule_true!(
+        r#"
+        import "filetype"
+        rule test {
+          condition:
+            filetype.type == filetype.TEXT and
+            filetype.description == "ASCII text"
+        }
+        "#,
+        b"hello\tworld\r\n"
+    );
+
+    rule_true!(
+        r#"
+        import "filetype"
+        rule test {
+          condition:
+            filetype.type == filetype.TEXT and
+            filetype.description == "Unicode text, UTF-8 text"
+        }
+        "#,
+        "¡hola mundo!".as_bytes()
+    );
+}
+
+#[test]
+fn unknown() {
+    rule_true!(
+        r#"
+        import "filetype"
+        rule test {
+          condition:
+            filetype.type == filetype.UNKNOWN and
+            filetype.mime == "application/octet-stream"
+        }
+        "#,
+        b"\x00\x01\x02\x03\xff\xfe"
+    );
+
+    rule_true!(
+        r#"
+        import "filetype"
+        rule test {
+          condition:
+            filetype.type == filetype.UNKNOWN and
+            filetype.mime == "application/x-empty"
+        }
+        "#,
+        b""
+    );
+
+    // A file that starts with "MZ" but is not a valid PE.
+    rule_false!(
+        r#"
+        import "filetype"
+        rule test {
+          condition:
+            filetype.type == filetype.PE
+        }
+        "#,
+        b"MZ\x00\x00"
+    );
+}
@@ -468,7 +468,7 @@ fn parse_magic(input: &[u8]) -> IResult<&[u8], u32> {
 ///
 /// `true` if the data starts with a file Mach-O magic constant, `false`
 /// otherwise.
-fn is_macho_file_block(data: &[u8]) -> bool {
+pub(crate) fn is_macho_file_block(data: &[u8]) -> bool {
     match parse_magic(data) {
         Ok((_, magic)) => {
             matches!(magic, MH_MAGIC | MH_CIGAM | MH_MAGIC_64 | MH_CIGAM_64)
@@ -487,7 +487,7 @@ fn is_macho_file_block(data: &[u8]) -> bool {
 ///
 /// `true` if the data starts with a FAT Mach-O magic constant, `false`
 /// otherwise.
-fn is_fat_macho_file_block(data: &[u8]) -> bool {
+pub(crate) fn is_fat_macho_file_block(data: &[u8]) -> bool {
     match parse_magic(data) {
         Ok((_, magic)) => matches!(
             magic,
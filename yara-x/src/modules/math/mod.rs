@@ -0,0 +1,453 @@
+/*! YARA module that implements mathematical and statistical functions.
+
+This module is compatible with the `math` module in YARA. Most functions
+have two variants, one that receives an offset and a size and operates on
+that range of the scanned data, and another one that operates on a string.
+Ranges that start at or beyond the end of the scanned data produce undefined
+results, while ranges that extend beyond the end of the data are truncated.
+ */
+
+use std::f64::consts::PI;
+
+use rustc_hash::FxHashMap;
+
+use crate::modules::prelude::*;
+use crate::modules::protos::math::*;
+
+#[cfg(test)]
+mod tests;
+
+/// Number of occurrences of each byte value.
+type Distribution = [u64; 256];
+
+/// Cache where keys are the start and end of a range of the scanned data,
+/// after truncating it to the data's size.
+type RangeCache<T> = FxHashMap<(usize, usize), T>;
+
+/// Ranges smaller than this are not cached, computing the functions for
+/// them is cheap enough.
+const MIN_CACHED_RANGE_SIZE: usize = 4096;
+
+/// Maximum number of ranges in each cache.
+const MAX_CACHED_RANGES: usize = 256;
+
+/// Values computed for ranges of the scanned data during the current scan.
+///
+/// Rules often call multiple functions on the same range of the scanned
+/// data (e.g: entropy and mean on the whole file). Most functions can be
+/// computed from the distribution of byte values in the range, which is
+/// computed only once per range. This is stored in the scan context, see
+/// [`ScanContext::module_data`].
+#[derive(Default)]
+struct Cache {
+    distribution: RangeCache<Box<Distribution>>,
+    serial_correlation: RangeCache<f64>,
+    monte_carlo_pi: RangeCache<Option<f64>>,
+}
+
+/// Returns true if the value computed for a range that goes from `start`
+/// to `end` must be stored in `cache`.
+fn must_cache<T>(cache: &RangeCache<T>, start: usize, end: usize) -> bool {
+    end - start >= MIN_CACHED_RANGE_SIZE && cache.len() < MAX_CACHED_RANGES
+}
+
+#[module_main]
+fn main(_data: &[u8]) -> Math {
+    let mut math = Math::new();
+    math.set_MEAN_BYTES(127.5);
+    math
+}
+
+#[module_export(name = "entropy")]
+fn entropy_data(ctx: &ScanContext, offset: i64, size: i64) -> Option<f64> {
+    with_distribution(ctx, offset, size, entropy)
+}
+
+#[module_export(name = "entropy")]
+fn entropy_str(ctx: &ScanContext, s: RuntimeString) -> Option<f64> {
+    let s = s.as_bstr(ctx);
+    Some(entropy(&distribution(s), s.len()))
+}
+
+#[module_export(name = "mean")]
+fn mean_data(ctx: &ScanContext, offset: i64, size: i64) -> Option<f64> {
+    with_distribution(ctx, offset, size, mean)?
+}
+
+#[module_export(name = "mean")]
+fn mean_str(ctx: &ScanContext, s: RuntimeString) -> Option<f64> {
+    let s = s.as_bstr(ctx);
+    mean(&distribution(s), s.len())
+}
+
+#[module_export(name = "deviation")]
+fn deviation_data(
+    ctx: &ScanContext,
+    offset: i64,
+    size: i64,
+    mean: f64,
+) -> Option<f64> {
+    with_distribution(ctx, offset, size, |dist, len| {
+        deviation(dist, len, mean)
+    })?
+}
+
+#[module_export(name = "deviation")]
+fn deviation_str(
+    ctx: &ScanContext,
+    s: RuntimeString,
+    mean: f64,
+) -> Option<f64> {
+    let s = s.as_bstr(ctx);
+    deviation(&distribution(s), s.len(), mean)
+}
+
+#[module_export(name = "serial_correlation")]
+fn serial_correlation_data(
+    ctx: &ScanContext,
+    offset: i64,
+    size: i64,
+) -> Option<f64> {
+    let (start, end) = data_range(ctx, offset, size)?;
+
+    let mut cache = ctx.module_data::<Cache>();
+
+    if let Some(cached) = cache.serial_correlation.get(&(start, end)) {
+        return Some(*cached);
+    }
+
+    let result = serial_correlation(&ctx.scanned_data()[start..end]);
+
+    if must_cache(&cache.serial_correlation, start, end) {
+        cache.serial_correlation.insert((start, end), result);
+    }
+
+    Some(result)
+}
+
+#[module_export(name = "serial_correlation")]
+fn serial_correlation_str(ctx: &ScanContext, s: RuntimeString) -> Option<f64> {
+    Some(serial_correlation(s.as_bstr(ctx)))
+}
+
+#[module_export(name = "monte_carlo_pi")]
+fn monte_carlo_pi_data(
+    ctx: &ScanContext,
+    offset: i64,
+    size: i64,
+) -> Option<f64> {
+    let (start, end) = data_range(ctx, offset, size)?;
+
+    let mut cache = ctx.module_data::<Cache>();
+
+    if let Some(cached) = cache.monte_carlo_pi.get(&(start, end)) {
+        return *cached;
+    }
+
+    let result = monte_carlo_pi(&ctx.scanned_data()[start..end]);
+
+    if must_cache(&cache.monte_carlo_pi, start, end) {
+        cache.monte_carlo_pi.insert((start, end), result);
+    }
+
+    result
+}
+
+#[module_export(name = "monte_carlo_pi")]
+fn monte_carlo_pi_str(ctx: &ScanContext, s: RuntimeString) -> Option<f64> {
+    monte_carlo_pi(s.as_bstr(ctx))
+}
+
+#[module_export(name = "count")]
+fn count_range(
+    ctx: &ScanContext,
+    byte: i64,
+    offset: i64,
+    size: i64,
+) -> Option<i64> {
+    let byte: u8 = byte.try_into().ok()?;
+    with_distribution(ctx, offset, size, |dist, _| {
+        dist[byte as usize].try_into().ok()
+    })?
+}
+
+#[module_export(name = "count")]
+fn count_all(ctx: &ScanContext, byte: i64) -> Option<i64> {
+    count_range(ctx, byte, 0, ctx.scanned_data().len() as i64)
+}
+
+#[module_export(name = "percentage")]
+fn percentage_range(
+    ctx: &ScanContext,
+    byte: i64,
+    offset: i64,
+    size: i64,
+) -> Option<f64> {
+    let byte: u8 = byte.try_into().ok()?;
+    with_distribution(ctx, offset, size, |dist, len| {
+        if len == 0 {
+            return None;
+        }
+        Some(dist[byte as usize] as f64 / len as f64)
+    })?
+}
+
+#[module_export(name = "percentage")]
+fn percentage_all(ctx: &ScanContext, byte: i64) -> Option<f64> {
+    percentage_range(ctx, byte, 0, ctx.scanned_data().len() as i64)
+}
+
+#[module_export(name = "mode")]
+fn mode_range(ctx: &ScanContext, offset: i64, size: i64) -> Option<i64> {
+    with_distribution(ctx, offset, size, |dist, len| {
+        if len == 0 {
+            return None;
+        }
+        // When multiple byte values have the same number of occurrences,
+        // the lowest one is returned.
+        let mut most_common = 0;
+        for (byte, count) in dist.iter().enumerate() {
+            if *count > dist[most_common] {
+                most_common = byte;
+            }
+        }
+        Some(most_common as i64)
+    })?
+}
+
+#[module_export(name = "mode")]
+fn mode_all(ctx: &ScanContext) -> Option<i64> {
+    mode_range(ctx, 0, ctx.scanned_data().len() as i64)
+}
+
+#[module_export]
+fn in_range(_ctx: &ScanContext, test: f64, lower: f64, upper: f64) -> bool {
+    lower <= test && test <= upper
+}
+
+/// Returns the minimum of two integers.
+///
+/// As in YARA, the integers are compared as unsigned integers, which means
+/// that negative numbers are larger than any positive number.
+#[module_export]
+fn min(_ctx: &ScanContext, a: i64, b: i64) -> i64 {
+    if (a as u64) < (b as u64) {
+        a
+    } else {
+        b
+    }
+}
+
+/// Returns the maximum of two integers.
+///
+/// As in YARA, the integers are compared as unsigned integers, which means
+/// that negative numbers are larger than any positive number.
+#[module_export]
+fn max(_ctx: &ScanContext, a: i64, b: i64) -> i64 {
+    if (a as u64) > (b as u64) {
+        a
+    } else {
+        b
+    }
+}
+
+#[module_export]
+fn abs(_ctx: &ScanContext, n: i64) -> Option<i64> {
+    n.checked_abs()
+}
+
+#[module_export]
+fn to_number(_ctx: &ScanContext, b: bool) -> i64 {
+    b as i64
+}
+
+#[module_export(name = "to_string")]
+fn to_string(ctx: &mut ScanContext, n: i64) -> Option<RuntimeString> {
+    Some(RuntimeString::from_bytes(ctx, n.to_string()))
+}
+
+/// Converts an integer to a string in base 8, 10 or 16.
+///
+/// As in YARA, negative numbers in base 8 and 16 are represented as their
+/// two's complement.
+#[module_export(name = "to_string")]
+fn to_string_base(
+    ctx: &mut ScanContext,
+    n: i64,
+    base: i64,
+) -> Option<RuntimeString> {
+    let s = match base {
+        8 => format!("{:o}", n),
+        10 => n.to_string(),
+        16 => format!("{:x}", n),
+        _ => return None,
+    };
+    Some(RuntimeString::from_bytes(ctx, s))
+}
+
+/// Returns the start and end of the range of the scanned data that starts
+/// at `offset` and has `size` bytes.
+///
+/// Returns `None` if `offset` or `size` are negative, or if `offset` is not
+/// within the scanned data. If the range extends beyond the end of the data,
+/// it is truncated.
+fn data_range(
+    ctx: &ScanContext,
+    offset: i64,
+    size: i64,
+) -> Option<(usize, usize)> {
+    let offset: usize = offset.try_into().ok()?;
+    let size: usize = size.try_into().ok()?;
+    let data_len = ctx.scanned_data().len();
+
+    if offset >= data_len {
+        return None;
+    }
+
+    Some((offset, data_len.min(offset.saturating_add(size))))
+}
+
+/// Calls `f` with the distribution of byte values in a range of the scanned
+/// data and the size of the range.
+///
+/// The distribution is computed the first time that some range is used,
+/// and it is reused in subsequent calls for the same range, except for
+/// ranges that are not cached (see [`must_cache`]).
+fn with_distribution<T>(
+    ctx: &ScanContext,
+    offset: i64,
+    size: i64,
+    f: impl FnOnce(&Distribution, usize) -> T,
+) -> Option<T> {
+    let (start, end) = data_range(ctx, offset, size)?;
+
+    let mut cache = ctx.module_data::<Cache>();
+
+    if let Some(dist) = cache.distribution.get(&(start, end)) {
+        return Some(f(dist, end - start));
+    }
+
+    let dist = Box::new(distribution(&ctx.scanned_data()[start..end]));
+    let result = f(&dist, end - start);
+
+    if must_cache(&cache.distribution, start, end) {
+        cache.distribution.insert((start, end), dist);
+    }
+
+    Some(result)
+}
+
+/// Computes the distribution of byte values in `data`.
+fn distribution(data: &[u8]) -> Distribution {
+    let mut dist = [0; 256];
+    for byte in data {
+        dist[*byte as usize] += 1;
+    }
+    dist
+}
+
+/// Computes the Shannon entropy of some data, given its distribution of
+/// byte values and its length.
+fn entropy(dist: &Distribution, len: usize) -> f64 {
+    let mut entropy = 0.0;
+    for count in dist.iter().filter(|count| **count != 0) {
+        let x = *count as f64 / len as f64;
+        entropy -= x * x.log2();
+    }
+    entropy
+}
+
+/// Computes the mean of the byte values in some data, given its
+/// distribution of byte values and its length.
+fn mean(dist: &Distribution, len: usize) -> Option<f64> {
+    if len == 0 {
+        return None;
+    }
+    let sum: f64 = dist
+        .iter()
+        .enumerate()
+        .map(|(byte, count)| byte as f64 * *count as f64)
+        .sum();
+    Some(sum / len as f64)
+}
+
+/// Computes the mean absolute deviation of the byte values in some data
+/// with respect to `mean`, given its distribution of byte values and its
+/// length.
+fn deviation(dist: &Distribution, len: usize, mean: f64) -> Option<f64> {
+    if len == 0 {
+        return None;
+    }
+    let sum: f64 = dist
+        .iter()
+        .enumerate()
+        .map(|(byte, count)| (byte as f64 - mean).abs() * *count as f64)
+        .sum();
+    Some(sum / len as f64)
+}
+
+/// Computes the serial correlation coefficient of some data, which measures
+/// the extent to which each byte depends on the previous one.
+///
+/// This follows the algorithm used by YARA, which returns -100000 when the
+/// coefficient can't be computed (e.g: all bytes are equal).
+fn serial_correlation(data: &[u8]) -> f64 {
+    let mut scct1 = 0.0;
+    let mut scct2 = 0.0;
+    let mut scct3 = 0.0;
+    let mut scclast = 0.0;
+
+    for byte in data {
+        let sccun = *byte as f64;
+        scct1 += scclast * sccun;
+        scct2 += sccun;
+        scct3 += sccun * sccun;
+        scclast = sccun;
+    }
+
+    let len = data.len() as f64;
+
+    scct2 *= scct2;
+
+    let scc = len * scct3 - scct2;
+
+    if scc == 0.0 {
+        -100000.0
+    } else {
+        (len * scct1 - scct2) / scc
+    }
+}
+
+/// Approximates the value of PI using the Monte Carlo method, and returns
+/// the relative error of the approximation.
+///
+/// Each group of 6 bytes is used as the 24-bit X and Y coordinates of a
+/// point in a square, and the proportion of points that fall within the
+/// inscribed circle approximates PI/4. Random data produces errors close
+/// to 0. Returns `None` if the data is shorter than 6 bytes.
+fn monte_carlo_pi(data: &[u8]) -> Option<f64> {
+    let incirc = (256.0_f64.powi(3) - 1.0).powi(2);
+
+    let mut mcount = 0;
+    let mut inmont = 0;
+
+    let coordinate =
+        |bytes: &[u8]| bytes.iter().fold(0.0, |c, b| c * 256.0 + *b as f64);
+
+    for point in data.chunks_exact(6) {
+        let mx = coordinate(&point[..3]);
+        let my = coordinate(&point[3..]);
+        mcount += 1;
+        if mx * mx + my * my <= incirc {
+            inmont += 1;
+        }
+    }
+
+    if mcount == 0 {
+        return None;
+    }
+
+    let mpi = 4.0 * (inmont as f64 / mcount as f64);
+
+    Some(((mpi - PI) / PI).abs())
+}
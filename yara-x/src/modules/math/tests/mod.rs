@@ -0,0 +1,178 @@
+use crate::tests;
+use tests::*;
+
+#[test]
+#[cfg(feature = "math-module")]
+fn test_math_module() {
+    rule_true!(
+        r#"
+        import "math"
+        rule test {
+          condition:
+            math.entropy("ab") == 1.0 and
+            math.entropy("aaaa") == 0.0 and
+            math.entropy(0, filesize) == math.entropy("foobarbaz") and
+            math.entropy(3, 3) == math.entropy("bar") and
+            math.in_range(math.entropy(0, filesize), 2.503, 2.504)
+        }
+        "#,
+        b"foobarbaz"
+    );
+
+    rule_true!(
+        r#"
+        import "math"
+        rule test {
+          condition:
+            math.entropy(0, filesize) == 8.0 and
+            math.entropy(0, 128) == 7.0 and
+            math.mean(0, filesize) == math.MEAN_BYTES and
+            math.mean(0, 2) == 0.5
+        }
+        "#,
+        (0..=255).collect::<Vec<u8>>().as_slice()
+    );
+
+    rule_true!(
+        r#"
+        import "math"
+        rule test {
+          condition:
+            math.mean("ABCD") == 66.5 and
+            math.mean(0, filesize) == 66.5 and
+            math.deviation("ABCD", 66.5) == 1.0 and
+            math.deviation(0, filesize, 66.5) == 1.0 and
+            math.deviation(0, filesize, math.MEAN_BYTES) == 61.0
+        }
+        "#,
+        b"ABCD"
+    );
+
+    rule_true!(
+        r#"
+        import "math"
+        rule test {
+          condition:
+            math.serial_correlation("aaaa") == -100000.0 and
+            math.serial_correlation(0, filesize) ==
+              math.serial_correlation("foobarbaz") and
+            math.in_range(
+              math.serial_correlation(0, filesize), -18.81, -18.80)
+        }
+        "#,
+        b"foobarbaz"
+    );
+
+    rule_true!(
+        r#"
+        import "math"
+        rule test {
+          condition:
+            math.monte_carlo_pi(0, filesize) ==
+              math.monte_carlo_pi("abcdef") and
+            math.in_range(math.monte_carlo_pi("abcdef"), 0.2732, 0.2733) and
+            not defined math.monte_carlo_pi("abcde") and
+            not defined math.monte_carlo_pi(1, filesize)
+        }
+        "#,
+        b"abcdef"
+    );
+
+    rule_true!(
+        r#"
+        import "math"
+        rule test {
+          condition:
+            math.count(0x61) == 3 and
+            math.count(0x61, 0, 2) == 1 and
+            math.count(0x7A, 0, filesize) == 1 and
+            math.count(0x78) == 0 and
+            math.percentage(0x61) == 0.5 and
+            math.percentage(0x7A, 0, 2) == 0.5 and
+            math.percentage(0x62, 0, 2) == 0.0 and
+            math.mode() == 0x61 and
+            math.mode(0, 2) == 0x61 and
+            math.mode(3, 3) == 0x62 and
+            not defined math.count(256) and
+            not defined math.count(0x61, filesize, 1) and
+            not defined math.count(0x61, -1, 1) and
+            not defined math.percentage(0x61, 0, 0)
+        }
+        "#,
+        b"azabab"
+    );
+
+    rule_false!(
+        r#"
+        import "math"
+        rule test {
+          condition:
+            math.count(0x61) == 0
+        }
+        "#
+    );
+
+    rule_true!(
+        r#"
+        import "math"
+        rule test {
+          condition:
+            math.in_range(1.0, 1.0, 2.0) and
+            not math.in_range(2.5, 1.0, 2.0) and
+            math.min(1, 2) == 1 and
+            math.max(1, 2) == 2 and
+            math.min(-1, 2) == 2 and
+            math.max(-1, 2) == -1 and
+            math.abs(-5) == 5 and
+            math.abs(5) == 5 and
+            math.to_number(true) == 1 and
+            math.to_number(false) == 0 and
+            math.to_number(filesize == 0) == 1 and
+            math.to_string(1234) == "1234" and
+            math.to_string(-1) == "-1" and
+            math.to_string(32, 16) == "20" and
+            math.to_string(8, 8) == "10" and
+            math.to_string(-1, 16) == "ffffffffffffffff" and
+            not defined math.to_string(1, 2)
+        }
+        "#
+    );
+}
+
+#[test]
+#[cfg(feature = "math-module")]
+fn test_math_cache() {
+    let rules = crate::compile(
+        r#"
+        import "math"
+        rule test {
+          condition:
+            math.entropy(0, filesize) == 0.0 and
+            math.entropy(0, filesize + 100) == 0.0
+        }
+        "#,
+    )
+    .unwrap();
+
+    let mut scanner = crate::scanner::Scanner::new(&rules);
+
+    // The data must be large enough for the computed values to be cached.
+    let a = [b'a'; 8192];
+    let b = [b'b'; 8192];
+    let mut ab = a;
+
+    ab[..4096].copy_from_slice(&b[..4096]);
+
+    // The cached values from one scan must not be used in the next one.
+    assert_eq!(scanner.scan(&a).unwrap().matching_rules().len(), 1);
+    assert_eq!(scanner.scan(&ab).unwrap().matching_rules().len(), 0);
+    assert_eq!(scanner.scan(&b).unwrap().matching_rules().len(), 1);
+
+    // Not even when the module's output is provided by the user, and
+    // therefore the module's main function is not called.
+    let output = crate::modules::protos::math::Math::new();
+
+    scanner.set_module_output("math", Box::new(output)).unwrap();
+
+    assert_eq!(scanner.scan(&ab).unwrap().matching_rules().len(), 0);
+}
@@ -132,8 +132,14 @@ pub mod mods {
     capabilities for their own purposes.
      */
 
+    /// Data structure returned by the `cuckoo` module.
+    pub use super::protos::cuckoo::Cuckoo;
+    /// Data structure returned by the `dotnet` module.
+    pub use super::protos::dotnet::Dotnet;
     /// Data structure returned by the `elf` module.
     pub use super::protos::elf::ELF;
+    /// Data structure returned by the `filetype` module.
+    pub use super::protos::filetype::Filetype;
     /// Data structure returned by the `lnk` module.
     pub use super::protos::lnk::Lnk;
     /// Data structure returned by the `macho` module.
@@ -18,4 +18,14 @@ mod lnk;
 #[cfg(feature = "time-module")]
 mod time;
 #[cfg(feature = "test_proto3-module")]
-mod test_proto3;
\ No newline at end of file
+mod test_proto3;
+#[cfg(feature = "math-module")]
+mod math;
+#[cfg(feature = "dotnet-module")]
+mod dotnet;
+#[cfg(feature = "console-module")]
+mod console;
+#[cfg(feature = "filetype-module")]
+mod filetype;
+#[cfg(feature = "cuckoo-module")]
+mod cuckoo;
\ No newline at end of file
@@ -0,0 +1,1327 @@
+/*! Parser for Authenticode signatures.
+
+Authenticode signatures are stored in the PE's certificate table, which is
+pointed to by the IMAGE_DIRECTORY_ENTRY_SECURITY entry of the PE directory.
+Each entry in the certificate table is a WIN_CERTIFICATE structure, which
+contains a PKCS#7 [`SignedData`][1] structure encoded in DER format. This
+module parses these structures and extracts information about the signer,
+the certificates and countersignatures.
+
+Signatures are verified only structurally, by checking that the digest of
+the PE file matches the digest stored in the signature, and that the digest
+of the signed content matches the one in the signer's authenticated
+attributes. The cryptographic signatures themselves are not verified, and
+certificates are not validated against any trust store.
+
+More info:
+
+https://learn.microsoft.com/en-us/windows-hardware/drivers/install/authenticode
+https://download.microsoft.com/download/9/c/5/9c5b2167-8017-4bae-9fde-d599bac8184a/Authenticode_PE.docx
+
+[1]: https://datatracker.ietf.org/doc/html/rfc2315#section-9.1
+ */
+
+use std::fmt::Write;
+
+use itertools::Itertools;
+use nom::bytes::complete::take;
+use nom::combinator::{opt, verify};
+use nom::error::ErrorKind;
+use nom::multi::many0;
+use nom::number::complete::u8;
+use nom::{Err, IResult};
+use protobuf::MessageField;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+use crate::modules::protos::pe;
+
+type Error<'a> = nom::error::Error<&'a [u8]>;
+
+/// Object identifiers (OIDs) used in Authenticode signatures.
+const OID_SIGNED_DATA: &str = "1.2.840.113549.1.7.2";
+const OID_SPC_INDIRECT_DATA: &str = "1.3.6.1.4.1.311.2.1.4";
+const OID_SPC_SP_OPUS_INFO: &str = "1.3.6.1.4.1.311.2.1.12";
+const OID_SPC_NESTED_SIGNATURE: &str = "1.3.6.1.4.1.311.2.4.1";
+const OID_MS_COUNTERSIGNATURE: &str = "1.3.6.1.4.1.311.3.3.1";
+const OID_MESSAGE_DIGEST: &str = "1.2.840.113549.1.9.4";
+const OID_SIGNING_TIME: &str = "1.2.840.113549.1.9.5";
+const OID_COUNTERSIGNATURE: &str = "1.2.840.113549.1.9.6";
+const OID_TST_INFO: &str = "1.2.840.113549.1.9.16.1.4";
+
+/// DER tags used in Authenticode signatures.
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_OID: u8 = 0x06;
+const TAG_UTF8_STRING: u8 = 0x0c;
+const TAG_UTC_TIME: u8 = 0x17;
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+const TAG_BMP_STRING: u8 = 0x1e;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_SET: u8 = 0x31;
+const TAG_CONTEXT_0: u8 = 0xa0;
+const TAG_CONTEXT_1: u8 = 0xa1;
+const TAG_CONTEXT_PRIMITIVE_0: u8 = 0x80;
+const TAG_CONTEXT_PRIMITIVE_1: u8 = 0x81;
+
+/// Maximum nesting level for nested signatures. Nested signatures are
+/// stored as unauthenticated attributes of the primary signature, and
+/// they can't contain other nested signatures.
+const MAX_NESTING_LEVEL: usize = 1;
+
+/// Digest algorithms supported while verifying Authenticode signatures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DigestAlgorithm {
+    Md5,
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl DigestAlgorithm {
+    /// Returns the algorithm identified by the given OID.
+    ///
+    /// Besides the OIDs that identify pure digest algorithms, the OIDs
+    /// for signature algorithms like `sha1WithRSAEncryption` are also
+    /// accepted, as some signers use them where a digest algorithm is
+    /// expected.
+    fn from_oid(oid: &str) -> Option<Self> {
+        match oid {
+            "1.2.840.113549.2.5" | "1.2.840.113549.1.1.4" => Some(Self::Md5),
+            "1.3.14.3.2.26" | "1.2.840.113549.1.1.5" | "1.3.14.3.2.29" => {
+                Some(Self::Sha1)
+            }
+            "2.16.840.1.101.3.4.2.1" | "1.2.840.113549.1.1.11" => {
+                Some(Self::Sha256)
+            }
+            "2.16.840.1.101.3.4.2.2" | "1.2.840.113549.1.1.12" => {
+                Some(Self::Sha384)
+            }
+            "2.16.840.1.101.3.4.2.3" | "1.2.840.113549.1.1.13" => {
+                Some(Self::Sha512)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the algorithm's name (e.g: "sha256").
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Md5 => "md5",
+            Self::Sha1 => "sha1",
+            Self::Sha256 => "sha256",
+            Self::Sha384 => "sha384",
+            Self::Sha512 => "sha512",
+        }
+    }
+
+    /// Computes the digest of `data` with this algorithm.
+    fn digest(&self, data: &[u8]) -> Vec<u8> {
+        let mut hasher = Hasher::new(*self);
+        hasher.update(data);
+        hasher.finalize()
+    }
+}
+
+/// Incremental hasher that supports all the [`DigestAlgorithm`]s.
+pub enum Hasher {
+    Md5(md5::Context),
+    Sha1(Sha1),
+    Sha256(Sha256),
+    Sha384(Sha384),
+    Sha512(Sha512),
+}
+
+impl Hasher {
+    /// Creates a new hasher for the given algorithm.
+    pub fn new(algorithm: DigestAlgorithm) -> Self {
+        match algorithm {
+            DigestAlgorithm::Md5 => Self::Md5(md5::Context::new()),
+            DigestAlgorithm::Sha1 => Self::Sha1(Sha1::new()),
+            DigestAlgorithm::Sha256 => Self::Sha256(Sha256::new()),
+            DigestAlgorithm::Sha384 => Self::Sha384(Sha384::new()),
+            DigestAlgorithm::Sha512 => Self::Sha512(Sha512::new()),
+        }
+    }
+
+    /// Feeds more data into the hasher.
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Md5(h) => h.consume(data),
+            Self::Sha1(h) => h.update(data),
+            Self::Sha256(h) => h.update(data),
+            Self::Sha384(h) => h.update(data),
+            Self::Sha512(h) => h.update(data),
+        }
+    }
+
+    /// Consumes the hasher and returns the digest.
+    pub fn finalize(self) -> Vec<u8> {
+        match self {
+            Self::Md5(h) => h.compute().0.to_vec(),
+            Self::Sha1(h) => h.finalize().to_vec(),
+            Self::Sha256(h) => h.finalize().to_vec(),
+            Self::Sha384(h) => h.finalize().to_vec(),
+            Self::Sha512(h) => h.finalize().to_vec(),
+        }
+    }
+}
+
+/// Trait implemented by types that can compute the Authenticode digest
+/// of a file.
+pub trait AuthenticodeHasher {
+    /// Feeds all the bytes covered by the Authenticode signature into
+    /// the given hasher.
+    ///
+    /// Returns `None` if the file is malformed in a way that prevents
+    /// the digest from being computed.
+    fn hash(&self, hasher: &mut Hasher) -> Option<()>;
+
+    /// Returns the Authenticode digest computed with the given algorithm.
+    fn digest(&self, algorithm: DigestAlgorithm) -> Option<Vec<u8>> {
+        let mut hasher = Hasher::new(algorithm);
+        self.hash(&mut hasher)?;
+        Some(hasher.finalize())
+    }
+}
+
+/// Parser for Authenticode signatures.
+pub struct AuthenticodeParser {}
+
+impl AuthenticodeParser {
+    /// Parses the content of a WIN_CERTIFICATE structure of type
+    /// WIN_CERT_TYPE_PKCS_SIGNED_DATA, and returns the signatures found
+    /// in it.
+    ///
+    /// The result contains the primary signature followed by any nested
+    /// signature. `file_digest` returns the digest of the file computed
+    /// with a given algorithm, which is compared with the digest in each
+    /// signature.
+    pub fn parse<'a>(
+        input: &'a [u8],
+        file_digest: &mut impl FnMut(DigestAlgorithm) -> Option<Vec<u8>>,
+    ) -> Vec<AuthenticodeSignature<'a>> {
+        let mut signatures = Vec::new();
+        Self::parse_signatures(input, file_digest, 0, &mut signatures);
+        signatures
+    }
+
+    fn parse_signatures<'a>(
+        input: &'a [u8],
+        file_digest: &mut impl FnMut(DigestAlgorithm) -> Option<Vec<u8>>,
+        nesting_level: usize,
+        signatures: &mut Vec<AuthenticodeSignature<'a>>,
+    ) {
+        let signed_data = match content_info(input) {
+            Ok((_, (content_type, content)))
+                if content_type == OID_SIGNED_DATA =>
+            {
+                match signed_data(content) {
+                    Ok((_, signed_data)) => signed_data,
+                    Err(_) => return,
+                }
+            }
+            _ => return,
+        };
+
+        let signature =
+            match AuthenticodeSignature::new(signed_data, file_digest) {
+                Some(signature) => signature,
+                None => return,
+            };
+
+        let nested_signatures = signature
+            .signer_info
+            .unauthenticated_attrs
+            .iter()
+            .filter(|attr| attr.oid == OID_SPC_NESTED_SIGNATURE)
+            .flat_map(|attr| attr.values.iter().map(|value| value.raw))
+            .collect::<Vec<_>>();
+
+        signatures.push(signature);
+
+        if nesting_level < MAX_NESTING_LEVEL {
+            for nested_signature in nested_signatures {
+                Self::parse_signatures(
+                    nested_signature,
+                    file_digest,
+                    nesting_level + 1,
+                    signatures,
+                );
+            }
+        }
+    }
+}
+
+/// An Authenticode signature.
+pub struct AuthenticodeSignature<'a> {
+    /// Algorithm used for computing the digest of the file.
+    digest_algorithm: Option<DigestAlgorithm>,
+    /// Digest of the file as stored in the signature.
+    digest: &'a [u8],
+    /// Digest of the file as computed while parsing it. This is `None`
+    /// if the digest algorithm is not supported.
+    file_digest: Option<Vec<u8>>,
+    /// Information about the signer.
+    signer_info: SignerInfo<'a>,
+    /// Indexes within `certificates` of the certificates that form the
+    /// signer's certificate chain. The first one is the signer's
+    /// certificate.
+    signer_chain: Vec<usize>,
+    /// Name of the signed program, as specified by the signer.
+    program_name: Option<String>,
+    /// All the certificates included in the signature, including the
+    /// ones in countersignatures.
+    certificates: Vec<Certificate<'a>>,
+    /// Countersignatures.
+    countersignatures: Vec<Countersignature<'a>>,
+    /// True if the signature was successfully verified.
+    verified: bool,
+}
+
+impl<'a> AuthenticodeSignature<'a> {
+    fn new(
+        signed_data: SignedData<'a>,
+        file_digest: &mut impl FnMut(DigestAlgorithm) -> Option<Vec<u8>>,
+    ) -> Option<Self> {
+        if signed_data.content_type != OID_SPC_INDIRECT_DATA {
+            return None;
+        }
+
+        // Authenticode signatures have exactly one signer.
+        let signer_info = signed_data.signer_infos.into_iter().next()?;
+
+        let (_, (digest_algorithm, digest)) =
+            spc_indirect_data_content(signed_data.content?.value).ok()?;
+
+        let digest_algorithm = DigestAlgorithm::from_oid(&digest_algorithm);
+
+        let file_digest = digest_algorithm.and_then(file_digest);
+
+        let mut certificates = signed_data.certificates;
+
+        let signer_chain = build_chain(
+            &certificates,
+            signer_info.issuer,
+            signer_info.serial_number,
+        );
+
+        // The messageDigest authenticated attribute must match the digest
+        // of the SpcIndirectDataContent structure. Notice that the digest is
+        // computed only over the content of the structure, excluding the tag
+        // and length.
+        let content_digest_matches =
+            match (signer_info.digest_algorithm, signer_info.message_digest())
+            {
+                (Some(alg), Some(message_digest)) => {
+                    alg.digest(signed_data.content?.value) == message_digest
+                }
+                _ => false,
+            };
+
+        let program_name = signer_info
+            .authenticated_attrs
+            .iter()
+            .find(|attr| attr.oid == OID_SPC_SP_OPUS_INFO)
+            .and_then(|attr| attr.values.first())
+            .and_then(|value| spc_sp_opus_info(value.value).ok())
+            .and_then(|(_, program_name)| program_name);
+
+        let mut countersignatures = Vec::new();
+
+        for attr in signer_info.unauthenticated_attrs.iter() {
+            match attr.oid.as_str() {
+                OID_COUNTERSIGNATURE => {
+                    for value in attr.values.iter() {
+                        if let Ok((_, cs)) = signer_info_parser(value.raw) {
+                            countersignatures.push(
+                                Countersignature::from_pkcs9(
+                                    cs,
+                                    signer_info.encrypted_digest,
+                                    &certificates,
+                                ),
+                            );
+                        }
+                    }
+                }
+                OID_MS_COUNTERSIGNATURE => {
+                    for value in attr.values.iter() {
+                        if let Some(cs) = Countersignature::from_ms(
+                            value.raw,
+                            signer_info.encrypted_digest,
+                            &mut certificates,
+                        ) {
+                            countersignatures.push(cs);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let verified = !signer_chain.is_empty()
+            && content_digest_matches
+            && file_digest.as_deref() == Some(digest);
+
+        Some(Self {
+            digest_algorithm,
+            digest,
+            file_digest,
+            signer_info,
+            signer_chain,
+            program_name,
+            certificates,
+            countersignatures,
+            verified,
+        })
+    }
+
+    /// Returns the signer's certificate, if it was found.
+    pub fn signer_certificate(&self) -> Option<&Certificate<'a>> {
+        self.signer_chain.first().map(|i| &self.certificates[*i])
+    }
+
+    /// Returns all the certificates included in the signature.
+    pub fn certificates(&self) -> &[Certificate<'a>] {
+        self.certificates.as_slice()
+    }
+
+    /// Returns the signature's countersignatures.
+    pub fn countersignatures(&self) -> &[Countersignature<'a>] {
+        self.countersignatures.as_slice()
+    }
+
+    /// Returns true if the signature was successfully verified.
+    pub fn verified(&self) -> bool {
+        self.verified
+    }
+}
+
+/// A countersignature, which is a signature over the signer's signature.
+///
+/// Countersignatures are usually produced by timestamping authorities and
+/// they prove that the signature existed at certain point in time.
+pub struct Countersignature<'a> {
+    /// Algorithm used for computing `digest`.
+    digest_algorithm: Option<DigestAlgorithm>,
+    /// Digest of the countersigned signature.
+    digest: Option<&'a [u8]>,
+    /// Time at which the countersignature was produced, as a UNIX
+    /// timestamp.
+    signing_time: Option<i64>,
+    /// Certificates that form the countersigner's certificate chain.
+    chain: Vec<Certificate<'a>>,
+    /// True if the countersignature was successfully verified.
+    verified: bool,
+}
+
+impl<'a> Countersignature<'a> {
+    /// Creates a countersignature from a PKCS#9 countersignature attribute,
+    /// which contains a `SignerInfo` structure.
+    fn from_pkcs9(
+        signer_info: SignerInfo<'a>,
+        countersigned_digest: &[u8],
+        certificates: &[Certificate<'a>],
+    ) -> Self {
+        let digest = signer_info.message_digest();
+
+        let chain = build_chain(
+            certificates,
+            signer_info.issuer,
+            signer_info.serial_number,
+        );
+
+        let verified = match (signer_info.digest_algorithm, digest) {
+            (Some(alg), Some(digest)) => {
+                !chain.is_empty() && alg.digest(countersigned_digest) == digest
+            }
+            _ => false,
+        };
+
+        Self {
+            digest_algorithm: signer_info.digest_algorithm,
+            digest,
+            signing_time: signer_info.signing_time(),
+            chain: chain
+                .into_iter()
+                .map(|i| certificates[i].clone())
+                .collect(),
+            verified,
+        }
+    }
+
+    /// Creates a countersignature from a Microsoft's countersignature
+    /// attribute, which contains a RFC3161 timestamp token.
+    ///
+    /// The certificates included in the timestamp token are appended to
+    /// `certificates`.
+    fn from_ms(
+        input: &'a [u8],
+        countersigned_digest: &[u8],
+        certificates: &mut Vec<Certificate<'a>>,
+    ) -> Option<Self> {
+        let (_, (content_type, content)) = content_info(input).ok()?;
+
+        if content_type != OID_SIGNED_DATA {
+            return None;
+        }
+
+        let (_, signed_data) = signed_data(content).ok()?;
+
+        if signed_data.content_type != OID_TST_INFO {
+            return None;
+        }
+
+        let signer_info = signed_data.signer_infos.first()?;
+
+        // The TSTInfo structure is wrapped in an OCTET STRING.
+        let (_, tst_info_data) =
+            tagged(TAG_OCTET_STRING)(signed_data.content?.raw).ok()?;
+
+        let (_, tst_info) = tst_info(tst_info_data.value).ok()?;
+
+        let chain = build_chain(
+            &signed_data.certificates,
+            signer_info.issuer,
+            signer_info.serial_number,
+        );
+
+        // The messageDigest authenticated attribute must match the digest of
+        // the TSTInfo structure, and the message imprint in the TSTInfo must
+        // match the digest of the countersigned signature.
+        let tst_info_digest_matches =
+            match (signer_info.digest_algorithm, signer_info.message_digest())
+            {
+                (Some(alg), Some(message_digest)) => {
+                    alg.digest(tst_info_data.value) == message_digest
+                }
+                _ => false,
+            };
+
+        let imprint_algorithm =
+            DigestAlgorithm::from_oid(&tst_info.imprint_algorithm);
+
+        let imprint_matches = match imprint_algorithm {
+            Some(alg) => {
+                alg.digest(countersigned_digest) == tst_info.imprint_digest
+            }
+            None => false,
+        };
+
+        let verified =
+            !chain.is_empty() && tst_info_digest_matches && imprint_matches;
+
+        let chain = chain
+            .into_iter()
+            .map(|i| signed_data.certificates[i].clone())
+            .collect();
+
+        certificates.extend(signed_data.certificates);
+
+        Some(Self {
+            digest_algorithm: imprint_algorithm,
+            digest: Some(tst_info.imprint_digest),
+            signing_time: tst_info.gen_time,
+            chain,
+            verified,
+        })
+    }
+}
+
+/// A X.509 certificate.
+#[derive(Clone)]
+pub struct Certificate<'a> {
+    /// The whole certificate, DER-encoded.
+    raw: &'a [u8],
+    /// Version number, as it appears in the certificate (0 means v1).
+    version: i64,
+    /// Serial number, without the leading zero used for padding.
+    serial_number: &'a [u8],
+    /// Issuer's distinguished name, DER-encoded.
+    issuer_raw: &'a [u8],
+    /// Subject's distinguished name, DER-encoded.
+    subject_raw: &'a [u8],
+    /// Issuer's distinguished name (e.g: "/C=US/O=Example/CN=Example CA").
+    issuer: String,
+    /// Subject's distinguished name.
+    subject: String,
+    /// OID of the algorithm used for signing the certificate.
+    signature_algorithm: String,
+    /// Start of the validity period, as a UNIX timestamp.
+    not_before: i64,
+    /// End of the validity period, as a UNIX timestamp.
+    not_after: i64,
+}
+
+impl Certificate<'_> {
+    /// Returns the SHA1 digest of the DER-encoded certificate, which is
+    /// usually known as the certificate's thumbprint.
+    pub fn thumbprint(&self) -> String {
+        hex(DigestAlgorithm::Sha1.digest(self.raw).as_slice())
+    }
+
+    /// Returns the certificate's serial number, as a sequence of colon
+    /// separated hex bytes (e.g: "61:07:02:dc:00:00:00:00:00:0b").
+    pub fn serial_number(&self) -> String {
+        self.serial_number.iter().map(|b| format!("{:02x}", b)).join(":")
+    }
+}
+
+/// A `SignerInfo` structure.
+///
+/// ```text
+/// SignerInfo ::= SEQUENCE {
+///   version                   Version,
+///   issuerAndSerialNumber     IssuerAndSerialNumber,
+///   digestAlgorithm           DigestAlgorithmIdentifier,
+///   authenticatedAttributes   [0] IMPLICIT Attributes OPTIONAL,
+///   digestEncryptionAlgorithm DigestEncryptionAlgorithmIdentifier,
+///   encryptedDigest           EncryptedDigest,
+///   unauthenticatedAttributes [1] IMPLICIT Attributes OPTIONAL
+/// }
+/// ```
+struct SignerInfo<'a> {
+    issuer: &'a [u8],
+    serial_number: &'a [u8],
+    digest_algorithm: Option<DigestAlgorithm>,
+    authenticated_attrs: Vec<Attribute<'a>>,
+    encrypted_digest: &'a [u8],
+    unauthenticated_attrs: Vec<Attribute<'a>>,
+}
+
+impl<'a> SignerInfo<'a> {
+    /// Returns the value of the messageDigest authenticated attribute.
+    fn message_digest(&self) -> Option<&'a [u8]> {
+        self.authenticated_attrs
+            .iter()
+            .find(|attr| attr.oid == OID_MESSAGE_DIGEST)
+            .and_then(|attr| attr.values.first())
+            .filter(|value| value.tag == TAG_OCTET_STRING)
+            .map(|value| value.value)
+    }
+
+    /// Returns the value of the signingTime authenticated attribute.
+    fn signing_time(&self) -> Option<i64> {
+        self.authenticated_attrs
+            .iter()
+            .find(|attr| attr.oid == OID_SIGNING_TIME)
+            .and_then(|attr| attr.values.first())
+            .and_then(time)
+    }
+}
+
+/// An attribute in a `SignerInfo` structure.
+///
+/// ```text
+/// Attribute ::= SEQUENCE {
+///   type   OBJECT IDENTIFIER,
+///   values SET OF ANY
+/// }
+/// ```
+struct Attribute<'a> {
+    oid: String,
+    values: Vec<Der<'a>>,
+}
+
+/// The PKCS#7 `SignedData` structure.
+///
+/// ```text
+/// SignedData ::= SEQUENCE {
+///   version           Version,
+///   digestAlgorithms  DigestAlgorithmIdentifiers,
+///   contentInfo       ContentInfo,
+///   certificates      [0] IMPLICIT ExtendedCertificatesAndCertificates OPTIONAL,
+///   crls              [1] IMPLICIT CertificateRevocationLists OPTIONAL,
+///   signerInfos       SignerInfos
+/// }
+/// ```
+struct SignedData<'a> {
+    /// Type of the content in `contentInfo`.
+    content_type: String,
+    /// The content in `contentInfo`, if any.
+    content: Option<Der<'a>>,
+    certificates: Vec<Certificate<'a>>,
+    signer_infos: Vec<SignerInfo<'a>>,
+}
+
+/// Information about a TSTInfo structure (RFC3161).
+struct TstInfo<'a> {
+    imprint_algorithm: String,
+    imprint_digest: &'a [u8],
+    gen_time: Option<i64>,
+}
+
+/// A DER-encoded value.
+#[derive(Clone, Copy)]
+struct Der<'a> {
+    /// The value's tag.
+    tag: u8,
+    /// The value's content, not including the tag and length.
+    value: &'a [u8],
+    /// The whole DER encoding, including the tag and length.
+    raw: &'a [u8],
+}
+
+/// Parses a DER-encoded value.
+fn der(input: &[u8]) -> IResult<&[u8], Der> {
+    let (remainder, tag) = u8(input)?;
+    let (remainder, length) = der_length(remainder)?;
+    let (remainder, value) = take(length)(remainder)?;
+    let raw = &input[..input.len() - remainder.len()];
+    Ok((remainder, Der { tag, value, raw }))
+}
+
+/// Parses the length of a DER-encoded value.
+///
+/// The indefinite length form is not accepted, as it is not valid in DER.
+fn der_length(input: &[u8]) -> IResult<&[u8], usize> {
+    let (remainder, first) = u8(input)?;
+
+    if first & 0x80 == 0 {
+        return Ok((remainder, first as usize));
+    }
+
+    let num_bytes = (first & 0x7f) as usize;
+
+    if num_bytes == 0 || num_bytes > 4 {
+        return Err(Err::Error(Error::new(input, ErrorKind::Verify)));
+    }
+
+    let (remainder, bytes) = take(num_bytes)(remainder)?;
+
+    Ok((
+        remainder,
+        bytes.iter().fold(0_usize, |len, b| (len << 8) | *b as usize),
+    ))
+}
+
+/// Returns a parser for DER-encoded values with the given tag.
+fn tagged<'a>(tag: u8) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Der<'a>> {
+    verify(der, move |value: &Der| value.tag == tag)
+}
+
+/// Parses an OBJECT IDENTIFIER and returns it in dotted form.
+fn oid(input: &[u8]) -> IResult<&[u8], String> {
+    let (remainder, value) = tagged(TAG_OID)(input)?;
+    Ok((remainder, oid_to_string(value.value)))
+}
+
+/// Parses an AlgorithmIdentifier and returns the algorithm's OID.
+///
+/// ```text
+/// AlgorithmIdentifier ::= SEQUENCE {
+///   algorithm   OBJECT IDENTIFIER,
+///   parameters  ANY DEFINED BY algorithm OPTIONAL
+/// }
+/// ```
+fn algorithm_identifier(input: &[u8]) -> IResult<&[u8], String> {
+    let (remainder, seq) = tagged(TAG_SEQUENCE)(input)?;
+    let (_, algorithm) = oid(seq.value)?;
+    Ok((remainder, algorithm))
+}
+
+/// Parses a ContentInfo structure, returning the content type and the
+/// content itself.
+///
+/// ```text
+/// ContentInfo ::= SEQUENCE {
+///   contentType ContentType,
+///   content     [0] EXPLICIT ANY DEFINED BY contentType
+/// }
+/// ```
+fn content_info(input: &[u8]) -> IResult<&[u8], (String, &[u8])> {
+    let (remainder, seq) = tagged(TAG_SEQUENCE)(input)?;
+    let (content, content_type) = oid(seq.value)?;
+    let (_, content) = tagged(TAG_CONTEXT_0)(content)?;
+    Ok((remainder, (content_type, content.value)))
+}
+
+/// Parses a SignedData structure.
+fn signed_data(input: &[u8]) -> IResult<&[u8], SignedData> {
+    let (remainder, seq) = tagged(TAG_SEQUENCE)(input)?;
+
+    let (fields, _version) = tagged(TAG_INTEGER)(seq.value)?;
+    let (fields, _digest_algorithms) = tagged(TAG_SET)(fields)?;
+
+    // The encapsulated content info is similar to ContentInfo, but the
+    // content is optional.
+    let (fields, content_info) = tagged(TAG_SEQUENCE)(fields)?;
+    let (content, content_type) = oid(content_info.value)?;
+    let (_, content) = opt(tagged(TAG_CONTEXT_0))(content)?;
+
+    // The content is wrapped in an explicit [0] tag, the actual content
+    // is inside it.
+    let content = match content {
+        Some(content) => Some(der(content.value)?.1),
+        None => None,
+    };
+
+    let (fields, certificates) = opt(tagged(TAG_CONTEXT_0))(fields)?;
+    let (fields, _crls) = opt(tagged(TAG_CONTEXT_1))(fields)?;
+    let (_, signer_infos) = tagged(TAG_SET)(fields)?;
+
+    // Certificates that can't be parsed are ignored.
+    let certificates = match certificates {
+        Some(certificates) => many0(der)(certificates.value)?
+            .1
+            .into_iter()
+            .filter_map(|cert| certificate(cert.raw).ok())
+            .map(|(_, cert)| cert)
+            .collect(),
+        None => Vec::new(),
+    };
+
+    let (_, signer_infos) = many0(signer_info_parser)(signer_infos.value)?;
+
+    Ok((
+        remainder,
+        SignedData { content_type, content, certificates, signer_infos },
+    ))
+}
+
+/// Parses a SignerInfo structure.
+fn signer_info_parser(input: &[u8]) -> IResult<&[u8], SignerInfo> {
+    let (remainder, seq) = tagged(TAG_SEQUENCE)(input)?;
+
+    let (fields, _version) = tagged(TAG_INTEGER)(seq.value)?;
+
+    // IssuerAndSerialNumber ::= SEQUENCE {
+    //   issuer        Name,
+    //   serialNumber  CertificateSerialNumber
+    // }
+    //
+    // CMS allows a SubjectKeyIdentifier ([0] IMPLICIT) instead of the
+    // issuer and serial number. In that case the signer's certificate
+    // won't be found.
+    let (fields, sid) = der(fields)?;
+
+    let (issuer, serial_number) = if sid.tag == TAG_SEQUENCE {
+        let (sid, issuer) = tagged(TAG_SEQUENCE)(sid.value)?;
+        let (_, serial_number) = tagged(TAG_INTEGER)(sid)?;
+        (issuer.raw, strip_leading_zero(serial_number.value))
+    } else {
+        (&[] as &[u8], &[] as &[u8])
+    };
+
+    let (fields, digest_algorithm) = algorithm_identifier(fields)?;
+    let (fields, authenticated_attrs) = opt(tagged(TAG_CONTEXT_0))(fields)?;
+    let (fields, _digest_encryption_algorithm) = algorithm_identifier(fields)?;
+    let (fields, encrypted_digest) = tagged(TAG_OCTET_STRING)(fields)?;
+    let (_, unauthenticated_attrs) = opt(tagged(TAG_CONTEXT_1))(fields)?;
+
+    let authenticated_attrs = match authenticated_attrs {
+        Some(attrs) => many0(attribute)(attrs.value)?.1,
+        None => Vec::new(),
+    };
+
+    let unauthenticated_attrs = match unauthenticated_attrs {
+        Some(attrs) => many0(attribute)(attrs.value)?.1,
+        None => Vec::new(),
+    };
+
+    Ok((
+        remainder,
+        SignerInfo {
+            issuer,
+            serial_number,
+            digest_algorithm: DigestAlgorithm::from_oid(&digest_algorithm),
+            authenticated_attrs,
+            encrypted_digest: encrypted_digest.value,
+            unauthenticated_attrs,
+        },
+    ))
+}
+
+/// Parses an Attribute structure.
+fn attribute(input: &[u8]) -> IResult<&[u8], Attribute> {
+    let (remainder, seq) = tagged(TAG_SEQUENCE)(input)?;
+    let (values, oid) = oid(seq.value)?;
+    let (_, values) = tagged(TAG_SET)(values)?;
+    let (_, values) = many0(der)(values.value)?;
+    Ok((remainder, Attribute { oid, values }))
+}
+
+/// Parses a X.509 certificate.
+///
+/// ```text
+/// Certificate ::= SEQUENCE {
+///   tbsCertificate       TBSCertificate,
+///   signatureAlgorithm   AlgorithmIdentifier,
+///   signatureValue       BIT STRING
+/// }
+///
+/// TBSCertificate ::= SEQUENCE {
+///   version         [0] EXPLICIT Version DEFAULT v1,
+///   serialNumber         CertificateSerialNumber,
+///   signature            AlgorithmIdentifier,
+///   issuer               Name,
+///   validity             Validity,
+///   subject              Name,
+///   ...
+/// }
+/// ```
+fn certificate(input: &[u8]) -> IResult<&[u8], Certificate> {
+    let (remainder, seq) = tagged(TAG_SEQUENCE)(input)?;
+    let raw = seq.raw;
+
+    let (fields, tbs_certificate) = tagged(TAG_SEQUENCE)(seq.value)?;
+    let (_, signature_algorithm) = algorithm_identifier(fields)?;
+
+    let (fields, version) = opt(tagged(TAG_CONTEXT_0))(tbs_certificate.value)?;
+
+    let version = match version {
+        Some(version) => integer(tagged(TAG_INTEGER)(version.value)?.1.value),
+        None => 0,
+    };
+
+    let (fields, serial_number) = tagged(TAG_INTEGER)(fields)?;
+    let (fields, _signature) = algorithm_identifier(fields)?;
+    let (fields, issuer) = tagged(TAG_SEQUENCE)(fields)?;
+    let (fields, validity) = tagged(TAG_SEQUENCE)(fields)?;
+    let (_, subject) = tagged(TAG_SEQUENCE)(fields)?;
+
+    let (validity, not_before) = der(validity.value)?;
+    let (_, not_after) = der(validity)?;
+
+    Ok((
+        remainder,
+        Certificate {
+            raw,
+            version,
+            serial_number: strip_leading_zero(serial_number.value),
+            issuer_raw: issuer.raw,
+            subject_raw: subject.raw,
+            issuer: name_to_string(issuer.value),
+            subject: name_to_string(subject.value),
+            signature_algorithm,
+            not_before: time(&not_before).unwrap_or(0),
+            not_after: time(&not_after).unwrap_or(0),
+        },
+    ))
+}
+
+/// Parses a SpcIndirectDataContent structure, returning the OID of the
+/// digest algorithm and the digest.
+///
+/// ```text
+/// SpcIndirectDataContent ::= SEQUENCE {
+///   data          SpcAttributeTypeAndOptionalValue,
+///   messageDigest DigestInfo
+/// }
+///
+/// DigestInfo ::= SEQUENCE {
+///   digestAlgorithm  AlgorithmIdentifier,
+///   digest           OCTETSTRING
+/// }
+/// ```
+fn spc_indirect_data_content(input: &[u8]) -> IResult<&[u8], (String, &[u8])> {
+    let (remainder, _data) = tagged(TAG_SEQUENCE)(input)?;
+    let (_, digest_info) = tagged(TAG_SEQUENCE)(remainder)?;
+    let (digest, digest_algorithm) = algorithm_identifier(digest_info.value)?;
+    let (_, digest) = tagged(TAG_OCTET_STRING)(digest)?;
+    Ok((&[], (digest_algorithm, digest.value)))
+}
+
+/// Parses a SpcSpOpusInfo structure, returning the program name.
+///
+/// ```text
+/// SpcSpOpusInfo ::= SEQUENCE {
+///   programName  [0] EXPLICIT SpcString OPTIONAL,
+///   moreInfo     [1] EXPLICIT SpcLink OPTIONAL,
+/// }
+///
+/// SpcString ::= CHOICE {
+///   unicode      [0] IMPLICIT BMPSTRING,
+///   ascii        [1] IMPLICIT IA5STRING
+/// }
+/// ```
+fn spc_sp_opus_info(input: &[u8]) -> IResult<&[u8], Option<String>> {
+    let (_, program_name) = opt(tagged(TAG_CONTEXT_0))(input)?;
+
+    let program_name = match program_name {
+        Some(program_name) => {
+            let (_, program_name) = der(program_name.value)?;
+            match program_name.tag {
+                TAG_CONTEXT_PRIMITIVE_0 => {
+                    Some(bmp_string(program_name.value))
+                }
+                TAG_CONTEXT_PRIMITIVE_1 => Some(
+                    String::from_utf8_lossy(program_name.value).into_owned(),
+                ),
+                _ => None,
+            }
+        }
+        None => None,
+    };
+
+    Ok((&[], program_name))
+}
+
+/// Parses a TSTInfo structure (RFC3161).
+///
+/// ```text
+/// TSTInfo ::= SEQUENCE  {
+///   version         INTEGER  { v1(1) },
+///   policy          TSAPolicyId,
+///   messageImprint  MessageImprint,
+///   serialNumber    INTEGER,
+///   genTime         GeneralizedTime,
+///   ...
+/// }
+///
+/// MessageImprint ::= SEQUENCE  {
+///   hashAlgorithm   AlgorithmIdentifier,
+///   hashedMessage   OCTET STRING
+/// }
+/// ```
+fn tst_info(input: &[u8]) -> IResult<&[u8], TstInfo> {
+    let (remainder, seq) = tagged(TAG_SEQUENCE)(input)?;
+    let (fields, _version) = tagged(TAG_INTEGER)(seq.value)?;
+    let (fields, _policy) = oid(fields)?;
+    let (fields, message_imprint) = tagged(TAG_SEQUENCE)(fields)?;
+    let (fields, _serial_number) = tagged(TAG_INTEGER)(fields)?;
+    let (_, gen_time) = tagged(TAG_GENERALIZED_TIME)(fields)?;
+
+    let (imprint_digest, imprint_algorithm) =
+        algorithm_identifier(message_imprint.value)?;
+
+    let (_, imprint_digest) = tagged(TAG_OCTET_STRING)(imprint_digest)?;
+
+    Ok((
+        remainder,
+        TstInfo {
+            imprint_algorithm,
+            imprint_digest: imprint_digest.value,
+            gen_time: time(&gen_time),
+        },
+    ))
+}
+
+/// Given the signer's issuer and serial number, returns the indexes within
+/// `certificates` of the certificates that form the signer's certificate
+/// chain. The first certificate in the chain is the signer's certificate,
+/// the second is the one that issued the signer's certificate, and so on.
+///
+/// The result is empty if the signer's certificate was not found.
+fn build_chain(
+    certificates: &[Certificate],
+    issuer: &[u8],
+    serial_number: &[u8],
+) -> Vec<usize> {
+    let mut chain = Vec::new();
+
+    let mut current = certificates.iter().position(|cert| {
+        cert.issuer_raw == issuer && cert.serial_number == serial_number
+    });
+
+    while let Some(i) = current {
+        // The length of the chain is limited to the number of certificates,
+        // this prevents infinite loops with circular chains.
+        if chain.len() == certificates.len() {
+            break;
+        }
+        chain.push(i);
+        let cert = &certificates[i];
+        // Stop at self-signed certificates.
+        if cert.issuer_raw == cert.subject_raw {
+            break;
+        }
+        current = certificates
+            .iter()
+            .position(|issuer| issuer.subject_raw == cert.issuer_raw);
+    }
+
+    chain
+}
+
+/// Converts a DER-encoded OID into its dotted form (e.g: "1.2.840.113549").
+fn oid_to_string(oid: &[u8]) -> String {
+    let mut result = String::new();
+    let mut value: u64 = 0;
+    let mut first = true;
+
+    for b in oid {
+        value = value.wrapping_shl(7) | (*b & 0x7f) as u64;
+        if b & 0x80 == 0 {
+            if first {
+                // The first two components are encoded in a single value
+                // X * 40 + Y, where X is 0, 1 or 2.
+                let x = (value / 40).min(2);
+                write!(result, "{}.{}", x, value - x * 40).unwrap();
+                first = false;
+            } else {
+                write!(result, ".{}", value).unwrap();
+            }
+            value = 0;
+        }
+    }
+
+    result
+}
+
+/// Converts a DER-encoded distinguished name into a string like
+/// "/C=US/O=Example/CN=Example CA".
+fn name_to_string(name: &[u8]) -> String {
+    let mut result = String::new();
+
+    // Name ::= SEQUENCE OF RelativeDistinguishedName
+    // RelativeDistinguishedName ::= SET OF AttributeTypeAndValue
+    // AttributeTypeAndValue ::= SEQUENCE { type OID, value ANY }
+    let rdns = many0(tagged(TAG_SET))(name).map(|(_, rdns)| rdns);
+
+    for rdn in rdns.unwrap_or_default() {
+        let attrs = many0(tagged(TAG_SEQUENCE))(rdn.value)
+            .map(|(_, attrs)| attrs)
+            .unwrap_or_default();
+
+        for attr in attrs {
+            if let Ok((value, attr_type)) = oid(attr.value) {
+                if let Ok((_, value)) = der(value) {
+                    let value = if value.tag == TAG_BMP_STRING {
+                        bmp_string(value.value)
+                    } else if value.tag == TAG_UTF8_STRING {
+                        String::from_utf8_lossy(value.value).into_owned()
+                    } else {
+                        // PrintableString, IA5String, T61String and
+                        // others are treated as Latin-1.
+                        value.value.iter().map(|b| *b as char).collect()
+                    };
+                    write!(
+                        result,
+                        "/{}={}",
+                        attribute_short_name(&attr_type),
+                        value
+                    )
+                    .unwrap();
+                }
+            }
+        }
+    }
+
+    result
+}
+
+/// Returns the short name for attributes in a distinguished name.
+fn attribute_short_name(oid: &str) -> &str {
+    match oid {
+        "2.5.4.3" => "CN",
+        "2.5.4.4" => "SN",
+        "2.5.4.5" => "serialNumber",
+        "2.5.4.6" => "C",
+        "2.5.4.7" => "L",
+        "2.5.4.8" => "ST",
+        "2.5.4.9" => "street",
+        "2.5.4.10" => "O",
+        "2.5.4.11" => "OU",
+        "2.5.4.12" => "title",
+        "2.5.4.15" => "businessCategory",
+        "2.5.4.17" => "postalCode",
+        "2.5.4.41" => "name",
+        "2.5.4.42" => "GN",
+        "2.5.4.43" => "initials",
+        "2.5.4.46" => "dnQualifier",
+        "2.5.4.65" => "pseudonym",
+        "0.9.2342.19200300.100.1.1" => "UID",
+        "0.9.2342.19200300.100.1.25" => "DC",
+        "1.2.840.113549.1.9.1" => "emailAddress",
+        "1.3.6.1.4.1.311.60.2.1.1" => "jurisdictionL",
+        "1.3.6.1.4.1.311.60.2.1.2" => "jurisdictionST",
+        "1.3.6.1.4.1.311.60.2.1.3" => "jurisdictionC",
+        oid => oid,
+    }
+}
+
+/// Returns the name of a signature algorithm given its OID.
+pub fn algorithm_name(oid: &str) -> Option<&'static str> {
+    match oid {
+        "1.2.840.113549.1.1.1" => Some("rsaEncryption"),
+        "1.2.840.113549.1.1.4" => Some("md5WithRSAEncryption"),
+        "1.2.840.113549.1.1.5" => Some("sha1WithRSAEncryption"),
+        "1.2.840.113549.1.1.10" => Some("rsassaPss"),
+        "1.2.840.113549.1.1.11" => Some("sha256WithRSAEncryption"),
+        "1.2.840.113549.1.1.12" => Some("sha384WithRSAEncryption"),
+        "1.2.840.113549.1.1.13" => Some("sha512WithRSAEncryption"),
+        "1.2.840.10040.4.3" => Some("dsaWithSHA1"),
+        "1.2.840.10045.4.1" => Some("ecdsa-with-SHA1"),
+        "1.2.840.10045.4.3.2" => Some("ecdsa-with-SHA256"),
+        "1.2.840.10045.4.3.3" => Some("ecdsa-with-SHA384"),
+        "1.2.840.10045.4.3.4" => Some("ecdsa-with-SHA512"),
+        "1.3.14.3.2.29" => Some("sha1WithRSA"),
+        _ => None,
+    }
+}
+
+/// Decodes a UTCTime or GeneralizedTime value, returning a UNIX timestamp.
+fn time(value: &Der) -> Option<i64> {
+    let s = std::str::from_utf8(value.value).ok()?;
+
+    let (year, rest) = match value.tag {
+        TAG_UTC_TIME => {
+            let year: i64 = s.get(0..2)?.parse().ok()?;
+            // Years from 50 to 99 are 19xx, years from 00 to 49 are 20xx.
+            (if year < 50 { 2000 + year } else { 1900 + year }, s.get(2..)?)
+        }
+        TAG_GENERALIZED_TIME => (s.get(0..4)?.parse().ok()?, s.get(4..)?),
+        _ => return None,
+    };
+
+    let field = |range: std::ops::Range<usize>| -> Option<i64> {
+        let field = rest.get(range)?;
+        if field.bytes().all(|b| b.is_ascii_digit()) {
+            field.parse().ok()
+        } else {
+            None
+        }
+    };
+
+    let month = field(0..2)?;
+    let day = field(2..4)?;
+    let hour = field(4..6)?;
+    let minute = field(6..8)?;
+    // Seconds are optional in UTCTime.
+    let second = field(8..10).unwrap_or(0);
+
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    Some(
+        days_from_civil(year, month, day) * 86400
+            + hour * 3600
+            + minute * 60
+            + second,
+    )
+}
+
+/// Returns the number of days since 1970-01-01 for the given date.
+///
+/// See: http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = (if year >= 0 { year } else { year - 399 }) / 400;
+    let yoe = year - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// Decodes a BMPString (UTF-16 big-endian) value.
+fn bmp_string(value: &[u8]) -> String {
+    let chars =
+        value.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]]));
+    char::decode_utf16(chars)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+/// Decodes a DER-encoded INTEGER that fits in an i64.
+fn integer(value: &[u8]) -> i64 {
+    value.iter().take(8).fold(0, |n, b| (n << 8) | *b as i64)
+}
+
+/// Removes the leading zero that is used in INTEGER values for preventing
+/// positive numbers from being interpreted as negative ones.
+fn strip_leading_zero(value: &[u8]) -> &[u8] {
+    match value {
+        [0, rest @ ..] if !rest.is_empty() => rest,
+        _ => value,
+    }
+}
+
+/// Returns a string with the given bytes in lowercase hex form.
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).join("")
+}
+
+impl From<&Certificate<'_>> for pe::Certificate {
+    fn from(value: &Certificate) -> Self {
+        let mut cert = pe::Certificate::new();
+        cert.issuer = Some(value.issuer.clone());
+        cert.subject = Some(value.subject.clone());
+        cert.thumbprint = Some(value.thumbprint());
+        cert.version = Some(value.version + 1);
+        cert.algorithm = algorithm_name(&value.signature_algorithm)
+            .map(String::from)
+            .or_else(|| Some(value.signature_algorithm.clone()));
+        cert.algorithm_oid = Some(value.signature_algorithm.clone());
+        cert.serial = Some(value.serial_number());
+        cert.not_before = Some(value.not_before);
+        cert.not_after = Some(value.not_after);
+        cert
+    }
+}
+
+impl From<&Countersignature<'_>> for pe::CounterSignature {
+    fn from(value: &Countersignature) -> Self {
+        let mut cs = pe::CounterSignature::new();
+        cs.verified = Some(value.verified);
+        cs.sign_time = value.signing_time;
+        cs.digest = value.digest.map(hex);
+        cs.digest_alg =
+            value.digest_algorithm.map(|alg| alg.name().to_owned());
+        cs.chain = value.chain.iter().map(pe::Certificate::from).collect();
+        cs
+    }
+}
+
+impl From<&AuthenticodeSignature<'_>> for pe::Signature {
+    fn from(value: &AuthenticodeSignature) -> Self {
+        let mut sig = pe::Signature::new();
+
+        // The fields that describe the signer's certificate are copied
+        // directly into the signature.
+        if let Some(signer) = value.signer_certificate() {
+            let cert = pe::Certificate::from(signer);
+            sig.subject = cert.subject;
+            sig.issuer = cert.issuer;
+            sig.thumbprint = cert.thumbprint;
+            sig.version = cert.version;
+            sig.algorithm = cert.algorithm;
+            sig.algorithm_oid = cert.algorithm_oid;
+            sig.serial = cert.serial;
+            sig.not_before = cert.not_before;
+            sig.not_after = cert.not_after;
+        }
+
+        sig.verified = Some(value.verified);
+        sig.digest_alg =
+            value.digest_algorithm.map(|alg| alg.name().to_owned());
+        sig.digest = Some(hex(value.digest));
+        sig.file_digest = value.file_digest.as_deref().map(hex);
+
+        sig.number_of_certificates =
+            Some(value.certificates.len().try_into().unwrap());
+
+        sig.number_of_countersignatures =
+            Some(value.countersignatures.len().try_into().unwrap());
+
+        let mut signer_info = pe::SignerInfo::new();
+
+        signer_info.program_name = value.program_name.clone();
+        signer_info.digest = value.signer_info.message_digest().map(hex);
+        signer_info.digest_alg = value
+            .signer_info
+            .digest_algorithm
+            .map(|alg| alg.name().to_owned());
+        signer_info.chain = value
+            .signer_chain
+            .iter()
+            .map(|i| pe::Certificate::from(&value.certificates[*i]))
+            .collect();
+
+        sig.signer_info = MessageField::some(signer_info);
+
+        sig.certificates =
+            value.certificates.iter().map(pe::Certificate::from).collect();
+
+        sig.countersignatures = value
+            .countersignatures
+            .iter()
+            .map(pe::CounterSignature::from)
+            .collect();
+
+        sig
+    }
+}
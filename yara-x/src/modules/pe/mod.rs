@@ -4,6 +4,7 @@ This allows creating YARA rules based on PE metadata, including sections,
 imports and exports, resources, etc.
  */
 
+use std::rc::Rc;
 use std::slice::Iter;
 
 use bstr::BStr;
@@ -14,12 +15,15 @@ use nom::combinator::map;
 use nom::number::complete::{le_u16, le_u32};
 
 use crate::compiler::RegexpId;
+use crate::modules::pe::parser::SectionBounds;
 use crate::modules::prelude::*;
 use crate::modules::protos::pe::*;
+use crate::types::Struct;
 
 #[cfg(test)]
 mod tests;
 
+pub mod authenticode;
 pub mod parser;
 
 #[module_main]
@@ -522,6 +526,89 @@ fn exports_index_regexp(
     }
 }
 
+/// Returns the index in the export table of the first exported function with
+/// the given RVA.
+#[module_export]
+fn exports_index_by_rva(ctx: &ScanContext, rva: i64) -> Option<i64> {
+    match exports_impl(ctx, MatchCriteria::Rva(rva)) {
+        Some((true, position)) => Some(position as i64),
+        _ => None,
+    }
+}
+
+/// Converts a relative virtual address (RVA) to a file offset.
+///
+/// The result is undefined if the RVA doesn't correspond to any location
+/// in the file.
+#[module_export]
+fn rva_to_offset(ctx: &ScanContext, rva: i64) -> Option<i64> {
+    let pe = ctx.module_output::<PE>()?;
+    let offset = parser::rva_to_offset(
+        rva.try_into().ok()?,
+        pe.sections.iter().map(SectionBounds::from),
+        pe.file_alignment?,
+        pe.section_alignment?,
+        ctx.scanned_data().len(),
+    )?;
+    Some(offset.into())
+}
+
+/// Converts a file offset to a relative virtual address (RVA).
+///
+/// The result is undefined if the offset is outside the file, or it doesn't
+/// correspond to the PE headers nor to any section.
+#[module_export]
+fn offset_to_rva(ctx: &ScanContext, offset: i64) -> Option<i64> {
+    let pe = ctx.module_output::<PE>()?;
+    let rva = parser::offset_to_rva(
+        offset.try_into().ok()?,
+        pe.sections.iter().map(SectionBounds::from),
+        pe.file_alignment?,
+        pe.section_alignment?,
+        ctx.scanned_data().len(),
+    )?;
+    Some(rva.into())
+}
+
+/// Returns the index of the first section with the given name.
+///
+/// The name is compared with both `name` and `full_name`, so that sections
+/// with names longer than 8 characters can be found by their full name.
+#[module_export(name = "section_index")]
+fn section_index_name(ctx: &ScanContext, name: RuntimeString) -> Option<i64> {
+    let pe = ctx.module_output::<PE>()?;
+    let name = name.as_bstr(ctx);
+
+    pe.sections
+        .iter()
+        .position(|section| {
+            section.name.as_deref().is_some_and(|n| n == name.as_bytes())
+                || section
+                    .full_name
+                    .as_deref()
+                    .is_some_and(|n| n == name.as_bytes())
+        })
+        .map(|index| index as i64)
+}
+
+/// Returns the index of the first section that contains the given file
+/// offset.
+///
+/// Sections without raw data never contain any offset.
+#[module_export(name = "section_index")]
+fn section_index_offset(ctx: &ScanContext, offset: i64) -> Option<i64> {
+    let pe = ctx.module_output::<PE>()?;
+
+    pe.sections
+        .iter()
+        .position(|section| {
+            let start = section.raw_data_offset() as i64;
+            let end = start + section.raw_data_size() as i64;
+            (start..end).contains(&offset)
+        })
+        .map(|index| index as i64)
+}
+
 /// Returns true if the PE contains some resource with the specified locale
 /// identifier.
 ///
@@ -557,11 +644,32 @@ fn language(ctx: &ScanContext, lang: i64) -> Option<bool> {
     }))
 }
 
+/// Returns true if the signature's certificate is valid at the given
+/// timestamp.
+///
+/// This is a method of `pe.Signature`, so it's used like this:
+/// `pe.signatures[0].valid_on(1491955200)`.
+#[module_export(method_of = "pe.Signature")]
+fn valid_on(
+    _ctx: &ScanContext,
+    signature: Rc<Struct>,
+    timestamp: i64,
+) -> Option<bool> {
+    let not_before =
+        signature.field_by_name("not_before")?.type_value.try_as_integer()?;
+
+    let not_after =
+        signature.field_by_name("not_after")?.type_value.try_as_integer()?;
+
+    Some(timestamp >= not_before && timestamp <= not_after)
+}
+
 enum MatchCriteria<'a> {
     Any,
     Regexp(RegexpId),
     Name(&'a BStr),
     Ordinal(i64),
+    Rva(i64),
 }
 
 fn imports_impl(
@@ -586,6 +694,9 @@ fn imports_impl(
             MatchCriteria::Ordinal(expected_ordinal) => func
                 .ordinal
                 .is_some_and(|ordinal| ordinal as i64 == expected_ordinal),
+            MatchCriteria::Rva(expected_rva) => {
+                func.rva.is_some_and(|rva| rva as i64 == expected_rva)
+            }
         })
         .count()
     };
@@ -604,7 +715,9 @@ fn imports_impl(
                         ctx.regexp_matches(regexp_id, name.as_bytes())
                     })
                 }
-                MatchCriteria::Ordinal(_) => unreachable!(),
+                MatchCriteria::Ordinal(_) | MatchCriteria::Rva(_) => {
+                    unreachable!()
+                }
             };
             if name_matches {
                 Some(count_matching_funcs(import.functions.iter()))
@@ -644,6 +757,7 @@ fn import_rva_impl(
             }
             MatchCriteria::Regexp(_) => unreachable!(),
             MatchCriteria::Ordinal(_) => unreachable!(),
+            MatchCriteria::Rva(_) => unreachable!(),
         };
 
         if matches {
@@ -665,6 +779,7 @@ fn import_rva_impl(
                         }
                     }
                     MatchCriteria::Regexp(_) => unreachable!(),
+                    MatchCriteria::Rva(_) => unreachable!(),
                 }
             }
         }
@@ -696,6 +811,9 @@ fn exports_impl(
                 .ordinal
                 .as_ref()
                 .is_some_and(|ordinal| expected_ordinal == *ordinal as i64),
+            MatchCriteria::Rva(expected_rva) => {
+                export.rva.is_some_and(|rva| expected_rva == rva as i64)
+            }
         })
         .map_or(Some((false, 0)), |(position, _)| Some((true, position)))
 }
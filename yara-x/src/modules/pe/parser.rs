@@ -21,7 +21,12 @@ use nom::number::complete::{le_u16, le_u32, le_u64, u8};
 use nom::sequence::tuple;
 use nom::{Err, IResult, Parser, ToUsize};
 use protobuf::{EnumOrUnknown, MessageField};
+use rustc_hash::FxHashMap;
 
+use crate::modules::pe::authenticode::{
+    AuthenticodeHasher, AuthenticodeParser, AuthenticodeSignature,
+    DigestAlgorithm, Hasher,
+};
 use crate::modules::protos::pe;
 
 type Error<'a> = nom::error::Error<&'a [u8]>;
@@ -67,6 +72,18 @@ pub struct PE<'a> {
     /// Path to PDB file containing debug information for the PE.
     pdb_path: OnceCell<Option<&'a str>>,
 
+    /// Entries in the debug directory. Entries are parsed lazily when
+    /// [`PE::get_dbg_entries`] is called for the first time.
+    dbg_entries: OnceCell<Option<Vec<DbgDirEntry>>>,
+
+    /// TLS directory. The directory is parsed lazily when [`PE::get_tls`]
+    /// is called for the first time.
+    tls: OnceCell<Option<TlsDirectory>>,
+
+    /// Blocks in the base relocation table. Blocks are parsed lazily when
+    /// [`PE::get_relocations`] is called for the first time.
+    relocations: OnceCell<Option<Vec<RelocationBlock>>>,
+
     /// Map that with the DLLs imported by this PE file. Keys are DLL names,
     /// and values are vectors of [`ImportedFunc`] that contain information
     /// about each function imported from the DLL. We use an [`IndexMap`]
@@ -81,6 +98,10 @@ pub struct PE<'a> {
     /// Export information about this PE file.
     exports: OnceCell<Option<ExportInfo<'a>>>,
 
+    /// Authenticode signatures found in the PE file. Signatures are parsed
+    /// lazily when [`PE::get_signatures`] is called for the first time.
+    signatures: OnceCell<Option<Vec<AuthenticodeSignature<'a>>>>,
+
     /// DOS header already parsed.
     pub dos_hdr: DOSHeader,
 
@@ -160,90 +181,13 @@ impl<'a> PE<'a> {
     /// program. The PE format uses RVAs in multiple places and sometimes
     /// is necessary to covert the RVA to a file offset.
     pub fn rva_to_offset(&self, rva: u32) -> Option<u32> {
-        // Find the RVA for the section with the lowest RVA.
-        let lowest_section_rva = self
-            .sections
-            .iter()
-            .map(|section| section.virtual_address)
-            .min()
-            .unwrap_or(0);
-
-        // The target RVA is lower than the RVA of all sections, in such
-        // cases the RVA is directly mapped to a file offset.
-        if rva < lowest_section_rva {
-            return Some(rva);
-        }
-
-        let mut section_rva = 0;
-        let mut section_offset = 0;
-        let mut section_raw_size = 0;
-
-        // Find the section that contains the target RVA. If there are multiple
-        // sections that may contain the RVA, the last one is used.
-        for s in self.sections.iter() {
-            // In theory we should use the section's virtual size while
-            // checking if some RVA is within the section. In most cases
-            // the virtual size is greater than the raw data size, but that's
-            // not always the case. So we use the larger of the two values.
-            //
-            // Example:
-            // db6a9934570fa98a93a979e7e0e218e0c9710e5a787b18c6948f2eedd9338984
-            let size = max(s.virtual_size, s.raw_data_size);
-            let start = s.virtual_address;
-            let end = start.saturating_add(size);
-
-            // Check if the target RVA is within the boundaries of this
-            // section, but only update `section_rva` with values
-            // that are higher than the current one.
-            if section_rva <= s.virtual_address && (start..end).contains(&rva)
-            {
-                section_rva = s.virtual_address;
-                section_offset = s.raw_data_offset;
-                section_raw_size = s.raw_data_size;
-
-                // According to the PE specification, file_alignment should
-                // be a power of 2 between 512 and 64KB, inclusive. And the
-                // default value is 512 (0x200). But PE files with lower values
-                // (like 64, 32, and even 1) do exist in the wild and are
-                // correctly handled by the Windows loader. For files with
-                // very small values of file_alignment see:
-                // http://www.phreedom.org/research/tinype/
-                //
-                // Also, according to Ero Carreras's pefile.py, file alignments
-                // greater than 512, are actually ignored and 512 is used
-                // instead.
-                let file_alignment =
-                    min(self.optional_hdr.file_alignment, 0x200);
-
-                // Round down section_offset to a multiple of file_alignment.
-                if let Some(rem) = section_offset.checked_rem(file_alignment) {
-                    section_offset -= rem;
-                }
-
-                if self.optional_hdr.section_alignment >= 0x1000 {
-                    // Round section_offset down to sector size (512 bytes).
-                    section_offset =
-                        section_offset.saturating_sub(section_offset % 0x200);
-                }
-            }
-        }
-
-        // PE sections can have a raw (on disk) size smaller than their
-        // in-memory size. In such cases, even though the RVA lays within
-        // the boundaries of the section while in memory, the RVA doesn't
-        // have an associated file offset.
-        if rva.saturating_sub(section_rva) >= section_raw_size {
-            return None;
-        }
-
-        let result = section_offset.saturating_add(rva - section_rva);
-
-        // Make sure the resulting offset is within the file.
-        if result as usize >= self.data.len() {
-            return None;
-        }
-
-        Some(result)
+        rva_to_offset(
+            rva,
+            self.sections.iter().map(SectionBounds::from),
+            self.optional_hdr.file_alignment,
+            self.optional_hdr.section_alignment,
+            self.data.len(),
+        )
     }
 
     /// Returns the PE entry point as a file offset.
@@ -253,6 +197,16 @@ impl<'a> PE<'a> {
             .get_or_init(|| self.rva_to_offset(self.optional_hdr.entry_point))
     }
 
+    /// Returns true if this is a PE32+ (64-bit) file.
+    pub fn is_64bits(&self) -> bool {
+        self.optional_hdr.magic == Self::IMAGE_NT_OPTIONAL_HDR64_MAGIC
+    }
+
+    /// Returns true if the file is a dynamic link library (DLL).
+    pub fn is_dll(&self) -> bool {
+        self.pe_hdr.characteristics & Self::IMAGE_FILE_DLL != 0
+    }
+
     /// Returns a slice of [`Section`] structures, one per each section
     /// declared in the PE file.
     ///
@@ -310,6 +264,34 @@ impl<'a> PE<'a> {
         *self.pdb_path.get_or_init(|| self.parse_dbg())
     }
 
+    /// Returns the entries in the debug directory.
+    ///
+    /// Each entry describes a chunk of debug information, which can be of
+    /// different types (CodeView, POGO, VC features, etc).
+    pub fn get_dbg_entries(&self) -> &[DbgDirEntry] {
+        self.dbg_entries
+            .get_or_init(|| self.parse_dbg_entries())
+            .as_deref()
+            .unwrap_or_default()
+    }
+
+    /// Returns information about the TLS (Thread Local Storage) directory,
+    /// including the TLS callbacks.
+    ///
+    /// TLS callbacks are functions that are executed when a process or
+    /// thread starts or ends, and they run before the PE's entry point.
+    pub fn get_tls(&self) -> Option<&TlsDirectory> {
+        self.tls.get_or_init(|| self.parse_tls()).as_ref()
+    }
+
+    /// Returns the blocks in the base relocation table.
+    pub fn get_relocations(&self) -> &[RelocationBlock] {
+        self.relocations
+            .get_or_init(|| self.parse_relocations())
+            .as_deref()
+            .unwrap_or_default()
+    }
+
     /// Returns a slice of [`Resource`] structures, one per each resource
     /// declared in the PE file.
     pub fn get_resources(&self) -> &[Resource<'a>] {
@@ -340,7 +322,9 @@ impl<'a> PE<'a> {
     ///
     /// This function translates the RVA into a file offset and returns the
     /// chunk of file that starts at that offset and has the size indicated by
-    /// the directory entry.
+    /// the directory entry. The only exception is the security directory
+    /// (IMAGE_DIRECTORY_ENTRY_SECURITY), where the address is already a
+    /// file offset.
     ///
     /// Returns `None` if the PE is corrupted in some way that prevents the
     /// data from being found.
@@ -369,7 +353,14 @@ impl<'a> PE<'a> {
             .and_then(|entry| Self::parse_dir_entry(entry).ok())
             .map(|(_reminder, entry)| entry)?;
 
-        let start = self.rva_to_offset(dir_entry.addr)? as usize;
+        // The address in the IMAGE_DIRECTORY_ENTRY_SECURITY entry is a file
+        // offset instead of a RVA.
+        let start = if index == Self::IMAGE_DIRECTORY_ENTRY_SECURITY {
+            dir_entry.addr as usize
+        } else {
+            self.rva_to_offset(dir_entry.addr)? as usize
+        };
+
         let end = min(
             self.data.len(),
             start.saturating_add(dir_entry.size as usize),
@@ -420,20 +411,41 @@ impl<'a> PE<'a> {
     pub fn get_exports(&self) -> Option<&ExportInfo<'a>> {
         self.exports.get_or_init(|| self.parse_exports()).as_ref()
     }
+
+    /// Returns the Authenticode signatures in this PE file.
+    ///
+    /// The result includes both the primary signatures and the nested ones,
+    /// in the order in which they appear in the file.
+    pub fn get_signatures(&self) -> &[AuthenticodeSignature<'a>] {
+        self.signatures
+            .get_or_init(|| self.parse_signatures())
+            .as_deref()
+            .unwrap_or_default()
+    }
 }
 
 impl<'a> PE<'a> {
     const IMAGE_NT_OPTIONAL_HDR32_MAGIC: u16 = 0x10b;
     const IMAGE_NT_OPTIONAL_HDR64_MAGIC: u16 = 0x20b;
 
+    const IMAGE_FILE_DLL: u16 = 0x2000;
+
     const IMAGE_DIRECTORY_ENTRY_EXPORT: usize = 0;
     const IMAGE_DIRECTORY_ENTRY_IMPORT: usize = 1;
     const IMAGE_DIRECTORY_ENTRY_RESOURCE: usize = 2;
+    const IMAGE_DIRECTORY_ENTRY_SECURITY: usize = 4;
+    const IMAGE_DIRECTORY_ENTRY_BASERELOC: usize = 5;
     const IMAGE_DIRECTORY_ENTRY_DEBUG: usize = 6;
+    const IMAGE_DIRECTORY_ENTRY_TLS: usize = 9;
     const IMAGE_DIRECTORY_ENTRY_DELAY_IMPORT: usize = 13;
+    pub(crate) const IMAGE_DIRECTORY_ENTRY_COM_DESCRIPTOR: usize = 14;
 
     const IMAGE_DEBUG_TYPE_CODEVIEW: u32 = 2;
 
+    const IMAGE_REL_BASED_ABSOLUTE: u16 = 0;
+
+    const WIN_CERT_TYPE_PKCS_SIGNED_DATA: u16 = 2;
+
     const RICH_TAG: &'static [u8] = &[0x52_u8, 0x69, 0x63, 0x68];
     const DANS_TAG: &'static [u8] = &[0x44_u8, 0x61, 0x6e, 0x53];
 
@@ -441,11 +453,19 @@ impl<'a> PE<'a> {
     const SIZE_OF_FILE_HEADER: usize = 20; // size of IMAGE_FILE_HEADER
     const SIZE_OF_DIR_ENTRY: usize = 8;
     const SIZE_OF_SYMBOL: u32 = 18;
+    const SIZE_OF_CERTIFICATE_HEADER: usize = 8; // size of WIN_CERTIFICATE header
+    const SIZE_OF_RELOC_BLOCK_HEADER: usize = 8; // size of IMAGE_BASE_RELOCATION
+
+    // Offset of the `checksum` field within IMAGE_OPTIONAL_HEADER. It's the
+    // same for both PE32 and PE32+.
+    const CHECKSUM_OFFSET: usize = 64;
 
     const MAX_PE_SECTIONS: usize = 96;
     const MAX_PE_IMPORTS: usize = 16384;
     const MAX_PE_EXPORTS: usize = 16384;
     const MAX_PE_RESOURCES: usize = 65535;
+    const MAX_PE_RELOC_BLOCKS: usize = 16384;
+    const MAX_PE_TLS_CALLBACKS: usize = 256;
     const MAX_DIR_ENTRIES: usize = 16;
 
     fn parse_dos_header(input: &[u8]) -> IResult<&[u8], DOSHeader> {
@@ -1327,16 +1347,74 @@ impl<'a> PE<'a> {
             .ok()
     }
 
-    /// Parses the PE debug information and extracts the PDB path.
-    fn parse_dbg(&self) -> Option<&'a str> {
-        let (_, _, dbg_section) =
-            self.get_dir_entry_data(Self::IMAGE_DIRECTORY_ENTRY_DEBUG)?;
+    /// Parses the certificate table and returns the Authenticode signatures
+    /// found in it.
+    ///
+    /// The certificate table is a sequence of WIN_CERTIFICATE structures,
+    /// each one aligned to an 8-byte boundary:
+    ///
+    /// ```text
+    /// typedef struct _WIN_CERTIFICATE {
+    ///   DWORD dwLength;
+    ///   WORD  wRevision;
+    ///   WORD  wCertificateType;
+    ///   BYTE  bCertificate[ANYSIZE_ARRAY];
+    /// } WIN_CERTIFICATE
+    /// ```
+    ///
+    /// Only certificates of type WIN_CERT_TYPE_PKCS_SIGNED_DATA are taken
+    /// into account.
+    fn parse_signatures(&self) -> Option<Vec<AuthenticodeSignature<'a>>> {
+        let (_, _, mut cert_table) =
+            self.get_dir_entry_data(Self::IMAGE_DIRECTORY_ENTRY_SECURITY)?;
+
+        let mut signatures = Vec::new();
+
+        // Signatures often use the same digest algorithm, the digest of the
+        // file is computed only once for each algorithm.
+        let mut file_digests = FxHashMap::default();
+        let mut file_digest = |alg: DigestAlgorithm| {
+            file_digests.entry(alg).or_insert_with(|| self.digest(alg)).clone()
+        };
 
-        let entries = many0(Self::parse_dbg_dir_entry)(dbg_section)
-            .map(|(_, entries)| entries)
-            .ok()?;
+        while let Ok((_, (length, _revision, cert_type))) =
+            tuple((le_u32::<&[u8], Error>, le_u16, le_u16))(cert_table)
+        {
+            let length = length as usize;
+
+            if length < Self::SIZE_OF_CERTIFICATE_HEADER {
+                break;
+            }
+
+            let cert = match cert_table
+                .get(Self::SIZE_OF_CERTIFICATE_HEADER..length)
+            {
+                Some(cert) => cert,
+                None => break,
+            };
+
+            if cert_type == Self::WIN_CERT_TYPE_PKCS_SIGNED_DATA {
+                signatures
+                    .extend(AuthenticodeParser::parse(cert, &mut file_digest));
+            }
+
+            // The next WIN_CERTIFICATE is aligned to an 8-byte boundary.
+            // TODO: use usize:div_ceil when we bump the MSRV to 1.73.0.
+            let next = num::Integer::div_ceil(&length, &8) * 8;
+
+            cert_table = match cert_table.get(next..) {
+                Some(remainder) => remainder,
+                None => break,
+            };
+        }
 
-        for entry in entries
+        Some(signatures)
+    }
+
+    /// Parses the PE debug information and extracts the PDB path.
+    fn parse_dbg(&self) -> Option<&'a str> {
+        for entry in self
+            .get_dbg_entries()
             .iter()
             .filter(|entry| entry.type_ == Self::IMAGE_DEBUG_TYPE_CODEVIEW)
         {
@@ -1427,6 +1505,17 @@ impl<'a> PE<'a> {
         None
     }
 
+    /// Parses the entries in the debug directory, which is an array of
+    /// IMAGE_DEBUG_DIRECTORY structures.
+    fn parse_dbg_entries(&self) -> Option<Vec<DbgDirEntry>> {
+        let (_, _, dbg_section) =
+            self.get_dir_entry_data(Self::IMAGE_DIRECTORY_ENTRY_DEBUG)?;
+
+        many0(Self::parse_dbg_dir_entry)(dbg_section)
+            .map(|(_, entries)| entries)
+            .ok()
+    }
+
     /// Parse the IMAGE_DEBUG_DIRECTORY structure.
     /// https://learn.microsoft.com/en-us/windows/win32/api/winnt/ns-winnt-image_debug_directory
     fn parse_dbg_dir_entry(input: &[u8]) -> IResult<&[u8], DbgDirEntry> {
@@ -1465,6 +1554,155 @@ impl<'a> PE<'a> {
         )(input)
     }
 
+    /// Parses the TLS directory.
+    ///
+    /// The TLS directory is an IMAGE_TLS_DIRECTORY32 or IMAGE_TLS_DIRECTORY64
+    /// structure, depending on whether this is a 64-bits PE:
+    ///
+    /// ```text
+    /// typedef struct _IMAGE_TLS_DIRECTORY {
+    ///     ULONGLONG StartAddressOfRawData;  // DWORD in 32-bits PEs
+    ///     ULONGLONG EndAddressOfRawData;    // DWORD in 32-bits PEs
+    ///     ULONGLONG AddressOfIndex;         // DWORD in 32-bits PEs
+    ///     ULONGLONG AddressOfCallBacks;     // DWORD in 32-bits PEs
+    ///     DWORD SizeOfZeroFill;
+    ///     DWORD Characteristics;
+    /// } IMAGE_TLS_DIRECTORY;
+    /// ```
+    ///
+    /// Notice that addresses in this structure are virtual addresses, not
+    /// RVAs. `AddressOfCallBacks` points to a null-terminated array of
+    /// virtual addresses, one per TLS callback.
+    fn parse_tls(&self) -> Option<TlsDirectory> {
+        let (tls_rva, _, tls_data) =
+            self.get_dir_entry_data(Self::IMAGE_DIRECTORY_ENTRY_TLS)?;
+
+        // A directory entry with RVA 0 is not a valid TLS directory, even
+        // if its size is not 0.
+        if tls_rva == 0 {
+            return None;
+        }
+
+        let is_32_bits =
+            self.optional_hdr.magic == Self::IMAGE_NT_OPTIONAL_HDR32_MAGIC;
+
+        let (_, mut tls) = map(
+            tuple((
+                uint(is_32_bits), // start_address_of_raw_data
+                uint(is_32_bits), // end_address_of_raw_data
+                uint(is_32_bits), // address_of_index
+                uint(is_32_bits), // address_of_callbacks
+                le_u32,           // size_of_zero_fill
+                le_u32,           // characteristics
+            )),
+            |(
+                start_address_of_raw_data,
+                end_address_of_raw_data,
+                address_of_index,
+                address_of_callbacks,
+                size_of_zero_fill,
+                characteristics,
+            )| TlsDirectory {
+                start_address_of_raw_data,
+                end_address_of_raw_data,
+                address_of_index,
+                address_of_callbacks,
+                size_of_zero_fill,
+                characteristics,
+                callbacks: Vec::new(),
+            },
+        )(tls_data)
+        .ok()?;
+
+        // Converts a virtual address into a RVA. Returns `None` if the
+        // address is lower than the image base, or the resulting RVA
+        // doesn't fit in 32 bits.
+        let va_to_rva = |va: u64| -> Option<u32> {
+            va.checked_sub(self.optional_hdr.image_base)?.try_into().ok()
+        };
+
+        if let Some(callbacks) = va_to_rva(tls.address_of_callbacks)
+            .and_then(|rva| self.data_at_rva(rva))
+        {
+            let mut callbacks = iterator(
+                callbacks,
+                verify(uint(is_32_bits), |callback| *callback != 0),
+            );
+
+            // Callbacks that can't be converted to a RVA are considered
+            // garbage, and the array of callbacks ends there.
+            tls.callbacks = (&mut callbacks)
+                .take(Self::MAX_PE_TLS_CALLBACKS)
+                .map_while(va_to_rva)
+                .map(|rva| TlsCallback {
+                    rva,
+                    offset: self.rva_to_offset(rva),
+                })
+                .collect();
+        }
+
+        Some(tls)
+    }
+
+    /// Parses the base relocation table.
+    ///
+    /// The table is a sequence of blocks, where each block contains the
+    /// relocations for a 4KB page. Blocks start with an IMAGE_BASE_RELOCATION
+    /// structure, followed by an array of 16-bit entries:
+    ///
+    /// ```text
+    /// typedef struct _IMAGE_BASE_RELOCATION {
+    ///     DWORD VirtualAddress;
+    ///     DWORD SizeOfBlock;
+    /// //  WORD  TypeOffset[1];
+    /// } IMAGE_BASE_RELOCATION;
+    /// ```
+    ///
+    /// The upper 4 bits of each entry indicate the type of relocation, and
+    /// the lower 12 bits are an offset within the page. Entries of type
+    /// IMAGE_REL_BASED_ABSOLUTE are used only for padding the block to a
+    /// 32-bit boundary.
+    fn parse_relocations(&self) -> Option<Vec<RelocationBlock>> {
+        let (_, _, mut reloc_data) =
+            self.get_dir_entry_data(Self::IMAGE_DIRECTORY_ENTRY_BASERELOC)?;
+
+        let mut blocks = Vec::new();
+
+        while let Ok((_, (rva, size))) =
+            tuple((le_u32::<&[u8], Error>, le_u32))(reloc_data)
+        {
+            if blocks.len() == Self::MAX_PE_RELOC_BLOCKS {
+                break;
+            }
+
+            // The size of the block includes the IMAGE_BASE_RELOCATION
+            // structure, so it can't be lower than the size of that
+            // structure.
+            let entries = match reloc_data
+                .get(Self::SIZE_OF_RELOC_BLOCK_HEADER..size as usize)
+            {
+                Some(entries) => entries,
+                None => break,
+            };
+
+            let number_of_relocations = entries
+                .chunks_exact(2)
+                .map(LE::read_u16)
+                .filter(|entry| entry >> 12 != Self::IMAGE_REL_BASED_ABSOLUTE)
+                .count();
+
+            blocks.push(RelocationBlock {
+                rva,
+                size,
+                number_of_relocations: number_of_relocations as u32,
+            });
+
+            reloc_data = &reloc_data[size as usize..];
+        }
+
+        Some(blocks)
+    }
+
     /// Parses PE imports.
     fn parse_imports(&self) -> Option<IndexMap<&'a str, Vec<ImportedFunc>>> {
         let (_, _, import_data) =
@@ -1905,6 +2143,55 @@ impl<'a> PE<'a> {
     }
 }
 
+impl AuthenticodeHasher for PE<'_> {
+    /// Feeds the hasher with the content of the PE file covered by the
+    /// Authenticode signature.
+    ///
+    /// The digest covers the whole file, except the checksum field in the
+    /// optional header, the IMAGE_DIRECTORY_ENTRY_SECURITY entry in the data
+    /// directory, and the certificate table itself, which is expected to be
+    /// at the end of the file.
+    fn hash(&self, hasher: &mut Hasher) -> Option<()> {
+        let checksum_offset = (self.dos_hdr.e_lfanew as usize)
+            .checked_add(Self::SIZE_OF_PE_SIGNATURE)?
+            .checked_add(Self::SIZE_OF_FILE_HEADER)?
+            .checked_add(Self::CHECKSUM_OFFSET)?;
+
+        // The data directory is at the start of `self.directory`, which goes
+        // from there to the end of the file.
+        let security_entry_offset =
+            self.data.len().checked_sub(self.directory.len())?.checked_add(
+                Self::IMAGE_DIRECTORY_ENTRY_SECURITY * Self::SIZE_OF_DIR_ENTRY,
+            )?;
+
+        let cert_table_offset = self
+            .directory
+            .get(
+                Self::IMAGE_DIRECTORY_ENTRY_SECURITY
+                    * Self::SIZE_OF_DIR_ENTRY..,
+            )
+            .and_then(|entry| Self::parse_dir_entry(entry).ok())
+            .map(|(_, entry)| entry.addr as usize)?;
+
+        // Everything after the start of the certificate table is excluded.
+        let data = if cert_table_offset > 0 {
+            self.data.get(..cert_table_offset)?
+        } else {
+            self.data
+        };
+
+        let checksum_end = checksum_offset.checked_add(4)?;
+        let security_entry_end =
+            security_entry_offset.checked_add(Self::SIZE_OF_DIR_ENTRY)?;
+
+        hasher.update(data.get(..checksum_offset)?);
+        hasher.update(data.get(checksum_end..security_entry_offset)?);
+        hasher.update(data.get(security_entry_end..)?);
+
+        Some(())
+    }
+}
+
 #[rustfmt::skip]
 impl From<PE<'_>> for pe::PE {
     fn from(pe: PE) -> Self {
@@ -2083,11 +2370,45 @@ impl From<PE<'_>> for pe::PE {
         result.set_number_of_exports(
             result.export_details.len().try_into().unwrap());
 
-        // TODO
-        //result.set_number_of_signatures(
-        //    result.signatures.len().try_into().unwrap());
-        //result.set_number_of_certificates
-        //result.set_number_of_countersignatures
+        result.debug_directory_entries = pe
+            .get_dbg_entries()
+            .iter()
+            .map(pe::DebugDirEntry::from)
+            .collect();
+
+        result.tls = pe.get_tls().map(pe::Tls::from).into();
+
+        result.relocations = pe
+            .get_relocations()
+            .iter()
+            .map(pe::RelocationBlock::from)
+            .collect();
+
+        let signatures = pe.get_signatures();
+
+        result.set_is_signed(signatures.iter().any(|s| s.verified()));
+
+        result.signatures =
+            signatures.iter().map(pe::Signature::from).collect();
+
+        result.set_number_of_signatures(
+            signatures.len().try_into().unwrap());
+
+        result.set_number_of_certificates(
+            signatures
+                .iter()
+                .map(|s| s.certificates().len())
+                .sum::<usize>()
+                .try_into()
+                .unwrap());
+
+        result.set_number_of_countersignatures(
+            signatures
+                .iter()
+                .map(|s| s.countersignatures().len())
+                .sum::<usize>()
+                .try_into()
+                .unwrap());
 
         // The overlay offset is the offset where the last section ends. The
         // last section is not the last one in the section table, but the one
@@ -2391,7 +2712,6 @@ impl From<&Resource<'_>> for pe::Resource {
     }
 }
 
-#[allow(dead_code)]
 #[derive(Debug)]
 pub struct DbgDirEntry {
     /// Reserved.
@@ -2414,6 +2734,304 @@ pub struct DbgDirEntry {
     raw_data_offset: u32,
 }
 
+impl From<&DbgDirEntry> for pe::DebugDirEntry {
+    fn from(value: &DbgDirEntry) -> Self {
+        let mut entry = pe::DebugDirEntry::new();
+        entry.characteristics = Some(value.characteristics);
+        entry.timestamp = Some(value.timestamp);
+        entry.major_version = Some(value.major_version.into());
+        entry.minor_version = Some(value.minor_version.into());
+        entry.type_ = value
+            .type_
+            .try_into()
+            .ok()
+            .map(EnumOrUnknown::<pe::DebugType>::from_i32);
+        entry.raw_data_size = Some(value.raw_data_size);
+        entry.virtual_address = Some(value.virtual_address);
+        entry.raw_data_offset = Some(value.raw_data_offset);
+        entry
+    }
+}
+
+pub struct TlsDirectory {
+    /// Virtual address where the TLS template starts.
+    start_address_of_raw_data: u64,
+    /// Virtual address where the TLS template ends.
+    end_address_of_raw_data: u64,
+    /// Virtual address where the loader stores the TLS index.
+    address_of_index: u64,
+    /// Virtual address of the null-terminated array of TLS callbacks.
+    address_of_callbacks: u64,
+    /// Size in bytes of the zero-filled area after the TLS template.
+    size_of_zero_fill: u32,
+    /// Alignment of the TLS data.
+    characteristics: u32,
+    /// TLS callbacks.
+    callbacks: Vec<TlsCallback>,
+}
+
+impl From<&TlsDirectory> for pe::Tls {
+    fn from(value: &TlsDirectory) -> Self {
+        let mut tls = pe::Tls::new();
+        tls.start_address_of_raw_data = Some(value.start_address_of_raw_data);
+        tls.end_address_of_raw_data = Some(value.end_address_of_raw_data);
+        tls.address_of_index = Some(value.address_of_index);
+        tls.address_of_callbacks = Some(value.address_of_callbacks);
+        tls.size_of_zero_fill = Some(value.size_of_zero_fill);
+        tls.characteristics = Some(value.characteristics);
+        tls.callbacks =
+            value.callbacks.iter().map(pe::TlsCallback::from).collect();
+        tls
+    }
+}
+
+pub struct TlsCallback {
+    /// Address of the callback function as a RVA.
+    rva: u32,
+    /// Address of the callback function as a file offset. `None` if the
+    /// RVA doesn't correspond to any location in the file.
+    offset: Option<u32>,
+}
+
+impl From<&TlsCallback> for pe::TlsCallback {
+    fn from(value: &TlsCallback) -> Self {
+        let mut callback = pe::TlsCallback::new();
+        callback.rva = Some(value.rva);
+        callback.offset = value.offset;
+        callback
+    }
+}
+
+pub struct RelocationBlock {
+    /// RVA of the page where the relocations are applied.
+    rva: u32,
+    /// Size of the block in bytes, including the IMAGE_BASE_RELOCATION
+    /// structure.
+    size: u32,
+    /// Number of relocations in the block, not including the ones of type
+    /// IMAGE_REL_BASED_ABSOLUTE, which are used only for padding.
+    number_of_relocations: u32,
+}
+
+impl From<&RelocationBlock> for pe::RelocationBlock {
+    fn from(value: &RelocationBlock) -> Self {
+        let mut block = pe::RelocationBlock::new();
+        block.rva = Some(value.rva);
+        block.size = Some(value.size);
+        block.number_of_relocations = Some(value.number_of_relocations);
+        block
+    }
+}
+
+/// Boundaries of a PE section, both in memory and in the file.
+///
+/// This contains the information required for translating RVAs into file
+/// offsets, and vice versa. See [`rva_to_offset`] and [`offset_to_rva`].
+#[derive(Clone, Copy)]
+pub struct SectionBounds {
+    pub virtual_address: u32,
+    pub virtual_size: u32,
+    pub raw_data_offset: u32,
+    pub raw_data_size: u32,
+}
+
+impl From<&Section<'_>> for SectionBounds {
+    fn from(value: &Section) -> Self {
+        Self {
+            virtual_address: value.virtual_address,
+            virtual_size: value.virtual_size,
+            raw_data_offset: value.raw_data_offset,
+            raw_data_size: value.raw_data_size,
+        }
+    }
+}
+
+impl From<&pe::Section> for SectionBounds {
+    fn from(value: &pe::Section) -> Self {
+        Self {
+            virtual_address: value.virtual_address(),
+            virtual_size: value.virtual_size(),
+            raw_data_offset: value.raw_data_offset(),
+            raw_data_size: value.raw_data_size(),
+        }
+    }
+}
+
+/// Convert a relative virtual address (RVA) to a file offset.
+///
+/// `sections` are the boundaries of the PE sections, in the same order they
+/// have in the section table. `file_alignment` and `section_alignment` are
+/// the values in the optional header, and `file_size` is the size of the
+/// whole PE file.
+pub fn rva_to_offset<I>(
+    rva: u32,
+    sections: I,
+    file_alignment: u32,
+    section_alignment: u32,
+    file_size: usize,
+) -> Option<u32>
+where
+    I: Iterator<Item = SectionBounds> + Clone,
+{
+    // Find the RVA for the section with the lowest RVA.
+    let lowest_section_rva = sections
+        .clone()
+        .map(|section| section.virtual_address)
+        .min()
+        .unwrap_or(0);
+
+    // The target RVA is lower than the RVA of all sections, in such
+    // cases the RVA is directly mapped to a file offset.
+    if rva < lowest_section_rva {
+        return Some(rva);
+    }
+
+    let mut section_rva = 0;
+    let mut section_offset = 0;
+    let mut section_raw_size = 0;
+
+    // Find the section that contains the target RVA. If there are multiple
+    // sections that may contain the RVA, the last one is used.
+    for s in sections {
+        // In theory we should use the section's virtual size while
+        // checking if some RVA is within the section. In most cases
+        // the virtual size is greater than the raw data size, but that's
+        // not always the case. So we use the larger of the two values.
+        //
+        // Example:
+        // db6a9934570fa98a93a979e7e0e218e0c9710e5a787b18c6948f2eedd9338984
+        let size = max(s.virtual_size, s.raw_data_size);
+        let start = s.virtual_address;
+        let end = start.saturating_add(size);
+
+        // Check if the target RVA is within the boundaries of this
+        // section, but only update `section_rva` with values
+        // that are higher than the current one.
+        if section_rva <= s.virtual_address && (start..end).contains(&rva) {
+            section_rva = s.virtual_address;
+            section_offset = aligned_raw_data_offset(
+                s.raw_data_offset,
+                file_alignment,
+                section_alignment,
+            );
+            section_raw_size = s.raw_data_size;
+        }
+    }
+
+    // PE sections can have a raw (on disk) size smaller than their
+    // in-memory size. In such cases, even though the RVA lays within
+    // the boundaries of the section while in memory, the RVA doesn't
+    // have an associated file offset.
+    if rva.saturating_sub(section_rva) >= section_raw_size {
+        return None;
+    }
+
+    let result = section_offset.saturating_add(rva - section_rva);
+
+    // Make sure the resulting offset is within the file.
+    if result as usize >= file_size {
+        return None;
+    }
+
+    Some(result)
+}
+
+/// Convert a file offset to a relative virtual address (RVA).
+///
+/// This is the inverse of [`rva_to_offset`], and receives the same
+/// arguments. If the offset is within the raw data of multiple sections,
+/// the one with the highest raw data offset is used. Offsets that are lower
+/// than the raw data offset of all sections (i.e: offsets within the PE
+/// headers) are mapped directly to an RVA with the same value.
+pub fn offset_to_rva<I>(
+    offset: u32,
+    sections: I,
+    file_alignment: u32,
+    section_alignment: u32,
+    file_size: usize,
+) -> Option<u32>
+where
+    I: Iterator<Item = SectionBounds> + Clone,
+{
+    // Make sure the offset is within the file.
+    if offset as usize >= file_size {
+        return None;
+    }
+
+    // Sections with no raw data are not taken into account, as there's no
+    // offset that corresponds to them.
+    let sections = sections.filter(|section| section.raw_data_size > 0);
+
+    let aligned_offset = |section: &SectionBounds| {
+        aligned_raw_data_offset(
+            section.raw_data_offset,
+            file_alignment,
+            section_alignment,
+        )
+    };
+
+    // Find the raw data offset for the section with the lowest offset.
+    let lowest_section_offset =
+        sections.clone().map(|section| aligned_offset(&section)).min()?;
+
+    // The target offset is lower than the offset of all sections, in such
+    // cases the offset is directly mapped to a RVA.
+    if offset < lowest_section_offset {
+        return Some(offset);
+    }
+
+    let mut result = None;
+    let mut section_offset = 0;
+
+    // Find the section that contains the target offset. If there are
+    // multiple sections that contain the offset, the one with the highest
+    // offset is used.
+    for s in sections {
+        let start = aligned_offset(&s);
+        let end = start.saturating_add(s.raw_data_size);
+
+        if section_offset <= start && (start..end).contains(&offset) {
+            section_offset = start;
+            result = s.virtual_address.checked_add(offset - start);
+        }
+    }
+
+    result
+}
+
+/// Returns the offset where the raw data of a section starts, as computed
+/// by the Windows loader.
+fn aligned_raw_data_offset(
+    raw_data_offset: u32,
+    file_alignment: u32,
+    section_alignment: u32,
+) -> u32 {
+    let mut offset = raw_data_offset;
+
+    // According to the PE specification, file_alignment should be a power
+    // of 2 between 512 and 64KB, inclusive. And the default value is 512
+    // (0x200). But PE files with lower values (like 64, 32, and even 1) do
+    // exist in the wild and are correctly handled by the Windows loader.
+    // For files with very small values of file_alignment see:
+    // http://www.phreedom.org/research/tinype/
+    //
+    // Also, according to Ero Carreras's pefile.py, file alignments greater
+    // than 512, are actually ignored and 512 is used instead.
+    let file_alignment = min(file_alignment, 0x200);
+
+    // Round down the offset to a multiple of file_alignment.
+    if let Some(rem) = offset.checked_rem(file_alignment) {
+        offset -= rem;
+    }
+
+    if section_alignment >= 0x1000 {
+        // Round the offset down to sector size (512 bytes).
+        offset = offset.saturating_sub(offset % 0x200);
+    }
+
+    offset
+}
+
 /// Parser that reads a 32-bits or 64-bits unsigned integer, depending on
 /// its argument. The result is always an `u64`.
 fn uint(_32bits: bool) -> impl FnMut(&[u8]) -> IResult<&[u8], u64> {
@@ -1,6 +1,10 @@
 use pretty_assertions::assert_eq;
 
+use crate::modules::pe::parser::{
+    offset_to_rva, rva_to_offset, SectionBounds,
+};
 use crate::modules::tests::create_binary_from_zipped_ihex;
+use crate::tests::rule_false;
 use crate::tests::rule_true;
 use crate::tests::test_rule;
 
@@ -392,3 +396,292 @@ fn is_dll() {
         &pe
     );
 }
+
+#[test]
+fn signatures() {
+    let pe = create_binary_from_zipped_ihex(
+        "src/modules/pe/tests/testdata/079a472d22290a94ebb212aa8015cdc8dd28a968c6b4d3b88acdd58ce2d3b885.in.zip",
+    );
+
+    rule_true!(
+        r#"
+        import "pe"
+        rule test {
+          condition:
+            pe.is_signed and
+            pe.number_of_signatures == 1 and
+            pe.signatures[0].verified and
+            pe.signatures[0].subject contains "Quicken, Inc." and
+            pe.signatures[0].issuer == "/C=US/O=Symantec Corporation/OU=Symantec Trust Network/CN=Symantec Class 3 SHA256 Code Signing CA" and
+            pe.signatures[0].serial == "21:bd:b2:cb:ec:e5:43:1e:24:f7:56:74:d6:0e:9c:1d" and
+            pe.signatures[0].thumbprint == "c1bf1b8f751bf97626ed77f755f0a393106f2454" and
+            pe.signatures[0].number_of_countersignatures == 1 and
+            pe.signatures[0].countersignatures[0].verified
+        }
+        "#,
+        &pe
+    );
+
+    rule_true!(
+        r#"
+        import "pe"
+        rule test {
+          condition:
+            pe.signatures[0].valid_on(1491955200) and
+            pe.signatures[0].valid_on(1559692799) and
+            not pe.signatures[0].valid_on(1491955199) and
+            not pe.signatures[0].valid_on(1559692800)
+        }
+        "#,
+        &pe
+    );
+
+    rule_true!(
+        r#"
+        import "pe"
+        rule test {
+          condition:
+            for any sig in pe.signatures : (
+              sig.valid_on(1528216551)
+            )
+        }
+        "#,
+        &pe
+    );
+
+    let pe = create_binary_from_zipped_ihex(
+        "src/modules/pe/tests/testdata/2e9c671b8a0411f2b397544b368c44d7f095eb395779de0ad1ac946914dfa34c.in.zip",
+    );
+
+    // This file has a nested signature.
+    rule_true!(
+        r#"
+        import "pe"
+        rule test {
+          condition:
+            pe.number_of_signatures == 2 and
+            for all sig in pe.signatures : (
+              sig.verified and sig.subject contains "Ghisler Software GmbH"
+            )
+        }
+        "#,
+        &pe
+    );
+
+    let pe = create_binary_from_zipped_ihex(
+        "src/modules/pe/tests/testdata/2775d97f8bdb3311ace960a42eee35dbec84b9d71a6abbacb26c14e83f5897e4.in.zip",
+    );
+
+    rule_false!(
+        r#"
+        import "pe"
+        rule test {
+          condition:
+            pe.is_signed
+        }
+        "#,
+        &pe
+    );
+}
+
+#[test]
+fn tls_callbacks() {
+    let pe = create_binary_from_zipped_ihex(
+        "src/modules/pe/tests/testdata/99df28014fae5f213c8decfde423b0eb69005158f981bc84560e9a5dde103d90.in.zip",
+    );
+
+    rule_true!(
+        r#"
+        import "pe"
+        rule test {
+          condition:
+            pe.tls.address_of_callbacks == 4223008 and
+            pe.tls.callbacks[0].rva == 6336 and
+            pe.tls.callbacks[0].offset == 6336 and
+            pe.tls.callbacks[1].rva == 6256 and
+            not defined pe.tls.callbacks[2].rva
+        }
+        "#,
+        &pe
+    );
+
+    let pe = create_binary_from_zipped_ihex(
+        "src/modules/pe/tests/testdata/29eeeecf2c458ea3da1ce9d6d54742c0fad490cb2165f371f53b61941eedf072.in.zip",
+    );
+
+    // This file has a TLS directory, but no TLS callbacks.
+    rule_false!(
+        r#"
+        import "pe"
+        rule test {
+          condition:
+            for any callback in pe.tls.callbacks : ( true )
+        }
+        "#,
+        &pe
+    );
+}
+
+#[test]
+fn relocations_and_debug_entries() {
+    let pe = create_binary_from_zipped_ihex(
+        "src/modules/pe/tests/testdata/23e72ce7e9cdbc80c0095484ebeb02f56b21e48fd67044e69e7a2ae76db631e5.in.zip",
+    );
+
+    rule_true!(
+        r#"
+        import "pe"
+        rule test {
+          condition:
+            pe.relocations[0].rva == 0x2000 and
+            pe.relocations[0].size == 28 and
+            pe.relocations[0].number_of_relocations == 9
+        }
+        "#,
+        &pe
+    );
+
+    rule_true!(
+        r#"
+        import "pe"
+        rule test {
+          condition:
+            pe.debug_directory_entries[0].type == pe.DEBUG_TYPE_CODEVIEW and
+            pe.debug_directory_entries[1].type == pe.DEBUG_TYPE_POGO and
+            pe.debug_directory_entries[2].type == pe.DEBUG_TYPE_REPRO and
+            for all entry in pe.debug_directory_entries : (
+              entry.timestamp == 1827812126
+            )
+        }
+        "#,
+        &pe
+    );
+}
+
+#[test]
+fn rva_and_offset_conversion() {
+    let sections = [
+        SectionBounds {
+            virtual_address: 0x1000,
+            virtual_size: 0x3000,
+            raw_data_offset: 0x400,
+            raw_data_size: 0x3000,
+        },
+        // Overlaps with the previous section, both in memory and in the file.
+        SectionBounds {
+            virtual_address: 0x2000,
+            virtual_size: 0x1000,
+            raw_data_offset: 0x2400,
+            raw_data_size: 0x1000,
+        },
+        // Section without raw data.
+        SectionBounds {
+            virtual_address: 0x5000,
+            virtual_size: 0,
+            raw_data_offset: 0x4400,
+            raw_data_size: 0,
+        },
+        // The raw data offset is not aligned, it's rounded down to 0x4400.
+        SectionBounds {
+            virtual_address: 0x6000,
+            virtual_size: 0x1000,
+            raw_data_offset: 0x4410,
+            raw_data_size: 0x200,
+        },
+    ];
+
+    let rva_to_offset = |rva| {
+        rva_to_offset(rva, sections.iter().cloned(), 0x200, 0x1000, 0x5000)
+    };
+
+    let offset_to_rva = |offset| {
+        offset_to_rva(offset, sections.iter().cloned(), 0x200, 0x1000, 0x5000)
+    };
+
+    // RVAs lower than the RVA of all sections are mapped as is.
+    assert_eq!(rva_to_offset(0x800), Some(0x800));
+    assert_eq!(rva_to_offset(0x1800), Some(0xc00));
+    // When multiple sections contain the RVA, the one with the highest
+    // RVA is used.
+    assert_eq!(rva_to_offset(0x2100), Some(0x2500));
+    assert_eq!(rva_to_offset(0x3100), Some(0x2500));
+    assert_eq!(rva_to_offset(0x5000), None);
+    assert_eq!(rva_to_offset(0x6010), Some(0x4410));
+    assert_eq!(rva_to_offset(0x6200), None);
+
+    // Offsets lower than the offset of all sections are mapped as is.
+    assert_eq!(offset_to_rva(0x200), Some(0x200));
+    assert_eq!(offset_to_rva(0x1000), Some(0x1c00));
+    // When multiple sections contain the offset, the one with the highest
+    // offset is used.
+    assert_eq!(offset_to_rva(0x2500), Some(0x2100));
+    assert_eq!(offset_to_rva(0x4400), Some(0x6000));
+    assert_eq!(offset_to_rva(0x4410), Some(0x6010));
+    assert_eq!(offset_to_rva(0x4700), None);
+    assert_eq!(offset_to_rva(0x5000), None);
+}
+
+#[test]
+fn rva_to_offset_and_section_index() {
+    // This file has a section with no raw data (UPX0), which starts at the
+    // same offset than the next section (UPX1).
+    let pe = create_binary_from_zipped_ihex(
+        "src/modules/pe/tests/testdata/0ba6042247d90a187919dd88dc2d55cd882c80e5afc511c4f7b2e0e193968f7f.in.zip",
+    );
+
+    rule_true!(
+        r#"
+        import "pe"
+        rule test {
+          condition:
+            pe.rva_to_offset(pe.entry_point_raw) == pe.entry_point and
+            pe.offset_to_rva(pe.entry_point) == pe.entry_point_raw and
+            pe.rva_to_offset(0x40) == 0x40 and
+            pe.offset_to_rva(0x40) == 0x40 and
+            pe.offset_to_rva(1024) == 28672 and
+            not defined pe.rva_to_offset(4096) and
+            not defined pe.rva_to_offset(-1) and
+            not defined pe.offset_to_rva(filesize) and
+            not defined pe.offset_to_rva(-1)
+        }
+        "#,
+        &pe
+    );
+
+    rule_true!(
+        r#"
+        import "pe"
+        rule test {
+          condition:
+            pe.section_index("UPX0") == 0 and
+            pe.section_index("UPX1") == 1 and
+            pe.section_index(".rsrc") == 2 and
+            not defined pe.section_index("UPX2") and
+            pe.section_index(1024) == 1 and
+            pe.section_index(8191) == 1 and
+            pe.section_index(8192) == 2 and
+            not defined pe.section_index(0) and
+            not defined pe.section_index(filesize)
+        }
+        "#,
+        &pe
+    );
+
+    let pe = create_binary_from_zipped_ihex(
+        "src/modules/pe/tests/testdata/079a472d22290a94ebb212aa8015cdc8dd28a968c6b4d3b88acdd58ce2d3b885.in.zip",
+    );
+
+    rule_true!(
+        r#"
+        import "pe"
+        rule test {
+          condition:
+            pe.exports_index_by_rva(4096) == 2 and
+            pe.exports_index_by_rva(4624) == 4 and
+            not defined pe.exports_index_by_rva(4097) and
+            pe.export_details[pe.exports_index_by_rva(4204)].name == "CP_PutItem"
+        }
+        "#,
+        &pe
+    );
+}
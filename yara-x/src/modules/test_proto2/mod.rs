@@ -1,6 +1,9 @@
+use std::rc::Rc;
+
 use crate::modules::prelude::*;
 use crate::modules::protos::test_proto2::NestedProto2;
 use crate::modules::protos::test_proto2::TestProto2;
+use crate::types::Struct;
 
 #[cfg(test)]
 mod tests;
@@ -52,6 +55,20 @@ fn to_int(ctx: &ScanContext, string: RuntimeString) -> Option<i64> {
     string.parse::<i64>().ok()
 }
 
+#[module_export(method_of = "test_proto2.NestedProto2")]
+fn nested_method(
+    _ctx: &ScanContext,
+    structure: Rc<Struct>,
+    a: i64,
+) -> Option<i64> {
+    let b = structure
+        .field_by_name("nested_int64_one")?
+        .type_value
+        .try_as_integer()?;
+
+    Some(a + b)
+}
+
 #[module_main]
 fn main(data: &[u8]) -> TestProto2 {
     let mut test = TestProto2::new();
@@ -6,6 +6,23 @@ fn test_proto2_module() {
     condition_true!(r#"test_proto2.add(1,2) == 3"#);
     condition_true!(r#"test_proto2.add(1.0,2.0) == 3.0"#);
     condition_true!(r#"test_proto2.nested.nested_func()"#);
+    condition_true!(r#"test_proto2.nested.nested_method(1) == 2"#);
+    condition_true!(r#"test_proto2.array_struct[0].nested_method(1) == 2"#);
+
+    condition_true!(
+        r#"test_proto2.map_string_struct["foo"].nested_method(2) == 3"#
+    );
+
+    condition_true!(
+        r#"test_proto2.map_int64_struct[100].nested_method(-1) == 0"#
+    );
+
+    condition_true!(
+        r#"for all s in test_proto2.array_struct : (s.nested_method(1) == 2)"#
+    );
+
+    condition_false!(r#"test_proto2.array_struct[1].nested_method(1) == 2"#);
+
     condition_true!(r#"test_proto2.uppercase("foo") == "FOO""#);
 
     condition_true!(
@@ -20,6 +37,11 @@ fn test_proto2_module() {
     condition_false!(r#"test_proto2.undef_i64() == 0"#);
     condition_false!(r#"test_proto2.undef_i64() != 0"#);
 
+    // An undefined item in a tuple makes the whole `of` expression
+    // undefined.
+    condition_false!(r#"any of (test_proto2.undef_i64() == 0, true)"#);
+    condition_false!(r#"none of (test_proto2.undef_i64() == 0, false)"#);
+
     condition_true!(r#"test_proto2.int64_zero == 0"#);
     condition_true!(r#"test_proto2.int64_one == 1"#);
     condition_true!(r#"test_proto2.int64_one + test_proto2.int64_zero == 1"#);
@@ -238,6 +260,51 @@ fn test_proto2_module() {
           )"#
     );
 
+    condition_true!(
+        r#"with s = test_proto2.array_struct[0] : (
+                s.nested_int64_one == 1 and s.nested_array_int64[1] == 10
+          )"#
+    );
+
+    condition_true!(
+        r#"with s = test_proto2.map_string_struct["foo"] : (
+                s.nested_int64_one == 1
+          )"#
+    );
+
+    condition_true!(
+        r#"with a = test_proto2.array_int64, n = test_proto2.nested : (
+                a[0] == 1 and n.nested_int64_one == 1
+          )"#
+    );
+
+    condition_true!(
+        r#"for any s in test_proto2.array_struct : (
+                with a = s.nested_array_int64 : ( a[2] == 100 )
+          )"#
+    );
+
+    // test_proto2.map_string_struct["bar"] is undefined.
+    condition_false!(
+        r#"with s = test_proto2.map_string_struct["bar"] : ( true )"#
+    );
+
+    // If any of the expressions is undefined the whole `with` statement is
+    // undefined, even if the variable is not used in the condition.
+    condition_false!(r#"with a = test_proto2.undef_i64() : ( true )"#);
+
+    condition_false!(
+        r#"with a = 1, b = test_proto2.undef_i64() : ( a == 1 )"#
+    );
+
+    condition_true!(
+        r#"not defined (with a = test_proto2.undef_i64() : ( true ))"#
+    );
+
+    condition_true!(
+        r#"(with a = test_proto2.undef_i64() : ( true )) or true"#
+    );
+
     condition_true!(r#"test_proto2.get_foo() == "foo""#);
     condition_true!(r#"test_proto2.to_int("123") == 123"#);
 
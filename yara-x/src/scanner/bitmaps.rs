@@ -0,0 +1,211 @@
+use std::ops::Range;
+
+use bitvec::order::Lsb0;
+use bitvec::slice::BitSlice;
+
+use crate::compiler::{PatternId, RuleId, Rules};
+use crate::wasm::MATCHING_RULES_BITMAP_BASE;
+
+/// Describes how the bitmaps used while scanning are laid out in the WASM
+/// main memory.
+///
+/// There are three bitmaps: one that tells which rules matched, another one
+/// that tells which patterns matched, and a third one that tells which rules
+/// are enabled by the rule filter. They are located one after the other,
+/// starting at [`MATCHING_RULES_BITMAP_BASE`].
+///
+/// Each WASM module in the rules (see [`Rules::wasm_mods`]) has its own
+/// region in every bitmap, starting at a byte boundary, where the N-th bit
+/// corresponds to the N-th rule (or pattern) in the module. This is because
+/// the code in each module identifies rules and patterns by the IDs assigned
+/// by the compiler that produced it, not by the IDs they have after linking
+/// the rules with [`Rules::link`]. For rules that were not linked there's a
+/// single module, and the N-th bit corresponds to the rule (or pattern) with
+/// ID = N.
+pub(crate) struct BitmapsLayout {
+    /// Offset in main memory where the bitmap for matching patterns starts.
+    matching_patterns: usize,
+    /// Offset in main memory where the bitmap for enabled rules starts.
+    enabled_rules: usize,
+    /// Offset in main memory where the bitmaps end.
+    end: usize,
+    /// Region corresponding to each WASM module in the bitmaps for matching
+    /// and enabled rules.
+    rules: Vec<Region>,
+    /// Region corresponding to each WASM module in the bitmap for matching
+    /// patterns.
+    patterns: Vec<Region>,
+}
+
+/// Region of a bitmap corresponding to a WASM module.
+struct Region {
+    /// ID of the first rule (or pattern) in the module.
+    first_id: usize,
+    /// Number of rules (or patterns) in the module.
+    len: usize,
+    /// Index of the bit corresponding to the first rule (or pattern).
+    first_bit: usize,
+}
+
+impl Region {
+    #[inline]
+    fn ids(&self) -> Range<usize> {
+        self.first_id..self.first_id + self.len
+    }
+
+    #[inline]
+    fn bits(&self) -> Range<usize> {
+        self.first_bit..self.first_bit + self.len
+    }
+}
+
+impl BitmapsLayout {
+    /// Computes the layout of the bitmaps for the given rules.
+    pub fn new(rules: &Rules) -> Self {
+        let wasm_mods = rules.wasm_mods();
+        let num_mods = wasm_mods.len();
+
+        let mut layout = Self {
+            matching_patterns: 0,
+            enabled_rules: 0,
+            end: 0,
+            rules: Vec::with_capacity(num_mods),
+            patterns: Vec::with_capacity(num_mods),
+        };
+
+        let mut rules_bitmap_size = 0;
+        let mut patterns_bitmap_size = 0;
+
+        for (i, wasm_mod) in wasm_mods.iter().enumerate() {
+            let first_rule = usize::from(wasm_mod.first_rule_id());
+            let first_pattern = usize::from(wasm_mod.first_pattern_id());
+
+            // The rules and patterns in a module go from its first ID up to
+            // the first ID of the next module.
+            let (end_rule, end_pattern) = match wasm_mods.get(i + 1) {
+                Some(next) => (
+                    usize::from(next.first_rule_id()),
+                    usize::from(next.first_pattern_id()),
+                ),
+                None => (rules.rules().len(), rules.num_patterns()),
+            };
+
+            let num_rules = end_rule - first_rule;
+            let num_patterns = end_pattern - first_pattern;
+
+            layout.rules.push(Region {
+                first_id: first_rule,
+                len: num_rules,
+                first_bit: rules_bitmap_size * 8,
+            });
+
+            layout.patterns.push(Region {
+                first_id: first_pattern,
+                len: num_patterns,
+                first_bit: patterns_bitmap_size * 8,
+            });
+
+            // The size of each region is computed as `N / 8 + 1` bytes.
+            rules_bitmap_size += num_rules / 8 + 1;
+            patterns_bitmap_size += num_patterns / 8 + 1;
+        }
+
+        layout.matching_patterns =
+            MATCHING_RULES_BITMAP_BASE as usize + rules_bitmap_size;
+        layout.enabled_rules = layout.matching_patterns + patterns_bitmap_size;
+        layout.end = layout.enabled_rules + rules_bitmap_size;
+        layout
+    }
+
+    /// Returns the range of main memory occupied by the bitmaps for matching
+    /// rules and patterns.
+    #[inline]
+    pub fn matches(&self) -> Range<usize> {
+        MATCHING_RULES_BITMAP_BASE as usize..self.enabled_rules
+    }
+
+    /// Returns the range of main memory occupied by the bitmap for matching
+    /// rules.
+    #[inline]
+    pub fn matching_rules(&self) -> Range<usize> {
+        MATCHING_RULES_BITMAP_BASE as usize..self.matching_patterns
+    }
+
+    /// Returns the range of main memory occupied by the bitmap for matching
+    /// patterns.
+    #[inline]
+    pub fn matching_patterns(&self) -> Range<usize> {
+        self.matching_patterns..self.enabled_rules
+    }
+
+    /// Returns the range of main memory occupied by the bitmap for enabled
+    /// rules.
+    #[inline]
+    pub fn enabled_rules(&self) -> Range<usize> {
+        self.enabled_rules..self.end
+    }
+
+    /// Returns the offsets in main memory where the regions of the N-th
+    /// WASM module start, in the bitmaps for matching rules, matching
+    /// patterns and enabled rules, respectively.
+    pub fn module_bases(&self, wasm_mod: usize) -> (usize, usize, usize) {
+        let rules_offset = self.rules[wasm_mod].first_bit / 8;
+        let patterns_offset = self.patterns[wasm_mod].first_bit / 8;
+        (
+            MATCHING_RULES_BITMAP_BASE as usize + rules_offset,
+            self.matching_patterns + patterns_offset,
+            self.enabled_rules + rules_offset,
+        )
+    }
+
+    /// Returns the index of the bit corresponding to the given rule in the
+    /// bitmaps for matching and enabled rules.
+    #[inline]
+    pub fn rule_bit(&self, rule_id: RuleId) -> usize {
+        bit(&self.rules, rule_id.into())
+    }
+
+    /// Returns the index of the bit corresponding to the given pattern in
+    /// the bitmap for matching patterns.
+    #[inline]
+    pub fn pattern_bit(&self, pattern_id: PatternId) -> usize {
+        bit(&self.patterns, pattern_id.into())
+    }
+
+    /// Copies the bits in `rules`, where the N-th bit corresponds to the
+    /// rule with ID = N, into `bitmap`, which must be either the bitmap for
+    /// matching rules or the one for enabled rules.
+    pub fn copy_rules(
+        &self,
+        rules: &BitSlice,
+        bitmap: &mut BitSlice<u8, Lsb0>,
+    ) {
+        for region in self.rules.iter() {
+            bitmap[region.bits()].clone_from_bitslice(&rules[region.ids()]);
+        }
+    }
+
+    /// Returns the rules whose bit is not set in `bitmap`, which must be
+    /// the bitmap for matching rules.
+    pub fn iter_zeros<'a>(
+        &'a self,
+        bitmap: &'a BitSlice<u8, Lsb0>,
+    ) -> impl Iterator<Item = RuleId> + 'a {
+        self.rules.iter().flat_map(move |region| {
+            bitmap[region.bits()]
+                .iter_zeros()
+                .map(move |bit| RuleId::from(region.first_id + bit))
+        })
+    }
+}
+
+/// Returns the index of the bit corresponding to `id` in a bitmap divided
+/// in the given regions.
+#[inline]
+fn bit(regions: &[Region], id: usize) -> usize {
+    // Modules without rules (or patterns) have the same first ID as the
+    // next one, in that case the last of them is the one containing `id`.
+    let region =
+        &regions[regions.partition_point(|region| region.first_id <= id) - 1];
+    region.first_bit + id - region.first_id
+}
@@ -0,0 +1,325 @@
+use std::cmp;
+use std::ptr::null;
+use std::time::Duration;
+
+use wasmtime::{AsContextMut, Val};
+
+use crate::compiler::Rules;
+use crate::scanner::{
+    CallbackAction, CancelHandle, RuleFilter, ScanError, ScanEvent,
+    ScanResults, ScannedData, Scanner,
+};
+use crate::variables::VariableError;
+use crate::Variable;
+
+/// Scans data that is received in blocks.
+///
+/// This scanner is useful when the scanned data can't be held in memory
+/// all at once, like network streams, pipes or very large disk images. The
+/// data is passed to the scanner in blocks with [`BlockScanner::scan`],
+/// each block accompanied by its offset within the whole data. Once all the
+/// blocks have been scanned, [`BlockScanner::finish`] evaluates the rule
+/// conditions and returns the results.
+///
+/// Blocks are usually contiguous (i.e: each block starts where the previous
+/// one ended), and in that case patterns that match across the boundary
+/// between two blocks are found, as long as the portion of the match that
+/// lies in the previous block is not longer than the overlap set with
+/// [`BlockScanner::overlap`]. Blocks are not required to be contiguous,
+/// though, there can be gaps between them, and they can be scanned in any
+/// order, but no matches are found across non-contiguous blocks.
+///
+/// The conditions are evaluated without having access to the data, which
+/// has the following implications:
+///
+/// * `filesize` is the offset where the block that ends at the highest
+///   offset ends.
+/// * Functions that read the data at some offset, like `uint32(x)`, return
+///   undefined.
+/// * Modules don't have access to the data either, all the fields in their
+///   structures are undefined.
+/// * The [`crate::Match::data`] field in the matches returned by the scan
+///   results is empty.
+///
+/// Pattern matches, and everything that depends on them (`$a at x`, `#a`,
+/// `@a[i]`, `!a[i]`, etc.) work as usual.
+///
+/// ```
+/// # use yara_x::{compile, BlockScanner};
+/// let rules = compile(r#"
+///     rule test {
+///       strings:
+///         $a = "foobar"
+///       condition:
+///         $a at 3 and filesize == 12
+///     }"#)?;
+///
+/// let mut scanner = BlockScanner::new(&rules);
+///
+/// scanner.scan(0, b"...foo")?;
+/// scanner.scan(6, b"bar...")?;
+///
+/// let results = scanner.finish()?;
+/// assert_eq!(results.matching_rules().len(), 1);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct BlockScanner<'r> {
+    scanner: Scanner<'r>,
+    blocks: Blocks,
+    /// True if some block was scanned after the last call to `finish`.
+    in_progress: bool,
+}
+
+impl<'r> BlockScanner<'r> {
+    /// Creates a new block scanner.
+    pub fn new(rules: &'r Rules) -> Self {
+        Self {
+            scanner: Scanner::new(rules),
+            blocks: Blocks::new(),
+            in_progress: false,
+        }
+    }
+
+    /// Sets the maximum number of bytes from the end of each block that are
+    /// scanned again together with the next block.
+    ///
+    /// Matches that start in one block and end in the next one are found
+    /// only if they start within the last `n` bytes of the first block. The
+    /// default value is 4096.
+    pub fn overlap(&mut self, n: usize) -> &mut Self {
+        self.blocks.overlap = n;
+        self
+    }
+
+    /// Sets a timeout for each call to [`BlockScanner::scan`] and
+    /// [`BlockScanner::finish`].
+    ///
+    /// See [`Scanner::timeout`].
+    pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.scanner.timeout(timeout);
+        self
+    }
+
+    /// Sets the maximum number of matches per pattern.
+    ///
+    /// See [`Scanner::max_matches_per_pattern`].
+    pub fn max_matches_per_pattern(&mut self, n: usize) -> &mut Self {
+        self.scanner.max_matches_per_pattern(n);
+        self
+    }
+
+    /// Returns a handle that can be used for cancelling the scan from some
+    /// other thread.
+    ///
+    /// Once the scan is cancelled, [`BlockScanner::scan`] and
+    /// [`BlockScanner::finish`] fail with [`ScanError::Cancelled`]. After
+    /// calling [`BlockScanner::finish`] the scanner can be used again.
+    ///
+    /// See [`Scanner::cancel_handle`].
+    pub fn cancel_handle(&self) -> CancelHandle {
+        self.scanner.cancel_handle()
+    }
+
+    /// Sets a callback that receives the events reported during the scan.
+    ///
+    /// See [`Scanner::callback`].
+    pub fn callback<F>(&mut self, callback: F) -> &mut Self
+    where
+        F: for<'a> FnMut(ScanEvent<'a, 'r>) -> CallbackAction + 'r,
+    {
+        self.scanner.callback(callback);
+        self
+    }
+
+    /// Sets a filter that selects the rules evaluated during the scan.
+    ///
+    /// See [`Scanner::rule_filter`].
+    pub fn rule_filter(&mut self, filter: RuleFilter) -> &mut Self {
+        self.scanner.rule_filter(filter);
+        self
+    }
+
+    /// Sets the value of a global variable.
+    ///
+    /// See [`Scanner::set_global`].
+    pub fn set_global<T: TryInto<Variable>>(
+        &mut self,
+        ident: &str,
+        value: T,
+    ) -> Result<&mut Self, VariableError>
+    where
+        VariableError: From<<T as TryInto<Variable>>::Error>,
+    {
+        self.scanner.set_global(ident, value)?;
+        Ok(self)
+    }
+
+    /// Scans a block of data that starts at offset `base` within the whole
+    /// data.
+    ///
+    /// If the scan times out, the matches found in the current block may be
+    /// incomplete, but the scan can continue with the next block anyways.
+    pub fn scan(
+        &mut self,
+        base: usize,
+        data: &[u8],
+    ) -> Result<&mut Self, ScanError> {
+        // This is the first block after a call to `finish`, or the first
+        // block ever, clear matches from any previous scan.
+        if !self.in_progress {
+            self.scanner.clear_matches();
+            self.blocks.reset();
+            self.in_progress = true;
+        }
+
+        self.scanner.set_deadline();
+        self.blocks.scan(&mut self.scanner, base, data)?;
+
+        Ok(self)
+    }
+
+    /// Evaluates the rule conditions once all the blocks have been scanned,
+    /// and returns the scan results.
+    ///
+    /// After calling this function the scanner can be used again for
+    /// scanning some other data.
+    pub fn finish(&mut self) -> Result<ScanResults<'_, 'r>, ScanError> {
+        // If no block was scanned, the matches from a previous scan must be
+        // cleared anyways.
+        if !self.in_progress {
+            self.scanner.clear_matches();
+            self.blocks.reset();
+        }
+
+        self.in_progress = false;
+        self.scanner.set_deadline();
+
+        // `filesize` is the offset where the last block ends.
+        let filesize = self.blocks.data_len;
+
+        self.blocks.reset();
+        self.scanner.finish_blocks(filesize)
+    }
+}
+
+/// Keeps track of the blocks scanned so far, and searches for patterns
+/// in each new block, taking into account the tail of the previous block.
+///
+/// This is used both by [`BlockScanner`] and by [`Scanner::scan_proc`],
+/// which scans each memory region of a process as a block.
+pub(super) struct Blocks {
+    /// Maximum number of bytes from the end of a block that are scanned
+    /// again together with the next block.
+    pub overlap: usize,
+    /// Last bytes of the previous block.
+    tail: Vec<u8>,
+    /// Offset where the previous block ended.
+    tail_end: usize,
+    /// Buffer where the tail of the previous block and the current block
+    /// are put together.
+    buffer: Vec<u8>,
+    /// The highest offset where some block ended.
+    pub data_len: usize,
+}
+
+impl Blocks {
+    const DEFAULT_OVERLAP: usize = 4096;
+
+    pub fn new() -> Self {
+        Self {
+            overlap: Self::DEFAULT_OVERLAP,
+            tail: Vec::new(),
+            tail_end: 0,
+            buffer: Vec::new(),
+            data_len: 0,
+        }
+    }
+
+    /// Forgets about previously scanned blocks.
+    pub fn reset(&mut self) {
+        self.tail.clear();
+        self.tail_end = 0;
+        self.data_len = 0;
+    }
+
+    /// Searches for patterns in a block that starts at offset `base`.
+    pub fn scan(
+        &mut self,
+        scanner: &mut Scanner,
+        base: usize,
+        data: &[u8],
+    ) -> Result<(), ScanError> {
+        // The tail of the previous block is used only if the current block
+        // starts where the previous one ended.
+        if base != self.tail_end {
+            self.tail.clear();
+        }
+
+        // If there's some tail, the tail and the current block are copied
+        // into a buffer, if not, the current block is scanned directly.
+        let scanned_data = if self.tail.is_empty() {
+            data
+        } else {
+            self.buffer.clear();
+            self.buffer.extend_from_slice(self.tail.as_slice());
+            self.buffer.extend_from_slice(data);
+            self.buffer.as_slice()
+        };
+
+        let ctx = scanner.wasm_store.data_mut();
+
+        ctx.scanned_data = scanned_data.as_ptr();
+        ctx.scanned_data_len = scanned_data.len();
+        ctx.scanned_data_offset = base - self.tail.len();
+        ctx.scanned_data_overlap = self.tail.len();
+
+        let result = ctx.search_for_patterns();
+
+        ctx.scanned_data = null();
+        ctx.scanned_data_len = 0;
+        ctx.scanned_data_offset = 0;
+        ctx.scanned_data_overlap = 0;
+
+        // Keep the last bytes of the scanned data, they will be scanned
+        // again together with the next block.
+        let tail_len = cmp::min(self.overlap, scanned_data.len());
+        let tail_start = scanned_data.len() - tail_len;
+
+        if self.tail.is_empty() {
+            self.tail.extend_from_slice(&scanned_data[tail_start..]);
+        } else {
+            // The scanned data is the buffer, which already starts with
+            // the old tail.
+            self.buffer.drain(..tail_start);
+            std::mem::swap(&mut self.tail, &mut self.buffer);
+        }
+
+        self.tail_end = base + data.len();
+        self.data_len = cmp::max(self.data_len, self.tail_end);
+
+        result
+    }
+}
+
+impl<'r> Scanner<'r> {
+    /// Evaluates the rule conditions after all the blocks have been scanned
+    /// with [`Blocks::scan`].
+    pub(super) fn finish_blocks(
+        &mut self,
+        filesize: usize,
+    ) -> Result<ScanResults<'_, 'r>, ScanError> {
+        self.filesize
+            .set(self.wasm_store.as_context_mut(), Val::I64(filesize as i64))
+            .unwrap();
+
+        // Modules don't have access to the data, but they still produce
+        // their outputs, which are either empty or provided by the user.
+        self.run_modules(None)?;
+
+        // The patterns were already searched for while scanning each block,
+        // the pattern search phase won't be executed again.
+        self.eval_conditions(true)?;
+
+        Ok(ScanResults::new(self.wasm_store.data(), ScannedData::Slice(&[])))
+    }
+}
@@ -1,9 +1,12 @@
-use std::cell::RefCell;
+use std::any::{Any, TypeId};
+use std::cell::{OnceCell, RefCell, RefMut};
 use std::collections::VecDeque;
 use std::ops::{Range, RangeInclusive};
 use std::ptr::NonNull;
 use std::rc::Rc;
-use std::sync::atomic::Ordering;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::{cmp, iter};
 
 #[cfg(feature = "logging")]
 use log::*;
@@ -12,6 +15,10 @@ use std::time::Duration;
 #[cfg(any(feature = "logging", feature = "rules-profiling"))]
 use std::time::Instant;
 
+#[cfg(feature = "rules-profiling")]
+use crate::scanner::{PatternProfilingData, ProfilingData};
+
+use aho_corasick::{AhoCorasick, Input};
 use base64::Engine;
 use bitvec::order::Lsb0;
 use bitvec::slice::BitSlice;
@@ -24,18 +31,29 @@ use wasmtime::Store;
 
 use crate::compiler::{
     NamespaceId, PatternId, RegexpId, RuleId, Rules, SubPattern,
-    SubPatternAtom, SubPatternFlagSet, SubPatternFlags, SubPatternId,
+    SubPatternAtom, SubPatternFlagSet, SubPatternFlags, SubPatternId, WasmMod,
 };
 use crate::re::fast::fastvm::FastVM;
 use crate::re::thompson::pikevm::PikeVM;
 use crate::re::Action;
+use crate::scanner::events::{CallbackAction, ScanEvent};
+use crate::scanner::filter::FilteredRules;
 use crate::scanner::matches::{Match, MatchList, UnconfirmedMatch};
-use crate::scanner::{RuntimeStringId, HEARTBEAT_COUNTER};
+use crate::scanner::{
+    BitmapsLayout, LimitKind, Pattern, Rule, RuntimeStringId, ScannedData,
+    HEARTBEAT_COUNTER,
+};
 use crate::string_pool::BStringPool;
 use crate::types::{Struct, TypeValue};
-use crate::wasm::MATCHING_RULES_BITMAP_BASE;
 use crate::ScanError;
 
+/// Type of the callback set with [`crate::Scanner::callback`].
+pub(crate) type ScanCallback<'r> =
+    dyn for<'a> FnMut(ScanEvent<'a, 'r>) -> CallbackAction + 'r;
+
+/// Type of the callback set with [`crate::Scanner::console_log`].
+pub(crate) type ConsoleLogCallback<'r> = dyn FnMut(String) + 'r;
+
 /// Structure that holds information about the current scan.
 pub(crate) struct ScanContext<'r> {
     /// Pointer to the WASM store.
@@ -51,12 +69,17 @@ pub(crate) struct ScanContext<'r> {
     /// to this vector.
     pub non_private_matching_rules: Vec<RuleId>,
     /// Vector containing the IDs of the private rules that matched, including
-    /// both global and non-global ones.
+    /// both global and non-global ones. Rules that were not selected by the
+    /// rule filter, but were evaluated because other rules depend on them,
+    /// are also put in this vector, as they are not reported either.
     pub private_matching_rules: Vec<RuleId>,
     /// Map containing the IDs of the global rules that matched.
     pub global_matching_rules: FxHashMap<NamespaceId, Vec<RuleId>>,
     /// Compiled rules for this scan.
     pub compiled_rules: &'r Rules,
+    /// Rules and patterns that are enabled according to the filter set
+    /// with [`crate::Scanner::rule_filter`].
+    pub filtered_rules: FilteredRules,
     /// Structure that contains top-level symbols, like module names
     /// and external variables. Symbols are normally looked up in this
     /// structure, except if `current_struct` is set to some other
@@ -80,6 +103,11 @@ pub(crate) struct ScanContext<'r> {
     /// Keys are the fully qualified protobuf message name, and values are
     /// the message returned by the corresponding module.
     pub module_outputs: FxHashMap<String, Box<dyn MessageDyn>>,
+    /// Hash map that contains the protobuf messages provided by the user
+    /// with [`crate::Scanner::set_module_output`], which are used instead
+    /// of the ones produced by the modules in the next scan. Keys are the
+    /// fully qualified protobuf message name.
+    pub user_provided_module_outputs: FxHashMap<String, Box<dyn MessageDyn>>,
     /// Hash map that tracks the matches occurred during a scan. The keys
     /// are the PatternId of the matching pattern, and values are a list
     /// of matches.
@@ -99,6 +127,18 @@ pub(crate) struct ScanContext<'r> {
     pub limit_reached: BitVec,
     /// Maximum number of matches per pattern.
     pub max_matches_per_pattern: usize,
+    /// Maximum number of matches for all patterns together, see
+    /// [`crate::Scanner::max_total_matches`].
+    pub max_total_matches: Option<usize>,
+    /// Maximum size of the modules outputs, see
+    /// [`crate::Scanner::max_module_output_size`].
+    pub max_module_output_size: Option<u64>,
+    /// Number of matches found so far in the current scan, for all
+    /// patterns together.
+    pub num_matches: usize,
+    /// The resource limit that was exceeded during the current scan, if
+    /// any.
+    pub limit_exceeded: Option<LimitKind>,
     /// When [`HEARTBEAT_COUNTER`] is larger than this value, the scan is
     /// aborted due to a timeout.
     pub deadline: u64,
@@ -108,45 +148,118 @@ pub(crate) struct ScanContext<'r> {
     /// is evaluated, it is compiled the first time and stored in this hash
     /// map.
     pub regexp_cache: RefCell<FxHashMap<RegexpId, Regex>>,
-    /// Hash map that tracks the time spend on each pattern. Keys are pattern
-    /// PatternIds and values are the cumulative time spent on verifying each
-    /// pattern.
+    /// Data stored by YARA modules during the current scan, indexed by the
+    /// type of the data. See [`ScanContext::module_data`].
+    pub module_data: RefCell<FxHashMap<TypeId, Box<dyn Any>>>,
+    /// Index of the WASM module that is currently being executed, within
+    /// the slice returned by [`Rules::wasm_mods`]. Literals, regexps and
+    /// fields in the root structure are identified by IDs that are relative
+    /// to this module.
+    pub current_wasm_mod: usize,
+    /// Layout of the bitmaps that tell which rules and patterns matched,
+    /// and which rules are enabled, in WASM main memory.
+    pub bitmaps: BitmapsLayout,
+    /// Offset of `scanned_data` within the data being scanned. This is zero,
+    /// except while scanning data in blocks with [`crate::BlockScanner`],
+    /// where `scanned_data` contains only the current block, preceded by
+    /// the last bytes of the previous block.
+    pub scanned_data_offset: usize,
+    /// Number of bytes at the start of `scanned_data` that belong to the
+    /// previous block. This is zero, except while scanning data in blocks
+    /// with [`crate::BlockScanner`].
+    pub scanned_data_overlap: usize,
+    /// Callback that receives the events reported during the scan, set
+    /// with [`crate::Scanner::callback`].
+    pub callback: Option<Box<ScanCallback<'r>>>,
+    /// True if the callback asked for aborting the scan.
+    pub aborted: bool,
+    /// Rules that declare each pattern, indexed by [`PatternId`]. This is
+    /// built the first time that some pattern reaches the maximum number
+    /// of matches, see `notify_too_many_matches`.
+    pub rules_by_pattern: OnceCell<Vec<Vec<RuleId>>>,
+    /// Callback that receives the messages logged with the `console`
+    /// module, set with [`crate::Scanner::console_log`].
+    pub console_log: Option<Box<ConsoleLogCallback<'r>>>,
+    /// Flag shared with the [`crate::CancelHandle`]s obtained from the
+    /// scanner, it is set when the current scan must be cancelled.
+    pub cancelled: Arc<AtomicBool>,
+    /// Vector with the cumulative time spent on verifying each pattern,
+    /// indexed by PatternId.
+    #[cfg(feature = "rules-profiling")]
+    pub time_spent_in_pattern: Vec<Duration>,
+    /// Vector with the cumulative number of atom matches for each pattern,
+    /// indexed by PatternId.
+    #[cfg(feature = "rules-profiling")]
+    pub atom_matches_in_pattern: Vec<u64>,
+    /// Vector with the cumulative time spent on evaluating the condition of
+    /// each rule, indexed by RuleId. This doesn't include the time spent in
+    /// the pattern search phase.
+    #[cfg(feature = "rules-profiling")]
+    pub time_spent_in_rule: Vec<Duration>,
+    /// Time when the evaluation of the current rule's condition started.
     #[cfg(feature = "rules-profiling")]
-    pub time_spent_in_pattern: FxHashMap<PatternId, Duration>,
+    pub rule_eval_start_time: Option<Instant>,
 }
 
 #[cfg(feature = "rules-profiling")]
 impl<'r> ScanContext<'r> {
-    pub fn most_expensive_rules(&self) -> Vec<(&'r str, &'r str, Duration)> {
-        let mut result = Vec::with_capacity(self.compiled_rules.rules().len());
-
-        for r in self.compiled_rules.rules() {
-            let mut rule_time = Duration::default();
-            for (_, pattern_id) in r.patterns.iter() {
-                if let Some(d) = self.time_spent_in_pattern.get(pattern_id) {
-                    rule_time += *d;
-                }
-            }
-            let rule_name =
-                self.compiled_rules.ident_pool().get(r.ident_id).unwrap();
+    /// Called after evaluating the condition of the rule identified by
+    /// `rule_id`, for tracking the time spent in it.
+    pub(crate) fn track_rule_eval_time(&mut self, rule_id: RuleId) {
+        if let Some(start) = self.rule_eval_start_time.take() {
+            self.time_spent_in_rule[usize::from(rule_id)] +=
+                Instant::elapsed(&start);
+        }
+    }
 
-            let namespace_name = self
-                .compiled_rules
-                .ident_pool()
-                .get(r.namespace_ident_id)
-                .unwrap();
+    /// Returns the profiling data for all the rules, sorted by the total
+    /// time spent in each of them, in descending order.
+    pub(crate) fn most_expensive_rules(&self) -> Vec<ProfilingData<'r>> {
+        let rules = self.compiled_rules;
+        let mut result = Vec::with_capacity(rules.rules().len());
+
+        for (rule_id, r) in rules.rules().iter().enumerate() {
+            let mut patterns = Vec::with_capacity(r.patterns.len());
+            let mut pattern_matching_time = Duration::default();
+
+            for (ident_id, pattern_id) in r.patterns.iter() {
+                let pattern_id = usize::from(*pattern_id);
+                let verification_time = self.time_spent_in_pattern[pattern_id];
+                pattern_matching_time += verification_time;
+                patterns.push(PatternProfilingData {
+                    identifier: rules.ident_pool().get(*ident_id).unwrap(),
+                    atom_matches: self.atom_matches_in_pattern[pattern_id],
+                    verification_time,
+                });
+            }
 
-            result.push((namespace_name, rule_name, rule_time));
+            result.push(ProfilingData {
+                namespace: rules
+                    .ident_pool()
+                    .get(r.namespace_ident_id)
+                    .unwrap(),
+                rule: rules.ident_pool().get(r.ident_id).unwrap(),
+                condition_exec_time: self.time_spent_in_rule[rule_id],
+                pattern_matching_time,
+                patterns,
+            });
         }
 
         // Sort the results by the time spent on each rule, in descending
         // order.
-        result.sort_by(|a, b| b.2.cmp(&a.2));
+        result.sort_by(|a, b| b.total_time().cmp(&a.total_time()));
         result
     }
+
+    /// Resets the profiling data.
+    pub(crate) fn clear_profiling_data(&mut self) {
+        self.time_spent_in_pattern.fill(Duration::default());
+        self.atom_matches_in_pattern.fill(0);
+        self.time_spent_in_rule.fill(Duration::default());
+    }
 }
 
-impl ScanContext<'_> {
+impl<'r> ScanContext<'r> {
     /// Returns a slice with the data being scanned.
     pub(crate) fn scanned_data<'a>(&self) -> &'a [u8] {
         unsafe {
@@ -157,13 +270,23 @@ impl ScanContext<'_> {
         }
     }
 
+    /// Returns the WASM module that is currently being executed.
+    #[inline]
+    pub(crate) fn wasm_mod(&self) -> &WasmMod {
+        &self.compiled_rules.wasm_mods()[self.current_wasm_mod]
+    }
+
     /// Returns true of the regexp identified by the given [`RegexpId`]
     /// matches `haystack`.
+    ///
+    /// The [`RegexpId`] is the one used by the WASM module that is currently
+    /// being executed.
     pub(crate) fn regexp_matches(
         &self,
         regexp_id: RegexpId,
         haystack: &[u8],
     ) -> bool {
+        let regexp_id = self.wasm_mod().regexp_id(regexp_id);
         self.regexp_cache
             .borrow_mut()
             .entry(regexp_id)
@@ -171,6 +294,22 @@ impl ScanContext<'_> {
             .is_match(haystack)
     }
 
+    /// Returns the data of type `T` stored by some YARA module during the
+    /// current scan.
+    ///
+    /// This allows modules to keep data that must live for the duration
+    /// of the scan, like caches for values that are expensive to compute.
+    /// The data is created with [`Default::default`] the first time it is
+    /// requested, and it is discarded when the next scan starts.
+    pub(crate) fn module_data<T: Any + Default>(&self) -> RefMut<'_, T> {
+        RefMut::map(self.module_data.borrow_mut(), |data| {
+            data.entry(TypeId::of::<T>())
+                .or_insert_with(|| Box::<T>::default())
+                .downcast_mut::<T>()
+                .unwrap()
+        })
+    }
+
     /// Returns the protobuf struct produced by a module.
     ///
     /// The main function of a module returns a protobuf message with data
@@ -210,6 +349,28 @@ impl ScanContext<'_> {
         info!("Started rule evaluation: {}:{}", rule_namespace, rule_name);
     }
 
+    /// Sets the rules and patterns that are enabled by the rule filter.
+    ///
+    /// Besides storing `filtered_rules` in the context, the bits for the
+    /// enabled rules are copied to the bitmap in WASM main memory that is
+    /// checked before evaluating the condition of each rule. This bitmap
+    /// goes after the bitmaps for matching rules and patterns.
+    pub(crate) fn set_filtered_rules(
+        &mut self,
+        filtered_rules: FilteredRules,
+    ) {
+        let wasm_store = unsafe { self.wasm_store.as_mut() };
+        let main_mem = self.main_memory.unwrap().data_mut(wasm_store);
+
+        let bits = BitSlice::<u8, Lsb0>::from_slice_mut(
+            &mut main_mem[self.bitmaps.enabled_rules()],
+        );
+
+        self.bitmaps.copy_rules(&filtered_rules.enabled_rules, bits);
+
+        self.filtered_rules = filtered_rules;
+    }
+
     /// Called during the scan process when a global rule didn't match.
     ///
     /// When this happen any other global rule in the same namespace that
@@ -218,8 +379,9 @@ impl ScanContext<'_> {
         let wasm_store = unsafe { self.wasm_store.as_mut() };
         let main_mem = self.main_memory.unwrap().data_mut(wasm_store);
 
-        let base = MATCHING_RULES_BITMAP_BASE as usize;
-        let bits = BitSlice::<u8, Lsb0>::from_slice_mut(&mut main_mem[base..]);
+        let bits = BitSlice::<u8, Lsb0>::from_slice_mut(
+            &mut main_mem[self.bitmaps.matching_rules()],
+        );
 
         let rule = self.compiled_rules.get(rule_id);
 
@@ -234,7 +396,7 @@ impl ScanContext<'_> {
             self.global_matching_rules.get_mut(&rule.namespace_id)
         {
             for rule_id in rules.iter() {
-                bits.set((*rule_id).into(), false);
+                bits.set(self.bitmaps.rule_bit(*rule_id), false);
             }
 
             rules.clear()
@@ -244,6 +406,12 @@ impl ScanContext<'_> {
     /// Called during the scan process when a rule has matched for tracking
     /// the matching rules.
     pub(crate) fn track_rule_match(&mut self, rule_id: RuleId) {
+        // Once the scan is aborted, rules evaluated before the WASM code
+        // is actually interrupted are ignored.
+        if self.aborted {
+            return;
+        }
+
         let rule = self.compiled_rules.get(rule_id);
 
         if rule.is_global {
@@ -251,7 +419,7 @@ impl ScanContext<'_> {
                 .entry(rule.namespace_id)
                 .or_default()
                 .push(rule_id);
-        } else if rule.is_private {
+        } else if !self.is_reported_rule(rule_id) {
             self.private_matching_rules.push(rule_id);
         } else {
             self.non_private_matching_rules.push(rule_id);
@@ -260,11 +428,138 @@ impl ScanContext<'_> {
         let wasm_store = unsafe { self.wasm_store.as_mut() };
         let main_mem = self.main_memory.unwrap().data_mut(wasm_store);
 
-        let base = MATCHING_RULES_BITMAP_BASE as usize;
-        let bits = BitSlice::<u8, Lsb0>::from_slice_mut(&mut main_mem[base..]);
+        let bits = BitSlice::<u8, Lsb0>::from_slice_mut(
+            &mut main_mem[self.bitmaps.matching_rules()],
+        );
+
+        // The bit corresponding to the rule in the `rule_matches` bit vector
+        // is set to 1.
+        bits.set(self.bitmaps.rule_bit(rule_id), true);
+
+        // Global rules are notified once all the rules have been evaluated,
+        // see `Scanner::eval_conditions`.
+        if !rule.is_global && self.is_reported_rule(rule_id) {
+            self.notify_rule_match(rule_id);
+        }
+    }
+
+    /// Sends a message to the callback set with
+    /// [`crate::Scanner::console_log`], if any.
+    pub(crate) fn console_log(&mut self, message: String) {
+        if let Some(console_log) = &mut self.console_log {
+            console_log(message)
+        }
+    }
+
+    /// Sends a [`ScanEvent::RuleMatch`] event to the callback.
+    pub(crate) fn notify_rule_match(&mut self, rule_id: RuleId) {
+        self.notify(|ctx, data| {
+            ScanEvent::RuleMatch(Rule {
+                ctx,
+                data,
+                rules: ctx.compiled_rules,
+                rule_info: ctx.compiled_rules.get(rule_id),
+            })
+        })
+    }
 
-        // The RuleId-th bit in the `rule_matches` bit vector is set to 1.
-        bits.set(rule_id.into(), true);
+    /// Sends a [`ScanEvent::RuleNoMatch`] event to the callback.
+    pub(crate) fn notify_rule_no_match(&mut self, rule_id: RuleId) {
+        self.notify(|ctx, data| {
+            ScanEvent::RuleNoMatch(Rule {
+                ctx,
+                data,
+                rules: ctx.compiled_rules,
+                rule_info: ctx.compiled_rules.get(rule_id),
+            })
+        })
+    }
+
+    /// Sends a [`ScanEvent::TooManyMatches`] event to the callback.
+    fn notify_too_many_matches(&mut self, pattern_id: PatternId) {
+        let compiled_rules = self.compiled_rules;
+
+        let rules_by_pattern = self.rules_by_pattern.get_or_init(|| {
+            let mut rules_by_pattern =
+                vec![Vec::new(); compiled_rules.num_patterns()];
+            for (id, rule) in compiled_rules.rules().iter().enumerate() {
+                for (_, pattern_id) in rule.patterns.iter() {
+                    rules_by_pattern[usize::from(*pattern_id)]
+                        .push(RuleId::from(id));
+                }
+            }
+            rules_by_pattern
+        });
+
+        // Find a rule that declares the pattern, giving preference to the
+        // rules that are reported.
+        let rules = &rules_by_pattern[usize::from(pattern_id)];
+
+        let rule_id = match rules
+            .iter()
+            .find(|rule_id| self.is_reported_rule(**rule_id))
+            .or(rules.first())
+        {
+            Some(rule_id) => *rule_id,
+            None => return,
+        };
+
+        self.notify(|ctx, data| {
+            let rule_info = ctx.compiled_rules.get(rule_id);
+            let ident_id = rule_info
+                .patterns
+                .iter()
+                .find(|(_, p)| *p == pattern_id)
+                .map(|(ident_id, _)| *ident_id)
+                .unwrap();
+
+            ScanEvent::TooManyMatches {
+                rule: Rule { ctx, data, rules: ctx.compiled_rules, rule_info },
+                pattern: Pattern { ctx, data, pattern_id, ident_id },
+            }
+        })
+    }
+
+    /// Invokes the callback set with [`crate::Scanner::callback`], if any,
+    /// with the event returned by `event`.
+    ///
+    /// If the callback returns [`CallbackAction::Abort`] the scan is marked
+    /// as aborted, and no more events are sent after that. The functions
+    /// called from WASM check this flag after notifying the callback, and
+    /// interrupt the WASM code if the scan was aborted.
+    pub(crate) fn notify<F>(&mut self, event: F)
+    where
+        F: for<'a> FnOnce(
+            &'a ScanContext<'r>,
+            &'a ScannedData<'a>,
+        ) -> ScanEvent<'a, 'r>,
+    {
+        if self.aborted {
+            return;
+        }
+
+        let mut callback = match self.callback.take() {
+            Some(callback) => callback,
+            None => return,
+        };
+
+        let data = ScannedData::Slice(self.scanned_data());
+        let action = callback(event(self, &data));
+
+        self.callback = Some(callback);
+
+        if action == CallbackAction::Abort {
+            self.aborted = true;
+        }
+    }
+
+    /// Returns true if the rule must be included in the scan results when it
+    /// matches. This is false for private rules and for rules that were not
+    /// selected by the rule filter, even if they are evaluated because some
+    /// other rule depends on them.
+    #[inline]
+    pub(crate) fn is_reported_rule(&self, rule_id: RuleId) -> bool {
+        self.filtered_rules.reported_rules[usize::from(rule_id)]
     }
 
     /// Called during the scan process when a pattern has matched for tracking
@@ -277,19 +572,34 @@ impl ScanContext<'_> {
     ) {
         let wasm_store = unsafe { self.wasm_store.as_mut() };
         let main_mem = self.main_memory.unwrap().data_mut(wasm_store);
-        let num_rules = self.compiled_rules.rules().len();
 
-        let base = MATCHING_RULES_BITMAP_BASE as usize + num_rules / 8 + 1;
-        let bits = BitSlice::<u8, Lsb0>::from_slice_mut(&mut main_mem[base..]);
+        let bits = BitSlice::<u8, Lsb0>::from_slice_mut(
+            &mut main_mem[self.bitmaps.matching_patterns()],
+        );
 
-        bits.set(pattern_id.into(), true);
+        bits.set(self.bitmaps.pattern_bit(pattern_id), true);
+
+        // Once the limit for the total number of matches is exceeded no
+        // more matches are tracked, the scan is going to fail anyways.
+        if self.limit_exceeded.is_some() {
+            return;
+        }
 
         let matches_list = self.pattern_matches.entry(pattern_id).or_default();
 
         if matches_list.len() < self.max_matches_per_pattern {
-            matches_list.add(match_, replace)
-        } else {
+            let len = matches_list.len();
+            matches_list.add(match_, replace);
+            self.num_matches += matches_list.len() - len;
+            if self
+                .max_total_matches
+                .is_some_and(|max_matches| self.num_matches > max_matches)
+            {
+                self.limit_exceeded = Some(LimitKind::TotalMatches);
+            }
+        } else if !self.limit_reached[usize::from(pattern_id)] {
             self.limit_reached.set(pattern_id.into(), true);
+            self.notify_too_many_matches(pattern_id);
         }
     }
 
@@ -307,6 +617,9 @@ impl ScanContext<'_> {
     pub(crate) fn search_for_patterns(&mut self) -> Result<(), ScanError> {
         let scanned_data = self.scanned_data();
 
+        #[cfg(feature = "rules-profiling")]
+        let search_start = Instant::now();
+
         // Verify the anchored pattern first. These are patterns that can match
         // at a single known offset within the data.
         self.verify_anchored_patterns();
@@ -326,10 +639,22 @@ impl ScanContext<'_> {
         #[cfg(feature = "logging")]
         let mut atom_matches = 0_usize;
 
-        for ac_match in ac.find_overlapping_iter(scanned_data) {
-            #[cfg(feature = "logging")]
-            {
-                atom_matches += 1;
+        // The checks at the beginning of the loop are performed for every
+        // atom match, and also at the beginning of each chunk of data, as
+        // the data could contain no atoms at all.
+        for ac_match in find_overlapping_in_chunks(ac, scanned_data) {
+            // The callback asked for aborting the scan, the remaining
+            // matches are not relevant.
+            if self.aborted {
+                return Ok(());
+            }
+
+            if self.cancelled.load(Ordering::Relaxed) {
+                return Err(ScanError::Cancelled);
+            }
+
+            if let Some(kind) = self.limit_exceeded {
+                return Err(ScanError::LimitExceeded { kind });
             }
 
             if HEARTBEAT_COUNTER.load(Ordering::Relaxed) >= self.deadline {
@@ -341,6 +666,16 @@ impl ScanContext<'_> {
                 return Err(ScanError::Timeout);
             }
 
+            let ac_match = match ac_match {
+                Some(ac_match) => ac_match,
+                None => continue,
+            };
+
+            #[cfg(feature = "logging")]
+            {
+                atom_matches += 1;
+            }
+
             let atom =
                 unsafe { atoms.get_unchecked(ac_match.pattern().as_usize()) };
 
@@ -362,6 +697,11 @@ impl ScanContext<'_> {
             let (pattern_id, sub_pattern) =
                 &self.compiled_rules.get_sub_pattern(sub_pattern_id);
 
+            #[cfg(feature = "rules-profiling")]
+            {
+                self.atom_matches_in_pattern[usize::from(*pattern_id)] += 1;
+            }
+
             // Check if the potentially matching pattern has reached the
             // maximum number of allowed matches. In that case continue without
             // verifying the match. `get_unchecked` is used for performance
@@ -375,6 +715,13 @@ impl ScanContext<'_> {
                 continue;
             }
 
+            // Patterns that are used only by rules disabled by the rule
+            // filter are not verified, they can't produce any match.
+            if !self.filtered_rules.enabled_patterns[usize::from(*pattern_id)]
+            {
+                continue;
+            }
+
             // If the atom is exact no further verification is needed, except
             // for making sure that the fullword requirements are met. An exact
             // atom is enough to guarantee that the whole sub-pattern matched.
@@ -536,16 +883,25 @@ impl ScanContext<'_> {
 
             #[cfg(feature = "rules-profiling")]
             {
-                let time_spent = Instant::elapsed(&verification_start);
-                self.time_spent_in_pattern
-                    .entry(*pattern_id)
-                    .and_modify(|t| {
-                        *t += time_spent;
-                    })
-                    .or_insert(time_spent);
+                self.time_spent_in_pattern[usize::from(*pattern_id)] +=
+                    Instant::elapsed(&verification_start);
             }
         }
 
+        // The limit may have been exceeded while verifying the last match,
+        // or the anchored patterns.
+        if let Some(kind) = self.limit_exceeded {
+            return Err(ScanError::LimitExceeded { kind });
+        }
+
+        // The pattern search phase is triggered while evaluating the
+        // condition of some rule. The time spent in this phase is not
+        // attributed to that rule.
+        #[cfg(feature = "rules-profiling")]
+        if let Some(start) = self.rule_eval_start_time.as_mut() {
+            *start += Instant::elapsed(&search_start);
+        }
+
         #[cfg(feature = "logging")]
         {
             info!("Scan time: {:?}", Instant::elapsed(&scan_start));
@@ -554,9 +910,9 @@ impl ScanContext<'_> {
             {
                 info!("Most expensive rules:");
                 for r in self.most_expensive_rules().iter().take(10) {
-                    info!("+ namespace: {}", r.0);
-                    info!("  rule: {}", r.1);
-                    info!("  time: {:?}", r.2);
+                    info!("+ namespace: {}", r.namespace);
+                    info!("  rule: {}", r.rule);
+                    info!("  time: {:?}", r.total_time());
                 }
             }
         }
@@ -570,6 +926,10 @@ impl ScanContext<'_> {
             .iter()
             .map(|id| (id, self.compiled_rules.get_sub_pattern(*id)))
         {
+            if !self.filtered_rules.enabled_patterns[usize::from(*pattern_id)]
+            {
+                continue;
+            }
             match sub_pattern {
                 SubPattern::Literal {
                     pattern,
@@ -577,13 +937,21 @@ impl ScanContext<'_> {
                     anchored_at: Some(offset),
                     ..
                 } => {
+                    // The offset is relative to the start of the data being
+                    // scanned, but `scanned_data` may start at some later
+                    // offset when the data is scanned in blocks.
+                    let offset =
+                        match offset.checked_sub(self.scanned_data_offset) {
+                            Some(offset) => offset,
+                            None => continue,
+                        };
                     if let Some(match_) = verify_literal_match(
                         self.compiled_rules
                             .lit_pool()
                             .get_bytes(*pattern)
                             .unwrap(),
                         self.scanned_data(),
-                        *offset,
+                        offset,
                         *flags,
                     ) {
                         self.handle_sub_pattern_match(
@@ -606,6 +974,20 @@ impl ScanContext<'_> {
         pattern_id: PatternId,
         match_: Match,
     ) {
+        // Matches that end within the bytes that belong to the previous
+        // block were already found while scanning that block.
+        if match_.range.end <= self.scanned_data_overlap {
+            return;
+        }
+
+        // Make the match range relative to the start of the data being
+        // scanned, instead of being relative to `scanned_data`.
+        let match_ = Match {
+            range: match_.range.start + self.scanned_data_offset
+                ..match_.range.end + self.scanned_data_offset,
+            xor_key: match_.xor_key,
+        };
+
         match sub_pattern {
             SubPattern::Literal { .. }
             | SubPattern::Xor { .. }
@@ -831,6 +1213,38 @@ impl ScanContext<'_> {
     }
 }
 
+/// Size of the chunks in which the data is split while searching for atoms,
+/// see [`find_overlapping_in_chunks`].
+const SEARCH_CHUNK_SIZE: usize = 1 << 20;
+
+/// Searches for the atoms in `data` with the Aho-Corasick automaton `ac`,
+/// processing the data in chunks of [`SEARCH_CHUNK_SIZE`] bytes.
+///
+/// The returned iterator yields `None` before starting with each chunk, and
+/// `Some` for each atom match in the chunk. This allows the caller to do
+/// something periodically, even if the data doesn't contain any atoms.
+///
+/// Each chunk is searched together with the first `max_pattern_len - 1`
+/// bytes of the next one, so that atoms that cross the boundary between
+/// two chunks are found. Atoms that start after the end of the chunk are
+/// not yielded, as they will be found again with the next chunk.
+fn find_overlapping_in_chunks<'a>(
+    ac: &'a AhoCorasick,
+    data: &'a [u8],
+) -> impl Iterator<Item = Option<aho_corasick::Match>> + 'a {
+    let overlap = ac.max_pattern_len().saturating_sub(1);
+    (0..data.len()).step_by(SEARCH_CHUNK_SIZE).flat_map(move |start| {
+        let end = cmp::min(start + SEARCH_CHUNK_SIZE, data.len());
+        let input =
+            Input::new(data).range(start..cmp::min(end + overlap, data.len()));
+        iter::once(None).chain(
+            ac.find_overlapping_iter(input)
+                .filter(move |ac_match| ac_match.start() < end)
+                .map(Some),
+        )
+    })
+}
+
 /// Verifies if a literal `pattern` matches at `atom_pos` in `scanned_data`.
 ///
 /// Returns a [`Match`] if the match was confirmed or [`None`] if otherwise.
@@ -0,0 +1,75 @@
+use protobuf::MessageDyn;
+
+use crate::scanner::{Pattern, Rule};
+
+/// Events reported during a scan to the callback set with
+/// [`crate::Scanner::callback`].
+///
+/// ```
+/// # use yara_x::{compile, CallbackAction, ScanEvent, Scanner};
+/// let rules = compile(r#"
+///     rule foo { condition: true }
+///     rule bar { condition: true }
+/// "#)?;
+///
+/// let mut scanner = Scanner::new(&rules);
+///
+/// // Abort the scan after the first matching rule.
+/// scanner.callback(|event| match event {
+///     ScanEvent::RuleMatch(rule) => {
+///         assert_eq!(rule.name(), "foo");
+///         CallbackAction::Abort
+///     }
+///     _ => CallbackAction::Continue,
+/// });
+///
+/// let results = scanner.scan(&[])?;
+///
+/// assert_eq!(results.matching_rules().len(), 1);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub enum ScanEvent<'a, 'r> {
+    /// A rule matched.
+    ///
+    /// Non-global rules are reported as soon as their conditions are
+    /// evaluated, but global rules are reported once all the rules have been
+    /// evaluated, as a global rule that matched initially can be reverted
+    /// to a non-matching state by some other global rule in the same
+    /// namespace.
+    RuleMatch(Rule<'a, 'r>),
+    /// A rule didn't match. These events are reported once all the rules
+    /// have been evaluated.
+    RuleNoMatch(Rule<'a, 'r>),
+    /// A module imported by the rules has processed the scanned data and
+    /// produced its output. This happens before evaluating any rule.
+    ModuleImport {
+        /// Name of the module.
+        name: &'r str,
+        /// Protobuf produced by the module.
+        output: &'a dyn MessageDyn,
+    },
+    /// A pattern reached the maximum number of matches set with
+    /// [`crate::Scanner::max_matches_per_pattern`]. Further matches for this
+    /// pattern are ignored.
+    TooManyMatches {
+        /// A rule that declares the pattern. When multiple rules declare
+        /// the same pattern, this is one of them.
+        rule: Rule<'a, 'r>,
+        /// The pattern that reached the limit.
+        pattern: Pattern<'a, 'r>,
+    },
+}
+
+/// Value returned by the callback set with [`crate::Scanner::callback`],
+/// which tells the scanner whether to continue or abort the scan.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CallbackAction {
+    /// Continue with the scan.
+    Continue,
+    /// Abort the scan as soon as possible.
+    ///
+    /// The scan doesn't fail, the scan results contain the rules that
+    /// matched up to the point where the scan was aborted. Rules that were
+    /// not evaluated are reported as non-matching.
+    Abort,
+}
@@ -0,0 +1,196 @@
+use bitvec::prelude::*;
+use rustc_hash::FxHashMap;
+
+use crate::compiler::{NamespaceId, RuleId, RuleInfo, Rules};
+
+/// Selects the rules evaluated by a [`crate::Scanner`].
+///
+/// A filter is built by adding namespaces, tags and rule names to it. A rule
+/// is selected when it satisfies every kind of criteria added to the filter,
+/// and it satisfies a criteria when it matches any of the values added for
+/// it. For instance, a filter with namespaces `foo` and `bar`, and tag `baz`
+/// selects the rules in either `foo` or `bar` that have the `baz` tag. A
+/// filter without any criteria selects all the rules.
+///
+/// ```
+/// # use yara_x::{Compiler, RuleFilter, Scanner};
+/// let mut compiler = Compiler::new();
+///
+/// compiler
+///     .add_source(r#"rule foo : windows { condition: true }"#)?
+///     .add_source(r#"rule bar : linux { condition: true }"#)?;
+///
+/// let rules = compiler.build();
+/// let mut scanner = Scanner::new(&rules);
+///
+/// scanner.rule_filter(RuleFilter::new().tag("linux"));
+///
+/// let results = scanner.scan(&[]).unwrap();
+/// let mut matching_rules = results.matching_rules();
+///
+/// assert_eq!(matching_rules.len(), 1);
+/// assert_eq!(matching_rules.next().unwrap().name(), "bar");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct RuleFilter {
+    namespaces: Vec<String>,
+    tags: Vec<String>,
+    names: Vec<String>,
+}
+
+impl RuleFilter {
+    /// Creates a new filter that selects all the rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Selects the rules in the given namespace.
+    pub fn namespace<N: Into<String>>(mut self, namespace: N) -> Self {
+        self.namespaces.push(namespace.into());
+        self
+    }
+
+    /// Selects the rules that have the given tag.
+    pub fn tag<T: Into<String>>(mut self, tag: T) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// Selects the rules with the given name.
+    ///
+    /// The name can end with an asterisk (e.g. `foo*`), in which case it
+    /// selects all the rules with names that start with the given prefix,
+    /// just like rule sets in `of` expressions do.
+    pub fn name<N: Into<String>>(mut self, name: N) -> Self {
+        self.names.push(name.into());
+        self
+    }
+
+    /// Returns true if the filter selects the given rule.
+    fn selects(&self, rules: &Rules, rule: &RuleInfo) -> bool {
+        let ident_pool = rules.ident_pool();
+
+        if !self.namespaces.is_empty() {
+            let namespace = ident_pool.get(rule.namespace_ident_id).unwrap();
+            if !self.namespaces.iter().any(|n| n == namespace) {
+                return false;
+            }
+        }
+
+        if !self.tags.is_empty() {
+            let mut tags =
+                rule.tags.iter().map(|tag| ident_pool.get(*tag).unwrap());
+            if !tags.any(|tag| self.tags.iter().any(|t| t == tag)) {
+                return false;
+            }
+        }
+
+        if !self.names.is_empty() {
+            let name = ident_pool.get(rule.ident_id).unwrap();
+            if !self.names.iter().any(|pattern| {
+                if let Some(prefix) = pattern.strip_suffix('*') {
+                    name.starts_with(prefix)
+                } else {
+                    name == pattern
+                }
+            }) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// The result of applying a [`RuleFilter`] to a set of compiled rules.
+#[derive(Clone)]
+pub(crate) struct FilteredRules {
+    /// Bit vector with one bit per rule. The N-th bit is set if the rule
+    /// with RuleId = N must be evaluated. This includes the rules selected
+    /// by the filter, the rules they depend on, and the global rules in the
+    /// same namespace than any of them.
+    pub enabled_rules: BitVec,
+    /// Bit vector with one bit per rule. The N-th bit is set if the rule
+    /// with RuleId = N is included in the scan results. These are the
+    /// non-private rules selected by the filter.
+    pub reported_rules: BitVec,
+    /// Bit vector with one bit per pattern. The N-th bit is set if the
+    /// pattern with PatternId = N is used by some enabled rule. Patterns
+    /// that are not enabled are not searched for.
+    pub enabled_patterns: BitVec,
+}
+
+impl FilteredRules {
+    /// Applies `filter` to `rules`.
+    pub fn new(rules: &Rules, filter: &RuleFilter) -> Self {
+        let num_rules = rules.rules().len();
+
+        let mut enabled_rules = BitVec::repeat(false, num_rules);
+        let mut reported_rules = BitVec::repeat(false, num_rules);
+        let mut enabled_patterns = BitVec::repeat(false, rules.num_patterns());
+
+        // Global rules grouped by namespace. When some rule is enabled, all
+        // the global rules in its namespace must be enabled too, as they
+        // can prevent the rule from matching.
+        let mut global_rules: FxHashMap<NamespaceId, Vec<RuleId>> =
+            FxHashMap::default();
+
+        // Rules that must be enabled, but haven't been processed yet.
+        let mut pending = Vec::new();
+
+        for (rule_id, rule) in rules.rules().iter().enumerate() {
+            if rule.is_global {
+                global_rules
+                    .entry(rule.namespace_id)
+                    .or_default()
+                    .push(RuleId::from(rule_id));
+            }
+            if filter.selects(rules, rule) {
+                if !rule.is_private {
+                    reported_rules.set(rule_id, true);
+                }
+                pending.push(RuleId::from(rule_id));
+            }
+        }
+
+        while let Some(rule_id) = pending.pop() {
+            if enabled_rules.replace(rule_id.into(), true) {
+                continue;
+            }
+
+            let rule = rules.get(rule_id);
+
+            for (_, pattern_id) in rule.patterns.iter() {
+                enabled_patterns.set((*pattern_id).into(), true);
+            }
+
+            pending.extend(rule.depends_on.iter().cloned());
+
+            if let Some(global_rules) = global_rules.get(&rule.namespace_id) {
+                pending.extend(global_rules.iter().cloned());
+            }
+        }
+
+        Self { enabled_rules, reported_rules, enabled_patterns }
+    }
+
+    /// Returns a [`FilteredRules`] where all the rules are enabled.
+    pub fn all(rules: &Rules) -> Self {
+        let num_rules = rules.rules().len();
+
+        let mut reported_rules = BitVec::repeat(true, num_rules);
+
+        for (rule_id, rule) in rules.rules().iter().enumerate() {
+            if rule.is_private {
+                reported_rules.set(rule_id, false);
+            }
+        }
+
+        Self {
+            enabled_rules: BitVec::repeat(true, num_rules),
+            reported_rules,
+            enabled_patterns: BitVec::repeat(true, rules.num_patterns()),
+        }
+    }
+}
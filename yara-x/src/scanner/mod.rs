@@ -3,7 +3,7 @@
 The scanner takes the rules produces by the compiler and scans data with them.
 */
 
-use std::cell::RefCell;
+use std::cell::{OnceCell, RefCell};
 use std::collections::hash_map;
 use std::io::Read;
 use std::ops::{Deref, Range};
@@ -12,34 +12,53 @@ use std::pin::Pin;
 use std::ptr::{null, NonNull};
 use std::rc::Rc;
 use std::slice::Iter;
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Once;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Once};
 use std::time::Duration;
-use std::{cmp, fs, thread};
+use std::{cmp, fmt, fs, thread};
 
 use bitvec::prelude::*;
+use bstr::BStr;
 use fmmap::{MmapFile, MmapFileExt};
 use protobuf::MessageDyn;
 use rustc_hash::FxHashMap;
 use thiserror::Error;
 use wasmtime::{
     AsContext, AsContextMut, Global, GlobalType, MemoryType, Mutability,
-    Store, TypedFunc, Val, ValType,
+    Store, TypedFunc, UpdateDeadline, Val, ValType,
 };
 
+use crate::compiler;
 use crate::compiler::{IdentId, PatternId, RuleId, RuleInfo, Rules};
 use crate::modules::{Module, BUILTIN_MODULES};
 use crate::string_pool::BStringPool;
 use crate::types::{Struct, TypeValue};
 use crate::variables::VariableError;
-use crate::wasm::{ENGINE, MATCHING_RULES_BITMAP_BASE};
+use crate::wasm::ENGINE;
 use crate::{modules, wasm, Variable};
 
+pub(crate) use crate::scanner::bitmaps::BitmapsLayout;
+pub use crate::scanner::blocks::BlockScanner;
 pub(crate) use crate::scanner::context::*;
+pub use crate::scanner::events::{CallbackAction, ScanEvent};
+pub(crate) use crate::scanner::filter::FilteredRules;
+pub use crate::scanner::filter::RuleFilter;
 pub use crate::scanner::matches::*;
+pub use crate::scanner::pool::{PooledScanner, ScannerPool};
+#[cfg(feature = "rules-profiling")]
+pub use crate::scanner::profiling::{PatternProfilingData, ProfilingData};
 
+mod bitmaps;
+mod blocks;
 mod context;
+mod events;
+mod filter;
 mod matches;
+mod pool;
+#[cfg(target_os = "linux")]
+mod proc;
+#[cfg(feature = "rules-profiling")]
+mod profiling;
 
 #[cfg(test)]
 mod tests;
@@ -50,12 +69,59 @@ pub enum ScanError {
     /// The scan was aborted after the timeout period.
     #[error("timeout")]
     Timeout,
+    /// The scan was cancelled with [`CancelHandle::cancel`].
+    #[error("cancelled")]
+    Cancelled,
     /// Could not open the scanned file.
     #[error("can not open `{path}`: {source}")]
     OpenError { path: PathBuf, source: std::io::Error },
     /// Could not map the scanned file into memory.
     #[error("can not map `{path}`: {source}")]
     MapError { path: PathBuf, source: fmmap::error::Error },
+    /// The module passed to [`Scanner::set_module_output`] doesn't exist.
+    #[error("unknown module `{module}`")]
+    UnknownModule { module: String },
+    /// The data passed to [`Scanner::set_module_output`] or
+    /// [`Scanner::set_module_output_raw`] is not valid for the module.
+    #[error("invalid output for module `{module}`: {err}")]
+    InvalidModuleOutput { module: String, err: String },
+    /// Could not read the memory of the scanned process.
+    #[error("can not read memory of process {pid}: {source}")]
+    ProcError { pid: u32, source: std::io::Error },
+    /// Could not read the data passed to [`Scanner::scan_reader`] or
+    /// [`Scanner::scan_fd`].
+    #[error("can not read scanned data: {source}")]
+    ReadError { source: std::io::Error },
+    /// The scan exceeded one of the resource limits set in the scanner. See
+    /// [`LimitKind`] for the existing limits.
+    #[error("{kind} limit exceeded")]
+    LimitExceeded { kind: LimitKind },
+}
+
+/// Resource limits that can be set in a [`Scanner`].
+///
+/// When some of these limits is exceeded the scan fails with
+/// [`ScanError::LimitExceeded`].
+///
+/// There's no limit for the memory used by the WASM code that evaluates the
+/// rule conditions. This memory has a fixed size, computed from the number
+/// of rules and patterns when the [`Scanner`] is created, and it never grows
+/// during the scan, regardless of the scanned data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LimitKind {
+    /// Limit set with [`Scanner::max_total_matches`].
+    TotalMatches,
+    /// Limit set with [`Scanner::max_module_output_size`].
+    ModuleOutputSize,
+}
+
+impl fmt::Display for LimitKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LimitKind::TotalMatches => write!(f, "total matches"),
+            LimitKind::ModuleOutputSize => write!(f, "module output size"),
+        }
+    }
 }
 
 /// Global counter that gets incremented every 1 second by a dedicated thread.
@@ -67,6 +133,51 @@ static HEARTBEAT_COUNTER: AtomicU64 = AtomicU64::new(0);
 /// Used for spawning the thread that increments `HEARTBEAT_COUNTER`.
 static INIT_HEARTBEAT: Once = Once::new();
 
+/// A handle that cancels the scan being performed by a [`Scanner`].
+///
+/// The handle is obtained with [`Scanner::cancel_handle`]. It can be cloned
+/// and sent to other threads, which can cancel the scan at any moment by
+/// calling [`CancelHandle::cancel`].
+///
+/// ```
+/// # use yara_x::{compile, ScanError, Scanner};
+/// let rules = compile(r#"
+///     rule test {
+///       condition:
+///         for all i in (0..10000000000) : (i >= 0)
+///     }"#)?;
+///
+/// let mut scanner = Scanner::new(&rules);
+/// let cancel_handle = scanner.cancel_handle();
+///
+/// std::thread::spawn(move || {
+///     std::thread::sleep(std::time::Duration::from_millis(100));
+///     cancel_handle.cancel();
+/// });
+///
+/// assert!(matches!(scanner.scan(&[]), Err(ScanError::Cancelled)));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct CancelHandle {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelHandle {
+    /// Cancels the scan in progress, if any.
+    ///
+    /// The scan returns [`ScanError::Cancelled`] shortly after calling this
+    /// function. If the scanner is not scanning anything, the next scan is
+    /// cancelled as soon as it starts, this guarantees that a cancellation
+    /// requested right before the scan starts is not lost.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        // Incrementing the epoch forces the WASM code being executed to
+        // invoke the epoch deadline callback, which checks the flag.
+        ENGINE.increment_epoch();
+    }
+}
+
 pub enum ScannedData<'a> {
     Slice(&'a [u8]),
     Vec(Vec<u8>),
@@ -91,9 +202,16 @@ impl<'a> AsRef<[u8]> for ScannedData<'a> {
 /// parallel.
 pub struct Scanner<'r> {
     wasm_store: Pin<Box<Store<ScanContext<'r>>>>,
-    wasm_main_func: TypedFunc<(), i32>,
+    wasm_main_funcs: Vec<TypedFunc<(), i32>>,
     filesize: Global,
+    pattern_search_done: Global,
+    timeout_occurred: Global,
     timeout: Option<Duration>,
+    /// Maximum number of bytes read by [`Scanner::scan_reader`].
+    max_reader_size: Option<usize>,
+    /// Buffer where [`Scanner::scan_reader`] puts the data read, it is
+    /// reused across scans.
+    reader_buffer: Vec<u8>,
 }
 
 impl<'r> Scanner<'r> {
@@ -102,7 +220,6 @@ impl<'r> Scanner<'r> {
 
     /// Creates a new scanner.
     pub fn new(rules: &'r Rules) -> Self {
-        let num_rules = rules.rules().len() as u32;
         let num_patterns = rules.num_patterns() as u32;
 
         // The ScanContext structure belongs to the WASM store, but at the same
@@ -119,6 +236,7 @@ impl<'r> Scanner<'r> {
             ScanContext {
                 wasm_store: NonNull::dangling(),
                 compiled_rules: rules,
+                filtered_rules: FilteredRules::all(rules),
                 string_pool: BStringPool::new(),
                 current_struct: None,
                 root_struct: rules.globals(),
@@ -130,14 +248,41 @@ impl<'r> Scanner<'r> {
                 main_memory: None,
                 vars_stack: Vec::new(),
                 module_outputs: FxHashMap::default(),
+                user_provided_module_outputs: FxHashMap::default(),
                 pattern_matches: FxHashMap::default(),
                 unconfirmed_matches: FxHashMap::default(),
                 deadline: 0,
                 limit_reached: BitVec::repeat(false, num_patterns as usize),
                 max_matches_per_pattern: Self::DEFAULT_MAX_MATCHES_PER_PATTERN,
+                max_total_matches: None,
+                max_module_output_size: None,
+                num_matches: 0,
+                limit_exceeded: None,
                 regexp_cache: RefCell::new(FxHashMap::default()),
+                module_data: RefCell::new(FxHashMap::default()),
+                current_wasm_mod: 0,
+                bitmaps: BitmapsLayout::new(rules),
+                scanned_data_offset: 0,
+                scanned_data_overlap: 0,
+                callback: None,
+                aborted: false,
+                rules_by_pattern: OnceCell::new(),
+                console_log: None,
+                cancelled: Arc::new(AtomicBool::new(false)),
+                #[cfg(feature = "rules-profiling")]
+                time_spent_in_pattern: vec![
+                    Duration::default();
+                    num_patterns as usize
+                ],
+                #[cfg(feature = "rules-profiling")]
+                atom_matches_in_pattern: vec![0; num_patterns as usize],
                 #[cfg(feature = "rules-profiling")]
-                time_spent_in_pattern: FxHashMap::default(),
+                time_spent_in_rule: vec![
+                    Duration::default();
+                    rules.rules().len()
+                ],
+                #[cfg(feature = "rules-profiling")]
+                rule_eval_start_time: None,
             },
         ));
 
@@ -174,44 +319,39 @@ impl<'r> Scanner<'r> {
         )
         .unwrap();
 
-        // Compute the base offset for the bitmap that contains matching
-        // information for patterns. This bitmap has 1 bit per pattern,
-        // the N-th bit is set if pattern with PatternId = N matched. The
-        // bitmap starts right after the bitmap that contains matching
-        // information for rules.
+        // Compute the required memory size in 64KB pages. The memory must
+        // be large enough for containing the bitmaps that tell which rules
+        // and patterns matched, and which rules are enabled.
         //
         // TODO: `div_ceil` will be included in `std` in the future
         // but is currently unstable. When it gets stabilized we can stop
         // using `num`.
         // https://doc.rust-lang.org/std/primitive.i8.html#method.div_ceil
-        let matching_patterns_bitmap_base = MATCHING_RULES_BITMAP_BASE as u32
-            + num::Integer::div_ceil(&num_rules, &8);
-
-        // Compute the required memory size in 64KB pages.
         let mem_size = num::Integer::div_ceil(
-            &(matching_patterns_bitmap_base
-                + num::Integer::div_ceil(&num_patterns, &8)),
+            &(wasm_store.data().bitmaps.enabled_rules().end as u32),
             &65536,
         );
 
-        let matching_patterns_bitmap_base = Global::new(
-            wasm_store.as_context_mut(),
-            GlobalType::new(ValType::I32, Mutability::Const),
-            Val::I32(matching_patterns_bitmap_base as i32),
-        )
-        .unwrap();
-
-        // Create module's main memory.
+        // Create module's main memory. The WASM code never grows this
+        // memory, so its size remains fixed during the whole scan.
         let main_memory = wasmtime::Memory::new(
             wasm_store.as_context_mut(),
             MemoryType::new(mem_size, None),
         )
         .unwrap();
 
-        // Instantiate the module. This takes the wasm code provided by the
-        // `wasm_mod` function and links its imported functions with the
-        // implementations that YARA provides.
-        let wasm_instance = wasm::new_linker()
+        // Instantiate the modules. There's one module for each set of rules
+        // that were linked together with `Rules::link`, or a single one if
+        // the rules were not linked. All the modules share the same memory
+        // and global variables, except the ones that indicate where the
+        // module's regions in the bitmaps start, which are defined for each
+        // module before instantiating it. The imported functions are linked
+        // with the implementations that YARA provides.
+        let mut linker = wasm::new_linker();
+
+        linker.allow_shadowing(true);
+
+        linker
             .define(wasm_store.as_context(), "yara_x", "filesize", filesize)
             .unwrap()
             .define(
@@ -228,31 +368,74 @@ impl<'r> Scanner<'r> {
                 timeout_occurred,
             )
             .unwrap()
-            .define(
-                wasm_store.as_context(),
-                "yara_x",
-                "matching_patterns_bitmap_base",
-                matching_patterns_bitmap_base,
-            )
-            .unwrap()
             .define(
                 wasm_store.as_context(),
                 "yara_x",
                 "main_memory",
                 main_memory,
             )
-            .unwrap()
-            .instantiate(wasm_store.as_context_mut(), rules.wasm_mod())
             .unwrap();
 
-        // Obtain a reference to the "main" function exported by the module.
-        let wasm_main_func = wasm_instance
-            .get_typed_func::<(), i32>(wasm_store.as_context_mut(), "main")
-            .unwrap();
+        let mut wasm_main_funcs = Vec::with_capacity(rules.wasm_mods().len());
+
+        for (i, wasm_mod) in rules.wasm_mods().iter().enumerate() {
+            let (
+                matching_rules_bitmap_base,
+                matching_patterns_bitmap_base,
+                enabled_rules_bitmap_base,
+            ) = wasm_store.data().bitmaps.module_bases(i);
+
+            for (name, base) in [
+                ("matching_rules_bitmap_base", matching_rules_bitmap_base),
+                (
+                    "matching_patterns_bitmap_base",
+                    matching_patterns_bitmap_base,
+                ),
+                ("enabled_rules_bitmap_base", enabled_rules_bitmap_base),
+            ] {
+                let base = Global::new(
+                    wasm_store.as_context_mut(),
+                    GlobalType::new(ValType::I32, Mutability::Const),
+                    Val::I32(base as i32),
+                )
+                .unwrap();
+
+                linker
+                    .define(wasm_store.as_context(), "yara_x", name, base)
+                    .unwrap();
+            }
 
-        wasm_store.data_mut().main_memory = Some(main_memory);
+            let wasm_instance = linker
+                .instantiate(wasm_store.as_context_mut(), wasm_mod.module())
+                .unwrap();
 
-        Self { wasm_store, wasm_main_func, filesize, timeout: None }
+            // Obtain a reference to the "main" function exported by the
+            // module.
+            wasm_main_funcs.push(
+                wasm_instance
+                    .get_typed_func::<(), i32>(
+                        wasm_store.as_context_mut(),
+                        "main",
+                    )
+                    .unwrap(),
+            );
+        }
+
+        let ctx = wasm_store.data_mut();
+
+        ctx.main_memory = Some(main_memory);
+        ctx.set_filtered_rules(FilteredRules::all(rules));
+
+        Self {
+            wasm_store,
+            wasm_main_funcs,
+            filesize,
+            pattern_search_done,
+            timeout_occurred,
+            timeout: None,
+            max_reader_size: None,
+            reader_buffer: Vec::new(),
+        }
     }
 
     /// Sets a timeout for scan operations.
@@ -269,6 +452,56 @@ impl<'r> Scanner<'r> {
         self
     }
 
+    /// Sets the maximum number of bytes read by [`Scanner::scan_reader`].
+    ///
+    /// Only the first `n` bytes are read and scanned, the rest of the data
+    /// is ignored and `filesize` is at most `n`. By default there's no
+    /// limit.
+    pub fn max_reader_size(&mut self, n: usize) -> &mut Self {
+        self.max_reader_size = Some(n);
+        self
+    }
+
+    /// Returns a handle that can be used for cancelling the scans performed
+    /// by this scanner from some other thread.
+    ///
+    /// See [`CancelHandle`].
+    pub fn cancel_handle(&self) -> CancelHandle {
+        CancelHandle { cancelled: self.wasm_store.data().cancelled.clone() }
+    }
+
+    /// Sets a callback that receives the events reported during the scan.
+    ///
+    /// The callback is invoked when a rule matches, when a rule doesn't
+    /// match, when a module produces its output, and when a pattern reaches
+    /// the maximum number of matches. See [`ScanEvent`] for details. The
+    /// scan can be aborted early by returning [`CallbackAction::Abort`].
+    ///
+    /// The callback replaces any callback set before.
+    pub fn callback<F>(&mut self, callback: F) -> &mut Self
+    where
+        F: for<'a> FnMut(ScanEvent<'a, 'r>) -> CallbackAction + 'r,
+    {
+        self.wasm_store.data_mut().callback = Some(Box::new(callback));
+        self
+    }
+
+    /// Sets a callback that receives the messages logged by the rules with
+    /// the `console` module.
+    ///
+    /// Functions like `console.log` and `console.hex` always return `true`
+    /// and send a message to this callback, which is useful for debugging
+    /// rule conditions. If no callback is set the messages are discarded.
+    ///
+    /// The callback replaces any callback set before.
+    pub fn console_log<F>(&mut self, callback: F) -> &mut Self
+    where
+        F: FnMut(String) + 'r,
+    {
+        self.wasm_store.data_mut().console_log = Some(Box::new(callback));
+        self
+    }
+
     /// Sets the maximum number of matches per pattern.
     ///
     /// When some pattern reaches the maximum number of patterns it won't
@@ -278,6 +511,54 @@ impl<'r> Scanner<'r> {
         self
     }
 
+    /// Sets the maximum number of matches for all the patterns together.
+    ///
+    /// Unlike [`Scanner::max_matches_per_pattern`], which silently ignores
+    /// further matches, the scan fails with [`ScanError::LimitExceeded`]
+    /// when this limit is exceeded. By default there's no limit.
+    pub fn max_total_matches(&mut self, n: usize) -> &mut Self {
+        self.wasm_store.data_mut().max_total_matches = Some(n);
+        self
+    }
+
+    /// Sets the maximum size, in bytes, of the outputs produced by the
+    /// modules imported by the rules.
+    ///
+    /// The size of each output is the size of its protobuf encoding, and
+    /// the limit applies to the sum of all of them. The scan fails with
+    /// [`ScanError::LimitExceeded`] if the limit is exceeded. By default
+    /// there's no limit.
+    ///
+    /// Notice that each output is checked after the module has produced
+    /// it, so this limit prevents the outputs from being kept in memory
+    /// during the evaluation of the rules, but not the memory used by the
+    /// module while parsing the scanned data.
+    pub fn max_module_output_size(&mut self, bytes: u64) -> &mut Self {
+        self.wasm_store.data_mut().max_module_output_size = Some(bytes);
+        self
+    }
+
+    /// Sets a filter that selects the rules evaluated during the scan.
+    ///
+    /// Rules not selected by the filter are not evaluated, and they won't
+    /// appear in the scan results, neither as matching nor as non-matching
+    /// rules. However, if some selected rule depends on a non-selected one,
+    /// the latter is still evaluated, as the result of the former depends on
+    /// it. Global rules are evaluated if some other rule in their namespace
+    /// is evaluated. Patterns that are used only by rules that are not
+    /// evaluated are not searched for, and won't produce any match.
+    ///
+    /// The filter replaces any filter set before. Use [`RuleFilter::new`]
+    /// for selecting all the rules again.
+    pub fn rule_filter(&mut self, filter: RuleFilter) -> &mut Self {
+        let ctx = self.wasm_store.data_mut();
+        ctx.set_filtered_rules(FilteredRules::new(
+            ctx.compiled_rules,
+            &filter,
+        ));
+        self
+    }
+
     /// Scans a file.
     pub fn scan_file<'a, P>(
         &'a mut self,
@@ -315,6 +596,103 @@ impl<'r> Scanner<'r> {
         self.scan_impl(data)
     }
 
+    /// Scans the data read from a [`Read`] implementation, like a socket or
+    /// a decompression stream.
+    ///
+    /// The data is read until the end of the stream, or until reaching the
+    /// limit set with [`Scanner::max_reader_size`], into a buffer owned by
+    /// the scanner. The buffer is reused by subsequent calls to this
+    /// function, so that scanning multiple streams doesn't require a new
+    /// allocation each time.
+    ///
+    /// ```
+    /// # use yara_x::{compile, Scanner};
+    /// let rules = compile(r#"rule test { strings: $a = "foo" condition: $a }"#)?;
+    /// let mut scanner = Scanner::new(&rules);
+    ///
+    /// let results = scanner.scan_reader(&b"foobar"[..])?;
+    /// assert_eq!(results.matching_rules().len(), 1);
+    ///
+    /// // Only "bar" is scanned.
+    /// let results = scanner.max_reader_size(3).scan_reader(&b"barfoo"[..])?;
+    /// assert_eq!(results.matching_rules().len(), 0);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn scan_reader<'a, R: Read>(
+        &'a mut self,
+        reader: R,
+    ) -> Result<ScanResults<'a, 'r>, ScanError> {
+        let limit = self.max_reader_size.map_or(u64::MAX, |n| n as u64);
+
+        self.reader_buffer.clear();
+
+        reader
+            .take(limit)
+            .read_to_end(&mut self.reader_buffer)
+            .map_err(|err| ScanError::ReadError { source: err })?;
+
+        // SAFETY: `scan_impl` doesn't access `reader_buffer`, and the buffer
+        // can't be modified while the returned `ScanResults`, which holds a
+        // mutable borrow of the scanner, is alive.
+        let data = unsafe {
+            std::slice::from_raw_parts(
+                self.reader_buffer.as_ptr(),
+                self.reader_buffer.len(),
+            )
+        };
+
+        self.scan_impl(ScannedData::Slice(data))
+    }
+
+    /// Scans the file referenced by a file descriptor.
+    ///
+    /// This is useful for scanning files that were opened by some other
+    /// means, like the descriptors received from `fanotify` or created with
+    /// `memfd_create`. Regular files are mapped into memory, while pipes,
+    /// sockets and other non-seekable files are read with
+    /// [`Scanner::scan_reader`], so the limit set with
+    /// [`Scanner::max_reader_size`] applies to them.
+    ///
+    /// The descriptor is not closed, but when the data is read (instead of
+    /// mapped) the descriptor's file offset advances.
+    #[cfg(unix)]
+    pub fn scan_fd<'a, F: std::os::fd::AsFd>(
+        &'a mut self,
+        fd: F,
+    ) -> Result<ScanResults<'a, 'r>, ScanError> {
+        let read_error = |err| ScanError::ReadError { source: err };
+
+        // The descriptor is duplicated for having a `File` that can be
+        // closed when done, without closing the original descriptor.
+        let file = fs::File::from(
+            fd.as_fd().try_clone_to_owned().map_err(read_error)?,
+        );
+
+        let metadata = file.metadata().map_err(read_error)?;
+
+        if !metadata.is_file() {
+            return self.scan_reader(file);
+        }
+
+        // Empty files can't be mapped into memory.
+        if metadata.len() == 0 {
+            return self.scan_impl(ScannedData::Slice(&[]));
+        }
+
+        // The file is mapped through its `/dev/fd` entry, which refers to
+        // the same file as the descriptor, even if it doesn't have a name
+        // in the file system, like the ones created with `memfd_create`.
+        let path = PathBuf::from(format!(
+            "/dev/fd/{}",
+            std::os::fd::AsRawFd::as_raw_fd(&file)
+        ));
+
+        let mapped_file = MmapFile::open(&path)
+            .map_err(|err| ScanError::MapError { path, source: err })?;
+
+        self.scan_impl(ScannedData::Mmap(mapped_file))
+    }
+
     /// Scans in-memory data.
     pub fn scan<'a>(
         &'a mut self,
@@ -323,6 +701,69 @@ impl<'r> Scanner<'r> {
         self.scan_impl(ScannedData::Slice(data))
     }
 
+    /// Sets the output of a YARA module for the next scan.
+    ///
+    /// By default, modules produce their output by parsing the scanned data.
+    /// With this function the output is provided by the user instead, and
+    /// the module doesn't parse the data. This allows using data obtained
+    /// from some other source, like a sandbox report, or the output produced
+    /// by the module in a previous scan.
+    ///
+    /// `data` must be a protobuf of the type declared as the root message
+    /// of the module. The output is used only during the next scan, and it
+    /// is ignored if the rules don't import the module.
+    pub fn set_module_output(
+        &mut self,
+        module_name: &str,
+        data: Box<dyn MessageDyn>,
+    ) -> Result<&mut Self, ScanError> {
+        let module = BUILTIN_MODULES.get(module_name).ok_or_else(|| {
+            ScanError::UnknownModule { module: module_name.to_string() }
+        })?;
+
+        let full_name = module.root_struct_descriptor.full_name();
+
+        if data.descriptor_dyn().full_name() != full_name {
+            return Err(ScanError::InvalidModuleOutput {
+                module: module_name.to_string(),
+                err: format!(
+                    "expecting `{}`, got `{}`",
+                    full_name,
+                    data.descriptor_dyn().full_name()
+                ),
+            });
+        }
+
+        self.wasm_store
+            .data_mut()
+            .user_provided_module_outputs
+            .insert(full_name.to_string(), data);
+
+        Ok(self)
+    }
+
+    /// Similar to [`Scanner::set_module_output`], but receives the module's
+    /// output serialized as a protobuf.
+    pub fn set_module_output_raw(
+        &mut self,
+        module_name: &str,
+        data: &[u8],
+    ) -> Result<&mut Self, ScanError> {
+        let module = BUILTIN_MODULES.get(module_name).ok_or_else(|| {
+            ScanError::UnknownModule { module: module_name.to_string() }
+        })?;
+
+        let data = module
+            .root_struct_descriptor
+            .parse_from_bytes(data)
+            .map_err(|err| ScanError::InvalidModuleOutput {
+                module: module_name.to_string(),
+                err: err.to_string(),
+            })?;
+
+        self.set_module_output(module_name, data)
+    }
+
     /// Sets the value of a global variable.
     ///
     /// The variable must has been previously defined by calling
@@ -339,27 +780,42 @@ impl<'r> Scanner<'r> {
     where
         VariableError: From<<T as TryInto<Variable>>::Error>,
     {
-        let ctx = self.wasm_store.data_mut();
+        set_global(
+            &mut self.wasm_store.data_mut().root_struct,
+            ident,
+            value.try_into()?,
+        )?;
 
-        if let Some(field) = ctx.root_struct.field_by_name_mut(ident) {
-            let variable: Variable = value.try_into()?;
-            let type_value: TypeValue = variable.into();
-            // The new type must match the the old one.
-            if type_value.eq_type(&field.type_value) {
-                field.type_value = type_value;
-            } else {
-                return Err(VariableError::InvalidType {
-                    variable: ident.to_string(),
-                    expected_type: field.type_value.ty().to_string(),
-                    actual_type: type_value.ty().to_string(),
-                });
-            }
+        Ok(self)
+    }
+}
+
+/// Sets the value of the global variable `ident` in `root_struct`.
+///
+/// The variable must exist in `root_struct`, and the new value must have the
+/// same type than the current one.
+fn set_global(
+    root_struct: &mut Struct,
+    ident: &str,
+    variable: Variable,
+) -> Result<(), VariableError> {
+    if let Some(field) = root_struct.field_by_name_mut(ident) {
+        let type_value: TypeValue = variable.into();
+        // The new type must match the the old one.
+        if type_value.eq_type(&field.type_value) {
+            field.type_value = type_value;
         } else {
-            return Err(VariableError::Undefined(ident.to_string()));
+            return Err(VariableError::InvalidType {
+                variable: ident.to_string(),
+                expected_type: field.type_value.ty().to_string(),
+                actual_type: type_value.ty().to_string(),
+            });
         }
-
-        Ok(self)
+    } else {
+        return Err(VariableError::Undefined(ident.to_string()));
     }
+
+    Ok(())
 }
 
 impl<'r> Scanner<'r> {
@@ -369,52 +825,7 @@ impl<'r> Scanner<'r> {
     ) -> Result<ScanResults<'a, 'r>, ScanError> {
         // Clear information about matches found in a previous scan, if any.
         self.clear_matches();
-
-        // Timeout in seconds. This is either the value provided by the user or
-        // 315.360.000 which is the number of seconds in a year. Using u64::MAX
-        // doesn't work because this value is added to the current epoch, and
-        // will cause an overflow. We need an integer large enough, but that
-        // has room before the u64 limit is reached. For this same reason if
-        // the user specifies a value larger than 315.360.000 we limit it to
-        // 315.360.000 anyways. One year should be enough, I hope you don't
-        // plan to run a YARA scan that takes longer.
-        let timeout_secs =
-            self.timeout.map_or(Self::DEFAULT_SCAN_TIMEOUT, |t| {
-                cmp::min(
-                    t.as_secs_f32().ceil() as u64,
-                    Self::DEFAULT_SCAN_TIMEOUT,
-                )
-            });
-
-        // Sets the deadline for the WASM store. The WASM main function will
-        // abort if the deadline is reached while the function is being
-        // executed.
-        self.wasm_store.set_epoch_deadline(timeout_secs);
-        self.wasm_store
-            .epoch_deadline_callback(|_| Err(ScanError::Timeout.into()));
-
-        // If the user specified some timeout, start the heartbeat thread, if
-        // not previously started. The heartbeat thread increments the WASM
-        // engine epoch and HEARTBEAT_COUNTER every second. There's a single
-        // instance of this thread, independently of the number of concurrent
-        // scans.
-        if self.timeout.is_some() {
-            INIT_HEARTBEAT.call_once(|| {
-                thread::spawn(|| loop {
-                    loop {
-                        thread::sleep(Duration::from_secs(1));
-                        ENGINE.increment_epoch();
-                        HEARTBEAT_COUNTER
-                            .fetch_update(
-                                Ordering::SeqCst,
-                                Ordering::SeqCst,
-                                |x| Some(x + 1),
-                            )
-                            .unwrap();
-                    }
-                });
-            });
-        }
+        self.set_deadline();
 
         // Set the global variable `filesize` to the size of the scanned data.
         self.filesize
@@ -426,8 +837,6 @@ impl<'r> Scanner<'r> {
 
         let ctx = self.wasm_store.data_mut();
 
-        ctx.deadline =
-            HEARTBEAT_COUNTER.load(Ordering::Relaxed) + timeout_secs;
         ctx.scanned_data = data.as_ref().as_ptr();
         ctx.scanned_data_len = data.as_ref().len();
 
@@ -439,6 +848,25 @@ impl<'r> Scanner<'r> {
             ctx.string_pool = BStringPool::new();
         }
 
+        self.run_modules(Some(data.as_ref()))?;
+        self.eval_conditions(false)?;
+
+        Ok(ScanResults::new(self.wasm_store.data(), data))
+    }
+
+    /// Produces the outputs of the modules imported by the rules, and adds
+    /// them to the root structure.
+    ///
+    /// `data` is the scanned data, which is passed to the main function of
+    /// each module. When the data is not available, like while scanning
+    /// data in blocks, the main functions are not called.
+    fn run_modules(&mut self, data: Option<&[u8]>) -> Result<(), ScanError> {
+        let ctx = self.wasm_store.data_mut();
+
+        // Total size of the module outputs, used for enforcing the limit
+        // set with `Scanner::max_module_output_size`.
+        let mut module_outputs_size = 0;
+
         for module_name in ctx.compiled_rules.imports() {
             // Lookup the module in the list of built-in modules.
             let module = modules::BUILTIN_MODULES.get(module_name).unwrap();
@@ -447,15 +875,38 @@ impl<'r> Scanner<'r> {
             // a data structure serialized as a protocol buffer. The format of
             // the data is specified by the .proto file associated to the
             // module.
-            let module_output = if let Some(main_fn) = module.main_fn {
-                main_fn(data.as_ref())
+            //
+            // If the module's output was provided by the user with
+            // `Scanner::set_module_output`, the main function is not called.
+            // Modules without a main function whose output is not provided,
+            // or whose main function can't be called because the data is
+            // not available, produce an empty structure where all fields
+            // are undefined.
+            let module_output = if let Some(module_output) = ctx
+                .user_provided_module_outputs
+                .remove(module.root_struct_descriptor.full_name())
+            {
+                module_output
+            } else if let (Some(main_fn), Some(data)) = (module.main_fn, data)
+            {
+                main_fn(data)
             } else {
-                // Implement the case in which the module doesn't have a main
-                // function and the serialized data should be provided by the
-                // user.
-                todo!()
+                module.root_struct_descriptor.new_instance()
             };
 
+            if let Some(max_size) = ctx.max_module_output_size {
+                module_outputs_size += module_output.compute_size_dyn();
+                if module_outputs_size > max_size {
+                    ctx.scanned_data = null();
+                    ctx.scanned_data_len = 0;
+                    ctx.user_provided_module_outputs.clear();
+                    ctx.cancelled.store(false, Ordering::Relaxed);
+                    return Err(ScanError::LimitExceeded {
+                        kind: LimitKind::ModuleOutputSize,
+                    });
+                }
+            }
+
             // Make sure that the module is returning a protobuf message of the
             // expected type.
             debug_assert_eq!(
@@ -510,8 +961,102 @@ impl<'r> Scanner<'r> {
                 module_name,
                 TypeValue::Struct(Rc::new(module_struct)),
             );
+
+            ctx.notify(|ctx, _| ScanEvent::ModuleImport {
+                name: module_name,
+                output: ctx
+                    .module_outputs
+                    .get(module.root_struct_descriptor.full_name())
+                    .unwrap()
+                    .as_ref(),
+            });
+
+            // If the scan was aborted, the remaining modules are not
+            // executed.
+            if ctx.aborted {
+                break;
+            }
+        }
+
+        // Module outputs provided by the user are used only in one scan.
+        ctx.user_provided_module_outputs.clear();
+
+        Ok(())
+    }
+
+    /// Sets the deadline for the current scan operation, according to the
+    /// timeout set with [`Scanner::timeout`].
+    fn set_deadline(&mut self) {
+        // Timeout in seconds. This is either the value provided by the user or
+        // 315.360.000 which is the number of seconds in a year. Using u64::MAX
+        // doesn't work because this value is added to HEARTBEAT_COUNTER, and
+        // will cause an overflow. We need an integer large enough, but that
+        // has room before the u64 limit is reached. For this same reason if
+        // the user specifies a value larger than 315.360.000 we limit it to
+        // 315.360.000 anyways. One year should be enough, I hope you don't
+        // plan to run a YARA scan that takes longer.
+        let timeout_secs =
+            self.timeout.map_or(Self::DEFAULT_SCAN_TIMEOUT, |t| {
+                cmp::min(
+                    t.as_secs_f32().ceil() as u64,
+                    Self::DEFAULT_SCAN_TIMEOUT,
+                )
+            });
+
+        // The WASM code invokes the epoch deadline callback every time the
+        // epoch is incremented, either by the heartbeat thread or by a
+        // `CancelHandle`. The callback decides if the WASM main function
+        // must be aborted because the timeout was reached or the scan was
+        // cancelled.
+        self.wasm_store.set_epoch_deadline(1);
+        self.wasm_store.epoch_deadline_callback(|store| {
+            let ctx = store.data();
+            if ctx.cancelled.load(Ordering::Relaxed) {
+                Err(ScanError::Cancelled.into())
+            } else if HEARTBEAT_COUNTER.load(Ordering::Relaxed) >= ctx.deadline
+            {
+                Err(ScanError::Timeout.into())
+            } else {
+                Ok(UpdateDeadline::Continue(1))
+            }
+        });
+
+        // If the user specified some timeout, start the heartbeat thread, if
+        // not previously started. The heartbeat thread increments the WASM
+        // engine epoch and HEARTBEAT_COUNTER every second. There's a single
+        // instance of this thread, independently of the number of concurrent
+        // scans.
+        if self.timeout.is_some() {
+            INIT_HEARTBEAT.call_once(|| {
+                thread::spawn(|| loop {
+                    loop {
+                        thread::sleep(Duration::from_secs(1));
+                        HEARTBEAT_COUNTER
+                            .fetch_update(
+                                Ordering::SeqCst,
+                                Ordering::SeqCst,
+                                |x| Some(x + 1),
+                            )
+                            .unwrap();
+                        ENGINE.increment_epoch();
+                    }
+                });
+            });
         }
 
+        self.wasm_store.data_mut().deadline =
+            HEARTBEAT_COUNTER.load(Ordering::Relaxed) + timeout_secs;
+    }
+
+    /// Evaluates the rule conditions, by calling the main function of each
+    /// WASM module.
+    ///
+    /// If `pattern_search_done` is true, the pattern search phase is not
+    /// executed, the patterns must have been searched for already.
+    fn eval_conditions(
+        &mut self,
+        pattern_search_done: bool,
+    ) -> Result<(), ScanError> {
         // Invoke the main function, which evaluates the rules' conditions. It
         // calls ScanContext::search_for_patterns (which does the Aho-Corasick
         // scanning) only if necessary.
@@ -521,15 +1066,41 @@ impl<'r> Scanner<'r> {
         // while ScanContext::search_for_patterns is being executed, the result
         // will be Ok(1). If the scan completes successfully the result is
         // Ok(0).`
-        let func_result =
-            self.wasm_main_func.call(self.wasm_store.as_context_mut(), ());
+        //
+        // When the rules were linked with `Rules::link` there are multiple
+        // main functions, one per WASM module, and they are called in order.
+        // The pattern search phase is shared by all of them, it's executed
+        // at most once, by the first one that needs it.
+        self.pattern_search_done
+            .set(
+                self.wasm_store.as_context_mut(),
+                Val::I32(pattern_search_done as i32),
+            )
+            .unwrap();
 
-        let ctx = self.wasm_store.data_mut();
+        self.timeout_occurred
+            .set(self.wasm_store.as_context_mut(), Val::I32(0))
+            .unwrap();
 
-        // Set pointer to data back to nil. This means that accessing
-        // `scanned_data` from within `ScanResults` is not possible.
-        ctx.scanned_data = null();
-        ctx.scanned_data_len = 0;
+        let mut func_result = Ok(0);
+
+        // If the scan was cancelled before evaluating the conditions (for
+        // instance, while the modules were parsing the data), or aborted
+        // by the callback, none of the main functions is called.
+        if self.wasm_store.data().cancelled.load(Ordering::Relaxed) {
+            func_result = Err(ScanError::Cancelled.into());
+        } else if !self.wasm_store.data().aborted {
+            for (i, main_func) in self.wasm_main_funcs.iter().enumerate() {
+                self.wasm_store.data_mut().current_wasm_mod = i;
+                func_result =
+                    main_func.call(self.wasm_store.as_context_mut(), ());
+                if !matches!(func_result, Ok(0)) {
+                    break;
+                }
+            }
+        }
+
+        let ctx = self.wasm_store.data_mut();
 
         // Clear the value of `current_struct` as it may contain a reference
         // to some struct.
@@ -538,18 +1109,75 @@ impl<'r> Scanner<'r> {
         // Move all the in `global_matching_rules` to `private_matching_rules`
         // and `non_private_matching_rules`, leaving `global_matching_rules`
         // empty.
+        let mut global_matching_rules = Vec::new();
+
         for rules in ctx.global_matching_rules.values_mut() {
             for rule_id in rules.drain(0..) {
-                if ctx.compiled_rules.get(rule_id).is_private {
+                if !ctx.filtered_rules.reported_rules[usize::from(rule_id)] {
                     ctx.private_matching_rules.push(rule_id);
                 } else {
                     ctx.non_private_matching_rules.push(rule_id);
+                    global_matching_rules.push(rule_id);
                 }
             }
         }
 
+        // Now that all the rules have been evaluated, notify the callback
+        // about matching global rules, and non-matching rules.
+        if ctx.callback.is_some() && !ctx.aborted {
+            for rule_id in global_matching_rules {
+                ctx.notify_rule_match(rule_id);
+            }
+
+            let main_memory = ctx
+                .main_memory
+                .unwrap()
+                .data(unsafe { ctx.wasm_store.as_ref() });
+
+            let matching_rules_bitmap = BitSlice::<_, Lsb0>::from_slice(
+                &main_memory[ctx.bitmaps.matching_rules()],
+            );
+
+            let non_matching_rules = ctx
+                .bitmaps
+                .iter_zeros(matching_rules_bitmap)
+                .filter(|rule_id| ctx.is_reported_rule(*rule_id))
+                .collect::<Vec<_>>();
+
+            for rule_id in non_matching_rules {
+                ctx.notify_rule_no_match(rule_id);
+            }
+        }
+
+        // Set pointer to data back to nil. This means that accessing
+        // `scanned_data` from within `ScanResults` is not possible.
+        ctx.scanned_data = null();
+        ctx.scanned_data_len = 0;
+
+        // The scan has finished, a cancellation requested from now on
+        // applies to the next scan. The flag is not cleared when the scan
+        // starts, as that would lose cancellations requested while the
+        // scan is starting.
+        let cancelled = ctx.cancelled.swap(false, Ordering::Relaxed);
+
+        // When the callback aborts the scan, the WASM code is interrupted
+        // with a trap (see `wasm::ScanAborted`) that is not actually an
+        // error.
+        if ctx.aborted {
+            return Ok(());
+        }
+
         match func_result {
-            Ok(0) => Ok(ScanResults::new(self.wasm_store.data(), data)),
+            Ok(0) => Ok(()),
+            // The main function returns 1 when `search_for_patterns` fails,
+            // either because of a timeout, a cancellation or some limit
+            // being exceeded.
+            Ok(1) if cancelled => Err(ScanError::Cancelled),
+            Ok(1) if ctx.limit_exceeded.is_some() => {
+                Err(ScanError::LimitExceeded {
+                    kind: ctx.limit_exceeded.unwrap(),
+                })
+            }
             Ok(1) => Err(ScanError::Timeout),
             Ok(_) => unreachable!(),
             Err(err) if err.is::<ScanError>() => {
@@ -565,20 +1193,29 @@ impl<'r> Scanner<'r> {
     // Clear information about previous matches.
     fn clear_matches(&mut self) {
         let ctx = self.wasm_store.data_mut();
-        let num_rules = ctx.compiled_rules.rules().len();
-        let num_patterns = ctx.compiled_rules.num_patterns();
 
         // Clear the unconfirmed matches.
         for (_, matches) in ctx.unconfirmed_matches.iter_mut() {
             matches.clear()
         }
 
+        // Patterns that reached the maximum number of matches in a previous
+        // scan can produce matches again.
+        ctx.limit_reached.fill(false);
+        ctx.num_matches = 0;
+        ctx.limit_exceeded = None;
+        ctx.aborted = false;
+
+        // Data stored by the modules is valid only for one scan.
+        ctx.module_data.get_mut().clear();
+
         // If some pattern or rule matched, clear the matches. Notice that a
         // rule may match without any pattern being matched, because there
         // there are rules without patterns, or that match if the pattern is
         // not found.
         if !ctx.pattern_matches.is_empty()
             || !ctx.non_private_matching_rules.is_empty()
+            || !ctx.private_matching_rules.is_empty()
         {
             // The hash map that tracks the pattern matches is not completely
             // cleared with pattern_matches.clear() because that would cause
@@ -592,23 +1229,21 @@ impl<'r> Scanner<'r> {
 
             // Clear the list of matching rules.
             ctx.non_private_matching_rules.clear();
+            ctx.private_matching_rules.clear();
+
+            // Starting at MATCHING_RULES_BITMAP_BASE in main memory there
+            // are bitmaps that indicate which rules and patterns matched. If
+            // some rule or pattern matched in a previous call the bitmaps
+            // will contain some bits set to 1 and need to be cleared.
+            let matches = ctx.bitmaps.matches();
 
             let mem = ctx
                 .main_memory
                 .unwrap()
                 .data_mut(self.wasm_store.as_context_mut());
 
-            // Starting at MATCHING_RULES_BITMAP in main memory there's a
-            // bitmap were the N-th bit indicates if the rule with
-            // ID = N matched or not, If some rule matched in a
-            // previous call the bitmap will contain some
-            // bits set to 1 and need to be cleared.
-            let base = MATCHING_RULES_BITMAP_BASE as usize;
-            let bitmap = BitSlice::<_, Lsb0>::from_slice_mut(
-                &mut mem[base..base
-                    + (num_rules / 8 + 1)
-                    + (num_patterns / 8 + 1)],
-            );
+            let bitmap =
+                BitSlice::<_, Lsb0>::from_slice_mut(&mut mem[matches]);
 
             // Set to zero all bits in the bitmap.
             bitmap.fill(false);
@@ -702,39 +1337,29 @@ impl<'a, 'r> ExactSizeIterator for MatchingRules<'a, 'r> {
 pub struct NonMatchingRules<'a, 'r> {
     ctx: &'a ScanContext<'r>,
     data: &'a ScannedData<'a>,
-    iterator: bitvec::slice::IterZeros<'a, u8, Lsb0>,
+    iterator: Box<dyn Iterator<Item = RuleId> + 'a>,
     len: usize,
 }
 
 impl<'a, 'r> NonMatchingRules<'a, 'r> {
     fn new(ctx: &'a ScanContext<'r>, data: &'a ScannedData<'a>) -> Self {
-        let num_rules = ctx.compiled_rules.rules().len();
         let main_memory =
             ctx.main_memory.unwrap().data(unsafe { ctx.wasm_store.as_ref() });
 
-        let base = MATCHING_RULES_BITMAP_BASE as usize;
-
         // Create a BitSlice that covers the region of main memory containing
         // the bitmap that tells which rules matched and which did not.
         let matching_rules_bitmap = BitSlice::<_, Lsb0>::from_slice(
-            &main_memory[base..base + num_rules / 8 + 1],
+            &main_memory[ctx.bitmaps.matching_rules()],
         );
 
-        // The BitSlice will cover more bits than necessary, for example, if
-        // there are 3 rules the BitSlice will have 8 bits because it is
-        // created from a u8 slice that has 1 byte. Here we make sure that
-        // the BitSlice has exactly as many bits as existing rules.
-        let matching_rules_bitmap = &matching_rules_bitmap[0..num_rules];
-
         Self {
             ctx,
             data,
-            iterator: matching_rules_bitmap.iter_zeros(),
-            // The number of non-matching rules is the total number of rules
-            // minus the number of matching rules, both private and
-            // non-private.
-            len: ctx.compiled_rules.rules().len()
-                - ctx.private_matching_rules.len()
+            iterator: Box::new(ctx.bitmaps.iter_zeros(matching_rules_bitmap)),
+            // The number of non-matching rules is the number of rules that
+            // can be reported (i.e: non-private rules selected by the rule
+            // filter) minus the number of reported matching rules.
+            len: ctx.filtered_rules.reported_rules.count_ones()
                 - ctx.non_private_matching_rules.len(),
         }
     }
@@ -745,13 +1370,14 @@ impl<'a, 'r> Iterator for NonMatchingRules<'a, 'r> {
 
     fn next(&mut self) -> Option<Self::Item> {
         loop {
-            self.len = self.len.saturating_sub(1);
-            let rule_id = RuleId::from(self.iterator.next()?);
-            let rules = self.ctx.compiled_rules;
-            let rule_info = rules.get(rule_id);
-            // Private rules are not returned, if the current rule is private
-            // keep in the loop and try with the next one.
-            if !rule_info.is_private {
+            let rule_id = self.iterator.next()?;
+            // Private rules and rules not selected by the rule filter are not
+            // returned, in that case keep in the loop and try with the next
+            // one.
+            if self.ctx.is_reported_rule(rule_id) {
+                self.len = self.len.saturating_sub(1);
+                let rules = self.ctx.compiled_rules;
+                let rule_info = rules.get(rule_id);
                 return Some(Rule {
                     rule_info,
                     rules,
@@ -818,6 +1444,23 @@ impl<'a, 'r> Rule<'a, 'r> {
         self.rules.ident_pool().get(self.rule_info.namespace_ident_id).unwrap()
     }
 
+    /// Returns the tags associated to this rule, in the same order they
+    /// were declared.
+    pub fn tags(&self) -> Tags<'r> {
+        Tags { rules: self.rules, iterator: self.rule_info.tags.iter() }
+    }
+
+    /// Returns the metadata associated to this rule.
+    ///
+    /// Metadata entries are returned in the same order they were declared.
+    /// Notice that the same identifier can appear more than once.
+    pub fn metadata(&self) -> Metadata<'r> {
+        Metadata {
+            rules: self.rules,
+            iterator: self.rule_info.metadata.iter(),
+        }
+    }
+
     /// Returns the patterns defined by this rule.
     pub fn patterns(&self) -> Patterns<'a, 'r> {
         Patterns {
@@ -828,6 +1471,79 @@ impl<'a, 'r> Rule<'a, 'r> {
     }
 }
 
+/// An iterator that returns the tags associated to a rule.
+pub struct Tags<'r> {
+    rules: &'r Rules,
+    iterator: Iter<'r, IdentId>,
+}
+
+impl<'r> Iterator for Tags<'r> {
+    type Item = &'r str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let ident_id = self.iterator.next()?;
+        Some(self.rules.ident_pool().get(*ident_id).unwrap())
+    }
+}
+
+impl<'r> ExactSizeIterator for Tags<'r> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.iterator.len()
+    }
+}
+
+/// An iterator that returns the metadata associated to a rule.
+///
+/// Each item is a tuple composed of the metadata identifier and its value.
+pub struct Metadata<'r> {
+    rules: &'r Rules,
+    iterator: Iter<'r, (IdentId, compiler::MetaValue)>,
+}
+
+impl<'r> Iterator for Metadata<'r> {
+    type Item = (&'r str, MetaValue<'r>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (ident_id, value) = self.iterator.next()?;
+
+        let ident = self.rules.ident_pool().get(*ident_id).unwrap();
+
+        let value = match value {
+            compiler::MetaValue::Bool(b) => MetaValue::Bool(*b),
+            compiler::MetaValue::Integer(i) => MetaValue::Integer(*i),
+            compiler::MetaValue::Float(f) => MetaValue::Float(*f),
+            compiler::MetaValue::String(id) => {
+                MetaValue::String(self.rules.lit_pool().get_str(*id).unwrap())
+            }
+            compiler::MetaValue::Bytes(id) => {
+                MetaValue::Bytes(self.rules.lit_pool().get(*id).unwrap())
+            }
+        };
+
+        Some((ident, value))
+    }
+}
+
+impl<'r> ExactSizeIterator for Metadata<'r> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.iterator.len()
+    }
+}
+
+/// The value of a metadata entry.
+#[derive(Debug, PartialEq)]
+pub enum MetaValue<'r> {
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    String(&'r str),
+    /// Strings with escape sequences that produced invalid UTF-8 are
+    /// returned as bytes.
+    Bytes(&'r BStr),
+}
+
 /// An iterator that returns the patterns defined by a rule.
 pub struct Patterns<'a, 'r> {
     ctx: &'a ScanContext<'r>,
@@ -890,8 +1606,13 @@ impl<'a> Iterator for Matches<'a> {
             let match_ = iter.next()?;
             Some(Match {
                 range: match_.range.clone(),
-                data: &self.data.as_ref()
-                    [match_.range.start..match_.range.end],
+                // The matching data is not available when the data was
+                // scanned in blocks with `BlockScanner`.
+                data: self
+                    .data
+                    .as_ref()
+                    .get(match_.range.start..match_.range.end)
+                    .unwrap_or_default(),
                 xor_key: match_.xor_key,
             })
         } else {
@@ -905,7 +1626,8 @@ impl<'a> Iterator for Matches<'a> {
 pub struct Match<'a> {
     /// Range within the original data where the match occurred.
     pub range: Range<usize>,
-    /// Slice containing the data that matched.
+    /// Slice containing the data that matched. This is empty when the data
+    /// was scanned in blocks with [`BlockScanner`].
     pub data: &'a [u8],
     /// XOR key used for decrypting the data if the pattern had the `xor`
     /// modifier, or `None` if otherwise.
@@ -0,0 +1,273 @@
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use bincode::Options;
+
+use crate::compiler::Rules;
+use crate::scanner::{
+    set_global, FilteredRules, RuleFilter, ScanError, ScanResults, Scanner,
+};
+use crate::types::Struct;
+use crate::variables::VariableError;
+use crate::Variable;
+
+/// A pool of scanners that can be shared by multiple threads.
+///
+/// A [`Scanner`] can't be shared between threads, each thread needs its own
+/// scanner. The pool keeps a set of scanners for the same [`Rules`], and
+/// lends them to the threads that need them with [`ScannerPool::get`]. When
+/// the scanner is not needed anymore it goes back to the pool, where it can
+/// be reused by some other thread.
+///
+/// All the scanners in the pool share the same configuration (global
+/// variables, timeout, etc.). Changes made to a borrowed scanner are
+/// reverted when the scanner goes back to the pool.
+///
+/// ```
+/// # use yara_x::{compile, ScannerPool};
+/// let rules = compile(r#"rule test { strings: $a = "foo" condition: $a }"#)?;
+/// let pool = ScannerPool::new(&rules);
+///
+/// std::thread::scope(|s| {
+///     for (data, num_matches) in [(b"foo", 1), (b"bar", 0)] {
+///         let pool = &pool;
+///         s.spawn(move || {
+///             let mut scanner = pool.get();
+///             let results = scanner.scan(data).unwrap();
+///             assert_eq!(results.matching_rules().len(), num_matches);
+///         });
+///     }
+/// });
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct ScannerPool<'r> {
+    rules: &'r Rules,
+    timeout: Option<Duration>,
+    max_matches_per_pattern: usize,
+    filtered_rules: FilteredRules,
+    /// The root structure with the values of global variables, serialized
+    /// with bincode. This is the same approach used by [`Rules`], as
+    /// [`Struct`] is not [`Send`], a copy of the structure is deserialized
+    /// for each scanner.
+    serialized_globals: Vec<u8>,
+    /// Scanners that are not being used at the moment.
+    idle: Mutex<Vec<IdleScanner<'r>>>,
+}
+
+impl<'r> ScannerPool<'r> {
+    /// Creates a new, empty, pool of scanners for the given rules.
+    ///
+    /// Scanners are created on demand, the pool grows up to the maximum
+    /// number of scanners that are used simultaneously.
+    pub fn new(rules: &'r Rules) -> Self {
+        Self {
+            rules,
+            timeout: None,
+            max_matches_per_pattern: Scanner::DEFAULT_MAX_MATCHES_PER_PATTERN,
+            filtered_rules: FilteredRules::all(rules),
+            serialized_globals: bincode::DefaultOptions::new()
+                .serialize(&rules.globals())
+                .expect("failed to serialize global variables"),
+            idle: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Sets a timeout for the scan operations performed by the scanners
+    /// in the pool.
+    ///
+    /// See [`Scanner::timeout`].
+    pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = Some(timeout);
+        self.clear();
+        self
+    }
+
+    /// Sets the maximum number of matches per pattern for the scanners in
+    /// the pool.
+    ///
+    /// See [`Scanner::max_matches_per_pattern`].
+    pub fn max_matches_per_pattern(&mut self, n: usize) -> &mut Self {
+        self.max_matches_per_pattern = n;
+        self.clear();
+        self
+    }
+
+    /// Sets a filter that selects the rules evaluated by the scanners in
+    /// the pool.
+    ///
+    /// See [`Scanner::rule_filter`].
+    pub fn rule_filter(&mut self, filter: RuleFilter) -> &mut Self {
+        self.filtered_rules = FilteredRules::new(self.rules, &filter);
+        self.clear();
+        self
+    }
+
+    /// Sets the value of a global variable for the scanners in the pool.
+    ///
+    /// See [`Scanner::set_global`].
+    pub fn set_global<T: TryInto<Variable>>(
+        &mut self,
+        ident: &str,
+        value: T,
+    ) -> Result<&mut Self, VariableError>
+    where
+        VariableError: From<<T as TryInto<Variable>>::Error>,
+    {
+        let mut globals = self.globals();
+
+        set_global(&mut globals, ident, value.try_into()?)?;
+
+        self.serialized_globals = bincode::DefaultOptions::new()
+            .serialize(&globals)
+            .expect("failed to serialize global variables");
+
+        self.clear();
+        Ok(self)
+    }
+
+    /// Borrows a scanner from the pool.
+    ///
+    /// If there are no idle scanners in the pool a new one is created. The
+    /// scanner goes back to the pool when the returned [`PooledScanner`] is
+    /// dropped.
+    pub fn get(&self) -> PooledScanner<'_, 'r> {
+        let idle = self.idle.lock().unwrap().pop();
+        let scanner = match idle {
+            Some(IdleScanner(scanner)) => scanner,
+            None => {
+                let mut scanner = Scanner::new(self.rules);
+                self.configure(&mut scanner);
+                scanner
+            }
+        };
+        PooledScanner { pool: self, scanner: Some(scanner) }
+    }
+
+    /// Scans multiple files in parallel, using `num_threads` threads.
+    ///
+    /// The results for each file are passed to `f` together with the file's
+    /// path, in the same thread where the file was scanned. Files are not
+    /// necessarily scanned in the order in which they are returned by
+    /// `paths`. If `num_threads` is 0, the number of threads is the
+    /// parallelism available in the system.
+    pub fn scan_files<I, F>(&self, paths: I, num_threads: usize, f: F)
+    where
+        I: IntoIterator,
+        I::IntoIter: Send,
+        I::Item: AsRef<Path>,
+        F: Fn(&Path, Result<ScanResults<'_, 'r>, ScanError>) + Sync,
+    {
+        let num_threads = if num_threads > 0 {
+            num_threads
+        } else {
+            thread::available_parallelism().map_or(1, |n| n.get())
+        };
+
+        let paths = Mutex::new(paths.into_iter());
+
+        thread::scope(|s| {
+            for _ in 0..num_threads {
+                s.spawn(|| {
+                    let mut scanner = self.get();
+                    loop {
+                        // The lock is released before scanning the file.
+                        let path = match paths.lock().unwrap().next() {
+                            Some(path) => path,
+                            None => break,
+                        };
+                        let path = path.as_ref();
+                        f(path, scanner.scan_file(path));
+                    }
+                });
+            }
+        });
+    }
+
+    /// Returns the root structure with the global variables.
+    fn globals(&self) -> Struct {
+        bincode::DefaultOptions::new()
+            .deserialize::<Struct>(self.serialized_globals.as_slice())
+            .expect("error deserializing global variables")
+    }
+
+    /// Removes the idle scanners, which are outdated after a change in the
+    /// pool's configuration.
+    fn clear(&mut self) {
+        self.idle.get_mut().unwrap().clear();
+    }
+
+    /// Applies the pool's configuration to a scanner.
+    fn configure(&self, scanner: &mut Scanner<'r>) {
+        scanner.timeout = self.timeout;
+        scanner.max_reader_size = None;
+
+        let ctx = scanner.wasm_store.data_mut();
+
+        ctx.max_matches_per_pattern = self.max_matches_per_pattern;
+        ctx.max_total_matches = None;
+        ctx.max_module_output_size = None;
+        ctx.set_filtered_rules(self.filtered_rules.clone());
+        ctx.root_struct = self.globals();
+        ctx.callback = None;
+        ctx.console_log = None;
+        ctx.user_provided_module_outputs.clear();
+        // Cancel handles obtained while the scanner was borrowed must not
+        // affect the scanner once it is borrowed again.
+        ctx.cancelled = Arc::new(AtomicBool::new(false));
+    }
+
+    /// Puts a scanner back into the pool.
+    fn release(&self, mut scanner: Scanner<'r>) {
+        self.configure(&mut scanner);
+        self.idle.lock().unwrap().push(IdleScanner(scanner));
+    }
+}
+
+/// A scanner borrowed from a [`ScannerPool`].
+///
+/// This type dereferences to [`Scanner`], and returns the scanner to the
+/// pool when dropped.
+pub struct PooledScanner<'a, 'r> {
+    pool: &'a ScannerPool<'r>,
+    scanner: Option<Scanner<'r>>,
+}
+
+impl<'a, 'r> Deref for PooledScanner<'a, 'r> {
+    type Target = Scanner<'r>;
+
+    fn deref(&self) -> &Self::Target {
+        self.scanner.as_ref().unwrap()
+    }
+}
+
+impl<'a, 'r> DerefMut for PooledScanner<'a, 'r> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.scanner.as_mut().unwrap()
+    }
+}
+
+impl<'a, 'r> Drop for PooledScanner<'a, 'r> {
+    fn drop(&mut self) {
+        if let Some(scanner) = self.scanner.take() {
+            self.pool.release(scanner);
+        }
+    }
+}
+
+/// A scanner that is idle in the pool.
+///
+/// [`Scanner`] is not [`Send`] because [`crate::scanner::ScanContext`]
+/// contains raw pointers, reference-counted values and the callback set with
+/// [`Scanner::callback`]. Idle scanners can be sent to other threads because
+/// [`ScannerPool::configure`] removes the callback and replaces the global
+/// variables before the scanner becomes idle, and between scans the raw
+/// pointers either point to the scanner's own pinned store or are null. The
+/// reference-counted values that remain are owned exclusively by the scanner,
+/// so they move to the other thread all together.
+struct IdleScanner<'r>(Scanner<'r>);
+
+unsafe impl Send for IdleScanner<'_> {}
@@ -0,0 +1,104 @@
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::os::unix::fs::FileExt;
+use std::sync::atomic::Ordering;
+
+use crate::scanner::blocks::Blocks;
+use crate::scanner::{ScanError, ScanResults, Scanner};
+
+/// Size of the chunks in which memory regions are read.
+const CHUNK_SIZE: usize = 1 << 20;
+
+impl<'r> Scanner<'r> {
+    /// Scans the memory of a running process.
+    ///
+    /// The readable memory regions listed in `/proc/<pid>/maps` are read from
+    /// `/proc/<pid>/mem` and scanned as blocks, in the same way that
+    /// [`crate::BlockScanner`] does. This means that the restrictions
+    /// described in [`crate::BlockScanner`] apply here too, with the
+    /// difference that `filesize` is always 0. Module outputs set with
+    /// [`Scanner::set_module_output`] are used as in any other scan.
+    ///
+    /// Match offsets are virtual addresses in the address space of the
+    /// scanned process. Regions that can't be read, like the ones that are
+    /// listed as readable but are not backed by actual memory, are skipped.
+    ///
+    /// The current process must have permission to trace the scanned
+    /// process, otherwise [`ScanError::ProcError`] is returned.
+    pub fn scan_proc(
+        &mut self,
+        pid: u32,
+    ) -> Result<ScanResults<'_, 'r>, ScanError> {
+        let proc_error = |err| ScanError::ProcError { pid, source: err };
+
+        let maps = fs::File::open(format!("/proc/{}/maps", pid))
+            .map_err(proc_error)?;
+
+        let mem = fs::File::open(format!("/proc/{}/mem", pid))
+            .map_err(proc_error)?;
+
+        let mut regions = Vec::new();
+
+        for line in BufReader::new(maps).lines() {
+            let line = line.map_err(proc_error)?;
+            if let Some(region) = parse_maps_line(line.as_str()) {
+                regions.push(region);
+            }
+        }
+
+        self.clear_matches();
+        self.set_deadline();
+
+        let mut blocks = Blocks::new();
+        let mut buffer = vec![0; CHUNK_SIZE];
+
+        for (start, end) in regions {
+            let mut addr = start;
+            while addr < end {
+                let len = (end - addr).min(CHUNK_SIZE as u64) as usize;
+                // Regions that can't be read are skipped entirely.
+                let n = match mem.read_at(&mut buffer[..len], addr) {
+                    Ok(n) if n > 0 => n,
+                    _ => break,
+                };
+                if let Err(err) =
+                    blocks.scan(self, addr as usize, &buffer[..n])
+                {
+                    // The scan ends here, a cancellation requested from now
+                    // on applies to the next scan, and module outputs set
+                    // for this scan are discarded.
+                    let ctx = self.wasm_store.data_mut();
+                    ctx.cancelled.store(false, Ordering::Relaxed);
+                    ctx.user_provided_module_outputs.clear();
+                    return Err(err);
+                }
+                addr += n as u64;
+            }
+        }
+
+        self.finish_blocks(0)
+    }
+}
+
+/// Parses a line from `/proc/<pid>/maps`, returning the start and end
+/// addresses of the memory region if it is readable.
+///
+/// Lines have the following format:
+///
+/// ```text
+/// 7f2b5c1e4000-7f2b5c1e6000 r--p 00000000 fd:01 1180050  /usr/lib/ld.so
+/// ```
+fn parse_maps_line(line: &str) -> Option<(u64, u64)> {
+    let mut fields = line.split_ascii_whitespace();
+    let (start, end) = fields.next()?.split_once('-')?;
+    let perms = fields.next()?;
+
+    if !perms.starts_with('r') {
+        return None;
+    }
+
+    let start = u64::from_str_radix(start, 16).ok()?;
+    let end = u64::from_str_radix(end, 16).ok()?;
+
+    Some((start, end))
+}
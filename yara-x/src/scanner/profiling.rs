@@ -0,0 +1,76 @@
+use std::time::Duration;
+
+use crate::scanner::Scanner;
+
+/// Profiling information about a rule.
+///
+/// The information is cumulative, it includes all the scans performed by the
+/// scanner since it was created, or since the last call to
+/// [`Scanner::clear_profiling_data`].
+#[derive(Clone, Debug)]
+pub struct ProfilingData<'r> {
+    /// Namespace of the rule.
+    pub namespace: &'r str,
+    /// Name of the rule.
+    pub rule: &'r str,
+    /// Time spent evaluating the rule's condition, not including the time
+    /// spent in the pattern search phase.
+    pub condition_exec_time: Duration,
+    /// Time spent verifying the atom matches for the patterns declared by
+    /// the rule. This is the sum of the verification times of the patterns
+    /// in [`ProfilingData::patterns`].
+    pub pattern_matching_time: Duration,
+    /// Profiling information about each of the patterns declared by the
+    /// rule.
+    pub patterns: Vec<PatternProfilingData<'r>>,
+}
+
+impl<'r> ProfilingData<'r> {
+    /// Total time spent in the rule, including both the condition
+    /// evaluation and the verification of its patterns.
+    pub fn total_time(&self) -> Duration {
+        self.condition_exec_time + self.pattern_matching_time
+    }
+
+    /// Total number of atom matches for the patterns declared by the rule.
+    pub fn atom_matches(&self) -> u64 {
+        self.patterns.iter().map(|p| p.atom_matches).sum()
+    }
+}
+
+/// Profiling information about a pattern.
+#[derive(Clone, Debug)]
+pub struct PatternProfilingData<'r> {
+    /// Pattern identifier (e.g: `$a`)
+    pub identifier: &'r str,
+    /// Number of times that some atom extracted from the pattern was found
+    /// in the scanned data. Each atom match must be verified in order to
+    /// determine if the pattern actually matches, patterns with a large
+    /// number of atom matches are usually slow.
+    pub atom_matches: u64,
+    /// Time spent verifying the atom matches.
+    pub verification_time: Duration,
+}
+
+impl<'r> Scanner<'r> {
+    /// Returns profiling information about the `n` slowest rules.
+    ///
+    /// Rules are sorted by [`ProfilingData::total_time`], in descending
+    /// order. The information is accumulated across all the scans performed
+    /// by this scanner, use [`Scanner::clear_profiling_data`] for resetting
+    /// it.
+    ///
+    /// Notice that profiling has a noticeable impact on performance, this
+    /// function is available only when the `rules-profiling` feature is
+    /// enabled.
+    pub fn slowest_rules(&self, n: usize) -> Vec<ProfilingData<'r>> {
+        let mut rules = self.wasm_store.data().most_expensive_rules();
+        rules.truncate(n);
+        rules
+    }
+
+    /// Resets the profiling information accumulated so far.
+    pub fn clear_profiling_data(&mut self) {
+        self.wasm_store.data_mut().clear_profiling_data();
+    }
+}
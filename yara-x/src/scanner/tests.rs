@@ -1,3 +1,6 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
 use pretty_assertions::assert_eq;
 use protobuf::MessageDyn;
 
@@ -210,6 +213,73 @@ fn module_outputs() {
     assert!(outputs.next().is_none());
 }
 
+#[cfg(all(feature = "test_proto2-module", feature = "test_proto3-module"))]
+#[test]
+fn set_module_output() {
+    use protobuf::Message;
+
+    use crate::modules::protos::test_proto2::TestProto2;
+    use crate::modules::protos::test_proto3::TestProto3;
+
+    let rules = crate::compile(
+        r#"
+        import "test_proto2"
+        rule test {
+            condition:
+                test_proto2.file_size == 100
+        }
+        "#,
+    )
+    .unwrap();
+
+    let mut scanner = Scanner::new(&rules);
+    let scan_results = scanner.scan(b"").unwrap();
+
+    assert_eq!(scan_results.matching_rules().len(), 0);
+
+    let mut output: TestProto2 = <dyn MessageDyn>::downcast_ref::<TestProto2>(
+        scan_results.module_output("test_proto2").unwrap(),
+    )
+    .unwrap()
+    .clone();
+
+    output.set_file_size(100);
+
+    // The output provided by the user replaces the one produced by the
+    // module.
+    scanner
+        .set_module_output("test_proto2", Box::new(output.clone()))
+        .unwrap();
+    assert_eq!(scanner.scan(b"").unwrap().matching_rules().len(), 1);
+
+    // The output is used only in one scan.
+    assert_eq!(scanner.scan(b"").unwrap().matching_rules().len(), 0);
+
+    scanner
+        .set_module_output_raw(
+            "test_proto2",
+            output.write_to_bytes().unwrap().as_slice(),
+        )
+        .unwrap();
+
+    assert_eq!(scanner.scan(b"").unwrap().matching_rules().len(), 1);
+
+    assert!(matches!(
+        scanner.set_module_output("foo", Box::new(output)),
+        Err(scanner::ScanError::UnknownModule { .. })
+    ));
+
+    assert!(matches!(
+        scanner.set_module_output("test_proto2", Box::new(TestProto3::new())),
+        Err(scanner::ScanError::InvalidModuleOutput { .. })
+    ));
+
+    assert!(matches!(
+        scanner.set_module_output_raw("test_proto2", b"\xff\xff"),
+        Err(scanner::ScanError::InvalidModuleOutput { .. })
+    ));
+}
+
 #[test]
 fn variables_1() {
     let mut compiler = crate::Compiler::new();
@@ -468,3 +538,827 @@ fn max_matches_per_pattern() {
 
     assert_eq!(matches.next(), None);
 }
+
+#[test]
+fn tags_and_metadata() {
+    let rules = crate::compile(
+        r#"
+        rule test : foo bar {
+            meta:
+                bool = true
+                int = 1
+                float = 2.5
+                str = "foo"
+                escaped_str = "foo\tbar"
+                bytes = "\xFF\xFE"
+            condition:
+                true
+        }
+        "#,
+    )
+    .unwrap();
+
+    // Tags and metadata must survive serialization.
+    let rules = crate::Rules::deserialize(rules.serialize().unwrap()).unwrap();
+
+    let mut scanner = Scanner::new(&rules);
+    let scan_results = scanner.scan(&[]).expect("scan should not fail");
+    let rule = scan_results.matching_rules().next().unwrap();
+
+    assert_eq!(rule.tags().collect::<Vec<_>>(), ["foo", "bar"]);
+
+    assert_eq!(
+        rule.metadata().collect::<Vec<_>>(),
+        [
+            ("bool", scanner::MetaValue::Bool(true)),
+            ("int", scanner::MetaValue::Integer(1)),
+            ("float", scanner::MetaValue::Float(2.5)),
+            ("str", scanner::MetaValue::String("foo")),
+            ("escaped_str", scanner::MetaValue::String("foo\tbar")),
+            ("bytes", scanner::MetaValue::Bytes(bstr::BStr::new(b"\xFF\xFE"))),
+        ]
+    );
+}
+
+#[test]
+fn rule_filter() {
+    let mut compiler = crate::Compiler::new();
+
+    compiler
+        .add_source(
+            r#"
+        rule dependency {
+            strings:
+                $a = "foo"
+            condition:
+                $a
+        }
+
+        rule test_1 : windows {
+            condition:
+                dependency
+        }
+
+        rule test_2 : linux {
+            strings:
+                $b = "bar"
+            condition:
+                $b
+        }
+
+        rule other {
+            strings:
+                $c = "baz"
+            condition:
+                $c
+        }
+        "#,
+        )
+        .unwrap()
+        .new_namespace("other")
+        .add_source(
+            r#"
+        global rule global_false {
+            condition:
+                false
+        }
+
+        rule test_3 : linux {
+            condition:
+                true
+        }
+        "#,
+        )
+        .unwrap();
+
+    // The filter must work with deserialized rules too.
+    let rules =
+        crate::Rules::deserialize(compiler.build().serialize().unwrap())
+            .unwrap();
+
+    let mut scanner = Scanner::new(&rules);
+
+    fn names<'r>(
+        rules: impl Iterator<Item = scanner::Rule<'_, 'r>>,
+    ) -> Vec<&'r str> {
+        let mut names = rules.map(|rule| rule.name()).collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    // `dependency` is evaluated because `test_1` depends on it, but it is
+    // not reported.
+    scanner.rule_filter(crate::RuleFilter::new().tag("windows"));
+
+    let scan_results = scanner.scan(b"foobarbaz").unwrap();
+
+    assert_eq!(names(scan_results.matching_rules()), ["test_1"]);
+    assert_eq!(scan_results.non_matching_rules().len(), 0);
+
+    // `test_3` is not matching because the global rule in its namespace is
+    // still evaluated. Patterns used only by `dependency` and `other` don't
+    // produce matches.
+    scanner.rule_filter(crate::RuleFilter::new().tag("linux"));
+
+    let scan_results = scanner.scan(b"foobarbaz").unwrap();
+
+    assert_eq!(names(scan_results.matching_rules()), ["test_2"]);
+    assert_eq!(scan_results.non_matching_rules().len(), 1);
+    assert_eq!(names(scan_results.non_matching_rules()), ["test_3"]);
+
+    assert_eq!(
+        scanner
+            .wasm_store
+            .data()
+            .pattern_matches
+            .values()
+            .map(|matches| matches.len())
+            .sum::<usize>(),
+        1
+    );
+
+    scanner.rule_filter(
+        crate::RuleFilter::new().namespace("default").name("test_*"),
+    );
+
+    let scan_results = scanner.scan(b"foobarbaz").unwrap();
+
+    assert_eq!(names(scan_results.matching_rules()), ["test_1", "test_2"]);
+
+    // An empty filter selects all the rules again.
+    scanner.rule_filter(crate::RuleFilter::new());
+
+    let scan_results = scanner.scan(b"foobarbaz").unwrap();
+
+    assert_eq!(
+        names(scan_results.matching_rules()),
+        ["dependency", "other", "test_1", "test_2"]
+    );
+
+    assert_eq!(
+        names(scan_results.non_matching_rules()),
+        ["global_false", "test_3"]
+    );
+}
+
+#[test]
+fn block_scanner() {
+    let rules = crate::compile(
+        r#"
+        rule test_1 {
+            strings:
+                $a = "foobar"
+            condition:
+                $a at 3 and filesize == 20
+        }
+
+        rule test_2 {
+            strings:
+                $b = "baz"
+            condition:
+                #b == 2
+        }
+
+        rule test_3 {
+            condition:
+                not defined uint8(0)
+        }
+        "#,
+    )
+    .unwrap();
+
+    let mut scanner = scanner::BlockScanner::new(&rules);
+
+    // `foobar` spans the first two blocks, and `baz` appears once in the
+    // second block and once in a block that is not contiguous with the
+    // previous one.
+    scanner.scan(0, b"...foo").unwrap();
+    scanner.scan(6, b"bar.baz").unwrap();
+    scanner.scan(15, b"..baz").unwrap();
+
+    let scan_results = scanner.finish().unwrap();
+
+    assert_eq!(scan_results.matching_rules().len(), 3);
+
+    let mut matching_rules = scan_results.matching_rules();
+
+    let mut matches =
+        matching_rules.next().unwrap().patterns().next().unwrap().matches();
+
+    // The matched data is not available in block scanning.
+    assert_eq!(
+        matches.next(),
+        Some(scanner::Match { range: (3..9), data: b"", xor_key: None })
+    );
+
+    let mut matches =
+        matching_rules.next().unwrap().patterns().next().unwrap().matches();
+
+    assert_eq!(
+        matches.next(),
+        Some(scanner::Match { range: (10..13), data: b"", xor_key: None })
+    );
+
+    assert_eq!(
+        matches.next(),
+        Some(scanner::Match { range: (17..20), data: b"", xor_key: None })
+    );
+
+    // With an overlap of 2 bytes `foobar` is not found across blocks.
+    scanner.overlap(2);
+    scanner.scan(0, b"...foo").unwrap();
+    scanner.scan(6, b"bar.baz").unwrap();
+    scanner.scan(15, b"..baz").unwrap();
+
+    let scan_results = scanner.finish().unwrap();
+
+    assert_eq!(scan_results.matching_rules().len(), 2);
+    assert_eq!(scan_results.non_matching_rules().len(), 1);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn scan_proc() {
+    let rules = crate::compile(
+        r#"
+        rule test {
+            strings:
+                $a = "yara-x-scan-proc-test"
+            condition:
+                $a and filesize == 0
+        }
+        "#,
+    )
+    .unwrap();
+
+    // The pattern is in the environment of the child process, which lives
+    // in its stack.
+    let mut child = std::process::Command::new("sleep")
+        .arg("10")
+        .env("YARA_X_TEST", "yara-x-scan-proc-test")
+        .spawn()
+        .unwrap();
+
+    let mut scanner = Scanner::new(&rules);
+    let scan_results = scanner.scan_proc(child.id());
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    let scan_results = scan_results.unwrap();
+
+    assert_eq!(scan_results.matching_rules().len(), 1);
+
+    let addr = scan_results
+        .matching_rules()
+        .next()
+        .unwrap()
+        .patterns()
+        .next()
+        .unwrap()
+        .matches()
+        .next()
+        .unwrap()
+        .range
+        .start;
+
+    // Match offsets are virtual addresses, which are much larger than the
+    // scanned data.
+    assert!(addr > 0x10000);
+
+    // The process doesn't exist anymore.
+    assert!(matches!(
+        scanner.scan_proc(child.id()),
+        Err(scanner::ScanError::ProcError { .. })
+    ));
+}
+
+#[cfg(all(target_os = "linux", feature = "test_proto2-module"))]
+#[test]
+fn scan_proc_with_module_output() {
+    use crate::modules::protos::test_proto2::TestProto2;
+
+    let rules = crate::compile(
+        r#"
+        import "test_proto2"
+        rule test {
+            condition:
+                test_proto2.file_size == 100
+        }
+        "#,
+    )
+    .unwrap();
+
+    let mut child =
+        std::process::Command::new("sleep").arg("10").spawn().unwrap();
+
+    let mut output = TestProto2::new();
+    output.set_file_size(100);
+
+    let mut scanner = Scanner::new(&rules);
+
+    scanner.set_module_output("test_proto2", Box::new(output)).unwrap();
+
+    let matching_rules =
+        scanner.scan_proc(child.id()).map(|r| r.matching_rules().len());
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    // The output provided by the user is used while scanning the process.
+    assert_eq!(matching_rules.unwrap(), 1);
+
+    // And it is not used in the next scan.
+    assert_eq!(scanner.scan(b"").unwrap().matching_rules().len(), 0);
+}
+
+#[cfg(feature = "test_proto2-module")]
+#[test]
+fn callback() {
+    let rules = crate::compile(
+        r#"
+        import "test_proto2"
+
+        global rule test_1 {
+            condition:
+                true
+        }
+
+        rule test_2 {
+            strings:
+                $a = "foo"
+            condition:
+                $a
+        }
+
+        rule test_3 {
+            condition:
+                false
+        }
+        "#,
+    )
+    .unwrap();
+
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let mut scanner = Scanner::new(&rules);
+
+    let events_clone = events.clone();
+
+    scanner.max_matches_per_pattern(1).callback(move |event| {
+        let event = match event {
+            scanner::ScanEvent::RuleMatch(rule) => {
+                format!("match {}", rule.name())
+            }
+            scanner::ScanEvent::RuleNoMatch(rule) => {
+                format!("no match {}", rule.name())
+            }
+            scanner::ScanEvent::ModuleImport { name, .. } => {
+                format!("import {}", name)
+            }
+            scanner::ScanEvent::TooManyMatches { rule, pattern } => {
+                format!("too many {} {}", rule.name(), pattern.identifier())
+            }
+        };
+        events_clone.borrow_mut().push(event);
+        scanner::CallbackAction::Continue
+    });
+
+    let scan_results = scanner.scan(b"foofoo").unwrap();
+
+    assert_eq!(scan_results.matching_rules().len(), 2);
+
+    // Global rules are notified after evaluating all the rules.
+    assert_eq!(
+        events.borrow().as_slice(),
+        [
+            "import test_proto2",
+            "too many test_2 $a",
+            "match test_2",
+            "match test_1",
+            "no match test_3"
+        ]
+    );
+
+    // The limit of matches per pattern must be reset in every scan.
+    events.borrow_mut().clear();
+    scanner.scan(b"foofoo").unwrap();
+
+    assert_eq!(events.borrow().len(), 5);
+}
+
+#[test]
+fn callback_abort() {
+    let rules = crate::compile(
+        r#"
+        rule test_1 {
+            condition:
+                true
+        }
+
+        rule test_2 {
+            condition:
+                true
+        }
+
+        rule test_3 {
+            condition:
+                for any i in (0..10) : (i == 9)
+        }
+        "#,
+    )
+    .unwrap();
+
+    let mut num_events = 0;
+    let mut scanner = Scanner::new(&rules);
+
+    scanner.callback(|_| {
+        num_events += 1;
+        scanner::CallbackAction::Abort
+    });
+
+    let scan_results = scanner.scan(b"").unwrap();
+
+    // The scan is aborted after the first match, rules that were not
+    // evaluated are reported as non-matching.
+    assert_eq!(scan_results.matching_rules().len(), 1);
+    assert_eq!(scan_results.matching_rules().next().unwrap().name(), "test_1");
+    assert_eq!(scan_results.non_matching_rules().len(), 2);
+
+    drop(scan_results);
+    drop(scanner);
+
+    assert_eq!(num_events, 1);
+}
+
+#[test]
+fn cancel_handle() {
+    let rules = crate::compile(
+        r#"
+        rule test {
+            condition:
+                for all i in (0..10000000000) : (i >= 0)
+        }
+        "#,
+    )
+    .unwrap();
+
+    let mut scanner = Scanner::new(&rules);
+    let cancel_handle = scanner.cancel_handle();
+
+    // Cancel while the WASM code is being executed.
+    std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        cancel_handle.cancel();
+    });
+
+    assert!(matches!(scanner.scan(b""), Err(scanner::ScanError::Cancelled)));
+
+    let rules = crate::compile(
+        r#"
+        rule test {
+            strings:
+                $a = { 00 00 00 00 }
+            condition:
+                $a
+        }
+        "#,
+    )
+    .unwrap();
+
+    let mut scanner = Scanner::new(&rules);
+    let cancel_handle = scanner.cancel_handle();
+
+    // Cancel while searching for patterns.
+    std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        cancel_handle.cancel();
+    });
+
+    let data = vec![0_u8; 256 * 1024 * 1024];
+
+    assert!(matches!(
+        scanner.scan(data.as_slice()),
+        Err(scanner::ScanError::Cancelled)
+    ));
+
+    // The next scan is not affected by the cancellation.
+    assert!(scanner.scan(b"\x00\x00\x00\x00").is_ok());
+
+    // A cancellation requested before the scan starts is not lost, it
+    // applies to the next scan.
+    scanner.cancel_handle().cancel();
+
+    assert!(matches!(
+        scanner.scan(b"\x00\x00\x00\x00"),
+        Err(scanner::ScanError::Cancelled)
+    ));
+
+    assert!(scanner.scan(b"\x00\x00\x00\x00").is_ok());
+}
+
+#[test]
+fn matches_across_search_chunks() {
+    let rules = crate::compile(
+        r#"
+        rule test {
+            strings:
+                $a = "foobar"
+            condition:
+                #a == 3
+        }
+        "#,
+    )
+    .unwrap();
+
+    // The data is searched in chunks of 1MB, put matches before, across,
+    // and after the boundary between the first two chunks.
+    let mut data = vec![0_u8; 3 * 1024 * 1024];
+    let boundary = 1024 * 1024;
+
+    for offset in [boundary - 12, boundary - 3, boundary + 3] {
+        data[offset..offset + 6].copy_from_slice(b"foobar");
+    }
+
+    let mut scanner = Scanner::new(&rules);
+    let scan_results = scanner.scan(data.as_slice()).unwrap();
+
+    assert_eq!(scan_results.matching_rules().len(), 1);
+
+    let offsets: Vec<usize> = scan_results
+        .matching_rules()
+        .next()
+        .unwrap()
+        .patterns()
+        .next()
+        .unwrap()
+        .matches()
+        .map(|m| m.range.start)
+        .collect();
+
+    assert_eq!(offsets, vec![boundary - 12, boundary - 3, boundary + 3]);
+}
+
+#[test]
+fn scanner_pool() {
+    let mut compiler = crate::Compiler::new();
+
+    compiler
+        .define_global("bar", "bar")
+        .unwrap()
+        .add_source(
+            r#"
+        rule test {
+            strings:
+                $a = "foo"
+            condition:
+                $a and bar == "baz"
+        }
+        "#,
+        )
+        .unwrap();
+
+    let rules = compiler.build();
+    let mut pool = scanner::ScannerPool::new(&rules);
+
+    pool.set_global("bar", "baz").unwrap();
+
+    assert_eq!(
+        pool.set_global("bar", 1).err().unwrap(),
+        VariableError::InvalidType {
+            variable: "bar".to_string(),
+            expected_type: "string".to_string(),
+            actual_type: "integer".to_string(),
+        }
+    );
+
+    // Changes made to a borrowed scanner are reverted when the scanner goes
+    // back to the pool.
+    {
+        let mut scanner = pool.get();
+        scanner.set_global("bar", "qux").unwrap();
+        assert_eq!(scanner.scan(b"foo").unwrap().matching_rules().len(), 0);
+    }
+
+    assert_eq!(pool.get().scan(b"foo").unwrap().matching_rules().len(), 1);
+
+    let dir = std::env::temp_dir()
+        .join(format!("yara-x-scanner-pool-{}", std::process::id()));
+
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let paths = (0..16)
+        .map(|i| {
+            let path = dir.join(i.to_string());
+            std::fs::write(&path, if i % 2 == 0 { "foo" } else { "bar" })
+                .unwrap();
+            path
+        })
+        .collect::<Vec<_>>();
+
+    let matching_files = std::sync::Mutex::new(Vec::new());
+
+    pool.scan_files(paths.iter(), 4, |path, results| {
+        if results.unwrap().matching_rules().len() > 0 {
+            matching_files.lock().unwrap().push(path.to_path_buf());
+        }
+    });
+
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let mut matching_files = matching_files.into_inner().unwrap();
+    matching_files.sort();
+
+    let mut expected = paths.into_iter().step_by(2).collect::<Vec<_>>();
+    expected.sort();
+
+    assert_eq!(matching_files, expected);
+}
+
+#[cfg(feature = "rules-profiling")]
+#[test]
+fn slowest_rules() {
+    let rules = crate::compile(
+        r#"
+        rule foo {
+          strings:
+            $a = "foo"
+            $b = "bar"
+          condition:
+            $a and $b
+        }
+        rule bar {
+          condition:
+            true
+        }
+        "#,
+    )
+    .unwrap();
+
+    let mut scanner = Scanner::new(&rules);
+
+    scanner.scan(b"foo foo bar").unwrap();
+    scanner.scan(b"foo").unwrap();
+
+    let profiling_data = scanner.slowest_rules(usize::MAX);
+
+    assert_eq!(profiling_data.len(), 2);
+
+    let foo = profiling_data.iter().find(|r| r.rule == "foo").unwrap();
+
+    assert_eq!(foo.namespace, "default");
+    assert_eq!(foo.patterns.len(), 2);
+    assert_eq!(foo.patterns[0].identifier, "$a");
+    assert_eq!(foo.patterns[0].atom_matches, 3);
+    assert_eq!(foo.patterns[1].identifier, "$b");
+    assert_eq!(foo.patterns[1].atom_matches, 1);
+    assert_eq!(foo.atom_matches(), 4);
+
+    assert_eq!(scanner.slowest_rules(1).len(), 1);
+
+    scanner.clear_profiling_data();
+
+    for r in scanner.slowest_rules(usize::MAX) {
+        assert_eq!(r.total_time(), std::time::Duration::default());
+        assert_eq!(r.atom_matches(), 0);
+    }
+}
+
+#[test]
+fn scan_reader() {
+    let rules = crate::compile(
+        r#"
+        rule test {
+          strings:
+            $a = "foo"
+          condition:
+            $a and filesize == 6
+        }
+        "#,
+    )
+    .unwrap();
+
+    let mut scanner = Scanner::new(&rules);
+
+    assert_eq!(
+        scanner.scan_reader(&b"foobar"[..]).unwrap().matching_rules().len(),
+        1
+    );
+
+    // The data beyond the maximum size is not scanned.
+    scanner.max_reader_size(6);
+
+    assert_eq!(
+        scanner.scan_reader(&b"barfoo..."[..]).unwrap().matching_rules().len(),
+        1
+    );
+
+    scanner.max_reader_size(5);
+
+    assert_eq!(
+        scanner.scan_reader(&b"barfoo"[..]).unwrap().matching_rules().len(),
+        0
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn scan_fd() {
+    use std::io::Write;
+
+    let rules = crate::compile(
+        r#"
+        rule test {
+          strings:
+            $a = "foo"
+          condition:
+            $a and filesize == 6
+        }
+        "#,
+    )
+    .unwrap();
+
+    let mut scanner = Scanner::new(&rules);
+
+    // A regular file, which is mapped into memory.
+    let path = std::env::temp_dir()
+        .join(format!("yara-x-scan-fd-{}", std::process::id()));
+
+    std::fs::write(&path, b"barfoo").unwrap();
+
+    let file = std::fs::File::open(&path).unwrap();
+
+    assert_eq!(scanner.scan_fd(&file).unwrap().matching_rules().len(), 1);
+
+    drop(file);
+    std::fs::remove_file(&path).unwrap();
+
+    // A socket, which is read.
+    let (mut writer, reader) = std::os::unix::net::UnixStream::pair().unwrap();
+
+    writer.write_all(b"foobar").unwrap();
+    drop(writer);
+
+    assert_eq!(scanner.scan_fd(&reader).unwrap().matching_rules().len(), 1);
+}
+
+#[test]
+fn limits() {
+    let rules = crate::compile(
+        r#"
+        rule test {
+          strings:
+            $a = "foo"
+            $b = "bar"
+          condition:
+            $a or $b
+        }
+        "#,
+    )
+    .unwrap();
+
+    let mut scanner = Scanner::new(&rules);
+
+    scanner.max_total_matches(3);
+
+    assert_eq!(
+        scanner.scan(b"foo bar foo").unwrap().matching_rules().len(),
+        1
+    );
+
+    assert!(matches!(
+        scanner.scan(b"foo bar foo bar"),
+        Err(scanner::ScanError::LimitExceeded {
+            kind: scanner::LimitKind::TotalMatches
+        })
+    ));
+}
+
+#[cfg(feature = "test_proto2-module")]
+#[test]
+fn module_output_size_limit() {
+    let rules = crate::compile(
+        r#"
+        import "test_proto2"
+        rule test {
+          condition:
+            test_proto2.int32_one == 1
+        }
+        "#,
+    )
+    .unwrap();
+
+    let mut scanner = Scanner::new(&rules);
+
+    scanner.max_module_output_size(1_000_000);
+
+    assert_eq!(scanner.scan(b"").unwrap().matching_rules().len(), 1);
+
+    scanner.max_module_output_size(1);
+
+    assert!(matches!(
+        scanner.scan(b""),
+        Err(scanner::ScanError::LimitExceeded {
+            kind: scanner::LimitKind::ModuleOutputSize
+        })
+    ));
+}
@@ -62,6 +62,12 @@ where
     pub fn get(&self, id: T) -> Option<&str> {
         self.pool.get(Symbol::from(id.into()))
     }
+
+    /// Returns an iterator that yields the strings in the pool, together
+    /// with their IDs, in the same order they were interned.
+    pub fn iter(&self) -> impl Iterator<Item = (T, &str)> {
+        self.pool.strings().enumerate().map(|(id, s)| (T::from(id as u32), s))
+    }
 }
 
 impl<T> Serialize for StringPool<T>
@@ -203,6 +209,15 @@ where
     pub fn size(&self) -> usize {
         self.size
     }
+
+    /// Returns an iterator that yields the strings in the pool, together
+    /// with their IDs, in the same order they were interned.
+    pub fn iter(&self) -> impl Iterator<Item = (T, &[u8])> {
+        self.pool
+            .bytestrings()
+            .enumerate()
+            .map(|(id, s)| (T::from(id as u32), s))
+    }
 }
 
 impl<T> Serialize for BStringPool<T>
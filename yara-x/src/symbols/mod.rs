@@ -35,6 +35,9 @@ pub(crate) enum SymbolKind {
     Rule(RuleId),
     /// The symbol refers to a function.
     Func(Rc<Func>),
+    /// The symbol refers to a module that was ignored with
+    /// [`crate::Compiler::ignore_module`].
+    IgnoredModule,
 }
 
 impl Symbol {
@@ -472,6 +472,61 @@ fn for_in() {
     condition_true!(r#"for none x in (1.0, 2.0, 3.0) : (x > 4.0)"#);
 }
 
+#[test]
+fn with() {
+    condition_true!("with a = 1 : ( a == 1 )");
+    condition_false!("with a = 1 : ( a == 2 )");
+    condition_true!("with a = 1 + 1, b = a * 2 : ( a == 2 and b == 4 )");
+    condition_true!(r#"with s = "foo" : ( s contains "oo" )"#);
+    condition_true!("with f = 1.5 : ( f > 1.0 )");
+    condition_true!("with a = filesize : ( a == 0 )");
+
+    condition_true!(
+        "for all i in (0..10) : (
+            with j = i * 2 : ( j == i + i )
+        )"
+    );
+
+    condition_true!(
+        "with n = 3 : (
+            for any i in (0..n) : ( with j = i + n : ( j == 6 ) )
+        )"
+    );
+}
+
+#[cfg(feature = "console-module")]
+#[test]
+fn with_evaluated_once() {
+    let rules = crate::compile(
+        r#"
+        import "console"
+        rule test {
+          condition:
+            with a = console.log("a") : ( a and a and a ) and
+            for all i in (0..1) : (
+              with b = console.log("b: ", i) : ( b and b )
+            )
+        }
+        "#,
+    )
+    .unwrap();
+
+    let messages = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    let mut scanner = crate::scanner::Scanner::new(&rules);
+
+    scanner.console_log({
+        let messages = messages.clone();
+        move |message| messages.borrow_mut().push(message)
+    });
+
+    assert_eq!(scanner.scan(b"").unwrap().matching_rules().len(), 1);
+
+    // Each expression is evaluated once, no matter how many times the
+    // variable is used, but the expression inside the loop is evaluated
+    // once per iteration.
+    assert_eq!(messages.borrow().as_slice(), ["a", "b: 0", "b: 1"]);
+}
+
 #[test]
 fn text_patterns() {
     pattern_true!(r#""issi""#, b"mississippi");
@@ -2913,6 +2968,51 @@ fn rule_reuse_2() {
     );
 }
 
+#[test]
+fn rule_set() {
+    let rules = crate::compile(
+        r#"
+        rule apt29_a {
+          condition:
+            true
+        }
+        rule apt29_b {
+          condition:
+            false
+        }
+        rule apt29_c {
+          condition:
+            true
+        }
+        rule meta_1 {
+          condition:
+            2 of (apt29_*)
+        }
+        rule meta_2 {
+          condition:
+            all of (apt29_*)
+        }
+        rule meta_3 {
+          condition:
+            any of (apt29_b, meta_*)
+        }
+        "#,
+    )
+    .unwrap();
+
+    let mut scanner = crate::scanner::Scanner::new(&rules);
+
+    let scan_results = scanner.scan(&[]).expect("scan should not fail");
+
+    let mut matching_rules: Vec<&str> =
+        scan_results.matching_rules().map(|r| r.name()).collect();
+
+    // The order of matching rules is arbitrary.
+    matching_rules.sort();
+
+    assert_eq!(matching_rules, vec!["apt29_a", "apt29_c", "meta_1", "meta_3"]);
+}
+
 #[test]
 fn test_defined_1() {
     condition_true!(r#"defined 1"#);
@@ -46,6 +46,10 @@ use crate::types::{TypeValue, Value};
 /// foo() -> Option<f32>           ->  foo@@fu
 /// foo() -> Option<(f64,f64)>     ->  foo@@ffu
 /// ```
+///
+/// Methods, which are functions that act on a structure, have the type of
+/// the structure as a prefix in their names, separated from the function
+/// name by `::` (e.g: `my_module.MyStruct::foo@i@b`).
 #[derive(Serialize, Deserialize)]
 pub struct MangledFnName(String);
 
@@ -97,6 +101,17 @@ impl MangledFnName {
     pub fn result_may_be_undef(&self) -> bool {
         self.0.ends_with('u')
     }
+
+    /// If the function is a method, returns the type of the structure the
+    /// method belongs to.
+    ///
+    /// The mangled names of methods have the format
+    /// `<struct type>::<method name>@<arguments>@<return type>`, for
+    /// instance: `my_module.MyStruct::my_method@i@i`.
+    pub fn method_of(&self) -> Option<&str> {
+        let (name, _) = self.0.split_once('@')?;
+        name.split_once("::").map(|(struct_type, _)| struct_type)
+    }
 }
 
 impl<S> From<S> for MangledFnName
@@ -224,6 +239,12 @@ mod test {
 
         assert!(!MangledFnName::from("foo@i@i").result_may_be_undef());
         assert!(MangledFnName::from("foo@i@iu").result_may_be_undef());
+
+        assert_eq!(MangledFnName::from("foo.bar@i@i").method_of(), None);
+        assert_eq!(
+            MangledFnName::from("foo.Bar::baz@i@i").method_of(),
+            Some("foo.Bar")
+        );
     }
 
     #[test]
@@ -17,7 +17,7 @@ use yara_x_proto::exts::enum_value;
 use yara_x_proto::exts::field_options;
 use yara_x_proto::exts::module_options;
 
-use crate::types::{Array, Map, TypeValue, Value};
+use crate::types::{Array, Func, Map, TypeValue, Value};
 
 /// A field in a [`Struct`].
 #[derive(Debug, Serialize, Deserialize)]
@@ -56,6 +56,12 @@ pub struct Struct {
     /// order in which they appear in the .proto source file is
     /// irrelevant.
     fields: IndexMap<String, StructField>,
+    /// For structures derived from a protobuf this contains the full name
+    /// of the protobuf message (e.g: `my_module.MyMessage`). For other
+    /// structures this is `None`. This is used only while compiling rules
+    /// for adding methods to the structures, so it's not serialized.
+    #[serde(skip)]
+    protobuf_type_name: Option<String>,
 }
 
 impl Default for Struct {
@@ -66,7 +72,7 @@ impl Default for Struct {
 
 impl Struct {
     pub fn new() -> Self {
-        Self { fields: IndexMap::new() }
+        Self { fields: IndexMap::new(), protobuf_type_name: None }
     }
 
     /// Adds a new field to the structure.
@@ -126,6 +132,84 @@ impl Struct {
         }
     }
 
+    /// Adds a method to all the structures of a given type.
+    ///
+    /// `type_name` is the full name of the protobuf message that defines the
+    /// structure (e.g: `my_module.MyMessage`). The method is added as a new
+    /// field of function type to this structure, if its type matches
+    /// `type_name`, and to any other structure of the same type that is
+    /// reachable from this one, including structures stored in arrays and
+    /// maps.
+    ///
+    /// Returns the number of structures where the method was added.
+    ///
+    /// # Panics
+    ///
+    /// If there is some [`Rc`] or [`Weak`] pointer pointing to any of the
+    /// structures that must be modified.
+    pub fn add_method(
+        &mut self,
+        type_name: &str,
+        name: &str,
+        method: Rc<Func>,
+    ) -> usize {
+        let mut count = 0;
+
+        for field in self.fields.values_mut() {
+            match &mut field.type_value {
+                TypeValue::Struct(s) => {
+                    count += Self::rc_get_mut(s, &field.name).add_method(
+                        type_name,
+                        name,
+                        method.clone(),
+                    );
+                }
+                TypeValue::Array(array) => {
+                    if let Array::Structs(structs) =
+                        Self::rc_get_mut(array, &field.name)
+                    {
+                        for s in structs.iter_mut() {
+                            count += Self::rc_get_mut(s, &field.name)
+                                .add_method(type_name, name, method.clone());
+                        }
+                    }
+                }
+                TypeValue::Map(map) => {
+                    let deputy = match Self::rc_get_mut(map, &field.name) {
+                        Map::IntegerKeys { deputy, .. } => deputy,
+                        Map::StringKeys { deputy, .. } => deputy,
+                    };
+                    if let Some(TypeValue::Struct(s)) = deputy {
+                        count += Self::rc_get_mut(s, &field.name).add_method(
+                            type_name,
+                            name,
+                            method.clone(),
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if self.protobuf_type_name.as_deref() == Some(type_name) {
+            if self.add_field(name, TypeValue::Func(method)).is_some() {
+                panic!("duplicate method `{}` in `{}`", name, type_name)
+            }
+            count += 1;
+        }
+
+        count
+    }
+
+    fn rc_get_mut<'a, T>(rc: &'a mut Rc<T>, field_name: &str) -> &'a mut T {
+        Rc::get_mut(rc).unwrap_or_else(|| {
+            panic!(
+                "`add_method` was called while an `Rc` or `Weak` pointer points to field `{}`",
+                field_name
+            )
+        })
+    }
+
     /// Returns true if the structure have a field with the given name.
     #[inline]
     pub fn has_field(&self, name: &str) -> bool {
@@ -369,7 +453,10 @@ impl Struct {
             }
         }
 
-        Self { fields: field_index }
+        Self {
+            fields: field_index,
+            protobuf_type_name: Some(msg_descriptor.full_name().to_owned()),
+        }
     }
 
     /// Returns true if the given message is the YARA module's root message.
@@ -959,7 +1046,7 @@ impl PartialEq for Struct {
 #[cfg(test)]
 mod tests {
     use super::Struct;
-    use crate::types::{Array, TypeValue, Value};
+    use crate::types::{Array, Func, FuncSignature, TypeValue, Value};
     use std::rc::Rc;
 
     #[test]
@@ -1027,4 +1114,47 @@ mod tests {
         // on each structure.
         assert_ne!(a, b);
     }
+
+    #[test]
+    fn add_method() {
+        let new_foo = || {
+            let mut foo = Struct::default();
+            foo.protobuf_type_name = Some("test.Foo".to_owned());
+            foo.add_field("integer", TypeValue::Integer(Value::Unknown));
+            Rc::new(foo)
+        };
+
+        let mut root = Struct::default();
+
+        root.add_field("foo", TypeValue::Struct(new_foo()));
+        root.add_field(
+            "foos",
+            TypeValue::Array(Rc::new(Array::Structs(vec![new_foo()]))),
+        );
+
+        let method = Rc::new(Func::with_signature(FuncSignature::from(
+            "test.Foo::bar@i@b".to_owned(),
+        )));
+
+        assert_eq!(root.add_method("test.Foo", "bar", method.clone()), 2);
+        assert_eq!(root.add_method("test.Baz", "bar", method), 0);
+
+        let foo = root.field_by_name("foo").unwrap().type_value.as_struct();
+
+        assert_eq!(foo.index_of("integer"), 0);
+        assert_eq!(foo.index_of("bar"), 1);
+
+        let foo = root
+            .field_by_name("foos")
+            .unwrap()
+            .type_value
+            .as_array()
+            .deputy()
+            .as_struct();
+
+        assert!(matches!(
+            foo.field_by_name("bar").unwrap().type_value,
+            TypeValue::Func(_)
+        ));
+    }
 }
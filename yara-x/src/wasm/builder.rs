@@ -159,7 +159,9 @@ impl WasmModuleBuilder {
             wasm_exports.insert(fully_qualified_name, func_id);
         }
 
+        global_const!(module, matching_rules_bitmap_base, I32);
         global_const!(module, matching_patterns_bitmap_base, I32);
+        global_const!(module, enabled_rules_bitmap_base, I32);
         global_var!(module, filesize, I64);
         global_var!(module, pattern_search_done, I32);
         global_var!(module, timeout_occurred, I32);
@@ -169,7 +171,9 @@ impl WasmModuleBuilder {
 
         let wasm_symbols = WasmSymbols {
             main_memory,
+            matching_rules_bitmap_base,
             matching_patterns_bitmap_base,
+            enabled_rules_bitmap_base,
             filesize,
             pattern_search_done,
             timeout_occurred,
@@ -194,15 +198,10 @@ impl WasmModuleBuilder {
         );
 
         // The main function receives no arguments and returns an I32.
-        let mut main_func =
-            FunctionBuilder::new(&mut module.types, &[], &[I32]);
-
-        // The first instructions in the main function initialize the global
-        // variables `pattern_search_done` and `timeout_occurred` to 0 (false).
-        main_func.func_body().i32_const(0);
-        main_func.func_body().global_set(pattern_search_done);
-        main_func.func_body().i32_const(0);
-        main_func.func_body().global_set(timeout_occurred);
+        // The global variables `pattern_search_done` and `timeout_occurred`
+        // are initialized by the scanner before calling this function, as
+        // they are shared by all the modules linked together.
+        let main_func = FunctionBuilder::new(&mut module.types, &[], &[I32]);
 
         let namespace_block = namespace_func.dangling_instr_seq(None).id();
         let global_rules_block = namespace_func.dangling_instr_seq(None).id();
@@ -467,38 +466,34 @@ mod tests {
         assert_eq!(
             text,
             r#"(module
-  (func (;122;) (type 1) (result i32)
-    i32.const 0
-    global.set 2
-    i32.const 0
-    global.set 3
-    call 123
-    call 124
+  (func (;159;) (type 1) (result i32)
+    call 160
+    call 161
     global.get 3
   )
-  (func (;123;) (type 0)
+  (func (;160;) (type 0)
     block ;; label = @1
-      call 125
+      call 162
     end
     block ;; label = @1
-      call 126
+      call 163
     end
   )
-  (func (;124;) (type 0)
+  (func (;161;) (type 0)
     block ;; label = @1
-      call 127
+      call 164
     end
   )
-  (func (;125;) (type 0)
+  (func (;162;) (type 0)
     i32.const 4
   )
-  (func (;126;) (type 0)
+  (func (;163;) (type 0)
     i32.const 5
   )
-  (func (;127;) (type 0)
+  (func (;164;) (type 0)
     i32.const 6
   )
-  (export "main" (func 122))
+  (export "main" (func 159))
 )"#
         );
     }
@@ -69,11 +69,13 @@ See the [`lookup_field`] function.
  */
 use std::any::{type_name, TypeId};
 use std::mem;
+use std::rc::Rc;
 
 use bstr::ByteSlice;
 use lazy_static::lazy_static;
 use linkme::distributed_slice;
 use smallvec::{smallvec, SmallVec};
+use thiserror::Error;
 use wasmtime::{
     AsContextMut, Caller, Config, Engine, FuncType, Linker, ValRaw,
 };
@@ -84,7 +86,7 @@ use yara_x_macros::wasm_export;
 use crate::compiler::{LiteralId, PatternId, RegexpId, RuleId};
 use crate::modules::BUILTIN_MODULES;
 use crate::scanner::ScanContext;
-use crate::types::{TypeValue, Value};
+use crate::types::{Struct, TypeValue, Value};
 use crate::wasm::string::{RuntimeString, RuntimeStringWasm};
 use crate::ScanError;
 
@@ -103,7 +105,9 @@ pub(crate) const LOOKUP_INDEXES_END: i32 = LOOKUP_INDEXES_START + 1024;
 
 /// Offset in module's main memory where resides the bitmap that tells if a
 /// rule matches or not. This bitmap contains one bit per rule, if the N-th
-/// bit is set, it indicates that the rule with RuleId = N matched.
+/// bit is set, it indicates that the rule with RuleId = N matched. When the
+/// rules were linked with [`crate::Rules::link`] each WASM module has its
+/// own region in this bitmap, see [`crate::scanner::BitmapsLayout`].
 pub(crate) const MATCHING_RULES_BITMAP_BASE: i32 = LOOKUP_INDEXES_END;
 
 /// Global slice that contains an entry for each function that is callable from
@@ -126,6 +130,10 @@ pub(crate) struct WasmExport {
     /// public, but many other functions callable from WASM are for internal
     /// use only and therefore are not public.
     pub public: bool,
+    /// If the function is a method of some structure, this is the full name
+    /// of the protobuf message that defines the structure's type (e.g:
+    /// `my_module.MyStruct`).
+    pub method_of: Option<&'static str>,
     /// Path of the module where the function resides. This an absolute path
     /// that includes the crate name (e.g: yara_x::modules::test_proto2)
     pub rust_module_path: &'static str,
@@ -137,8 +145,13 @@ impl WasmExport {
     /// Returns the fully qualified name for a #[wasm_export] function.
     ///
     /// The fully qualified name includes not only the function's name, but
-    /// also the module's name (e.g: `my_module.my_struct.my_func@ii@i`)
+    /// also the module's name (e.g: `my_module.my_struct.my_func@ii@i`). For
+    /// methods, the name is prefixed with the type of the structure the
+    /// method belongs to (e.g: `my_module.MyStruct::my_method@i@i`).
     pub fn fully_qualified_mangled_name(&self) -> String {
+        if let Some(method_of) = self.method_of {
+            return format!("{}::{}", method_of, self.mangled_name);
+        }
         for (module_name, module) in BUILTIN_MODULES.iter() {
             if let Some(rust_module_name) = module.rust_module_name {
                 if self.rust_module_path.contains(rust_module_name) {
@@ -228,6 +241,12 @@ impl From<WasmArg> for f32 {
     }
 }
 
+impl From<WasmArg> for bool {
+    fn from(value: WasmArg) -> Self {
+        value.0.get_i32() != 0
+    }
+}
+
 impl From<WasmArg> for RuleId {
     fn from(value: WasmArg) -> Self {
         RuleId::from(value.0.get_i32())
@@ -269,6 +288,12 @@ pub(crate) trait WasmResult {
 
     // Returns the WASM types that conform this result.
     fn types() -> WasmResultArray<wasmtime::ValType>;
+
+    // Returns an error if this result must interrupt the execution of the
+    // WASM code. The error is propagated to the caller as a trap.
+    fn trap(&self) -> Option<anyhow::Error> {
+        None
+    }
 }
 
 impl WasmResult for () {
@@ -359,6 +384,33 @@ where
     }
 }
 
+/// Error returned by functions called from WASM after the scan was aborted
+/// by the callback set with [`crate::Scanner::callback`]. The error
+/// interrupts the execution of the WASM code.
+#[derive(Error, Debug)]
+#[error("scan aborted")]
+pub(crate) struct ScanAborted;
+
+impl<T> WasmResult for Result<T, ScanAborted>
+where
+    T: WasmResult,
+{
+    fn values(&self) -> WasmResultArray<ValRaw> {
+        match self {
+            Ok(value) => value.values(),
+            Err(_) => smallvec![],
+        }
+    }
+
+    fn types() -> WasmResultArray<wasmtime::ValType> {
+        T::types()
+    }
+
+    fn trap(&self) -> Option<anyhow::Error> {
+        self.as_ref().err().map(|_| anyhow::Error::new(ScanAborted))
+    }
+}
+
 impl<T> WasmResult for Option<T>
 where
     T: WasmResult + Default,
@@ -478,6 +530,11 @@ macro_rules! impl_wasm_exported_fn {
                         )*
 
                         let result = (self.target_fn)(caller, $($args),*);
+
+                        if let Some(err) = result.trap() {
+                            return Err(err);
+                        }
+
                         let result = result.values();
 
                         let result_slice = result.as_slice();
@@ -509,11 +566,22 @@ pub(crate) struct WasmSymbols {
     /// The WASM module's main memory.
     pub main_memory: walrus::MemoryId,
 
+    /// Global variable that contains the offset within the module's main
+    /// memory where resides the bitmap that indicates if a rule matches
+    /// or not.
+    pub matching_rules_bitmap_base: walrus::GlobalId,
+
     /// Global variable that contains the offset within the module's main
     /// memory where resides the bitmap that indicates if a pattern matches
     /// or not.
     pub matching_patterns_bitmap_base: walrus::GlobalId,
 
+    /// Global variable that contains the offset within the module's main
+    /// memory where resides the bitmap that indicates if a rule is enabled
+    /// by the rule filter or not. The conditions of disabled rules are not
+    /// evaluated.
+    pub enabled_rules_bitmap_base: walrus::GlobalId,
+
     /// Global variable that contains the value for `filesize`.
     pub filesize: walrus::GlobalId,
 
@@ -578,30 +646,76 @@ pub(crate) fn log_rule_eval_start(
     mut caller: Caller<'_, ScanContext>,
     rule_id: RuleId,
 ) {
-    caller.data_mut().log_rule_eval_start(rule_id);
+    let ctx = caller.data_mut();
+    let rule_id = ctx.wasm_mod().rule_id(rule_id);
+    ctx.log_rule_eval_start(rule_id);
+}
+
+/// Invoked from WASM before evaluating the condition of the rule identified
+/// by the given [`RuleId`]. This only happens when the "rules-profiling"
+/// feature is enabled.
+#[wasm_export]
+#[cfg(feature = "rules-profiling")]
+pub(crate) fn rule_eval_start(
+    mut caller: Caller<'_, ScanContext>,
+    _rule_id: RuleId,
+) {
+    caller.data_mut().rule_eval_start_time = Some(std::time::Instant::now());
+}
+
+/// Invoked from WASM after evaluating the condition of the rule identified
+/// by the given [`RuleId`]. This only happens when the "rules-profiling"
+/// feature is enabled.
+#[wasm_export]
+#[cfg(feature = "rules-profiling")]
+pub(crate) fn rule_eval_end(
+    mut caller: Caller<'_, ScanContext>,
+    rule_id: RuleId,
+) {
+    let ctx = caller.data_mut();
+    let rule_id = ctx.wasm_mod().rule_id(rule_id);
+    ctx.track_rule_eval_time(rule_id);
 }
 
 /// Invoked from WASM for triggering the pattern search phase.
 ///
-/// Returns `true` on success and `false` when a timeout occurs.
+/// Returns `true` on success and `false` when a timeout occurs, the scan
+/// is cancelled or some resource limit is exceeded. If the scan is aborted
+/// by the callback while searching for patterns, returns [`ScanAborted`].
 #[wasm_export]
 pub(crate) fn search_for_patterns(
     mut caller: Caller<'_, ScanContext>,
-) -> bool {
-    match caller.data_mut().search_for_patterns() {
+) -> Result<bool, ScanAborted> {
+    let ctx = caller.data_mut();
+    let result = match ctx.search_for_patterns() {
         Ok(_) => true,
-        Err(ScanError::Timeout) => false,
+        Err(ScanError::Timeout)
+        | Err(ScanError::Cancelled)
+        | Err(ScanError::LimitExceeded { .. }) => false,
         Err(_) => unreachable!(),
+    };
+    if ctx.aborted {
+        return Err(ScanAborted);
     }
+    Ok(result)
 }
 
 /// Invoked from WASM to notify when a rule matches.
+///
+/// Returns [`ScanAborted`] if the callback aborted the scan when notified
+/// about the match.
 #[wasm_export]
 pub(crate) fn rule_match(
     mut caller: Caller<'_, ScanContext>,
     rule_id: RuleId,
-) {
-    caller.data_mut().track_rule_match(rule_id);
+) -> Result<(), ScanAborted> {
+    let ctx = caller.data_mut();
+    let rule_id = ctx.wasm_mod().rule_id(rule_id);
+    ctx.track_rule_match(rule_id);
+    if ctx.aborted {
+        return Err(ScanAborted);
+    }
+    Ok(())
 }
 
 /// Invoked from WASM to notify when a global rule doesn't match.
@@ -610,7 +724,9 @@ pub(crate) fn global_rule_no_match(
     mut caller: Caller<'_, ScanContext>,
     rule_id: RuleId,
 ) {
-    caller.data_mut().track_global_rule_no_match(rule_id);
+    let ctx = caller.data_mut();
+    let rule_id = ctx.wasm_mod().rule_id(rule_id);
+    ctx.track_global_rule_no_match(rule_id);
 }
 
 /// Invoked from WASM to ask whether a pattern matches at a given file
@@ -628,7 +744,9 @@ pub(crate) fn is_pat_match_at(
     if offset < 0 {
         return false;
     }
-    if let Some(matches) = caller.data().pattern_matches.get(&pattern_id) {
+    let ctx = caller.data();
+    let pattern_id = ctx.wasm_mod().pattern_id(pattern_id);
+    if let Some(matches) = ctx.pattern_matches.get(&pattern_id) {
         matches.search(offset.try_into().unwrap()).is_ok()
     } else {
         false
@@ -647,7 +765,9 @@ pub(crate) fn is_pat_match_in(
     lower_bound: i64,
     upper_bound: i64,
 ) -> bool {
-    if let Some(matches) = caller.data().pattern_matches.get(&pattern_id) {
+    let ctx = caller.data();
+    let pattern_id = ctx.wasm_mod().pattern_id(pattern_id);
+    if let Some(matches) = ctx.pattern_matches.get(&pattern_id) {
         matches
             .matches_in_range(lower_bound as isize..=upper_bound as isize)
             .is_positive()
@@ -662,7 +782,9 @@ pub(crate) fn pat_matches(
     caller: Caller<'_, ScanContext>,
     pattern_id: PatternId,
 ) -> i64 {
-    if let Some(matches) = caller.data().pattern_matches.get(&pattern_id) {
+    let ctx = caller.data();
+    let pattern_id = ctx.wasm_mod().pattern_id(pattern_id);
+    if let Some(matches) = ctx.pattern_matches.get(&pattern_id) {
         matches.len().try_into().unwrap()
     } else {
         0
@@ -681,7 +803,9 @@ pub(crate) fn pat_matches_in(
     lower_bound: i64,
     upper_bound: i64,
 ) -> i64 {
-    if let Some(matches) = caller.data().pattern_matches.get(&pattern_id) {
+    let ctx = caller.data();
+    let pattern_id = ctx.wasm_mod().pattern_id(pattern_id);
+    if let Some(matches) = ctx.pattern_matches.get(&pattern_id) {
         matches.matches_in_range(lower_bound as isize..=upper_bound as isize)
     } else {
         0
@@ -699,7 +823,9 @@ pub(crate) fn pat_length(
     pattern_id: PatternId,
     index: i64,
 ) -> Option<i64> {
-    if let Some(matches) = caller.data().pattern_matches.get(&pattern_id) {
+    let ctx = caller.data();
+    let pattern_id = ctx.wasm_mod().pattern_id(pattern_id);
+    if let Some(matches) = ctx.pattern_matches.get(&pattern_id) {
         // Make sure that index >= 1.
         debug_assert!(index >= 1);
         let m = matches.get(index as usize - 1)?;
@@ -720,7 +846,9 @@ pub(crate) fn pat_offset(
     pattern_id: PatternId,
     index: i64,
 ) -> Option<i64> {
-    if let Some(matches) = caller.data().pattern_matches.get(&pattern_id) {
+    let ctx = caller.data();
+    let pattern_id = ctx.wasm_mod().pattern_id(pattern_id);
+    if let Some(matches) = ctx.pattern_matches.get(&pattern_id) {
         // Make sure that index >= 1.
         debug_assert!(index >= 1);
         let m = matches.get(index as usize - 1)?;
@@ -786,7 +914,8 @@ pub(crate) fn map_len(mut caller: Caller<'_, ScanContext>, var: i32) -> i64 {
 ///
 /// The sequence of indexes is stored in WASM main memory, starting at
 /// `LOOKUP_INDEXES_START`, and the number of indexes is indicated by the
-/// argument `num_lookup_indexes`.
+/// argument `num_lookup_indexes`. When there are no indexes, the result is
+/// the initial structure itself.
 fn lookup_field(
     caller: &mut Caller<'_, ScanContext>,
     num_lookup_indexes: i32,
@@ -821,6 +950,11 @@ fn lookup_field(
             &store_ctx.data().root_struct
         };
 
+        // The indexes of fields in the root structure are translated, as
+        // they are relative to the WASM module being executed.
+        let mut at_root =
+            struct_var == -1 && store_ctx.data().current_struct.is_none();
+
         let mut final_field = None;
 
         for field_index in lookup_indexes {
@@ -834,6 +968,13 @@ fn lookup_field(
                 *field_index
             };
 
+            let field_index = if at_root {
+                at_root = false;
+                store_ctx.data().wasm_mod().root_field_index(field_index)
+            } else {
+                field_index
+            };
+
             let field = structure
                 .field_by_index(field_index as usize)
                 .unwrap_or_else(|| {
@@ -850,20 +991,35 @@ fn lookup_field(
             }
         }
 
-        &final_field.unwrap().type_value
+        final_field.unwrap().type_value.clone()
     } else if struct_var != -1 {
-        &store_ctx.data().vars_stack[struct_var as usize]
+        store_ctx.data().vars_stack[struct_var as usize].clone()
+    } else if let Some(current_structure) = &store_ctx.data().current_struct {
+        // When there are no lookup indexes and no variable, the result is
+        // the current structure itself. This happens when the structure was
+        // obtained from an array or map lookup.
+        TypeValue::Struct(current_structure.clone())
     } else {
         unreachable!();
     };
 
-    let type_value = type_value.clone();
-
     caller.data_mut().current_struct = None;
 
     type_value
 }
 
+/// Lookup a structure that is the object of a method call.
+///
+/// This is used by methods exported by modules for obtaining the structure
+/// they were called on. See [`lookup_field`].
+pub(crate) fn lookup_object(
+    caller: &mut Caller<'_, ScanContext>,
+    num_lookup_indexes: i32,
+    struct_var: i32,
+) -> Rc<Struct> {
+    lookup_field(caller, num_lookup_indexes, struct_var).as_struct()
+}
+
 /// Lookup a field of string type and returns its value.
 ///
 /// See [`lookup_field`].
@@ -108,9 +108,11 @@ impl RuntimeString {
     /// Returns this string as a &[`BStr`].
     pub(crate) fn as_bstr<'a>(&self, ctx: &'a ScanContext) -> &'a BStr {
         match self {
-            Self::Literal(id) => {
-                ctx.compiled_rules.lit_pool().get(*id).unwrap()
-            }
+            Self::Literal(id) => ctx
+                .compiled_rules
+                .lit_pool()
+                .get(ctx.wasm_mod().literal_id(*id))
+                .unwrap(),
             Self::ScannedDataSlice { offset, length } => {
                 let data = ctx.scanned_data();
                 BStr::new(&data[*offset..*offset + *length])